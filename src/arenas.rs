@@ -0,0 +1,151 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    coordinate_frame::CoordinateFrame,
+    field_dimensions::FieldDimensions,
+    pan_orbit_camera::PanOrbitCamera,
+    shortcuts::{dispatch_shortcuts, triggered, ShortcutAction, ShortcutsPlugin},
+};
+
+/// Free space between the carpets of neighboring arenas in meters
+const ARENA_GAP: f32 = 3.0;
+
+/// Simulates several independent matches side by side in one process, e.g. to evaluate controller
+/// variants in parallel.
+///
+/// Every arena has its own field, goals, ball and teams, lined up along the field y axis far
+/// enough apart that nothing crosses from one carpet to another. Arenas share one physics world,
+/// contacts between bodies of different arenas are filtered out by the
+/// [`SelfCollisionFilter`](crate::self_collision::SelfCollisionFilter). Game control, the referee and the network interfaces
+/// act on the main arena, scenario commands are repeated in every arena. The camera jumps to the
+/// next arena with [`ShortcutAction::NextArena`].
+pub struct ArenasPlugin;
+
+impl Plugin for ArenasPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Arenas>().add_system(
+            filter_contacts_between_arenas.run_if(|arenas: Res<Arenas>| arenas.count > 1),
+        );
+        if app.is_plugin_added::<ShortcutsPlugin>() {
+            app.add_system(jump_to_next_arena.after(dispatch_shortcuts));
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct Arenas {
+    /// Number of arenas spawned at startup
+    pub count: usize,
+    /// Arena the camera looks at
+    pub focused: Arena,
+}
+
+impl Default for Arenas {
+    fn default() -> Self {
+        Self {
+            count: 1,
+            focused: Arena::MAIN,
+        }
+    }
+}
+
+impl Arenas {
+    pub fn iter(&self) -> impl Iterator<Item = Arena> {
+        (0..self.count.max(1)).map(Arena)
+    }
+}
+
+/// Arena a field, ball or robot belongs to, entities without it belong to the main arena.
+#[derive(Clone, Copy, Component, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Arena(pub usize);
+
+impl Arena {
+    pub const MAIN: Self = Self(0);
+
+    /// Position of the field center of this arena in the field frame of the main arena.
+    pub fn offset(self, field_dimensions: &FieldDimensions) -> Vec3 {
+        let spacing =
+            field_dimensions.width + 2.0 * field_dimensions.border_strip_width + ARENA_GAP;
+        Vec3::Y * spacing * self.0 as f32
+    }
+
+    /// World coordinates of the `point` in the field frame of this arena.
+    pub fn to_world(
+        self,
+        frame: &CoordinateFrame,
+        field_dimensions: &FieldDimensions,
+        point: Vec3,
+    ) -> Vec3 {
+        frame.to_world(point + self.offset(field_dimensions))
+    }
+
+    /// Pose in the field frame of this arena in world coordinates.
+    pub fn transform_to_world(
+        self,
+        frame: &CoordinateFrame,
+        field_dimensions: &FieldDimensions,
+        transform: Transform,
+    ) -> Transform {
+        frame.transform_to_world(Transform {
+            translation: transform.translation + self.offset(field_dimensions),
+            ..transform
+        })
+    }
+
+    /// Entity name with the arena appended outside the main arena.
+    pub fn name(self, name: &str) -> String {
+        if self == Self::MAIN {
+            name.to_string()
+        } else {
+            format!("{name} (arena {})", self.0)
+        }
+    }
+}
+
+/// Arena of `entity` or of its nearest ancestor with an [`Arena`], the main arena without any.
+pub fn arena_of(entity: Entity, parents: &Query<&Parent>, arenas: &Query<&Arena>) -> Arena {
+    std::iter::once(entity)
+        .chain(parents.iter_ancestors(entity))
+        .find_map(|entity| arenas.get(entity).ok().copied())
+        .unwrap_or_default()
+}
+
+/// Whether an entity with this [`Arena`] belongs to the main arena.
+pub fn in_main_arena(arena: Option<&Arena>) -> bool {
+    arena.map_or(true, |arena| *arena == Arena::MAIN)
+}
+
+/// Runs the contact filter on every collider, so bodies of different arenas never touch.
+fn filter_contacts_between_arenas(
+    mut commands: Commands,
+    colliders: Query<Entity, Added<Collider>>,
+) {
+    for collider in colliders.iter() {
+        commands
+            .entity(collider)
+            .insert(ActiveHooks::FILTER_CONTACT_PAIRS);
+    }
+}
+
+fn jump_to_next_arena(
+    mut actions: EventReader<ShortcutAction>,
+    mut arenas: ResMut<Arenas>,
+    frame: Res<CoordinateFrame>,
+    field_dimensions: Res<FieldDimensions>,
+    mut cameras: Query<(&mut PanOrbitCamera, &mut Transform)>,
+) {
+    if !triggered(&mut actions, ShortcutAction::NextArena) || arenas.count < 2 {
+        return;
+    }
+    let next = Arena((arenas.focused.0 + 1) % arenas.count);
+    let delta = frame
+        .vector_to_world(next.offset(&field_dimensions) - arenas.focused.offset(&field_dimensions));
+    arenas.focused = next;
+    info!("Looking at arena {}", next.0);
+    for (mut pan_orbit, mut transform) in cameras.iter_mut() {
+        pan_orbit.follow = None;
+        pan_orbit.focus += delta;
+        transform.translation += delta;
+    }
+}
@@ -0,0 +1,102 @@
+use std::{net::SocketAddr, path::PathBuf};
+
+use clap::Parser;
+
+use crate::{coordinate_frame::UpAxis, mesh_colliders::ColliderFidelity};
+
+/// Simulator for RoboCup SPL NAO robots
+///
+/// Arguments without default override the configuration file, which defaults to
+/// `simulation.toml` in the working directory if it exists.
+#[derive(Debug, Parser)]
+pub struct Arguments {
+    /// Configuration of the simulation in TOML
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+    /// URDF or xacro of robots that do not name one in the team configuration
+    #[arg(long)]
+    pub urdf: Option<PathBuf>,
+    /// Directory meshes and textures are loaded from
+    #[arg(long)]
+    pub assets: Option<PathBuf>,
+    /// Directory of a ROS package referenced by `package://` URIs in URDFs, as NAME=PATH.
+    /// Packages without a directory are looked up in the assets directory.
+    #[arg(long = "package", value_parser = parse_package)]
+    pub packages: Vec<(String, PathBuf)>,
+    /// URDF or xacro of a robot to spawn next to the field outside the teams, may be repeated.
+    /// Link and joint names are free, meshes may be STL or Collada.
+    #[arg(long = "additional-robot")]
+    pub additional_robots: Vec<PathBuf>,
+    /// Robots to spawn per team, in JSON
+    #[arg(long)]
+    pub teams: Option<PathBuf>,
+    /// Field dimensions in JSON, those of the configuration if not given. Changes to the file are
+    /// applied while running.
+    #[arg(long, alias = "field")]
+    pub field_dimensions: Option<PathBuf>,
+    /// How closely colliders follow collision meshes in URDFs
+    #[arg(long, value_enum)]
+    pub collider_fidelity: Option<ColliderFidelity>,
+    /// World axis pointing up, Y for compatibility with Bevy and glTF assets. URDFs, scenarios
+    /// and external interfaces keep using z-up field coordinates.
+    #[arg(long, value_enum)]
+    pub up_axis: Option<UpAxis>,
+    /// Run without window and rendering, simulating as fast as possible
+    #[arg(long)]
+    pub headless: bool,
+    /// Simulated seconds per real second
+    #[arg(long)]
+    pub timescale: Option<f32>,
+    /// Rhai script setting up and checking a test scenario
+    #[arg(long)]
+    pub scenario: Option<PathBuf>,
+    /// Spawn only the first robots of each team in the team configuration
+    #[arg(long)]
+    pub robots: Option<usize>,
+    /// Record every physics step to this file
+    #[arg(long, conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+    /// Play a recording back instead of simulating, with the team configuration it was
+    /// recorded with
+    #[arg(long)]
+    pub replay: Option<PathBuf>,
+    /// Show the robots and balls of a recording as ghosts alongside the simulation, to compare
+    /// against it. Needs the team configuration it was recorded with.
+    #[arg(long, conflicts_with_all = ["headless", "replay"])]
+    pub ghost: Option<PathBuf>,
+    /// Record a video of the main camera from startup, as PNG image sequence into this directory
+    /// or through ffmpeg if the path has a video file extension like `.mp4`
+    #[arg(long, conflicts_with = "headless")]
+    pub video: Option<PathBuf>,
+    /// Video frames per simulated second
+    #[arg(long, default_value_t = 30.0)]
+    pub video_fps: f32,
+    /// Equirectangular sky image, e.g. an HDRI, relative to the assets directory
+    #[arg(long, conflicts_with = "headless")]
+    pub skybox: Option<String>,
+    /// Publish ground truth robot and ball poses to this address, e.g. 127.0.0.1:10700
+    #[arg(long)]
+    pub ground_truth: Option<SocketAddr>,
+    /// Ground truth messages per simulated second
+    #[arg(long)]
+    pub ground_truth_rate: Option<f32>,
+    /// Export the head camera images with ground truth annotations into this directory
+    #[arg(long, conflicts_with = "headless")]
+    pub vision_dataset: Option<PathBuf>,
+    /// Rendered frames between two exported dataset frames
+    #[arg(long, default_value_t = 10)]
+    pub vision_dataset_interval: u32,
+    /// Independent matches simulated side by side, each with its own field, ball and teams
+    #[arg(long)]
+    pub arenas: Option<usize>,
+    /// Seed of all randomized systems
+    #[arg(long)]
+    pub seed: Option<u64>,
+}
+
+fn parse_package(value: &str) -> Result<(String, PathBuf), String> {
+    let (name, path) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected NAME=PATH, got {value:?}"))?;
+    Ok((name.to_string(), PathBuf::from(path)))
+}
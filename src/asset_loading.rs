@@ -0,0 +1,58 @@
+use bevy::{
+    asset::{HandleId, LoadState},
+    prelude::*,
+};
+
+/// Holds the simulation until the meshes and materials of the scene are loaded.
+///
+/// Assets load asynchronously, without waiting robots would fall and settle before their visuals
+/// exist. Physics steps only run in [`SimulationState::Running`], assets that fail to load do not
+/// hold the simulation back.
+pub struct AssetLoadingPlugin;
+
+impl Plugin for AssetLoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state::<SimulationState>()
+            .add_system(release_simulation_when_loaded.in_set(OnUpdate(SimulationState::Loading)));
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, States)]
+pub enum SimulationState {
+    /// Waiting for assets, the simulation is frozen
+    #[default]
+    Loading,
+    Running,
+}
+
+fn release_simulation_when_loaded(
+    time: Res<Time>,
+    server: Res<AssetServer>,
+    meshes: Query<&Handle<Mesh>>,
+    materials: Query<&Handle<StandardMaterial>>,
+    mut next_state: ResMut<NextState<SimulationState>>,
+) {
+    // assets added in code instead of loaded by the server are ready right away
+    let states: Vec<_> = meshes
+        .iter()
+        .map(|handle| handle.id())
+        .chain(materials.iter().map(|handle| handle.id()))
+        .filter(|id| matches!(id, HandleId::AssetPathId(_)))
+        .map(|id| server.get_load_state(id))
+        .collect();
+    if states
+        .iter()
+        .any(|state| matches!(state, LoadState::NotLoaded | LoadState::Loading))
+    {
+        return;
+    }
+    let failed = states
+        .iter()
+        .filter(|state| **state == LoadState::Failed)
+        .count();
+    if failed > 0 {
+        warn!("{failed} assets failed to load, starting the simulation anyway");
+    }
+    info!("Assets loaded after {:.1} s", time.elapsed_seconds());
+    next_state.set(SimulationState::Running);
+}
@@ -0,0 +1,288 @@
+use std::{collections::HashSet, f32::consts::TAU, sync::Arc, time::Duration};
+
+use bevy::{
+    audio::{AddAudioSource, AudioPlugin, Decodable, Source},
+    prelude::*,
+    reflect::TypeUuid,
+};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    game_controller::GameState, game_phase::GamePhaseChanged, simulation_time::PhysicsSchedule,
+    Ball,
+};
+
+/// Sample rate of the synthesized sounds in Hz
+pub const SAMPLE_RATE: u32 = 44100;
+/// Pitch of a pea whistle in Hz, within the band whistle detectors listen to
+const WHISTLE_FREQUENCY: f32 = 3200.0;
+/// Pitch deviation caused by the rolling pea in Hz
+const WHISTLE_FREQUENCY_DEVIATION: f32 = 150.0;
+/// Rate the pea rolls around the whistle chamber in Hz
+const WHISTLE_TRILL_FREQUENCY: f32 = 35.0;
+/// Length of a whistle blast in seconds
+const WHISTLE_DURATION: f32 = 0.8;
+/// Length of an impact sound in seconds
+const IMPACT_DURATION: f32 = 0.15;
+/// Time constant the impact sound decays with in seconds
+const IMPACT_DECAY: f32 = 0.025;
+/// Duration of the fade in and out avoiding clicks in seconds
+const FADE_DURATION: f32 = 0.01;
+
+/// Whistles on game state transitions and lets the ball sound on impacts.
+///
+/// Referees whistle when the game starts or stops playing, i.e. at kick-offs, goals and the end of
+/// a half. Ball impacts sound louder with the impulse of the contact. All sounds are synthesized
+/// and raised as [`SoundEmitted`], so simulated microphones can pick them up. They are played on
+/// the speakers unless running headless.
+pub struct AudioEventsPlugin;
+
+impl Plugin for AudioEventsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AudioEvents>()
+            .add_event::<SoundEmitted>()
+            .add_system(blow_whistle)
+            .add_system(
+                detect_ball_impacts
+                    .after(PhysicsSet::Writeback)
+                    .in_schedule(PhysicsSchedule),
+            );
+        if app.is_plugin_added::<AudioPlugin>() {
+            app.add_audio_source::<SynthesizedSound>()
+                .add_startup_system(synthesize_sounds)
+                .add_system(play_sounds.after(blow_whistle));
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct AudioEvents {
+    /// Gain of all sounds played on the speakers between 0 and 1
+    pub volume: f32,
+    /// Change of the ball velocity by an impact below which it is silent in m/s
+    pub minimum_impact: f32,
+    /// Change of the ball velocity by an impact sounding at full volume in m/s
+    pub full_volume_impact: f32,
+}
+
+impl Default for AudioEvents {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            minimum_impact: 0.3,
+            full_volume_impact: 5.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Sound {
+    Whistle,
+    BallImpact,
+}
+
+impl Sound {
+    /// Mono samples at [`SAMPLE_RATE`] between -1 and 1.
+    pub fn samples(self) -> Vec<f32> {
+        match self {
+            Sound::Whistle => synthesize(WHISTLE_DURATION, {
+                let mut phase = 0.0;
+                move |time| {
+                    // the rolling pea modulates pitch and loudness
+                    let trill = (TAU * WHISTLE_TRILL_FREQUENCY * time).sin();
+                    phase += TAU * (WHISTLE_FREQUENCY + WHISTLE_FREQUENCY_DEVIATION * trill)
+                        / SAMPLE_RATE as f32;
+                    (0.8 + 0.2 * trill) * (0.5 * phase.sin() + 0.15 * (2.0 * phase).sin())
+                }
+            }),
+            Sound::BallImpact => synthesize(IMPACT_DURATION, |time| {
+                (-time / IMPACT_DECAY).exp()
+                    * (0.6 * (TAU * 150.0 * time).sin() + 0.3 * (TAU * 620.0 * time).sin())
+            }),
+        }
+    }
+}
+
+/// Samples `signal` over `duration` seconds, faded in and out.
+fn synthesize(duration: f32, mut signal: impl FnMut(f32) -> f32) -> Vec<f32> {
+    let length = (duration * SAMPLE_RATE as f32) as usize;
+    (0..length)
+        .map(|index| {
+            let time = index as f32 / SAMPLE_RATE as f32;
+            let fade = (time / FADE_DURATION)
+                .min((duration - time) / FADE_DURATION)
+                .clamp(0.0, 1.0);
+            fade * signal(time)
+        })
+        .collect()
+}
+
+/// A sound made in the simulation.
+#[derive(Clone, Debug)]
+pub struct SoundEmitted {
+    pub sound: Sound,
+    /// Gain between 0 and 1
+    pub volume: f32,
+    /// Source in world coordinates, `None` for sounds heard everywhere alike
+    pub position: Option<Vec3>,
+}
+
+/// Mono samples played through bevy_audio.
+#[derive(TypeUuid)]
+#[uuid = "9d3a6c1e-52f4-4b8e-a7d0-3e6f1b2c8a45"]
+struct SynthesizedSound {
+    samples: Arc<[f32]>,
+}
+
+impl Decodable for SynthesizedSound {
+    type DecoderItem = f32;
+    type Decoder = SynthesizedSoundDecoder;
+
+    fn decoder(&self) -> Self::Decoder {
+        SynthesizedSoundDecoder {
+            samples: self.samples.clone(),
+            position: 0,
+        }
+    }
+}
+
+struct SynthesizedSoundDecoder {
+    samples: Arc<[f32]>,
+    position: usize,
+}
+
+impl Iterator for SynthesizedSoundDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = self.samples.get(self.position).copied();
+        self.position += 1;
+        sample
+    }
+}
+
+impl Source for SynthesizedSoundDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        Some(self.samples.len().saturating_sub(self.position))
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(Duration::from_secs_f32(
+            self.samples.len() as f32 / SAMPLE_RATE as f32,
+        ))
+    }
+}
+
+#[derive(Resource)]
+struct SoundHandles {
+    whistle: Handle<SynthesizedSound>,
+    ball_impact: Handle<SynthesizedSound>,
+}
+
+fn synthesize_sounds(mut commands: Commands, mut sounds: ResMut<Assets<SynthesizedSound>>) {
+    let mut add = |sound: Sound| {
+        sounds.add(SynthesizedSound {
+            samples: sound.samples().into(),
+        })
+    };
+    commands.insert_resource(SoundHandles {
+        whistle: add(Sound::Whistle),
+        ball_impact: add(Sound::BallImpact),
+    });
+}
+
+fn blow_whistle(mut changes: EventReader<GamePhaseChanged>, mut sounds: EventWriter<SoundEmitted>) {
+    for change in changes.iter() {
+        let (previous, current) = (change.previous.state, change.current.state);
+        if previous != current && (previous == GameState::Playing || current == GameState::Playing)
+        {
+            sounds.send(SoundEmitted {
+                sound: Sound::Whistle,
+                volume: 1.0,
+                position: None,
+            });
+        }
+    }
+}
+
+/// Sounds the first step of every contact of a ball, louder the more the impact changes the ball
+/// velocity.
+fn detect_ball_impacts(
+    settings: Res<AudioEvents>,
+    context: Res<RapierContext>,
+    mut touching: Local<HashSet<(Entity, Entity)>>,
+    balls: Query<(Entity, &Transform), With<Ball>>,
+    mut sounds: EventWriter<SoundEmitted>,
+) {
+    let mut now_touching = HashSet::new();
+    for (ball, transform) in balls.iter() {
+        let Some(mass) = context
+            .entity2body()
+            .get(&ball)
+            .and_then(|handle| context.bodies.get(*handle))
+            .map(|body| body.mass())
+            .filter(|mass| *mass > 0.0)
+        else {
+            continue;
+        };
+        for pair in context.contacts_with(ball) {
+            if !pair.has_any_active_contacts() {
+                continue;
+            }
+            let other = if pair.collider1() == ball {
+                pair.collider2()
+            } else {
+                pair.collider1()
+            };
+            now_touching.insert((ball, other));
+            if touching.contains(&(ball, other)) {
+                continue;
+            }
+            let mut impulse = 0.0;
+            for manifold in pair.manifolds() {
+                for contact in manifold.points() {
+                    impulse += contact.impulse();
+                }
+            }
+            let impact = impulse / mass;
+            if impact < settings.minimum_impact {
+                continue;
+            }
+            sounds.send(SoundEmitted {
+                sound: Sound::BallImpact,
+                volume: (impact / settings.full_volume_impact).min(1.0),
+                position: Some(transform.translation),
+            });
+        }
+    }
+    *touching = now_touching;
+}
+
+fn play_sounds(
+    settings: Res<AudioEvents>,
+    handles: Option<Res<SoundHandles>>,
+    audio: Res<Audio<SynthesizedSound>>,
+    mut sounds: EventReader<SoundEmitted>,
+) {
+    let Some(handles) = handles else {
+        return;
+    };
+    for emitted in sounds.iter() {
+        let handle = match emitted.sound {
+            Sound::Whistle => &handles.whistle,
+            Sound::BallImpact => &handles.ball_impact,
+        };
+        audio.play_with_settings(
+            handle.clone(),
+            PlaybackSettings::ONCE.with_volume(settings.volume * emitted.volume),
+        );
+    }
+}
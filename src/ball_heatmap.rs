@@ -0,0 +1,212 @@
+use bevy::{
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+use color_eyre::{
+    eyre::{eyre, WrapErr},
+    Result,
+};
+
+use crate::{
+    arenas::{in_main_arena, Arena},
+    coordinate_frame::CoordinateFrame,
+    field_dimensions::FieldDimensions,
+    shortcuts::{triggered, ShortcutAction},
+    Ball,
+};
+
+/// Accumulates the ball position over a run into a heatmap overlaid on the field.
+///
+/// The heatmap can be exported as PNG to [`BallHeatmap::export_path`].
+pub struct BallHeatmapPlugin;
+
+impl Plugin for BallHeatmapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BallHeatmap>()
+            .add_startup_system(spawn_heatmap_overlay)
+            .add_system(accumulate_ball_positions)
+            .add_system(toggle_heatmap)
+            .add_system(update_heatmap_image.after(accumulate_ball_positions))
+            .add_system(export_heatmap.after(update_heatmap_image));
+    }
+}
+
+#[derive(Resource)]
+pub struct BallHeatmap {
+    pub enabled: bool,
+    /// Number of heatmap cells per meter
+    pub resolution: f32,
+    pub export_path: String,
+    half_extent: Vec2,
+    columns: usize,
+    rows: usize,
+    /// Time the ball spent in each cell in seconds, row by row starting at the positive y side
+    dwell_times: Vec<f32>,
+    image: Handle<Image>,
+}
+
+impl Default for BallHeatmap {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            resolution: 10.0,
+            export_path: "ball_heatmap.png".to_string(),
+            half_extent: Vec2::ZERO,
+            columns: 0,
+            rows: 0,
+            dwell_times: Vec::new(),
+            image: Default::default(),
+        }
+    }
+}
+
+impl BallHeatmap {
+    fn cell_index(&self, position: Vec2) -> Option<usize> {
+        let relative = (position + self.half_extent) * self.resolution;
+        if relative.x < 0.0 || relative.y < 0.0 {
+            return None;
+        }
+        let column = relative.x as usize;
+        let row_from_bottom = relative.y as usize;
+        if column >= self.columns || row_from_bottom >= self.rows {
+            return None;
+        }
+        Some((self.rows - 1 - row_from_bottom) * self.columns + column)
+    }
+
+    fn render_pixels(&self, data: &mut [u8]) {
+        let maximum = self.dwell_times.iter().copied().fold(0.0, f32::max);
+        for (pixel, dwell_time) in data.chunks_exact_mut(4).zip(&self.dwell_times) {
+            if maximum <= 0.0 || *dwell_time <= 0.0 {
+                pixel.copy_from_slice(&[0, 0, 0, 0]);
+                continue;
+            }
+            // square root keeps rarely visited cells visible next to a few hot spots
+            let t = (dwell_time / maximum).sqrt();
+            let color = Color::rgba(t, 1.0 - (2.0 * t - 1.0).abs(), 1.0 - t, 0.3 + 0.5 * t);
+            pixel.copy_from_slice(&color.as_rgba_f32().map(|channel| (channel * 255.0) as u8));
+        }
+    }
+}
+
+fn spawn_heatmap_overlay(
+    mut commands: Commands,
+    frame: Res<CoordinateFrame>,
+    mut heatmap: ResMut<BallHeatmap>,
+    field_dimensions: Res<FieldDimensions>,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let size = Vec2::new(
+        field_dimensions.length + field_dimensions.border_strip_width * 2.0,
+        field_dimensions.width + field_dimensions.border_strip_width * 2.0,
+    );
+    heatmap.half_extent = size / 2.0;
+    heatmap.columns = (size.x * heatmap.resolution).ceil() as usize;
+    heatmap.rows = (size.y * heatmap.resolution).ceil() as usize;
+    heatmap.dwell_times = vec![0.0; heatmap.columns * heatmap.rows];
+    heatmap.image = images.add(Image::new(
+        Extent3d {
+            width: heatmap.columns as u32,
+            height: heatmap.rows as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        vec![0; heatmap.columns * heatmap.rows * 4],
+        TextureFormat::Rgba8UnormSrgb,
+    ));
+
+    let visibility = if heatmap.enabled {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::Quad::new(size))),
+            material: materials.add(StandardMaterial {
+                base_color_texture: Some(heatmap.image.clone()),
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..Default::default()
+            }),
+            transform: frame.transform_to_world(Transform::from_xyz(0.0, 0.0, 0.01)),
+            visibility,
+            ..Default::default()
+        },
+        BallHeatmapOverlay,
+        Name::new("ball heatmap"),
+    ));
+}
+
+#[derive(Component)]
+struct BallHeatmapOverlay;
+
+fn accumulate_ball_positions(
+    time: Res<Time>,
+    mut heatmap: ResMut<BallHeatmap>,
+    frame: Res<CoordinateFrame>,
+    balls: Query<(&GlobalTransform, Option<&Arena>), With<Ball>>,
+) {
+    for (transform, _) in balls.iter().filter(|(_, arena)| in_main_arena(*arena)) {
+        let position = frame.to_field(transform.translation()).truncate();
+        if let Some(index) = heatmap.cell_index(position) {
+            heatmap.dwell_times[index] += time.delta_seconds();
+        }
+    }
+}
+
+fn toggle_heatmap(
+    mut actions: EventReader<ShortcutAction>,
+    mut heatmap: ResMut<BallHeatmap>,
+    mut overlays: Query<&mut Visibility, With<BallHeatmapOverlay>>,
+) {
+    if !triggered(&mut actions, ShortcutAction::ToggleBallHeatmap) {
+        return;
+    }
+    heatmap.enabled = !heatmap.enabled;
+    for mut visibility in overlays.iter_mut() {
+        *visibility = if heatmap.enabled {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+fn update_heatmap_image(heatmap: Res<BallHeatmap>, mut images: ResMut<Assets<Image>>) {
+    if !heatmap.enabled {
+        return;
+    }
+    if let Some(image) = images.get_mut(&heatmap.image) {
+        heatmap.render_pixels(&mut image.data);
+    }
+}
+
+fn export_heatmap(
+    mut actions: EventReader<ShortcutAction>,
+    heatmap: Res<BallHeatmap>,
+    images: Res<Assets<Image>>,
+) {
+    if !triggered(&mut actions, ShortcutAction::ExportBallHeatmap) {
+        return;
+    }
+    let Some(image) = images.get(&heatmap.image) else {
+        return;
+    };
+    let mut image = image.clone();
+    heatmap.render_pixels(&mut image.data);
+    match save_png(image, &heatmap.export_path) {
+        Ok(()) => info!("Exported ball heatmap to {}", heatmap.export_path),
+        Err(error) => error!("{error:?}"),
+    }
+}
+
+fn save_png(image: Image, path: &str) -> Result<()> {
+    image
+        .try_into_dynamic()
+        .map_err(|error| eyre!("{error}"))?
+        .save(path)
+        .wrap_err_with(|| format!("Failed to save heatmap to {path}"))
+}
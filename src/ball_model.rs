@@ -0,0 +1,96 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    coordinate_frame::CoordinateFrame, field_dimensions::FieldDimensions,
+    simulation_time::PhysicsSchedule, Ball,
+};
+
+/// Gap between ball and ground up to which the ball counts as rolling on the field in meters
+const GROUND_CONTACT_TOLERANCE: f32 = 0.005;
+/// Speed below which rolling resistance fades out, keeps a resting ball from jittering in m/s
+const STOP_SPEED: f32 = 0.01;
+
+/// Slows the ball down by rolling resistance on the field and bends its flight by the Magnus
+/// force of its spin.
+pub struct BallModelPlugin;
+
+impl Plugin for BallModelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BallModel>()
+            .add_system(add_ball_forces)
+            .add_system(
+                apply_ball_model
+                    .before(PhysicsSet::SyncBackend)
+                    .in_schedule(PhysicsSchedule),
+            );
+    }
+}
+
+#[derive(Clone, Debug, Resource)]
+pub struct BallModel {
+    /// Rolling resistance force per weight of the ball on the field
+    pub rolling_resistance: f32,
+    /// Magnus force per cross product of angular and linear velocity in kg
+    pub magnus_coefficient: f32,
+}
+
+impl Default for BallModel {
+    fn default() -> Self {
+        Self {
+            // artificial turf slows the ball much more than a hard floor
+            rolling_resistance: 0.05,
+            // 0.5 * air density * cross section * radius of a 10 cm ball, lift coefficient 1
+            magnus_coefficient: 2.4e-4,
+        }
+    }
+}
+
+fn add_ball_forces(
+    mut commands: Commands,
+    balls: Query<Entity, (Added<Ball>, Without<ExternalForce>)>,
+) {
+    for ball in balls.iter() {
+        commands
+            .entity(ball)
+            .insert((ExternalForce::default(), ReadMassProperties::default()));
+    }
+}
+
+fn apply_ball_model(
+    model: Res<BallModel>,
+    frame: Res<CoordinateFrame>,
+    field_dimensions: Res<FieldDimensions>,
+    configuration: Res<RapierConfiguration>,
+    mut balls: Query<
+        (
+            &Transform,
+            &Velocity,
+            &ReadMassProperties,
+            &mut ExternalForce,
+        ),
+        With<Ball>,
+    >,
+) {
+    let radius = field_dimensions.ball_radius;
+    for (transform, velocity, mass_properties, mut external_force) in balls.iter_mut() {
+        let weight = mass_properties.0.mass * configuration.gravity.length();
+        let mut force = model.magnus_coefficient * velocity.angvel.cross(velocity.linvel);
+        let mut torque = Vec3::ZERO;
+        let on_ground = frame.height(transform.translation) <= radius + GROUND_CONTACT_TOLERANCE;
+        if on_ground {
+            let up = frame.up();
+            let ground_velocity = velocity.linvel - up * velocity.linvel.dot(up);
+            force -= model.rolling_resistance * weight * fading_direction(ground_velocity);
+            torque -=
+                model.rolling_resistance * weight * radius * fading_direction(velocity.angvel);
+        }
+        external_force.force = force;
+        external_force.torque = torque;
+    }
+}
+
+/// Direction of `velocity`, shrinking to zero below [`STOP_SPEED`].
+fn fading_direction(velocity: Vec3) -> Vec3 {
+    velocity / velocity.length().max(STOP_SPEED)
+}
@@ -0,0 +1,114 @@
+use bevy::prelude::*;
+use bevy_egui::EguiContexts;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    coordinate_frame::CoordinateFrame, pan_orbit_camera::pan_orbit_camera, picking::Picking,
+    selection::selectable_entity, tools::ActiveTool, Ball, RobotRoot,
+};
+
+/// With the camera tool, dragging the ball or a robot moves it across the field instead of
+/// panning the camera.
+///
+/// The dragged body is kinematic while it follows the cursor and gets its previous body type back
+/// at rest when released.
+pub struct BodyDragPlugin;
+
+impl Plugin for BodyDragPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BodyDrag>()
+            .add_system(start_body_drag.before(pan_orbit_camera))
+            .add_system(update_body_drag.after(start_body_drag));
+    }
+}
+
+#[derive(Default, Resource)]
+pub struct BodyDrag {
+    active: Option<ActiveBodyDrag>,
+}
+
+impl BodyDrag {
+    pub fn is_dragging(&self) -> bool {
+        self.active.is_some()
+    }
+}
+
+struct ActiveBodyDrag {
+    entity: Entity,
+    /// Body type to restore when the drag ends, `None` if the entity has no rigid body
+    original_body: Option<RigidBody>,
+    /// Point on the horizontal plane the cursor is projected onto
+    plane_point: Vec3,
+    /// Offset from the cursor on the plane to the entity
+    offset: Vec3,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn start_body_drag(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    active_tool: Res<ActiveTool>,
+    mouse: Res<Input<MouseButton>>,
+    picking: Picking,
+    parents: Query<&Parent>,
+    robots: Query<(), With<RobotRoot>>,
+    draggables: Query<(&Transform, Option<&RigidBody>), Or<(With<Ball>, With<RobotRoot>)>>,
+    mut drag: ResMut<BodyDrag>,
+) {
+    if *active_tool != ActiveTool::Camera
+        || !mouse.just_pressed(MouseButton::Left)
+        || contexts.ctx_mut().is_pointer_over_area()
+    {
+        return;
+    }
+    let Some((picked, grab_point)) = picking.pick(QueryFilter::default()) else {
+        return;
+    };
+    let entity = selectable_entity(picked, &parents, &robots);
+    let Ok((transform, body)) = draggables.get(entity) else {
+        return;
+    };
+
+    if body.is_some() {
+        commands
+            .entity(entity)
+            .insert((RigidBody::KinematicPositionBased, Velocity::zero()));
+    }
+    drag.active = Some(ActiveBodyDrag {
+        entity,
+        original_body: body.copied(),
+        plane_point: grab_point,
+        offset: transform.translation - grab_point,
+    });
+}
+
+fn update_body_drag(
+    mut commands: Commands,
+    mouse: Res<Input<MouseButton>>,
+    frame: Res<CoordinateFrame>,
+    picking: Picking,
+    mut drag: ResMut<BodyDrag>,
+    mut transforms: Query<&mut Transform>,
+) {
+    let Some(active) = &drag.active else {
+        return;
+    };
+    if !mouse.pressed(MouseButton::Left) || !transforms.contains(active.entity) {
+        if let (Some(body), Some(mut entity)) =
+            (active.original_body, commands.get_entity(active.entity))
+        {
+            entity.insert((body, Velocity::zero()));
+        }
+        drag.active = None;
+        return;
+    }
+    let Some(ray) = picking.cursor_ray() else {
+        return;
+    };
+    let Some(distance) = ray.intersect_plane(active.plane_point, frame.up()) else {
+        return;
+    };
+    if let Ok(mut transform) = transforms.get_mut(active.entity) {
+        transform.translation = ray.get_point(distance) + active.offset;
+    }
+}
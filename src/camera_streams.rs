@@ -0,0 +1,393 @@
+use std::{
+    collections::HashMap,
+    io::Write,
+    net::{TcpListener, TcpStream},
+    num::NonZeroU32,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, SyncSender, TrySendError},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use bevy::{
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        extract_resource::{ExtractResource, ExtractResourcePlugin},
+        main_graph::node::CAMERA_DRIVER,
+        render_asset::RenderAssets,
+        render_graph::{Node, NodeRunError, RenderGraph, RenderGraphContext},
+        render_resource::{
+            Buffer, BufferDescriptor, BufferUsages, Extent3d, ImageCopyBuffer, ImageDataLayout,
+            MapMode, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+        },
+        renderer::{RenderContext, RenderDevice},
+        RenderApp, RenderSet,
+    },
+};
+use color_eyre::{eyre::WrapErr, Result};
+use wgpu::Maintain;
+
+use crate::{
+    head_cameras::{HeadCamera, HEAD_CAMERAS},
+    player::{Player, TeamColor},
+    vision_dataset::{DatasetFrame, FrameAnnotation, VisionDataset},
+    RobotLink,
+};
+
+const COPY_NODE: &str = "camera_stream_copy";
+
+/// Renders the images of the head cameras of one robot and streams them over TCP.
+///
+/// Each camera gets its own port, starting at [`CameraStreams::port`] in the order of
+/// [`HEAD_CAMERAS`]. A connected client receives raw YUV 4:2:2 (YUYV) frames of the camera's
+/// image size back to back, the same format the NAO cameras deliver.
+pub struct CameraStreamsPlugin;
+
+impl Plugin for CameraStreamsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraStreams>()
+            .init_resource::<StreamedImages>()
+            .add_plugin(ExtractResourcePlugin::<StreamedImages>::default())
+            .add_system(spawn_stream_cameras);
+
+        let render_app = app.sub_app_mut(RenderApp);
+        render_app
+            .init_resource::<StreamBuffers>()
+            .add_system(prepare_stream_buffers.in_set(RenderSet::Prepare))
+            .add_system(send_stream_frames.in_set(RenderSet::Cleanup));
+        let mut graph = render_app.world.resource_mut::<RenderGraph>();
+        graph.add_node(COPY_NODE, CopyStreamedImagesNode);
+        graph.add_node_edge(CAMERA_DRIVER, COPY_NODE);
+    }
+}
+
+#[derive(Resource)]
+pub struct CameraStreams {
+    pub team_color: TeamColor,
+    pub jersey_number: u8,
+    /// Port of the top camera, the bottom camera uses the next one
+    pub port: u16,
+}
+
+impl Default for CameraStreams {
+    fn default() -> Self {
+        Self {
+            team_color: TeamColor::Blue,
+            jersey_number: 1,
+            port: 10080,
+        }
+    }
+}
+
+#[derive(Clone, Default, Resource)]
+pub struct StreamedImages(pub Vec<StreamedImage>);
+
+impl ExtractResource for StreamedImages {
+    type Source = Self;
+
+    fn extract_resource(source: &Self::Source) -> Self {
+        source.clone()
+    }
+}
+
+#[derive(Clone)]
+pub struct StreamedImage {
+    /// Index of the camera in [`HEAD_CAMERAS`]
+    pub camera: usize,
+    /// Link the camera is mounted on
+    pub link: Entity,
+    /// Ground truth of the frame rendered next, the frame is exported to the [`VisionDataset`]
+    pub annotation: Option<Arc<FrameAnnotation>>,
+    image: Handle<Image>,
+    size: Extent3d,
+    /// Set by the server thread, images are only read back while someone watches
+    client_connected: Arc<AtomicBool>,
+    /// The mutex only makes the sender `Sync`
+    frames: Arc<Mutex<SyncSender<Vec<u8>>>>,
+}
+
+impl StreamedImage {
+    fn buffer_size(&self) -> u64 {
+        u64::from(self.size.width) * u64::from(self.size.height) * 4
+    }
+
+    /// Whether the rendered image is needed in main memory this frame.
+    fn read_back(&self) -> bool {
+        self.annotation.is_some() || self.client_connected.load(Ordering::Relaxed)
+    }
+}
+
+/// Buffers in the render world the camera images are copied into for reading them back.
+#[derive(Default, Resource)]
+struct StreamBuffers(HashMap<Handle<Image>, Buffer>);
+
+fn spawn_stream_cameras(
+    mut commands: Commands,
+    streams: Res<CameraStreams>,
+    mut streamed_images: ResMut<StreamedImages>,
+    mut images: ResMut<Assets<Image>>,
+    links: Query<(Entity, &RobotLink), Added<RobotLink>>,
+    players: Query<&Player>,
+) {
+    for (entity, link) in links.iter() {
+        let Some((index, camera)) = HEAD_CAMERAS
+            .iter()
+            .enumerate()
+            .find(|(_, camera)| camera.link == link.name)
+        else {
+            continue;
+        };
+        let Ok(player) = players.get(link.robot) else {
+            continue;
+        };
+        if player.team_color != streams.team_color || player.jersey_number != streams.jersey_number
+        {
+            continue;
+        }
+
+        let port = streams.port + index as u16;
+        let listener = match TcpListener::bind(("0.0.0.0", port))
+            .wrap_err_with(|| format!("failed to bind camera stream port {port}"))
+        {
+            Ok(listener) => listener,
+            Err(error) => {
+                error!("{error:?}");
+                continue;
+            }
+        };
+        let size = Extent3d {
+            width: camera.image_width,
+            height: camera.image_height,
+            depth_or_array_layers: 1,
+        };
+        // only the latest frame is buffered, a slow client skips frames instead of lagging behind
+        let (sender, receiver) = mpsc::sync_channel(1);
+        let client_connected = Arc::new(AtomicBool::new(false));
+        {
+            let client_connected = client_connected.clone();
+            thread::spawn(move || serve(listener, receiver, client_connected));
+        }
+        info!("Streaming {} on port {port}", camera.link);
+
+        let image = images.add(render_target(size));
+        streamed_images.0.push(StreamedImage {
+            camera: index,
+            link: entity,
+            annotation: None,
+            image: image.clone(),
+            size,
+            client_connected,
+            frames: Arc::new(Mutex::new(sender)),
+        });
+        let camera_entity = commands
+            .spawn(Camera3dBundle {
+                camera: Camera {
+                    // render the streams before the window
+                    order: -1,
+                    target: RenderTarget::Image(image),
+                    ..default()
+                },
+                projection: Projection::Perspective(PerspectiveProjection {
+                    fov: vertical_fov(camera),
+                    aspect_ratio: camera.image_width as f32 / camera.image_height as f32,
+                    ..default()
+                }),
+                // bevy cameras look along -z with y up, head cameras along x with z up
+                transform: Transform::from_rotation(Quat::from_mat3(&Mat3::from_cols(
+                    Vec3::NEG_Y,
+                    Vec3::Z,
+                    Vec3::NEG_X,
+                ))),
+                ..default()
+            })
+            .insert(Name::new(format!("{} stream", camera.link)))
+            .id();
+        commands.entity(entity).add_child(camera_entity);
+    }
+}
+
+fn render_target(size: Extent3d) -> Image {
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: None,
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rgba8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::COPY_SRC
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        ..default()
+    };
+    image.resize(size);
+    image
+}
+
+fn vertical_fov(camera: &HeadCamera) -> f32 {
+    let aspect_ratio = camera.image_height as f32 / camera.image_width as f32;
+    2.0 * ((camera.horizontal_fov / 2.0).tan() * aspect_ratio).atan()
+}
+
+fn prepare_stream_buffers(
+    device: Res<RenderDevice>,
+    streamed_images: Res<StreamedImages>,
+    mut buffers: ResMut<StreamBuffers>,
+) {
+    for streamed_image in &streamed_images.0 {
+        buffers
+            .0
+            .entry(streamed_image.image.clone())
+            .or_insert_with(|| {
+                device.create_buffer(&BufferDescriptor {
+                    label: Some("camera stream buffer"),
+                    size: streamed_image.buffer_size(),
+                    usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+                    mapped_at_creation: false,
+                })
+            });
+    }
+}
+
+/// Copies the rendered camera images into their read back buffers after all cameras rendered.
+struct CopyStreamedImagesNode;
+
+impl Node for CopyStreamedImagesNode {
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let gpu_images = world.resource::<RenderAssets<Image>>();
+        let buffers = world.resource::<StreamBuffers>();
+        for streamed_image in &world.resource::<StreamedImages>().0 {
+            if !streamed_image.read_back() {
+                continue;
+            }
+            let (Some(gpu_image), Some(buffer)) = (
+                gpu_images.get(&streamed_image.image),
+                buffers.0.get(&streamed_image.image),
+            ) else {
+                continue;
+            };
+            // rows of 4 byte pixels, the image widths of the NAO cameras satisfy the 256 byte
+            // row alignment of texture copies
+            render_context.command_encoder().copy_texture_to_buffer(
+                gpu_image.texture.as_image_copy(),
+                ImageCopyBuffer {
+                    buffer,
+                    layout: ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: NonZeroU32::new(streamed_image.size.width * 4),
+                        rows_per_image: None,
+                    },
+                },
+                streamed_image.size,
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Reads the copied images back and hands them to the server threads and the dataset writer.
+///
+/// Waits for the GPU to finish the frame, which costs frame rate while a client is connected.
+fn send_stream_frames(
+    device: Res<RenderDevice>,
+    streamed_images: Res<StreamedImages>,
+    buffers: Res<StreamBuffers>,
+    dataset: Option<Res<VisionDataset>>,
+) {
+    for streamed_image in &streamed_images.0 {
+        if !streamed_image.read_back() {
+            continue;
+        }
+        let Some(buffer) = buffers.0.get(&streamed_image.image) else {
+            continue;
+        };
+        let slice = buffer.slice(..);
+        device.map_buffer(&slice, MapMode::Read, |result| {
+            if let Err(error) = result {
+                error!("Failed to map camera stream buffer: {error}");
+            }
+        });
+        device.poll(Maintain::Wait);
+        let rgba = slice.get_mapped_range().to_vec();
+        buffer.unmap();
+        if let (Some(annotation), Some(dataset)) = (&streamed_image.annotation, &dataset) {
+            dataset.send(DatasetFrame {
+                rgba: rgba.clone(),
+                annotation: annotation.clone(),
+            });
+        }
+        if !streamed_image.client_connected.load(Ordering::Relaxed) {
+            continue;
+        }
+        match streamed_image.frames.lock().unwrap().try_send(rgba) {
+            Ok(()) | Err(TrySendError::Full(_)) => {}
+            Err(TrySendError::Disconnected(_)) => error!("Camera stream server stopped"),
+        }
+    }
+}
+
+fn serve(listener: TcpListener, frames: Receiver<Vec<u8>>, client_connected: Arc<AtomicBool>) {
+    for stream in listener.incoming() {
+        let mut stream = match stream {
+            Ok(stream) => stream,
+            Err(error) => {
+                error!("Failed to accept camera stream client: {error}");
+                continue;
+            }
+        };
+        info!("Camera stream client connected");
+        client_connected.store(true, Ordering::Relaxed);
+        let result = handle_client(&mut stream, &frames);
+        client_connected.store(false, Ordering::Relaxed);
+        match result {
+            Ok(()) => return,
+            Err(error) => warn!("Camera stream client disconnected: {error:?}"),
+        }
+    }
+}
+
+/// Sends frames to a client until it disconnects, returns `Ok` when the simulation quits.
+fn handle_client(stream: &mut TcpStream, frames: &Receiver<Vec<u8>>) -> Result<()> {
+    // a frame rendered before the client connected is outdated
+    while frames.try_recv().is_ok() {}
+    loop {
+        let Ok(rgba) = frames.recv() else {
+            return Ok(());
+        };
+        stream
+            .write_all(&rgba_to_yuyv(&rgba))
+            .wrap_err("failed to send camera frame")?;
+    }
+}
+
+/// Converts sRGB pixels to YUV 4:2:2 with full range BT.601 coefficients, sharing the chroma of
+/// each horizontal pixel pair.
+fn rgba_to_yuyv(rgba: &[u8]) -> Vec<u8> {
+    let mut yuyv = Vec::with_capacity(rgba.len() / 2);
+    for pair in rgba.chunks_exact(8) {
+        let (left, right) = pair.split_at(4);
+        let luminance = |pixel: &[u8]| {
+            0.299 * f32::from(pixel[0]) + 0.587 * f32::from(pixel[1]) + 0.114 * f32::from(pixel[2])
+        };
+        let y0 = luminance(left);
+        let y1 = luminance(right);
+        let red = (f32::from(left[0]) + f32::from(right[0])) / 2.0;
+        let blue = (f32::from(left[2]) + f32::from(right[2])) / 2.0;
+        let y = (y0 + y1) / 2.0;
+        let u = 128.0 + 0.564 * (blue - y);
+        let v = 128.0 + 0.713 * (red - y);
+        yuyv.extend([y0, u, y1, v].map(|value| value.round().clamp(0.0, 255.0) as u8));
+    }
+    yuyv
+}
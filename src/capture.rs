@@ -0,0 +1,560 @@
+use std::{
+    fs,
+    io::Write,
+    num::NonZeroU32,
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    sync::{
+        mpsc::{self, Receiver, SyncSender},
+        Arc, Mutex,
+    },
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        extract_resource::{ExtractResource, ExtractResourcePlugin},
+        main_graph::node::CAMERA_DRIVER,
+        render_asset::RenderAssets,
+        render_graph::{Node, NodeRunError, RenderGraph, RenderGraphContext},
+        render_resource::{
+            Buffer, BufferDescriptor, BufferUsages, Extent3d, ImageCopyBuffer, ImageDataLayout,
+            MapMode, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+        },
+        renderer::{RenderContext, RenderDevice},
+        RenderApp, RenderSet,
+    },
+    transform::TransformSystem,
+};
+use color_eyre::{
+    eyre::{eyre, WrapErr},
+    Result,
+};
+use wgpu::Maintain;
+
+use crate::{
+    pan_orbit_camera::PanOrbitCamera,
+    shortcuts::{dispatch_shortcuts, ShortcutAction},
+    simulation_time::{run_physics_schedule, SimulationTime, PHYSICS_TIMESTEP},
+};
+
+const COPY_NODE: &str = "capture_copy";
+/// Directory screenshots and videos started by shortcut are written to
+const CAPTURE_DIRECTORY: &str = "captures";
+/// Captured frames waiting to be written, sending more blocks rendering until the writer catches
+/// up so no video frame is dropped
+const QUEUED_FRAMES: usize = 4;
+
+/// Captures the view of the main camera to PNG screenshots and videos.
+///
+/// Videos are captured at a fixed rate of simulated time instead of real time: while recording,
+/// the simulation advances by one step per rendered frame and every frame due is captured, so
+/// videos play smoothly however fast or slow the machine simulates. A video is written as PNG
+/// image sequence into a directory, or piped through `ffmpeg` if its path has a video file
+/// extension.
+pub struct CapturePlugin;
+
+impl Plugin for CapturePlugin {
+    fn build(&self, app: &mut App) {
+        let (sender, receiver) = mpsc::sync_channel(QUEUED_FRAMES);
+        thread::spawn(move || write_captures(receiver));
+
+        app.init_resource::<Capture>()
+            .init_resource::<CaptureCommands>()
+            .add_event::<StartVideo>()
+            .add_plugin(ExtractResourcePlugin::<CaptureCommands>::default())
+            .add_startup_system(spawn_capture_camera)
+            .add_system(clear_capture_commands.in_base_set(CoreSet::First))
+            .add_system(capture_shortcuts.after(dispatch_shortcuts))
+            .add_system(start_videos.after(capture_shortcuts))
+            .add_system(
+                schedule_captures
+                    .in_base_set(CoreSet::PostUpdate)
+                    .after(run_physics_schedule)
+                    .before(TransformSystem::TransformPropagate),
+            );
+
+        let render_app = app.sub_app_mut(RenderApp);
+        render_app
+            .insert_resource(CaptureWriter(Mutex::new(sender)))
+            .init_resource::<CaptureBuffer>()
+            .add_system(prepare_capture_buffer.in_set(RenderSet::Prepare))
+            .add_system(send_captures.in_set(RenderSet::Cleanup));
+        let mut graph = render_app.world.resource_mut::<RenderGraph>();
+        graph.add_node(COPY_NODE, CopyCaptureNode);
+        graph.add_node_edge(CAMERA_DRIVER, COPY_NODE);
+    }
+}
+
+#[derive(Resource)]
+pub struct Capture {
+    /// Size of screenshots and video frames in pixels, the width must be a multiple of 64
+    pub resolution: UVec2,
+    /// Frames per simulated second of videos
+    pub video_fps: f32,
+    video: Option<RecordingVideo>,
+}
+
+impl Default for Capture {
+    fn default() -> Self {
+        Self {
+            resolution: UVec2::new(1920, 1080),
+            video_fps: 30.0,
+            video: None,
+        }
+    }
+}
+
+struct RecordingVideo {
+    /// Simulated seconds of the next frame
+    next_frame_time: f64,
+    /// Restored when the recording stops
+    was_free_running: bool,
+}
+
+/// Starts recording a video of the main camera to the path.
+pub struct StartVideo(pub PathBuf);
+
+#[derive(Clone)]
+enum CaptureCommand {
+    Screenshot(PathBuf),
+    StartVideo { path: PathBuf, fps: f32 },
+    VideoFrame,
+    StopVideo,
+}
+
+/// Captures of the current frame, executed in order by the render world.
+#[derive(Clone, Default, Resource)]
+struct CaptureCommands {
+    image: Handle<Image>,
+    size: Extent3d,
+    commands: Vec<CaptureCommand>,
+}
+
+impl CaptureCommands {
+    fn captures_frame(&self) -> bool {
+        self.commands.iter().any(|command| {
+            matches!(
+                command,
+                CaptureCommand::Screenshot(_) | CaptureCommand::VideoFrame
+            )
+        })
+    }
+}
+
+impl ExtractResource for CaptureCommands {
+    type Source = Self;
+
+    fn extract_resource(source: &Self::Source) -> Self {
+        source.clone()
+    }
+}
+
+/// Follows the main camera and renders into an image only in frames that are captured.
+#[derive(Component)]
+struct CaptureCamera;
+
+fn spawn_capture_camera(
+    mut commands: Commands,
+    capture: Res<Capture>,
+    mut capture_commands: ResMut<CaptureCommands>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let size = Extent3d {
+        width: capture.resolution.x,
+        height: capture.resolution.y,
+        depth_or_array_layers: 1,
+    };
+    let image = images.add(render_target(size));
+    capture_commands.image = image.clone();
+    capture_commands.size = size;
+    commands
+        .spawn(Camera3dBundle {
+            camera: Camera {
+                order: -1,
+                target: RenderTarget::Image(image),
+                is_active: false,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(CaptureCamera)
+        .insert(Name::new("capture camera"));
+}
+
+fn render_target(size: Extent3d) -> Image {
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: None,
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rgba8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::COPY_SRC
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        ..default()
+    };
+    image.resize(size);
+    image
+}
+
+fn clear_capture_commands(mut capture_commands: ResMut<CaptureCommands>) {
+    capture_commands.commands.clear();
+}
+
+fn capture_shortcuts(
+    mut actions: EventReader<ShortcutAction>,
+    mut capture: ResMut<Capture>,
+    mut simulation_time: ResMut<SimulationTime>,
+    mut capture_commands: ResMut<CaptureCommands>,
+    mut videos: EventWriter<StartVideo>,
+) {
+    for action in actions.iter() {
+        match action {
+            ShortcutAction::Screenshot => {
+                let path = timestamped_path("screenshot", "png");
+                info!("Saving screenshot to {}", path.display());
+                capture_commands
+                    .commands
+                    .push(CaptureCommand::Screenshot(path));
+            }
+            ShortcutAction::ToggleVideoRecording => match capture.video.take() {
+                Some(video) => {
+                    info!("Stopped recording video");
+                    simulation_time.free_running = video.was_free_running;
+                    capture_commands.commands.push(CaptureCommand::StopVideo);
+                }
+                None => videos.send(StartVideo(timestamped_path("video", ""))),
+            },
+            _ => {}
+        }
+    }
+}
+
+fn start_videos(
+    mut requests: EventReader<StartVideo>,
+    mut capture: ResMut<Capture>,
+    mut simulation_time: ResMut<SimulationTime>,
+    mut capture_commands: ResMut<CaptureCommands>,
+) {
+    for StartVideo(path) in requests.iter() {
+        if capture.video.is_some() {
+            capture_commands.commands.push(CaptureCommand::StopVideo);
+        }
+        info!("Recording video to {}", path.display());
+        capture.video = Some(RecordingVideo {
+            next_frame_time: simulation_time.elapsed_seconds(),
+            was_free_running: capture
+                .video
+                .as_ref()
+                .map_or(simulation_time.free_running, |video| video.was_free_running),
+        });
+        simulation_time.free_running = true;
+        capture_commands.commands.push(CaptureCommand::StartVideo {
+            path: path.clone(),
+            fps: capture.video_fps,
+        });
+    }
+}
+
+/// Decides whether the current frame is captured and points the capture camera like the main
+/// camera.
+fn schedule_captures(
+    simulation_time: Res<SimulationTime>,
+    mut capture: ResMut<Capture>,
+    mut capture_commands: ResMut<CaptureCommands>,
+    main_cameras: Query<(&Transform, &Projection), (With<PanOrbitCamera>, Without<CaptureCamera>)>,
+    mut capture_cameras: Query<(&mut Camera, &mut Transform, &mut Projection), With<CaptureCamera>>,
+) {
+    let video_fps = capture.video_fps;
+    if let Some(video) = &mut capture.video {
+        let time = simulation_time.elapsed_seconds();
+        // a time step of a few frames is captured once, frames are not duplicated
+        if time >= video.next_frame_time {
+            let frame_duration = 1.0 / f64::from(video_fps);
+            video.next_frame_time = (video.next_frame_time + frame_duration)
+                .max(time + frame_duration - f64::from(PHYSICS_TIMESTEP) / 2.0);
+            capture_commands.commands.push(CaptureCommand::VideoFrame);
+        }
+    }
+
+    let Ok((mut camera, mut transform, mut projection)) = capture_cameras.get_single_mut() else {
+        return;
+    };
+    camera.is_active = capture_commands.captures_frame();
+    let Ok((main_transform, main_projection)) = main_cameras.get_single() else {
+        return;
+    };
+    *transform = *main_transform;
+    // the aspect ratio follows the capture resolution, only the field of view is copied
+    if let (Projection::Perspective(main), Projection::Perspective(captured)) =
+        (main_projection, &mut *projection)
+    {
+        if captured.fov != main.fov {
+            captured.fov = main.fov;
+        }
+    }
+}
+
+pub fn timestamped_path(prefix: &str, extension: &str) -> PathBuf {
+    let milliseconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let mut path = Path::new(CAPTURE_DIRECTORY).join(format!("{prefix}_{milliseconds}"));
+    if !extension.is_empty() {
+        path.set_extension(extension);
+    }
+    path
+}
+
+/// Buffer in the render world the capture image is copied into for reading it back.
+#[derive(Default, Resource)]
+struct CaptureBuffer(Option<Buffer>);
+
+#[derive(Resource)]
+struct CaptureWriter(Mutex<SyncSender<WriterMessage>>);
+
+fn prepare_capture_buffer(
+    device: Res<RenderDevice>,
+    capture_commands: Res<CaptureCommands>,
+    mut buffer: ResMut<CaptureBuffer>,
+) {
+    if buffer.0.is_some() || capture_commands.size.width == 0 {
+        return;
+    }
+    let size = capture_commands.size;
+    buffer.0 = Some(device.create_buffer(&BufferDescriptor {
+        label: Some("capture buffer"),
+        size: u64::from(size.width) * u64::from(size.height) * 4,
+        usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    }));
+}
+
+/// Copies the rendered capture image into the read back buffer after all cameras rendered.
+struct CopyCaptureNode;
+
+impl Node for CopyCaptureNode {
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let capture_commands = world.resource::<CaptureCommands>();
+        if !capture_commands.captures_frame() {
+            return Ok(());
+        }
+        let gpu_images = world.resource::<RenderAssets<Image>>();
+        let (Some(gpu_image), Some(buffer)) = (
+            gpu_images.get(&capture_commands.image),
+            &world.resource::<CaptureBuffer>().0,
+        ) else {
+            return Ok(());
+        };
+        render_context.command_encoder().copy_texture_to_buffer(
+            gpu_image.texture.as_image_copy(),
+            ImageCopyBuffer {
+                buffer,
+                layout: ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(capture_commands.size.width * 4),
+                    rows_per_image: None,
+                },
+            },
+            capture_commands.size,
+        );
+        Ok(())
+    }
+}
+
+/// Reads the captured frame back and hands the commands to the writer thread.
+fn send_captures(
+    device: Res<RenderDevice>,
+    capture_commands: Res<CaptureCommands>,
+    buffer: Res<CaptureBuffer>,
+    writer: Res<CaptureWriter>,
+) {
+    let rgba = match (&buffer.0, capture_commands.captures_frame()) {
+        (Some(buffer), true) => {
+            let slice = buffer.slice(..);
+            device.map_buffer(&slice, MapMode::Read, |result| {
+                if let Err(error) = result {
+                    error!("Failed to map capture buffer: {error}");
+                }
+            });
+            device.poll(Maintain::Wait);
+            let rgba = slice.get_mapped_range().to_vec();
+            buffer.unmap();
+            Arc::new(rgba)
+        }
+        _ => Arc::default(),
+    };
+    let size = UVec2::new(capture_commands.size.width, capture_commands.size.height);
+    let writer = writer.0.lock().unwrap();
+    for command in &capture_commands.commands {
+        let message = match command.clone() {
+            CaptureCommand::Screenshot(path) => WriterMessage::Screenshot {
+                path,
+                size,
+                rgba: rgba.clone(),
+            },
+            CaptureCommand::StartVideo { path, fps } => {
+                WriterMessage::StartVideo { path, size, fps }
+            }
+            CaptureCommand::VideoFrame => WriterMessage::VideoFrame(rgba.clone()),
+            CaptureCommand::StopVideo => WriterMessage::StopVideo,
+        };
+        if writer.send(message).is_err() {
+            error!("Capture writer stopped");
+        }
+    }
+}
+
+enum WriterMessage {
+    Screenshot {
+        path: PathBuf,
+        size: UVec2,
+        rgba: Arc<Vec<u8>>,
+    },
+    StartVideo {
+        path: PathBuf,
+        size: UVec2,
+        fps: f32,
+    },
+    VideoFrame(Arc<Vec<u8>>),
+    StopVideo,
+}
+
+enum VideoWriter {
+    ImageSequence {
+        directory: PathBuf,
+        size: UVec2,
+        frames: u64,
+    },
+    Ffmpeg(Child),
+}
+
+impl VideoWriter {
+    fn start(path: &Path, size: UVec2, fps: f32) -> Result<Self> {
+        if path.extension().is_none() {
+            fs::create_dir_all(path)
+                .wrap_err_with(|| format!("failed to create {}", path.display()))?;
+            return Ok(Self::ImageSequence {
+                directory: path.to_path_buf(),
+                size,
+                frames: 0,
+            });
+        }
+        create_parent_directory(path)?;
+        let child = Command::new("ffmpeg")
+            .args([
+                "-y",
+                "-loglevel",
+                "error",
+                "-f",
+                "rawvideo",
+                "-pix_fmt",
+                "rgba",
+            ])
+            .args(["-s", &format!("{}x{}", size.x, size.y)])
+            .args(["-r", &fps.to_string(), "-i", "-", "-pix_fmt", "yuv420p"])
+            .arg(path)
+            .stdin(Stdio::piped())
+            .spawn()
+            .wrap_err("failed to start ffmpeg")?;
+        Ok(Self::Ffmpeg(child))
+    }
+
+    fn write_frame(&mut self, rgba: &[u8]) -> Result<()> {
+        match self {
+            Self::ImageSequence {
+                directory,
+                size,
+                frames,
+            } => {
+                *frames += 1;
+                save_png(&directory.join(format!("{frames:06}.png")), *size, rgba)
+            }
+            Self::Ffmpeg(child) => child
+                .stdin
+                .as_mut()
+                .ok_or_else(|| eyre!("ffmpeg has no input"))?
+                .write_all(rgba)
+                .wrap_err("failed to pipe frame to ffmpeg"),
+        }
+    }
+
+    fn finish(self) -> Result<()> {
+        if let Self::Ffmpeg(mut child) = self {
+            // closing the input lets ffmpeg finish the file
+            drop(child.stdin.take());
+            let status = child.wait().wrap_err("failed to wait for ffmpeg")?;
+            if !status.success() {
+                return Err(eyre!("ffmpeg failed with {status}"));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_captures(messages: Receiver<WriterMessage>) {
+    let mut video: Option<VideoWriter> = None;
+    for message in messages {
+        let result = match message {
+            WriterMessage::Screenshot { path, size, rgba } => save_png(&path, size, &rgba),
+            WriterMessage::StartVideo { path, size, fps } => {
+                VideoWriter::start(&path, size, fps).map(|writer| video = Some(writer))
+            }
+            WriterMessage::VideoFrame(rgba) => match &mut video {
+                Some(writer) => writer.write_frame(&rgba),
+                None => Ok(()),
+            },
+            WriterMessage::StopVideo => video.take().map_or(Ok(()), VideoWriter::finish),
+        };
+        if let Err(error) = result {
+            error!("{error:?}");
+        }
+    }
+    if let Some(writer) = video {
+        if let Err(error) = writer.finish() {
+            error!("{error:?}");
+        }
+    }
+}
+
+pub fn save_png(path: &Path, size: UVec2, rgba: &[u8]) -> Result<()> {
+    create_parent_directory(path)?;
+    let image = Image::new(
+        Extent3d {
+            width: size.x,
+            height: size.y,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        rgba.to_vec(),
+        TextureFormat::Rgba8UnormSrgb,
+    );
+    image
+        .try_into_dynamic()
+        .map_err(|error| eyre!("{error}"))?
+        .save(path)
+        .wrap_err_with(|| format!("failed to save {}", path.display()))
+}
+
+pub fn create_parent_directory(path: &Path) -> Result<()> {
+    match path.parent() {
+        Some(directory) if !directory.as_os_str().is_empty() => fs::create_dir_all(directory)
+            .wrap_err_with(|| format!("failed to create {}", directory.display())),
+        _ => Ok(()),
+    }
+}
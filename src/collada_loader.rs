@@ -0,0 +1,116 @@
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    render::render_resource::PrimitiveTopology,
+    utils::BoxedFuture,
+};
+use collada::{document::ColladaDocument, PrimitiveElement, Shape, VTNIndex};
+
+/// Loads Collada (.dae) files as meshes, so URDF visuals exported as Collada are rendered.
+///
+/// All geometries of a file are merged into one mesh in the coordinates of their geometry, the
+/// transforms of the scene nodes, the unit and the up axis of the file are not applied.
+pub struct ColladaPlugin;
+
+impl Plugin for ColladaPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset_loader::<ColladaLoader>();
+    }
+}
+
+#[derive(Default)]
+struct ColladaLoader;
+
+impl AssetLoader for ColladaLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let text = std::str::from_utf8(bytes)?;
+            let document = ColladaDocument::from_str(text).map_err(bevy::asset::Error::msg)?;
+            let mesh = mesh_from_document(&document)
+                .ok_or_else(|| bevy::asset::Error::msg("Collada file contains no geometry"))?;
+            load_context.set_default_asset(LoadedAsset::new(mesh));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["dae"]
+    }
+}
+
+/// Merges all triangles of the geometries in `document` into one unindexed mesh.
+pub fn mesh_from_document(document: &ColladaDocument) -> Option<Mesh> {
+    let object_set = document.get_obj_set()?;
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    let mut has_normals = true;
+
+    for object in &object_set.objects {
+        let triangles = object
+            .geometry
+            .iter()
+            .flat_map(|geometry| geometry.mesh.iter())
+            .flat_map(triangles);
+        for triangle in triangles {
+            for (vertex, uv, normal) in triangle {
+                let vertex = object.vertices.get(vertex)?;
+                positions.push([vertex.x as f32, vertex.y as f32, vertex.z as f32]);
+                let uv = uv.and_then(|uv| object.tex_vertices.get(uv));
+                // Collada texture coordinates start at the bottom, bevy's at the top
+                uvs.push(uv.map_or([0.0, 0.0], |uv| [uv.x as f32, 1.0 - uv.y as f32]));
+                match normal.and_then(|normal| object.normals.get(normal)) {
+                    Some(normal) => {
+                        normals.push([normal.x as f32, normal.y as f32, normal.z as f32])
+                    }
+                    None => has_normals = false,
+                }
+            }
+        }
+    }
+    if positions.is_empty() {
+        return None;
+    }
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    if has_normals {
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    } else {
+        mesh.compute_flat_normals();
+    }
+    Some(mesh)
+}
+
+/// Triangles of a primitive, points and lines are skipped.
+fn triangles(primitive: &PrimitiveElement) -> Vec<[VTNIndex; 3]> {
+    match primitive {
+        PrimitiveElement::Triangles(triangles) => triangles
+            .vertices
+            .iter()
+            .enumerate()
+            .map(|(index, &(a, b, c))| {
+                let uvs = triangles.tex_vertices.as_ref().map(|uvs| uvs[index]);
+                let normals = triangles.normals.as_ref().map(|normals| normals[index]);
+                [
+                    (a, uvs.map(|uvs| uvs.0), normals.map(|normals| normals.0)),
+                    (b, uvs.map(|uvs| uvs.1), normals.map(|normals| normals.1)),
+                    (c, uvs.map(|uvs| uvs.2), normals.map(|normals| normals.2)),
+                ]
+            })
+            .collect(),
+        PrimitiveElement::Polylist(polylist) => polylist
+            .shapes
+            .iter()
+            .filter_map(|shape| match *shape {
+                Shape::Triangle(a, b, c) => Some([a, b, c]),
+                _ => None,
+            })
+            .collect(),
+    }
+}
@@ -0,0 +1,63 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::shortcuts::{triggered, ShortcutAction};
+
+/// Tints the debug rendering of colliders by their collision group membership.
+pub struct CollisionGroupColorsPlugin;
+
+impl Plugin for CollisionGroupColorsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CollisionGroupColors>()
+            .add_system(toggle_collision_group_colors)
+            .add_system(update_collider_colors.after(toggle_collision_group_colors));
+    }
+}
+
+#[derive(Default, Resource)]
+pub struct CollisionGroupColors {
+    pub enabled: bool,
+}
+
+fn toggle_collision_group_colors(
+    mut actions: EventReader<ShortcutAction>,
+    mut settings: ResMut<CollisionGroupColors>,
+) {
+    if triggered(&mut actions, ShortcutAction::ToggleCollisionGroupColors) {
+        settings.enabled = !settings.enabled;
+    }
+}
+
+fn update_collider_colors(
+    mut commands: Commands,
+    settings: Res<CollisionGroupColors>,
+    colliders: Query<(Entity, Ref<CollisionGroups>)>,
+) {
+    for (entity, groups) in colliders.iter() {
+        if !settings.is_changed() && !groups.is_changed() {
+            continue;
+        }
+        if settings.enabled {
+            commands
+                .entity(entity)
+                .insert(ColliderDebugColor(group_color(groups.memberships)));
+        } else {
+            commands.entity(entity).remove::<ColliderDebugColor>();
+        }
+    }
+}
+
+/// Field, robot links, ball and obstacles each get a distinct color, anything else is grey.
+fn group_color(memberships: Group) -> Color {
+    if memberships.contains(Group::GROUP_1) {
+        Color::GREEN
+    } else if memberships.contains(Group::GROUP_2) {
+        Color::BLUE
+    } else if memberships.contains(Group::GROUP_3) {
+        Color::ORANGE
+    } else if memberships.contains(Group::GROUP_4) {
+        Color::FUCHSIA
+    } else {
+        Color::GRAY
+    }
+}
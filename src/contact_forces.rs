@@ -0,0 +1,182 @@
+use bevy::{
+    prelude::*,
+    render::{mesh::PrimitiveTopology, view::NoFrustumCulling},
+    transform::TransformSystem,
+};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    shortcuts::{triggered, ShortcutAction},
+    simulation_time::PHYSICS_TIMESTEP,
+    Field, RobotLink, RobotRoot,
+};
+
+/// Length of the arrow heads in meters
+const HEAD_LENGTH: f32 = 0.01;
+
+/// Draws an arrow for every contact between a robot and the field, pointing along the normal
+/// force the field exerts on the robot.
+///
+/// Arrows scale with the force and fade from green to red towards the
+/// [`ContactForces::maximum_force`], so the weight moving between the feet is visible while
+/// balancing.
+pub struct ContactForcesPlugin;
+
+impl Plugin for ContactForcesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ContactForces>()
+            .add_startup_system(spawn_contact_arrows)
+            .add_system(toggle_contact_forces)
+            .add_system(
+                update_contact_arrows
+                    .in_base_set(CoreSet::PostUpdate)
+                    .after(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+#[derive(Resource)]
+pub struct ContactForces {
+    pub enabled: bool,
+    /// Arrow length per force in m/N
+    pub scale: f32,
+    /// Force drawn fully red in newtons
+    pub maximum_force: f32,
+}
+
+impl Default for ContactForces {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            scale: 0.005,
+            maximum_force: 30.0,
+        }
+    }
+}
+
+#[derive(Component)]
+struct ContactArrows;
+
+fn spawn_contact_arrows(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    // start with a degenerate line, the arrows are computed every frame
+    let mut mesh = Mesh::new(PrimitiveTopology::LineList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, vec![[0.0; 3]; 2]);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, vec![[0.0; 4]; 2]);
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(mesh),
+            material: materials.add(StandardMaterial {
+                base_color: Color::WHITE,
+                unlit: true,
+                ..Default::default()
+            }),
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        },
+        // the bounding box is only computed for the initial mesh
+        NoFrustumCulling,
+        ContactArrows,
+        Name::new("contact force arrows"),
+    ));
+}
+
+fn toggle_contact_forces(
+    mut actions: EventReader<ShortcutAction>,
+    mut settings: ResMut<ContactForces>,
+) {
+    if triggered(&mut actions, ShortcutAction::ToggleContactForces) {
+        settings.enabled = !settings.enabled;
+    }
+}
+
+fn update_contact_arrows(
+    settings: Res<ContactForces>,
+    context: Res<RapierContext>,
+    fields: Query<Entity, With<Field>>,
+    robots: Query<Entity, With<RobotRoot>>,
+    children: Query<&Children>,
+    links: Query<&GlobalTransform, With<RobotLink>>,
+    mut arrows: Query<(&Handle<Mesh>, &mut Visibility), With<ContactArrows>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let Ok((mesh, mut visibility)) = arrows.get_single_mut() else {
+        return;
+    };
+    if !settings.enabled {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+
+    let mut positions = Vec::new();
+    let mut colors = Vec::new();
+    for robot in robots.iter() {
+        for link in std::iter::once(robot).chain(children.iter_descendants(robot)) {
+            let Ok(transform) = links.get(link) else {
+                continue;
+            };
+            for field in fields.iter() {
+                let Some(pair) = context.contact_pair(link, field) else {
+                    continue;
+                };
+                let link_is_first = pair.collider1() == link;
+                for manifold in pair.manifolds() {
+                    // the normals point out of the link, the force pushes it away from the field
+                    let normal = if link_is_first {
+                        manifold.local_n1()
+                    } else {
+                        manifold.local_n2()
+                    };
+                    let direction = -(transform.affine().matrix3 * normal).normalize_or_zero();
+                    for contact in manifold.points() {
+                        let local_point = if link_is_first {
+                            contact.local_p1()
+                        } else {
+                            contact.local_p2()
+                        };
+                        let force = contact.impulse() / PHYSICS_TIMESTEP;
+                        if force <= 0.0 {
+                            continue;
+                        }
+                        let start = transform.transform_point(local_point);
+                        let color = force_color(force / settings.maximum_force);
+                        for (from, to) in arrow(start, direction * force * settings.scale) {
+                            positions.extend([from.to_array(), to.to_array()]);
+                            colors.extend([color; 2]);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    if positions.is_empty() {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+    *visibility = Visibility::Inherited;
+    if let Some(mesh) = meshes.get_mut(mesh) {
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    }
+}
+
+/// Shaft and four head lines of an arrow from `start` along `vector`.
+fn arrow(start: Vec3, vector: Vec3) -> Vec<(Vec3, Vec3)> {
+    let end = start + vector;
+    let direction = vector.normalize_or_zero();
+    let side = direction.any_orthonormal_vector() * HEAD_LENGTH * 0.5;
+    let up = direction.cross(side);
+    let head_base = end - direction * HEAD_LENGTH;
+    let mut lines = vec![(start, end)];
+    lines.extend([side, -side, up, -up].map(|offset| (end, head_base + offset)));
+    lines
+}
+
+/// Green for no force, red at `ratio` one and beyond.
+fn force_color(ratio: f32) -> [f32; 4] {
+    let ratio = ratio.clamp(0.0, 1.0);
+    Color::rgb(ratio, 1.0 - ratio, 0.0).as_linear_rgba_f32()
+}
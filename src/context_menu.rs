@@ -0,0 +1,182 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_egui::{egui, EguiContexts};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    pan_orbit_camera::PanOrbitCamera,
+    picking::Picking,
+    player::RobotStatus,
+    scene_reset::SpawnPose,
+    selection::{selectable_entity, Selection},
+    RobotRoot,
+};
+
+/// Maximum cursor movement in pixels between pressing and releasing the right mouse button for
+/// the press to count as a click instead of orbiting the camera
+const CLICK_TOLERANCE: f32 = 4.0;
+
+/// Right-clicking an entity in the viewport selects it and opens a menu with actions for it.
+pub struct ContextMenuPlugin;
+
+impl Plugin for ContextMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ContextMenu>()
+            .add_system(open_context_menu)
+            .add_system(context_menu_ui.after(open_context_menu));
+    }
+}
+
+#[derive(Default, Resource)]
+struct ContextMenu {
+    press_position: Option<Vec2>,
+    target: Option<Entity>,
+    position: egui::Pos2,
+    just_opened: bool,
+}
+
+/// Body that was dynamic before it got frozen.
+#[derive(Component)]
+struct Frozen;
+
+#[allow(clippy::too_many_arguments)]
+fn open_context_menu(
+    mut contexts: EguiContexts,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mouse: Res<Input<MouseButton>>,
+    picking: Picking,
+    parents: Query<&Parent>,
+    robots: Query<(), With<RobotRoot>>,
+    mut selection: ResMut<Selection>,
+    mut menu: ResMut<ContextMenu>,
+) {
+    let cursor = windows
+        .get_single()
+        .ok()
+        .and_then(|window| window.cursor_position());
+    if mouse.just_pressed(MouseButton::Right) {
+        menu.press_position = cursor.filter(|_| !contexts.ctx_mut().is_pointer_over_area());
+    }
+    if !mouse.just_released(MouseButton::Right) {
+        return;
+    }
+    let Some(press_position) = menu.press_position.take() else {
+        return;
+    };
+    if cursor.map_or(true, |cursor| {
+        cursor.distance(press_position) > CLICK_TOLERANCE
+    }) {
+        return;
+    }
+    let Some(pointer) = contexts.ctx_mut().pointer_latest_pos() else {
+        return;
+    };
+    let target = picking
+        .pick(QueryFilter::default())
+        .map(|(picked, _)| selectable_entity(picked, &parents, &robots));
+    if let Some(target) = target {
+        selection.entity = Some(target);
+        menu.position = pointer;
+        menu.just_opened = true;
+    }
+    menu.target = target;
+}
+
+#[allow(clippy::too_many_arguments)]
+fn context_menu_ui(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    mut menu: ResMut<ContextMenu>,
+    names: Query<&Name>,
+    children: Query<&Children>,
+    bodies: Query<(&RigidBody, Option<&Frozen>)>,
+    spawn_poses: Query<&SpawnPose>,
+    mut robots: Query<&mut RobotStatus>,
+    mut cameras: Query<&mut PanOrbitCamera>,
+) {
+    let Some(target) = menu.target else {
+        return;
+    };
+    let tree: Vec<_> = std::iter::once(target)
+        .chain(children.iter_descendants(target))
+        .collect();
+    let frozen = tree
+        .iter()
+        .any(|&entity| matches!(bodies.get(entity), Ok((_, Some(_)))));
+    let title = names
+        .get(target)
+        .map_or_else(|_| format!("{target:?}"), |name| name.to_string());
+
+    let response = egui::Area::new("context menu")
+        .fixed_pos(menu.position)
+        .order(egui::Order::Foreground)
+        .show(contexts.ctx_mut(), |ui| {
+            egui::Frame::menu(ui.style())
+                .show(ui, |ui| {
+                    ui.label(title);
+                    ui.separator();
+                    if ui.button("Delete").clicked() {
+                        commands.entity(target).despawn_recursive();
+                        return true;
+                    }
+                    if ui
+                        .button(if frozen { "Unfreeze" } else { "Freeze" })
+                        .clicked()
+                    {
+                        for &entity in &tree {
+                            match bodies.get(entity) {
+                                Ok((_, Some(_))) => {
+                                    commands
+                                        .entity(entity)
+                                        .insert(RigidBody::Dynamic)
+                                        .remove::<Frozen>();
+                                }
+                                Ok((RigidBody::Dynamic, None)) if !frozen => {
+                                    commands.entity(entity).insert((
+                                        RigidBody::Fixed,
+                                        Frozen,
+                                        Velocity::zero(),
+                                    ));
+                                }
+                                _ => {}
+                            }
+                        }
+                        return true;
+                    }
+                    if let Ok(SpawnPose(pose)) = spawn_poses.get(target) {
+                        if ui.button("Reset pose").clicked() {
+                            commands.entity(target).insert(*pose);
+                            for &entity in &tree {
+                                if bodies.contains(entity) {
+                                    commands.entity(entity).insert(Velocity::zero());
+                                }
+                            }
+                            return true;
+                        }
+                    }
+                    if ui.button("Follow with camera").clicked() {
+                        for mut camera in cameras.iter_mut() {
+                            camera.follow = Some(target);
+                        }
+                        return true;
+                    }
+                    if let Ok(mut status) = robots.get_mut(target) {
+                        let label = if status.penalized {
+                            "Unpenalize"
+                        } else {
+                            "Penalize"
+                        };
+                        if ui.button(label).clicked() {
+                            status.penalized = !status.penalized;
+                            return true;
+                        }
+                    }
+                    false
+                })
+                .inner
+        });
+
+    let just_opened = std::mem::take(&mut menu.just_opened);
+    if response.inner || (!just_opened && response.response.clicked_elsewhere()) {
+        menu.target = None;
+    }
+}
@@ -0,0 +1,111 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+use clap::ValueEnum;
+use serde::Deserialize;
+
+/// World axis pointing up, against gravity.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Reflect, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum UpAxis {
+    /// Z up like URDFs and ROS, the field x and y axes are the world x and y axes
+    #[default]
+    Z,
+    /// Y up like Bevy and glTF assets, the field y axis points along the world -z axis
+    Y,
+}
+
+/// Relation between the field frame and the world frame entities are simulated in.
+///
+/// The field frame is right-handed with x towards the opponent goal, z up and its origin at the
+/// field center on the ground. Robots, URDFs, scenarios and all external interfaces use field
+/// coordinates, only transforms of entities are world coordinates. Systems convert with
+/// [`CoordinateFrame::to_field`] and [`CoordinateFrame::to_world`] instead of assuming an up axis
+/// or a ground height.
+#[derive(Clone, Copy, Debug, Resource)]
+pub struct CoordinateFrame {
+    pub up_axis: UpAxis,
+    /// Height of the field surface along the up axis in world coordinates
+    pub ground_height: f32,
+    /// Gravitational acceleration in m/s²
+    pub gravity: f32,
+}
+
+impl Default for CoordinateFrame {
+    fn default() -> Self {
+        Self {
+            up_axis: UpAxis::Z,
+            ground_height: -1.0,
+            // joint motors and contacts are tuned for this reduced gravity
+            gravity: 1.0,
+        }
+    }
+}
+
+impl CoordinateFrame {
+    /// Rotation of the field frame in the world frame
+    pub fn rotation(&self) -> Quat {
+        match self.up_axis {
+            UpAxis::Z => Quat::IDENTITY,
+            UpAxis::Y => Quat::from_rotation_x(-FRAC_PI_2),
+        }
+    }
+
+    /// Up direction in world coordinates
+    pub fn up(&self) -> Vec3 {
+        self.rotation() * Vec3::Z
+    }
+
+    /// Gravity vector in world coordinates
+    pub fn gravity(&self) -> Vec3 {
+        -self.up() * self.gravity
+    }
+
+    /// Pose of the field frame in the world frame
+    pub fn field_pose(&self) -> Transform {
+        Transform::from_translation(self.up() * self.ground_height).with_rotation(self.rotation())
+    }
+
+    /// Field coordinates of the world `point`.
+    pub fn to_field(&self, point: Vec3) -> Vec3 {
+        self.rotation().inverse() * (point - self.up() * self.ground_height)
+    }
+
+    /// World coordinates of the field `point`.
+    pub fn to_world(&self, point: Vec3) -> Vec3 {
+        self.rotation() * point + self.up() * self.ground_height
+    }
+
+    /// Direction or velocity in world coordinates in field coordinates.
+    pub fn vector_to_field(&self, vector: Vec3) -> Vec3 {
+        self.rotation().inverse() * vector
+    }
+
+    /// Direction or velocity in field coordinates in world coordinates.
+    pub fn vector_to_world(&self, vector: Vec3) -> Vec3 {
+        self.rotation() * vector
+    }
+
+    /// Pose in world coordinates in field coordinates, e.g. to read the yaw of a robot.
+    pub fn transform_to_field(&self, transform: Transform) -> Transform {
+        Transform {
+            translation: self.to_field(transform.translation),
+            rotation: self.rotation().inverse() * transform.rotation,
+            scale: transform.scale,
+        }
+    }
+
+    /// Pose in field coordinates in world coordinates, e.g. to place a robot.
+    pub fn transform_to_world(&self, transform: Transform) -> Transform {
+        Transform {
+            translation: self.to_world(transform.translation),
+            rotation: self.rotation() * transform.rotation,
+            scale: transform.scale,
+        }
+    }
+
+    /// Height of the world `point` above the field surface.
+    pub fn height(&self, point: Vec3) -> f32 {
+        (point - self.up() * self.ground_height).dot(self.up())
+    }
+}
@@ -0,0 +1,184 @@
+use bevy::{
+    diagnostic::{Diagnostic, DiagnosticId, Diagnostics, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+};
+use bevy_egui::{egui, EguiContexts};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    shortcuts::{dispatch_shortcuts, triggered, ShortcutAction},
+    simulation_time::{run_physics_schedule, PhysicsTimings, PHYSICS_TIMESTEP},
+};
+
+const PHYSICS_FRAME_TIME: DiagnosticId =
+    DiagnosticId::from_u128(0x5e1f_2a3b_7c4d_4e5f_8a6b_9c0d_1e2f_3a01);
+const PHYSICS_STEP_TIME: DiagnosticId =
+    DiagnosticId::from_u128(0x5e1f_2a3b_7c4d_4e5f_8a6b_9c0d_1e2f_3a02);
+const ACTIVE_BODIES: DiagnosticId =
+    DiagnosticId::from_u128(0x5e1f_2a3b_7c4d_4e5f_8a6b_9c0d_1e2f_3a03);
+const ACTIVE_CONTACTS: DiagnosticId =
+    DiagnosticId::from_u128(0x5e1f_2a3b_7c4d_4e5f_8a6b_9c0d_1e2f_3a04);
+/// Per step durations of the physics stages in the order of [`PhysicsTimings::STAGE_NAMES`]
+const PHYSICS_STAGE_TIMES: [DiagnosticId; 4] = [
+    DiagnosticId::from_u128(0x5e1f_2a3b_7c4d_4e5f_8a6b_9c0d_1e2f_3a10),
+    DiagnosticId::from_u128(0x5e1f_2a3b_7c4d_4e5f_8a6b_9c0d_1e2f_3a11),
+    DiagnosticId::from_u128(0x5e1f_2a3b_7c4d_4e5f_8a6b_9c0d_1e2f_3a12),
+    DiagnosticId::from_u128(0x5e1f_2a3b_7c4d_4e5f_8a6b_9c0d_1e2f_3a13),
+];
+/// Frames the shown values are averaged over
+const HISTORY_LENGTH: usize = 30;
+
+/// Shows frame rate, physics cost and the size of the simulated scene, to judge how many robots a
+/// machine simulates in real time.
+///
+/// The physics time of a frame includes all steps simulated in it, the real time factor compares
+/// it to the simulated time. The step time is broken down into the stages of the Rapier systems.
+pub struct DiagnosticsOverlayPlugin;
+
+impl Plugin for DiagnosticsOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+            app.add_plugin(FrameTimeDiagnosticsPlugin);
+        }
+        app.init_resource::<DiagnosticsOverlay>()
+            .add_startup_system(setup_diagnostics)
+            .add_system(
+                measure_physics
+                    .in_base_set(CoreSet::PostUpdate)
+                    .after(run_physics_schedule),
+            )
+            .add_system(toggle_diagnostics_overlay.after(dispatch_shortcuts))
+            .add_system(diagnostics_overlay_ui);
+    }
+}
+
+#[derive(Default, Resource)]
+pub struct DiagnosticsOverlay {
+    pub enabled: bool,
+}
+
+fn setup_diagnostics(mut diagnostics: ResMut<Diagnostics>) {
+    diagnostics.add(
+        Diagnostic::new(PHYSICS_FRAME_TIME, "physics_frame_time", HISTORY_LENGTH).with_suffix("ms"),
+    );
+    diagnostics.add(
+        Diagnostic::new(PHYSICS_STEP_TIME, "physics_step_time", HISTORY_LENGTH).with_suffix("ms"),
+    );
+    diagnostics.add(Diagnostic::new(
+        ACTIVE_BODIES,
+        "active_bodies",
+        HISTORY_LENGTH,
+    ));
+    diagnostics.add(Diagnostic::new(
+        ACTIVE_CONTACTS,
+        "active_contacts",
+        HISTORY_LENGTH,
+    ));
+    for (id, name) in PHYSICS_STAGE_TIMES
+        .into_iter()
+        .zip(PhysicsTimings::STAGE_NAMES)
+    {
+        diagnostics.add(Diagnostic::new(id, name, HISTORY_LENGTH).with_suffix("ms"));
+    }
+}
+
+fn measure_physics(
+    mut diagnostics: ResMut<Diagnostics>,
+    timings: Res<PhysicsTimings>,
+    context: Res<RapierContext>,
+) {
+    diagnostics.add_measurement(PHYSICS_FRAME_TIME, || timings.total.as_secs_f64() * 1000.0);
+    // paused frames have no steps to average over
+    if timings.steps > 0 {
+        let steps = f64::from(timings.steps);
+        diagnostics.add_measurement(PHYSICS_STEP_TIME, || {
+            timings.total.as_secs_f64() * 1000.0 / steps
+        });
+        for (id, duration) in PHYSICS_STAGE_TIMES.into_iter().zip(timings.stages) {
+            diagnostics.add_measurement(id, || duration.as_secs_f64() * 1000.0 / steps);
+        }
+    }
+    diagnostics.add_measurement(ACTIVE_BODIES, || {
+        context.islands.active_dynamic_bodies().len() as f64
+    });
+    diagnostics.add_measurement(ACTIVE_CONTACTS, || {
+        context
+            .narrow_phase
+            .contact_pairs()
+            .filter(|pair| pair.has_any_active_contact)
+            .count() as f64
+    });
+}
+
+fn toggle_diagnostics_overlay(
+    mut actions: EventReader<ShortcutAction>,
+    mut overlay: ResMut<DiagnosticsOverlay>,
+) {
+    if triggered(&mut actions, ShortcutAction::ToggleDiagnosticsOverlay) {
+        overlay.enabled = !overlay.enabled;
+    }
+}
+
+fn diagnostics_overlay_ui(
+    mut contexts: EguiContexts,
+    overlay: Res<DiagnosticsOverlay>,
+    diagnostics: Res<Diagnostics>,
+) {
+    if !overlay.enabled {
+        return;
+    }
+    let average = |id| {
+        diagnostics
+            .get(id)
+            .and_then(Diagnostic::average)
+            .unwrap_or_default()
+    };
+    let frame_time = average(FrameTimeDiagnosticsPlugin::FRAME_TIME);
+    let physics_frame_time = average(PHYSICS_FRAME_TIME);
+    let step_time = average(PHYSICS_STEP_TIME);
+    // simulated time per wall clock time if the machine only simulated physics
+    let real_time_factor = if step_time > 0.0 {
+        f64::from(PHYSICS_TIMESTEP) * 1000.0 / step_time
+    } else {
+        0.0
+    };
+    egui::Window::new("Diagnostics")
+        .anchor(egui::Align2::RIGHT_TOP, [-10.0, 10.0])
+        .resizable(false)
+        .title_bar(false)
+        .show(contexts.ctx_mut(), |ui| {
+            egui::Grid::new("diagnostics")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("FPS");
+                    ui.label(format!("{:.0}", average(FrameTimeDiagnosticsPlugin::FPS)));
+                    ui.end_row();
+                    ui.label("frame");
+                    ui.label(format!("{frame_time:.2} ms"));
+                    ui.end_row();
+                    ui.label("physics per frame");
+                    ui.label(format!("{physics_frame_time:.2} ms"));
+                    ui.end_row();
+                    ui.label("physics per step");
+                    ui.label(format!("{step_time:.2} ms"));
+                    ui.end_row();
+                    for (id, name) in PHYSICS_STAGE_TIMES
+                        .into_iter()
+                        .zip(PhysicsTimings::STAGE_NAMES)
+                    {
+                        ui.label(format!("  {name}"));
+                        ui.label(format!("{:.2} ms", average(id)));
+                        ui.end_row();
+                    }
+                    ui.label("physics real time factor");
+                    ui.label(format!("{real_time_factor:.1}x"));
+                    ui.end_row();
+                    ui.label("active bodies");
+                    ui.label(format!("{:.0}", average(ACTIVE_BODIES)));
+                    ui.end_row();
+                    ui.label("active contacts");
+                    ui.label(format!("{:.0}", average(ACTIVE_CONTACTS)));
+                    ui.end_row();
+                });
+        });
+}
@@ -0,0 +1,186 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::{
+    pbr::{CascadeShadowConfig, CascadeShadowConfigBuilder, NotShadowCaster, NotShadowReceiver},
+    prelude::*,
+};
+
+use crate::coordinate_frame::CoordinateFrame;
+
+/// Radius of the sphere the skybox is drawn on in meters, within the far plane of the cameras
+const SKYBOX_RADIUS: f32 = 400.0;
+
+/// Lights the stadium and draws the sky around it as configured in the [`Environment`].
+///
+/// The skybox is an equirectangular image, e.g. an HDRI, mapped onto a large sphere around the
+/// field. Without one the sky is a plain color.
+pub struct EnvironmentPlugin;
+
+impl Plugin for EnvironmentPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Environment>()
+            .register_type::<Environment>()
+            .add_startup_system(spawn_sun)
+            .add_system(apply_environment.run_if(resource_changed::<Environment>()))
+            .add_system(update_skybox.run_if(resource_changed::<Environment>()));
+    }
+}
+
+#[derive(Reflect, Resource)]
+#[reflect(Resource)]
+pub struct Environment {
+    /// Equirectangular sky image relative to the assets directory, a plain sky without
+    pub skybox: Option<String>,
+    pub sky_color: Color,
+    pub ambient_color: Color,
+    pub ambient_brightness: f32,
+    pub sun_color: Color,
+    /// Illuminance of the sun in lux
+    pub sun_illuminance: f32,
+    /// Angle of the sun above the horizon in radians
+    pub sun_elevation: f32,
+    /// Direction of the sun counterclockwise from the positive x-axis in radians
+    pub sun_azimuth: f32,
+    pub shadows_enabled: bool,
+    /// Offset of shadow depths away from the light, larger values avoid acne
+    pub shadow_depth_bias: f32,
+    /// Offset of shadow receivers along their normals, larger values avoid acne on slopes
+    pub shadow_normal_bias: f32,
+    pub shadow_cascades: usize,
+    /// Distance up to which shadows are drawn in meters
+    pub shadow_maximum_distance: f32,
+    /// Far bound of the sharpest cascade in meters
+    pub shadow_first_cascade_far_bound: f32,
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self {
+            skybox: None,
+            sky_color: Color::rgb(0.53, 0.71, 0.88),
+            ambient_color: Color::WHITE,
+            ambient_brightness: 0.3,
+            sun_color: Color::WHITE,
+            sun_illuminance: 50_000.0,
+            sun_elevation: 0.9,
+            sun_azimuth: 0.6,
+            shadows_enabled: true,
+            shadow_depth_bias: 0.05,
+            shadow_normal_bias: 1.2,
+            shadow_cascades: 4,
+            shadow_maximum_distance: 30.0,
+            shadow_first_cascade_far_bound: 4.0,
+        }
+    }
+}
+
+impl Environment {
+    /// Unit vector pointing from the field towards the sun in field coordinates
+    fn sun_direction(&self) -> Vec3 {
+        Vec3::new(
+            self.sun_elevation.cos() * self.sun_azimuth.cos(),
+            self.sun_elevation.cos() * self.sun_azimuth.sin(),
+            self.sun_elevation.sin(),
+        )
+    }
+}
+
+#[derive(Component)]
+struct Sun;
+
+#[derive(Component)]
+struct Skybox;
+
+fn spawn_sun(mut commands: Commands) {
+    commands.spawn((DirectionalLightBundle::default(), Sun, Name::new("sun")));
+}
+
+fn apply_environment(
+    environment: Res<Environment>,
+    frame: Res<CoordinateFrame>,
+    mut ambient_light: ResMut<AmbientLight>,
+    mut clear_color: ResMut<ClearColor>,
+    mut suns: Query<
+        (
+            &mut DirectionalLight,
+            &mut Transform,
+            &mut CascadeShadowConfig,
+        ),
+        With<Sun>,
+    >,
+) {
+    ambient_light.color = environment.ambient_color;
+    ambient_light.brightness = environment.ambient_brightness;
+    clear_color.0 = environment.sky_color;
+    for (mut light, mut transform, mut cascades) in suns.iter_mut() {
+        light.color = environment.sun_color;
+        light.illuminance = environment.sun_illuminance;
+        light.shadows_enabled = environment.shadows_enabled;
+        light.shadow_depth_bias = environment.shadow_depth_bias;
+        light.shadow_normal_bias = environment.shadow_normal_bias;
+        // directional lights shine along their forward direction, only the rotation matters
+        *transform =
+            Transform::from_translation(frame.vector_to_world(environment.sun_direction()))
+                .looking_at(Vec3::ZERO, frame.up());
+        *cascades = CascadeShadowConfigBuilder {
+            num_cascades: environment.shadow_cascades.max(1),
+            maximum_distance: environment.shadow_maximum_distance,
+            first_cascade_far_bound: environment
+                .shadow_first_cascade_far_bound
+                .min(environment.shadow_maximum_distance),
+            ..Default::default()
+        }
+        .build();
+    }
+}
+
+/// Respawns the skybox whenever its image changes.
+#[allow(clippy::too_many_arguments)]
+fn update_skybox(
+    mut commands: Commands,
+    environment: Res<Environment>,
+    frame: Res<CoordinateFrame>,
+    mut shown: Local<Option<String>>,
+    server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    skyboxes: Query<Entity, With<Skybox>>,
+) {
+    if *shown == environment.skybox {
+        return;
+    }
+    *shown = environment.skybox.clone();
+    for skybox in skyboxes.iter() {
+        commands.entity(skybox).despawn_recursive();
+    }
+    let Some(path) = &environment.skybox else {
+        return;
+    };
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::UVSphere {
+                radius: 1.0,
+                sectors: 64,
+                stacks: 32,
+            })),
+            material: materials.add(StandardMaterial {
+                base_color_texture: Some(server.load(path.as_str())),
+                unlit: true,
+                // the sphere is seen from the inside
+                cull_mode: None,
+                ..Default::default()
+            }),
+            // the sphere has its poles on the y-axis, the mirrored x-axis keeps the image from
+            // appearing flipped from the inside
+            transform: Transform::from_rotation(
+                frame.rotation() * Quat::from_rotation_x(FRAC_PI_2),
+            )
+            .with_scale(Vec3::new(-SKYBOX_RADIUS, SKYBOX_RADIUS, SKYBOX_RADIUS)),
+            ..Default::default()
+        },
+        NotShadowCaster,
+        NotShadowReceiver,
+        Skybox,
+        Name::new("skybox"),
+    ));
+}
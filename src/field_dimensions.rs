@@ -1,6 +1,36 @@
-use bevy::prelude::*;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
-#[derive(Clone, Debug, Resource)]
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use color_eyre::{eyre::WrapErr, Result};
+use serde::Deserialize;
+
+/// Reloads the [`FieldDimensions`] resource whenever the file given in [`FieldDimensionsFile`]
+/// changes, everything built from the dimensions is rebuilt.
+pub struct FieldDimensionsPlugin;
+
+impl Plugin for FieldDimensionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<FieldDimensions>()
+            .init_asset_loader::<FieldDimensionsLoader>()
+            .add_startup_system(watch_field_dimensions_file)
+            .add_system(reload_field_dimensions);
+    }
+}
+
+/// Dimensions of the field in meters, the defaults are those of the SPL standard field.
+///
+/// Files may leave out fields, those keep their default.
+#[derive(Clone, Debug, Deserialize, PartialEq, Reflect, Resource, TypeUuid)]
+#[serde(default)]
+#[uuid = "04bb68e0-38ec-487e-864b-4bed8275af4a"]
 pub struct FieldDimensions {
     pub ball_radius: f32,
     pub length: f32,
@@ -40,3 +70,91 @@ impl Default for FieldDimensions {
         }
     }
 }
+
+impl FieldDimensions {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .wrap_err_with(|| format!("failed to read field dimensions {}", path.display()))?;
+        serde_json::from_str(&content)
+            .wrap_err_with(|| format!("failed to parse field dimensions {}", path.display()))
+    }
+}
+
+/// JSON file the field dimensions were loaded from.
+#[derive(Resource)]
+pub struct FieldDimensionsFile(pub PathBuf);
+
+#[derive(Resource)]
+struct FieldDimensionsHandle(Handle<FieldDimensions>);
+
+#[derive(Default)]
+struct FieldDimensionsLoader;
+
+impl AssetLoader for FieldDimensionsLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let field_dimensions: FieldDimensions = serde_json::from_slice(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(field_dimensions));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["json"]
+    }
+}
+
+fn watch_field_dimensions_file(
+    mut commands: Commands,
+    server: Res<AssetServer>,
+    file: Option<Res<FieldDimensionsFile>>,
+) {
+    let Some(file) = file else {
+        return;
+    };
+    // the asset server resolves relative paths against the asset folder, not the working
+    // directory the path was given in
+    let path = match fs::canonicalize(&file.0)
+        .wrap_err_with(|| format!("failed to resolve {}", file.0.display()))
+    {
+        Ok(path) => path,
+        Err(error) => {
+            error!("{error:?}");
+            return;
+        }
+    };
+    commands.insert_resource(FieldDimensionsHandle(server.load(path)));
+}
+
+fn reload_field_dimensions(
+    mut events: EventReader<AssetEvent<FieldDimensions>>,
+    handle: Option<Res<FieldDimensionsHandle>>,
+    assets: Res<Assets<FieldDimensions>>,
+    mut field_dimensions: ResMut<FieldDimensions>,
+) {
+    let Some(handle) = handle else {
+        events.clear();
+        return;
+    };
+    for event in events.iter() {
+        let (AssetEvent::Created { handle: changed } | AssetEvent::Modified { handle: changed }) =
+            event
+        else {
+            continue;
+        };
+        if *changed != handle.0 {
+            continue;
+        }
+        if let Some(reloaded) = assets.get(changed) {
+            if *reloaded != *field_dimensions {
+                info!("Reloaded field dimensions");
+                *field_dimensions = reloaded.clone();
+            }
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use bevy::{prelude::*, render::mesh::PrimitiveTopology};
+
+use crate::{
+    coordinate_frame::CoordinateFrame,
+    field_dimensions::FieldDimensions,
+    shortcuts::{triggered, ShortcutAction},
+    world_labels::WorldLabel,
+};
+
+/// Metric grid aligned with the field coordinate system.
+pub struct FieldGridPlugin;
+
+impl Plugin for FieldGridPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FieldGrid>()
+            .add_startup_system(spawn_field_grid)
+            .add_system(toggle_field_grid);
+    }
+}
+
+#[derive(Resource)]
+pub struct FieldGrid {
+    pub enabled: bool,
+    /// Distance between two grid lines in meters
+    pub spacing: f32,
+}
+
+impl Default for FieldGrid {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            spacing: 1.0,
+        }
+    }
+}
+
+#[derive(Component)]
+struct FieldGridRoot;
+
+fn spawn_field_grid(
+    mut commands: Commands,
+    frame: Res<CoordinateFrame>,
+    field_grid: Res<FieldGrid>,
+    field_dimensions: Res<FieldDimensions>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let half_length = field_dimensions.length / 2.0 + field_dimensions.border_strip_width;
+    let half_width = field_dimensions.width / 2.0 + field_dimensions.border_strip_width;
+    let steps_x = (half_length / field_grid.spacing).floor() as i32;
+    let steps_y = (half_width / field_grid.spacing).floor() as i32;
+
+    let mut positions = Vec::new();
+    let mut colors = Vec::new();
+    let mut add_line = |start: Vec3, end: Vec3, color: Color| {
+        positions.extend([start.to_array(), end.to_array()]);
+        colors.extend([color.as_linear_rgba_f32(); 2]);
+    };
+    for i in -steps_x..=steps_x {
+        let x = i as f32 * field_grid.spacing;
+        let color = if i == 0 { Color::GREEN } else { Color::GRAY };
+        add_line(
+            Vec3::new(x, -half_width, 0.0),
+            Vec3::new(x, half_width, 0.0),
+            color,
+        );
+    }
+    for i in -steps_y..=steps_y {
+        let y = i as f32 * field_grid.spacing;
+        let color = if i == 0 { Color::RED } else { Color::GRAY };
+        add_line(
+            Vec3::new(-half_length, y, 0.0),
+            Vec3::new(half_length, y, 0.0),
+            color,
+        );
+    }
+    // origin marker pointing up
+    add_line(Vec3::ZERO, Vec3::Z * 0.5, Color::BLUE);
+
+    let mut mesh = Mesh::new(PrimitiveTopology::LineList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+
+    let visibility = if field_grid.enabled {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    commands
+        .spawn((
+            PbrBundle {
+                mesh: meshes.add(mesh),
+                material: materials.add(StandardMaterial {
+                    base_color: Color::WHITE,
+                    unlit: true,
+                    ..Default::default()
+                }),
+                // slightly above the ground to avoid z-fighting
+                transform: frame.transform_to_world(Transform::from_xyz(0.0, 0.0, 0.005)),
+                visibility,
+                ..Default::default()
+            },
+            FieldGridRoot,
+            Name::new("field grid"),
+        ))
+        .with_children(|grid| {
+            let mut spawn_label = |position: Vec3, label: WorldLabel| {
+                grid.spawn((
+                    TransformBundle::from(Transform::from_translation(position)),
+                    VisibilityBundle::default(),
+                    label,
+                ));
+            };
+            for i in (-steps_x..=steps_x).filter(|&i| i != 0) {
+                let x = i as f32 * field_grid.spacing;
+                spawn_label(Vec3::new(x, 0.0, 0.0), WorldLabel::new(format!("{x}")));
+            }
+            for i in (-steps_y..=steps_y).filter(|&i| i != 0) {
+                let y = i as f32 * field_grid.spacing;
+                spawn_label(Vec3::new(0.0, y, 0.0), WorldLabel::new(format!("{y}")));
+            }
+            spawn_label(Vec3::ZERO, WorldLabel::new("0"));
+            spawn_label(
+                Vec3::new(half_length, 0.0, 0.0),
+                WorldLabel {
+                    color: Color::RED,
+                    ..WorldLabel::new("x")
+                },
+            );
+            spawn_label(
+                Vec3::new(0.0, half_width, 0.0),
+                WorldLabel {
+                    color: Color::GREEN,
+                    ..WorldLabel::new("y")
+                },
+            );
+        });
+}
+
+fn toggle_field_grid(
+    mut actions: EventReader<ShortcutAction>,
+    mut field_grid: ResMut<FieldGrid>,
+    mut grids: Query<&mut Visibility, With<FieldGridRoot>>,
+) {
+    if !triggered(&mut actions, ShortcutAction::ToggleFieldGrid) {
+        return;
+    }
+    field_grid.enabled = !field_grid.enabled;
+    for mut visibility in grids.iter_mut() {
+        *visibility = if field_grid.enabled {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
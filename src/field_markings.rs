@@ -0,0 +1,188 @@
+use std::f32::consts::TAU;
+
+use bevy::{
+    prelude::*,
+    render::mesh::{Indices, PrimitiveTopology},
+};
+
+use crate::{arenas::Arenas, coordinate_frame::CoordinateFrame, field_dimensions::FieldDimensions};
+
+/// Segments of the center circle
+const CIRCLE_SEGMENTS: usize = 64;
+
+/// Field lines, center circle and penalty marks generated from [`FieldDimensions`].
+///
+/// The markings are rebuilt whenever the field dimensions change.
+pub struct FieldMarkingsPlugin;
+
+impl Plugin for FieldMarkingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(spawn_field_markings.run_if(resource_changed::<FieldDimensions>()));
+    }
+}
+
+#[derive(Component)]
+struct FieldMarkings;
+
+fn spawn_field_markings(
+    mut commands: Commands,
+    frame: Res<CoordinateFrame>,
+    field_dimensions: Res<FieldDimensions>,
+    arenas: Res<Arenas>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    markings: Query<Entity, With<FieldMarkings>>,
+) {
+    for entity in markings.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let mesh = meshes.add(markings_builder(&field_dimensions).build());
+    let material = materials.add(StandardMaterial {
+        base_color: Color::WHITE,
+        perceptual_roughness: 0.8,
+        ..Default::default()
+    });
+    for arena in arenas.iter() {
+        commands.spawn((
+            PbrBundle {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                // slightly above the ground to avoid z-fighting
+                transform: arena.transform_to_world(
+                    &frame,
+                    &field_dimensions,
+                    Transform::from_xyz(0.0, 0.0, 0.002),
+                ),
+                ..Default::default()
+            },
+            FieldMarkings,
+            arena,
+            Name::new(arena.name("field markings")),
+        ));
+    }
+}
+
+/// Center lines of all markings in field coordinates on the ground, circles are split into
+/// straight segments.
+pub fn field_line_segments(field_dimensions: &FieldDimensions) -> Vec<[Vec2; 2]> {
+    markings_builder(field_dimensions).segments
+}
+
+fn markings_builder(field_dimensions: &FieldDimensions) -> MarkingsBuilder {
+    let mut builder = MarkingsBuilder {
+        line_width: field_dimensions.line_width,
+        ..Default::default()
+    };
+    let half_length = field_dimensions.length / 2.0;
+    let half_width = field_dimensions.width / 2.0;
+
+    builder.add_rectangle(Vec2::ZERO, Vec2::new(half_length, half_width));
+    builder.add_line(Vec2::new(0.0, -half_width), Vec2::new(0.0, half_width));
+    builder.add_circle(Vec2::ZERO, field_dimensions.center_circle_diameter / 2.0);
+    builder.add_cross(Vec2::ZERO, field_dimensions.penalty_marker_size);
+    for side in [-1.0, 1.0] {
+        let goal_line = side * half_length;
+        for (length, width) in [
+            (
+                field_dimensions.penalty_area_length,
+                field_dimensions.penalty_area_width,
+            ),
+            (
+                field_dimensions.goal_box_area_length,
+                field_dimensions.goal_box_area_width,
+            ),
+        ] {
+            builder.add_rectangle(
+                Vec2::new(goal_line - side * length / 2.0, 0.0),
+                Vec2::new(length / 2.0, width / 2.0),
+            );
+        }
+        builder.add_cross(
+            Vec2::new(
+                goal_line - side * field_dimensions.penalty_marker_distance,
+                0.0,
+            ),
+            field_dimensions.penalty_marker_size,
+        );
+    }
+    builder
+}
+
+/// Collects flat quads of all markings into a single triangle mesh.
+#[derive(Default)]
+struct MarkingsBuilder {
+    line_width: f32,
+    positions: Vec<[f32; 3]>,
+    indices: Vec<u32>,
+    /// Center lines of the added markings
+    segments: Vec<[Vec2; 2]>,
+}
+
+impl MarkingsBuilder {
+    fn add_quad(&mut self, corners: [Vec2; 4]) {
+        let offset = self.positions.len() as u32;
+        self.positions
+            .extend(corners.iter().map(|corner| corner.extend(0.0).to_array()));
+        self.indices
+            .extend([0, 1, 2, 0, 2, 3].map(|index| offset + index));
+    }
+
+    /// Line of the configured width centered on the segment from `start` to `end`.
+    fn add_line(&mut self, start: Vec2, end: Vec2) {
+        let Some(direction) = (end - start).try_normalize() else {
+            return;
+        };
+        self.segments.push([start, end]);
+        let normal = direction.perp() * self.line_width / 2.0;
+        // extend the ends so lines meeting in a corner overlap instead of leaving a notch
+        let start = start - direction * self.line_width / 2.0;
+        let end = end + direction * self.line_width / 2.0;
+        self.add_quad([start - normal, end - normal, end + normal, start + normal]);
+    }
+
+    fn add_rectangle(&mut self, center: Vec2, half_size: Vec2) {
+        let corners = [
+            center + Vec2::new(-half_size.x, -half_size.y),
+            center + Vec2::new(half_size.x, -half_size.y),
+            center + Vec2::new(half_size.x, half_size.y),
+            center + Vec2::new(-half_size.x, half_size.y),
+        ];
+        for (index, corner) in corners.iter().enumerate() {
+            self.add_line(*corner, corners[(index + 1) % corners.len()]);
+        }
+    }
+
+    fn add_circle(&mut self, center: Vec2, radius: f32) {
+        let inner = radius - self.line_width / 2.0;
+        let outer = radius + self.line_width / 2.0;
+        for segment in 0..CIRCLE_SEGMENTS {
+            let start = Vec2::from_angle(segment as f32 / CIRCLE_SEGMENTS as f32 * TAU);
+            let end = Vec2::from_angle((segment + 1) as f32 / CIRCLE_SEGMENTS as f32 * TAU);
+            self.segments
+                .push([center + start * radius, center + end * radius]);
+            self.add_quad([
+                center + start * inner,
+                center + start * outer,
+                center + end * outer,
+                center + end * inner,
+            ]);
+        }
+    }
+
+    fn add_cross(&mut self, center: Vec2, size: f32) {
+        let half_size = size / 2.0 - self.line_width / 2.0;
+        self.add_line(center - Vec2::X * half_size, center + Vec2::X * half_size);
+        self.add_line(center - Vec2::Y * half_size, center + Vec2::Y * half_size);
+    }
+
+    fn build(self) -> Mesh {
+        let vertex_count = self.positions.len();
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, self.positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 0.0, 1.0]; vertex_count]);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0]; vertex_count]);
+        mesh.set_indices(Some(Indices::U32(self.indices)));
+        mesh
+    }
+}
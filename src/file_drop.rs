@@ -0,0 +1,131 @@
+use std::path::Path;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use color_eyre::{
+    eyre::{bail, eyre},
+    Result,
+};
+
+use crate::{
+    coordinate_frame::CoordinateFrame,
+    mesh_uris::{read_urdf, PackagePaths},
+    picking::Picking,
+    robot_assets::RobotAssets,
+    robot_spawn::RobotSpawn,
+    spawn_robot,
+};
+
+/// Spawns URDF and STL files dropped onto the window at the field position under the cursor.
+///
+/// URDFs become robots, STLs become props with a convex hull collider. Props are static unless
+/// shift is held while dropping.
+pub struct FileDropPlugin;
+
+impl Plugin for FileDropPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(spawn_dropped_files);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_dropped_files(
+    mut commands: Commands,
+    mut events: EventReader<FileDragAndDrop>,
+    mut assets: RobotAssets,
+    keys: Res<Input<KeyCode>>,
+    robot_spawn: Res<RobotSpawn>,
+    frame: Res<CoordinateFrame>,
+    packages: Res<PackagePaths>,
+    picking: Picking,
+) {
+    for event in events.iter() {
+        let FileDragAndDrop::DroppedFile { path_buf, .. } = event else {
+            continue;
+        };
+        let position = cursor_field_position(&picking, &frame).unwrap_or(Vec2::ZERO);
+        let extension = path_buf
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+        let result = match extension.as_deref() {
+            Some("urdf") => spawn_dropped_robot(
+                &mut commands,
+                &mut assets,
+                &packages,
+                path_buf,
+                frame.transform_to_world(
+                    robot_spawn.transform(Transform::from_xyz(position.x, position.y, 0.0)),
+                ),
+            ),
+            Some("stl") => {
+                spawn_dropped_prop(
+                    &mut commands,
+                    &mut assets,
+                    path_buf,
+                    frame.transform_to_world(Transform::from_xyz(position.x, position.y, 0.0)),
+                    keys.any_pressed([KeyCode::LShift, KeyCode::RShift]),
+                );
+                Ok(())
+            }
+            _ => Err(eyre!("unsupported file type")),
+        };
+        match result {
+            Ok(()) => info!("Spawned {} at {position}", path_buf.display()),
+            Err(error) => error!("Failed to spawn {}: {error:?}", path_buf.display()),
+        }
+    }
+}
+
+/// Point on the ground under the cursor in field coordinates.
+fn cursor_field_position(picking: &Picking, frame: &CoordinateFrame) -> Option<Vec2> {
+    let ray = picking.cursor_ray()?;
+    let distance = ray.intersect_plane(frame.to_world(Vec3::ZERO), frame.up())?;
+    Some(frame.to_field(ray.get_point(distance)).truncate())
+}
+
+fn spawn_dropped_robot(
+    commands: &mut Commands,
+    assets: &mut RobotAssets,
+    packages: &PackagePaths,
+    path: &Path,
+    transform: Transform,
+) -> Result<()> {
+    let urdf = read_urdf(path, packages)?;
+    if spawn_robot(commands, assets, &urdf, transform).is_none() {
+        bail!("URDF has no root link");
+    }
+    Ok(())
+}
+
+fn spawn_dropped_prop(
+    commands: &mut Commands,
+    assets: &mut RobotAssets,
+    path: &Path,
+    transform: Transform,
+    dynamic: bool,
+) {
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "prop".to_string());
+    let mut prop = commands.spawn((
+        PbrBundle {
+            mesh: assets.server.load(path.to_path_buf()),
+            material: assets.color_material(Color::GRAY),
+            transform,
+            ..Default::default()
+        },
+        // replaced by the collider once the mesh is loaded
+        AsyncCollider(ComputedColliderShape::ConvexDecomposition(
+            VHACDParameters::default(),
+        )),
+        CollisionGroups::new(Group::GROUP_4, Group::ALL),
+        Name::new(name),
+    ));
+    if dynamic {
+        prop.insert((RigidBody::Dynamic, Velocity::zero()));
+    } else {
+        prop.insert(RigidBody::Fixed);
+    }
+}
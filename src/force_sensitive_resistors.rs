@@ -0,0 +1,118 @@
+use bevy::{prelude::*, transform::TransformSystem};
+use bevy_rapier3d::prelude::*;
+
+use crate::{simulation_time::PHYSICS_TIMESTEP, Field, RobotLink, RobotRoot};
+
+/// Sensors under each foot in the order LoLA reports them
+const SENSOR_NAMES: [&str; 4] = ["FrontLeft", "FrontRight", "RearLeft", "RearRight"];
+/// Collision shape link and sensor link prefix of the left and right foot
+const FEET: [(&str, &str); 2] = [
+    ("LAnkleRollBothCollision_shape", "LFoot/FSR/"),
+    ("RAnkleRollBothCollision_shape", "RFoot/FSR/"),
+];
+/// Keeps the weight of a contact right at a sensor finite, in m²
+const DISTANCE_EPSILON: f32 = 1e-4;
+
+/// Simulates the four force sensitive resistors in the sole of each foot.
+///
+/// The normal forces of the contacts between a foot and the field are split among the sensors
+/// of the foot by inverse squared distance, so the reading follows the center of pressure.
+pub struct ForceSensitiveResistorsPlugin;
+
+impl Plugin for ForceSensitiveResistorsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(add_force_sensitive_resistors).add_system(
+            update_force_sensitive_resistors
+                .in_base_set(CoreSet::PostUpdate)
+                .after(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+/// Forces measured by the sensors in newtons, ordered front left, front right, rear left, rear
+/// right.
+#[derive(Clone, Component, Debug, Default)]
+pub struct ForceSensitiveResistors {
+    pub left: [f32; 4],
+    pub right: [f32; 4],
+}
+
+fn add_force_sensitive_resistors(
+    mut commands: Commands,
+    robots: Query<Entity, (Added<RobotRoot>, Without<ForceSensitiveResistors>)>,
+) {
+    for robot in robots.iter() {
+        commands
+            .entity(robot)
+            .insert(ForceSensitiveResistors::default());
+    }
+}
+
+fn update_force_sensitive_resistors(
+    context: Res<RapierContext>,
+    fields: Query<Entity, With<Field>>,
+    mut robots: Query<(Entity, &mut ForceSensitiveResistors)>,
+    children: Query<&Children>,
+    links: Query<(&RobotLink, &GlobalTransform)>,
+) {
+    for (robot, mut sensors) in robots.iter_mut() {
+        let mut colliders = [None; 2];
+        let mut positions = [[Vec3::ZERO; 4]; 2];
+        for link in children.iter_descendants(robot) {
+            let Ok((robot_link, transform)) = links.get(link) else {
+                continue;
+            };
+            for (foot, (collider_name, sensor_prefix)) in FEET.iter().enumerate() {
+                if robot_link.name == *collider_name {
+                    colliders[foot] = Some((link, *transform));
+                }
+                if let Some(index) = robot_link
+                    .name
+                    .strip_prefix(sensor_prefix)
+                    .and_then(|sensor| SENSOR_NAMES.iter().position(|name| *name == sensor))
+                {
+                    positions[foot][index] = transform.translation();
+                }
+            }
+        }
+
+        let mut forces = [[0.0; 4]; 2];
+        for (foot, collider) in colliders.iter().enumerate() {
+            let Some((collider, transform)) = collider else {
+                continue;
+            };
+            for field in fields.iter() {
+                let Some(pair) = context.contact_pair(*collider, field) else {
+                    continue;
+                };
+                let foot_is_first = pair.collider1() == *collider;
+                for manifold in pair.manifolds() {
+                    for contact in manifold.points() {
+                        let local_point = if foot_is_first {
+                            contact.local_p1()
+                        } else {
+                            contact.local_p2()
+                        };
+                        distribute(
+                            &mut forces[foot],
+                            &positions[foot],
+                            transform.transform_point(local_point),
+                            contact.impulse() / PHYSICS_TIMESTEP,
+                        );
+                    }
+                }
+            }
+        }
+        let [left, right] = forces;
+        sensors.left = left;
+        sensors.right = right;
+    }
+}
+
+fn distribute(forces: &mut [f32; 4], sensors: &[Vec3; 4], point: Vec3, force: f32) {
+    let weights = sensors.map(|sensor| 1.0 / (sensor.distance_squared(point) + DISTANCE_EPSILON));
+    let total: f32 = weights.iter().sum();
+    for (force_at_sensor, weight) in forces.iter_mut().zip(weights) {
+        *force_at_sensor += force * weight / total;
+    }
+}
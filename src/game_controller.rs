@@ -0,0 +1,707 @@
+use std::{
+    net::{Ipv4Addr, SocketAddr, UdpSocket},
+    time::Duration,
+};
+
+use bevy::{prelude::*, time::common_conditions::on_timer};
+use bevy_egui::{egui, EguiContexts, EguiPlugin};
+use color_eyre::{eyre::WrapErr, Result};
+
+use crate::{
+    arenas::{in_main_arena, Arena},
+    coordinate_frame::CoordinateFrame,
+    player::{Player, RobotStatus, TeamColor},
+    referee::GoalScored,
+    simulation_time::SimulationTime,
+    Ball, RobotRoot,
+};
+
+const GAME_CONTROLLER_DATA_PORT: u16 = 3838;
+const GAME_CONTROLLER_RETURN_PORT: u16 = 3939;
+const GAME_CONTROLLER_DATA_HEADER: &[u8; 4] = b"RGme";
+const GAME_CONTROLLER_DATA_VERSION: u8 = 15;
+const GAME_CONTROLLER_RETURN_HEADER: &[u8; 4] = b"RGrt";
+const GAME_CONTROLLER_RETURN_VERSION: u8 = 4;
+const MAXIMUM_NUMBER_OF_PLAYERS: usize = 20;
+const GAME_CONTROLLER_DATA_SIZE: usize = 18 + 2 * TEAM_INFO_SIZE;
+const TEAM_INFO_SIZE: usize = 10 + 2 * MAXIMUM_NUMBER_OF_PLAYERS;
+/// Penalty the mini GameController assigns to penalized robots
+const PENALTY_MANUAL: u8 = 15;
+/// The GameController and robots exchange packets twice per second
+const SEND_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Speaks the SPL GameController protocol over UDP.
+///
+/// In [`GameControllerMode::Listen`] packets of a real GameController update the
+/// [`GameControllerState`] and the penalties of the robots, and every robot answers with a return
+/// packet. In [`GameControllerMode::Host`] the simulator acts as a minimal GameController itself
+/// and broadcasts the state controlled from the UI.
+pub struct GameControllerPlugin;
+
+impl Plugin for GameControllerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameController>()
+            .init_resource::<GameControllerState>()
+            .add_startup_system(open_game_controller_socket)
+            .add_system(receive_game_controller_data)
+            .add_system(
+                apply_penalties
+                    .after(receive_game_controller_data)
+                    .run_if(resource_changed::<GameControllerState>()),
+            )
+            .add_system(count_goals)
+            .add_system(run_game_clock)
+            .add_system(broadcast_game_controller_data.run_if(on_timer(SEND_INTERVAL)))
+            .add_system(send_return_data.run_if(on_timer(SEND_INTERVAL)));
+        if app.is_plugin_added::<EguiPlugin>() {
+            app.add_system(game_controller_ui);
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GameControllerMode {
+    /// Follow an external GameController
+    #[default]
+    Listen,
+    /// Act as GameController for standalone testing
+    Host,
+}
+
+#[derive(Resource)]
+pub struct GameController {
+    pub mode: GameControllerMode,
+    /// Port GameController data is received and broadcast on
+    pub data_port: u16,
+    /// Port of the GameController return packets are sent to
+    pub return_port: u16,
+    /// Team numbers of the two teams when hosting
+    pub team_numbers: [u8; 2],
+    socket: Option<UdpSocket>,
+    /// Address of the GameController the last packet was received from
+    game_controller_address: Option<SocketAddr>,
+    packet_number: u8,
+    /// Fraction of a second not yet subtracted from the remaining time
+    clock: f32,
+}
+
+impl Default for GameController {
+    fn default() -> Self {
+        Self {
+            mode: GameControllerMode::default(),
+            data_port: GAME_CONTROLLER_DATA_PORT,
+            return_port: GAME_CONTROLLER_RETURN_PORT,
+            team_numbers: [1, 2],
+            socket: None,
+            game_controller_address: None,
+            packet_number: 0,
+            clock: 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GameState {
+    #[default]
+    Initial,
+    Ready,
+    Set,
+    Playing,
+    Finished,
+    Standby,
+}
+
+impl GameState {
+    pub const ALL: [GameState; 6] = [
+        GameState::Initial,
+        GameState::Standby,
+        GameState::Ready,
+        GameState::Set,
+        GameState::Playing,
+        GameState::Finished,
+    ];
+
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(GameState::Initial),
+            1 => Some(GameState::Ready),
+            2 => Some(GameState::Set),
+            3 => Some(GameState::Playing),
+            4 => Some(GameState::Finished),
+            5 => Some(GameState::Standby),
+            _ => None,
+        }
+    }
+
+    fn to_u8(self) -> u8 {
+        match self {
+            GameState::Initial => 0,
+            GameState::Ready => 1,
+            GameState::Set => 2,
+            GameState::Playing => 3,
+            GameState::Finished => 4,
+            GameState::Standby => 5,
+        }
+    }
+}
+
+/// Game state as announced by the GameController.
+#[derive(Clone, Debug, Resource)]
+pub struct GameControllerState {
+    pub state: GameState,
+    /// Normal, penalty shoot-out, overtime or timeout as numbered by the GameController
+    pub game_phase: u8,
+    /// Active set play as numbered by the GameController, zero if there is none
+    pub set_play: u8,
+    pub first_half: bool,
+    /// Team number of the team that has kickoff or the current set play
+    pub kicking_team: u8,
+    pub seconds_remaining: i16,
+    pub secondary_time: i16,
+    pub teams: [TeamState; 2],
+}
+
+impl Default for GameControllerState {
+    fn default() -> Self {
+        Self {
+            state: GameState::default(),
+            game_phase: 0,
+            set_play: 0,
+            first_half: true,
+            kicking_team: 1,
+            seconds_remaining: 600,
+            secondary_time: 0,
+            teams: [
+                TeamState::new(1, TeamColor::Blue),
+                TeamState::new(2, TeamColor::Red),
+            ],
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TeamState {
+    pub team_number: u8,
+    pub field_player_color: TeamColor,
+    pub goalkeeper_color: TeamColor,
+    /// Jersey number of the goalkeeper
+    pub goalkeeper: u8,
+    pub score: u8,
+    pub message_budget: u16,
+    /// Penalty and seconds until it ends for each jersey number starting at one
+    pub players: [(u8, u8); MAXIMUM_NUMBER_OF_PLAYERS],
+}
+
+impl TeamState {
+    fn new(team_number: u8, field_player_color: TeamColor) -> Self {
+        Self {
+            team_number,
+            field_player_color,
+            goalkeeper_color: field_player_color,
+            goalkeeper: 1,
+            score: 0,
+            message_budget: 1200,
+            players: [(0, 0); MAXIMUM_NUMBER_OF_PLAYERS],
+        }
+    }
+
+    fn penalty(&self, jersey_number: u8) -> Option<u8> {
+        let index = usize::from(jersey_number).checked_sub(1)?;
+        self.players.get(index).map(|(penalty, _)| *penalty)
+    }
+}
+
+fn open_game_controller_socket(mut game_controller: ResMut<GameController>) {
+    match open_socket(game_controller.data_port) {
+        Ok(socket) => game_controller.socket = Some(socket),
+        Err(error) => error!("{error:?}"),
+    }
+}
+
+fn open_socket(port: u16) -> Result<UdpSocket> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, port))
+        .wrap_err_with(|| format!("failed to bind GameController port {port}"))?;
+    socket
+        .set_nonblocking(true)
+        .wrap_err("failed to make GameController socket non-blocking")?;
+    socket
+        .set_broadcast(true)
+        .wrap_err("failed to enable broadcast on GameController socket")?;
+    Ok(socket)
+}
+
+fn receive_game_controller_data(
+    mut game_controller: ResMut<GameController>,
+    mut state: ResMut<GameControllerState>,
+) {
+    let Some(socket) = &game_controller.socket else {
+        return;
+    };
+    let mut buffer = [0; 1024];
+    let mut latest = None;
+    while let Ok((size, address)) = socket.recv_from(&mut buffer) {
+        if game_controller.mode == GameControllerMode::Host {
+            // our own broadcasts come back on the same port
+            continue;
+        }
+        if let Some(received) = parse_game_controller_data(&buffer[..size]) {
+            latest = Some((received, address));
+        }
+    }
+    if let Some((received, address)) = latest {
+        *state = received;
+        game_controller.game_controller_address = Some(address);
+    }
+}
+
+/// Penalizes robots according to the GameController, robots are matched by team color and jersey
+/// number.
+fn apply_penalties(
+    game_controller: Res<GameController>,
+    state: Res<GameControllerState>,
+    mut robots: Query<(&Player, &mut RobotStatus)>,
+) {
+    if game_controller.mode != GameControllerMode::Listen {
+        return;
+    }
+    for (player, mut status) in robots.iter_mut() {
+        let Some(penalty) = state
+            .teams
+            .iter()
+            .find(|team| team.field_player_color == player.team_color)
+            .and_then(|team| team.penalty(player.jersey_number))
+        else {
+            continue;
+        };
+        let penalized = penalty != 0;
+        if status.penalized != penalized {
+            status.penalized = penalized;
+        }
+    }
+}
+
+fn count_goals(
+    game_controller: Res<GameController>,
+    mut goals: EventReader<GoalScored>,
+    mut state: ResMut<GameControllerState>,
+) {
+    for goal in goals.iter() {
+        if game_controller.mode != GameControllerMode::Host {
+            continue;
+        }
+        let Some(team) = goal.team.and_then(|color| {
+            state
+                .teams
+                .iter_mut()
+                .find(|team| team.field_player_color == color)
+        }) else {
+            continue;
+        };
+        team.score = team.score.saturating_add(1);
+        let team_number = team.team_number;
+        // the conceding team kicks off
+        if let Some(other) = state
+            .teams
+            .iter()
+            .find(|team| team.team_number != team_number)
+        {
+            state.kicking_team = other.team_number;
+        }
+        state.state = GameState::Ready;
+    }
+}
+
+fn game_controller_ui(
+    mut contexts: EguiContexts,
+    mut game_controller: ResMut<GameController>,
+    mut state: ResMut<GameControllerState>,
+) {
+    egui::Window::new("GameController")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.radio_value(
+                    &mut game_controller.mode,
+                    GameControllerMode::Listen,
+                    "listen",
+                );
+                ui.radio_value(&mut game_controller.mode, GameControllerMode::Host, "host");
+            });
+            let teams = &state.teams;
+            ui.label(format!(
+                "{:?} | {} {} : {} {} | kickoff {}",
+                state.state,
+                teams[0].team_number,
+                teams[0].score,
+                teams[1].score,
+                teams[1].team_number,
+                state.kicking_team,
+            ));
+            if game_controller.mode != GameControllerMode::Host {
+                return;
+            }
+            ui.horizontal(|ui| {
+                for game_state in GameState::ALL {
+                    if ui
+                        .selectable_label(state.state == game_state, format!("{game_state:?}"))
+                        .clicked()
+                    {
+                        state.state = game_state;
+                    }
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("kickoff");
+                for index in 0..2 {
+                    let team_number = state.teams[index].team_number;
+                    if ui
+                        .selectable_label(
+                            state.kicking_team == team_number,
+                            team_number.to_string(),
+                        )
+                        .clicked()
+                    {
+                        state.kicking_team = team_number;
+                    }
+                }
+            });
+            if ui.button("switch half").clicked() {
+                state.first_half = !state.first_half;
+                state.state = GameState::Initial;
+            }
+        });
+}
+
+/// Counts down the remaining time of the half while playing, in simulation time.
+fn run_game_clock(
+    simulation_time: Res<SimulationTime>,
+    mut previous_time: Local<f64>,
+    mut game_controller: ResMut<GameController>,
+    mut state: ResMut<GameControllerState>,
+) {
+    let now = simulation_time.elapsed_seconds();
+    let delta = now - std::mem::replace(&mut *previous_time, now);
+    if game_controller.mode != GameControllerMode::Host || state.state != GameState::Playing {
+        return;
+    }
+    game_controller.clock += delta as f32;
+    while game_controller.clock >= 1.0 {
+        game_controller.clock -= 1.0;
+        state.seconds_remaining = (state.seconds_remaining - 1).max(0);
+    }
+}
+
+fn broadcast_game_controller_data(
+    mut game_controller: ResMut<GameController>,
+    mut state: ResMut<GameControllerState>,
+    robots: Query<(&Player, &RobotStatus)>,
+) {
+    if game_controller.mode != GameControllerMode::Host {
+        return;
+    }
+    let team_numbers = game_controller.team_numbers;
+    for (team, team_number) in state.teams.iter_mut().zip(team_numbers) {
+        team.team_number = team_number;
+        team.players = [(0, 0); MAXIMUM_NUMBER_OF_PLAYERS];
+        for (player, status) in robots.iter() {
+            if player.team_color != team.field_player_color || !status.penalized {
+                continue;
+            }
+            if let Some(slot) = usize::from(player.jersey_number)
+                .checked_sub(1)
+                .and_then(|index| team.players.get_mut(index))
+            {
+                *slot = (PENALTY_MANUAL, 0);
+            }
+        }
+    }
+    let Some(socket) = &game_controller.socket else {
+        return;
+    };
+    let packet = serialize_game_controller_data(&state, game_controller.packet_number);
+    let address = (Ipv4Addr::BROADCAST, game_controller.data_port);
+    if let Err(error) = socket.send_to(&packet, address) {
+        warn!("Failed to broadcast GameController data: {error}");
+    }
+    game_controller.packet_number = game_controller.packet_number.wrapping_add(1);
+}
+
+/// Sends a return packet with the ground truth pose of every robot to the GameController.
+fn send_return_data(
+    game_controller: Res<GameController>,
+    state: Res<GameControllerState>,
+    frame: Res<CoordinateFrame>,
+    robots: Query<(&Player, &RobotStatus, &GlobalTransform, Option<&Arena>), With<RobotRoot>>,
+    balls: Query<(&GlobalTransform, Option<&Arena>), With<Ball>>,
+) {
+    if game_controller.mode != GameControllerMode::Listen {
+        return;
+    }
+    let (Some(socket), Some(address)) = (
+        &game_controller.socket,
+        game_controller.game_controller_address,
+    ) else {
+        return;
+    };
+    let address = SocketAddr::new(address.ip(), game_controller.return_port);
+    let ball = balls
+        .iter()
+        .find(|(_, arena)| in_main_arena(*arena))
+        .map(|(ball, _)| frame.to_field(ball.translation()));
+    for (player, status, transform, _) in robots.iter().filter(|(.., arena)| in_main_arena(*arena))
+    {
+        let Some(team) = state
+            .teams
+            .iter()
+            .find(|team| team.field_player_color == player.team_color)
+        else {
+            continue;
+        };
+        let Transform {
+            translation,
+            rotation,
+            ..
+        } = frame.transform_to_field(transform.compute_transform());
+        let (yaw, _, _) = rotation.to_euler(EulerRot::ZYX);
+        let relative_ball = ball.map(|ball| {
+            let relative = Quat::from_rotation_z(-yaw) * (ball - translation);
+            relative.truncate()
+        });
+        let packet = serialize_return_data(
+            player.jersey_number,
+            team.team_number,
+            status.fallen,
+            translation.truncate(),
+            yaw,
+            relative_ball,
+        );
+        if let Err(error) = socket.send_to(&packet, address) {
+            warn!("Failed to send GameController return data: {error}");
+        }
+    }
+}
+
+fn parse_game_controller_data(bytes: &[u8]) -> Option<GameControllerState> {
+    if bytes.len() != GAME_CONTROLLER_DATA_SIZE
+        || &bytes[0..4] != GAME_CONTROLLER_DATA_HEADER
+        || bytes[4] != GAME_CONTROLLER_DATA_VERSION
+    {
+        return None;
+    }
+    let parse_team = |bytes: &[u8]| -> Option<TeamState> {
+        let mut players = [(0, 0); MAXIMUM_NUMBER_OF_PLAYERS];
+        for (index, player) in players.iter_mut().enumerate() {
+            *player = (bytes[10 + 2 * index], bytes[11 + 2 * index]);
+        }
+        Some(TeamState {
+            team_number: bytes[0],
+            field_player_color: team_color_from_u8(bytes[1])?,
+            goalkeeper_color: team_color_from_u8(bytes[2])?,
+            goalkeeper: bytes[3],
+            score: bytes[4],
+            message_budget: u16::from_le_bytes([bytes[8], bytes[9]]),
+            players,
+        })
+    };
+    Some(GameControllerState {
+        game_phase: bytes[9],
+        state: GameState::from_u8(bytes[10])?,
+        set_play: bytes[11],
+        first_half: bytes[12] != 0,
+        kicking_team: bytes[13],
+        seconds_remaining: i16::from_le_bytes([bytes[14], bytes[15]]),
+        secondary_time: i16::from_le_bytes([bytes[16], bytes[17]]),
+        teams: [
+            parse_team(&bytes[18..18 + TEAM_INFO_SIZE])?,
+            parse_team(&bytes[18 + TEAM_INFO_SIZE..])?,
+        ],
+    })
+}
+
+fn serialize_game_controller_data(state: &GameControllerState, packet_number: u8) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(GAME_CONTROLLER_DATA_SIZE);
+    packet.extend_from_slice(GAME_CONTROLLER_DATA_HEADER);
+    packet.extend_from_slice(&[
+        GAME_CONTROLLER_DATA_VERSION,
+        packet_number,
+        MAXIMUM_NUMBER_OF_PLAYERS as u8,
+        // competition phase and type: round robin, normal
+        0,
+        0,
+        state.game_phase,
+        state.state.to_u8(),
+        state.set_play,
+        u8::from(state.first_half),
+        state.kicking_team,
+    ]);
+    packet.extend_from_slice(&state.seconds_remaining.to_le_bytes());
+    packet.extend_from_slice(&state.secondary_time.to_le_bytes());
+    for team in &state.teams {
+        packet.extend_from_slice(&[
+            team.team_number,
+            team.field_player_color as u8,
+            team.goalkeeper_color as u8,
+            team.goalkeeper,
+            team.score,
+            // penalty shot counter
+            0,
+        ]);
+        // single shots bitmask
+        packet.extend_from_slice(&0u16.to_le_bytes());
+        packet.extend_from_slice(&team.message_budget.to_le_bytes());
+        for (penalty, seconds_until_unpenalized) in team.players {
+            packet.extend_from_slice(&[penalty, seconds_until_unpenalized]);
+        }
+    }
+    packet
+}
+
+fn serialize_return_data(
+    jersey_number: u8,
+    team_number: u8,
+    fallen: bool,
+    position: Vec2,
+    orientation: f32,
+    relative_ball: Option<Vec2>,
+) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(32);
+    packet.extend_from_slice(GAME_CONTROLLER_RETURN_HEADER);
+    packet.extend_from_slice(&[
+        GAME_CONTROLLER_RETURN_VERSION,
+        jersey_number,
+        team_number,
+        u8::from(fallen),
+    ]);
+    // positions in millimeters, the ball age is -1 if the ball was never seen
+    for value in [position.x * 1000.0, position.y * 1000.0, orientation] {
+        packet.extend_from_slice(&value.to_le_bytes());
+    }
+    let (ball_age, ball) = match relative_ball {
+        Some(ball) => (0.0f32, ball * 1000.0),
+        None => (-1.0, Vec2::ZERO),
+    };
+    packet.extend_from_slice(&ball_age.to_le_bytes());
+    packet.extend_from_slice(&ball.x.to_le_bytes());
+    packet.extend_from_slice(&ball.y.to_le_bytes());
+    packet
+}
+
+fn team_color_from_u8(value: u8) -> Option<TeamColor> {
+    Some(match value {
+        0 => TeamColor::Blue,
+        1 => TeamColor::Red,
+        2 => TeamColor::Yellow,
+        3 => TeamColor::Black,
+        4 => TeamColor::White,
+        5 => TeamColor::Green,
+        6 => TeamColor::Orange,
+        7 => TeamColor::Purple,
+        8 => TeamColor::Brown,
+        9 => TeamColor::Gray,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state() -> GameControllerState {
+        let mut state = GameControllerState {
+            state: GameState::Playing,
+            game_phase: 1,
+            set_play: 3,
+            first_half: false,
+            kicking_team: 24,
+            seconds_remaining: -12,
+            secondary_time: 30,
+            teams: [
+                TeamState::new(24, TeamColor::Yellow),
+                TeamState::new(5, TeamColor::Black),
+            ],
+        };
+        state.teams[0].goalkeeper_color = TeamColor::Purple;
+        state.teams[0].score = 2;
+        state.teams[0].players[4] = (PENALTY_MANUAL, 45);
+        state.teams[1].goalkeeper = 7;
+        state.teams[1].message_budget = 1185;
+        state
+    }
+
+    #[test]
+    fn game_controller_data_round_trip() {
+        let state = state();
+        let packet = serialize_game_controller_data(&state, 42);
+        assert_eq!(packet.len(), GAME_CONTROLLER_DATA_SIZE);
+        let parsed = parse_game_controller_data(&packet).unwrap();
+        assert_eq!(parsed.state, state.state);
+        assert_eq!(parsed.game_phase, state.game_phase);
+        assert_eq!(parsed.set_play, state.set_play);
+        assert_eq!(parsed.first_half, state.first_half);
+        assert_eq!(parsed.kicking_team, state.kicking_team);
+        assert_eq!(parsed.seconds_remaining, state.seconds_remaining);
+        assert_eq!(parsed.secondary_time, state.secondary_time);
+        for (parsed, team) in parsed.teams.iter().zip(&state.teams) {
+            assert_eq!(parsed.team_number, team.team_number);
+            assert_eq!(parsed.field_player_color, team.field_player_color);
+            assert_eq!(parsed.goalkeeper_color, team.goalkeeper_color);
+            assert_eq!(parsed.goalkeeper, team.goalkeeper);
+            assert_eq!(parsed.score, team.score);
+            assert_eq!(parsed.message_budget, team.message_budget);
+            assert_eq!(parsed.players, team.players);
+        }
+    }
+
+    #[test]
+    fn game_controller_data_layout() {
+        let packet = serialize_game_controller_data(&state(), 42);
+        assert_eq!(&packet[0..4], b"RGme");
+        assert_eq!(packet[4], 15);
+        assert_eq!(packet[5], 42);
+        assert_eq!(packet[6], 20);
+        assert_eq!(packet[10], 3);
+        assert_eq!(i16::from_le_bytes([packet[14], packet[15]]), -12);
+        // first team info
+        assert_eq!(packet[18], 24);
+        assert_eq!(packet[19], TeamColor::Yellow as u8);
+        assert_eq!(packet[20], TeamColor::Purple as u8);
+        assert_eq!(
+            &packet[18 + 10 + 2 * 4..18 + 10 + 2 * 5],
+            &[PENALTY_MANUAL, 45]
+        );
+        // second team info
+        assert_eq!(packet[18 + TEAM_INFO_SIZE], 5);
+        assert_eq!(
+            u16::from_le_bytes([
+                packet[18 + TEAM_INFO_SIZE + 8],
+                packet[18 + TEAM_INFO_SIZE + 9]
+            ]),
+            1185
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_game_controller_data() {
+        let packet = serialize_game_controller_data(&state(), 0);
+        assert!(parse_game_controller_data(&packet[..packet.len() - 1]).is_none());
+        let mut wrong_version = packet.clone();
+        wrong_version[4] = GAME_CONTROLLER_DATA_VERSION - 1;
+        assert!(parse_game_controller_data(&wrong_version).is_none());
+        let mut wrong_state = packet;
+        wrong_state[10] = 6;
+        assert!(parse_game_controller_data(&wrong_state).is_none());
+    }
+
+    #[test]
+    fn return_data_layout() {
+        let packet = serialize_return_data(3, 24, true, Vec2::new(1.5, -2.0), 0.5, None);
+        assert_eq!(packet.len(), 32);
+        assert_eq!(&packet[0..8], &[b'R', b'G', b'r', b't', 4, 3, 24, 1]);
+        let float =
+            |offset: usize| f32::from_le_bytes(packet[offset..offset + 4].try_into().unwrap());
+        assert_eq!(float(8), 1500.0);
+        assert_eq!(float(12), -2000.0);
+        assert_eq!(float(16), 0.5);
+        assert_eq!(float(20), -1.0);
+    }
+}
@@ -0,0 +1,136 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts, EguiPlugin};
+
+use crate::{
+    game_controller::{GameController, GameControllerMode, GameControllerState, GameState},
+    player::TeamColor,
+};
+
+/// Tracks the phase of the game, raising a [`GamePhaseChanged`] event on every transition.
+///
+/// The phase follows the GameController. It can also be switched from the UI, a listening
+/// GameController overrides manual changes with its next packet.
+pub struct GamePhasePlugin;
+
+impl Plugin for GamePhasePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GamePhase>()
+            .add_event::<GamePhaseChanged>()
+            .add_system(follow_game_controller.run_if(resource_changed::<GameControllerState>()))
+            .add_system(send_game_phase_changes.after(follow_game_controller));
+        if app.is_plugin_added::<EguiPlugin>() {
+            app.add_system(game_phase_ui.before(send_game_phase_changes));
+        }
+    }
+}
+
+/// Set plays as numbered by the GameController.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SetPlay {
+    #[default]
+    None,
+    GoalKick,
+    PushingFreeKick,
+    CornerKick,
+    KickIn,
+    PenaltyKick,
+}
+
+impl SetPlay {
+    const ALL: [SetPlay; 6] = [
+        SetPlay::None,
+        SetPlay::GoalKick,
+        SetPlay::PushingFreeKick,
+        SetPlay::CornerKick,
+        SetPlay::KickIn,
+        SetPlay::PenaltyKick,
+    ];
+
+    fn from_u8(value: u8) -> Option<Self> {
+        Self::ALL.get(usize::from(value)).copied()
+    }
+
+    fn to_u8(self) -> u8 {
+        Self::ALL
+            .iter()
+            .position(|set_play| *set_play == self)
+            .expect("every set play is listed") as u8
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Resource)]
+pub struct GamePhase {
+    pub state: GameState,
+    pub set_play: SetPlay,
+    /// Team that has kickoff or the current set play
+    pub kicking_team: Option<TeamColor>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct GamePhaseChanged {
+    pub previous: GamePhase,
+    pub current: GamePhase,
+}
+
+fn follow_game_controller(state: Res<GameControllerState>, mut phase: ResMut<GamePhase>) {
+    let followed = GamePhase {
+        state: state.state,
+        set_play: SetPlay::from_u8(state.set_play).unwrap_or_default(),
+        kicking_team: state
+            .teams
+            .iter()
+            .find(|team| team.team_number == state.kicking_team)
+            .map(|team| team.field_player_color),
+    };
+    if *phase != followed {
+        *phase = followed;
+    }
+}
+
+fn send_game_phase_changes(
+    phase: Res<GamePhase>,
+    mut previous: Local<GamePhase>,
+    mut changes: EventWriter<GamePhaseChanged>,
+) {
+    if *phase == *previous {
+        return;
+    }
+    info!("Game phase {:?} -> {:?}", *previous, *phase);
+    changes.send(GamePhaseChanged {
+        previous: *previous,
+        current: *phase,
+    });
+    *previous = *phase;
+}
+
+fn game_phase_ui(
+    mut contexts: EguiContexts,
+    game_controller: Res<GameController>,
+    mut game_controller_state: ResMut<GameControllerState>,
+    mut phase: ResMut<GamePhase>,
+) {
+    let mut changed = *phase;
+    egui::Window::new("Game Phase")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                for state in GameState::ALL {
+                    ui.selectable_value(&mut changed.state, state, format!("{state:?}"));
+                }
+            });
+            ui.horizontal(|ui| {
+                for set_play in SetPlay::ALL {
+                    ui.selectable_value(&mut changed.set_play, set_play, format!("{set_play:?}"));
+                }
+            });
+        });
+    if changed == *phase {
+        return;
+    }
+    *phase = changed;
+    // a hosted GameController broadcasts the manual phase to the robots
+    if game_controller.mode == GameControllerMode::Host {
+        game_controller_state.state = changed.state;
+        game_controller_state.set_play = changed.set_play.to_u8();
+    }
+}
@@ -0,0 +1,227 @@
+use std::{collections::HashMap, path::Path};
+
+use bevy::{pbr::NotShadowCaster, prelude::*, transform::TransformSystem};
+use bevy_egui::{egui, EguiContexts, EguiPlugin};
+use bevy_rapier3d::prelude::*;
+use color_eyre::Result;
+
+use crate::{
+    recording::Recording,
+    scene_reset::ResetScene,
+    simulation_time::{run_physics_schedule, SimulationTime},
+    snapshot::BodyKeys,
+    Ball, RobotLink,
+};
+
+/// Color of the ghosts, translucent so the live bodies stay visible inside them
+const GHOST_COLOR: Color = Color::rgba(0.6, 0.8, 1.0, 0.35);
+
+/// Shows the robots and balls of a recording as translucent ghosts within the live simulation,
+/// to compare a run against a baseline step by step.
+///
+/// Ghosts show the recorded step at the current simulation time and start over with the
+/// recording when the scene is reset. Like replays, ghosts are matched to bodies by their keys
+/// and need the team configuration the recording was made with.
+pub struct GhostReplayPlugin;
+
+impl Plugin for GhostReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(spawn_ghosts.run_if(resource_exists::<GhostReplay>()))
+            .add_system(restart_ghosts_on_reset.run_if(resource_exists::<GhostReplay>()))
+            .add_system(
+                move_ghosts
+                    .in_base_set(CoreSet::PostUpdate)
+                    .after(run_physics_schedule)
+                    .before(TransformSystem::TransformPropagate)
+                    .run_if(resource_exists::<GhostReplay>()),
+            );
+        if app.is_plugin_added::<EguiPlugin>() {
+            app.add_system(ghost_replay_ui.run_if(resource_exists::<GhostReplay>()));
+        }
+    }
+}
+
+/// A recording shown as ghosts alongside the simulation.
+#[derive(Resource)]
+pub struct GhostReplay {
+    recording: Recording,
+    /// Simulation time the recording is aligned to start at in seconds
+    pub start: f64,
+    pub visible: bool,
+    /// Index of the step currently shown, `None` after a change
+    shown: Option<usize>,
+}
+
+impl GhostReplay {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self {
+            recording: Recording::load(path)?,
+            start: 0.0,
+            visible: true,
+            shown: None,
+        })
+    }
+}
+
+/// Translucent copy of the recorded body with this key.
+#[derive(Component)]
+struct Ghost {
+    key: String,
+}
+
+/// Mirrors the robot links and balls together with their visuals, whenever robots or balls are
+/// spawned.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn spawn_ghosts(
+    mut commands: Commands,
+    mut ghost_replay: ResMut<GhostReplay>,
+    mut material: Local<Option<Handle<StandardMaterial>>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    keys: BodyKeys,
+    added: Query<(), Or<(Added<RobotLink>, Added<Ball>)>>,
+    bodies: Query<
+        (Entity, Option<&Parent>, Option<&Handle<Mesh>>),
+        (With<RigidBody>, Or<(With<RobotLink>, With<Ball>)>),
+    >,
+    children: Query<&Children>,
+    visuals: Query<(&Handle<Mesh>, &Transform), (Without<RobotLink>, Without<Ball>)>,
+    ghosts: Query<Entity, (With<Ghost>, Without<Parent>)>,
+) {
+    if added.is_empty() {
+        return;
+    }
+    for ghost in ghosts.iter() {
+        commands.entity(ghost).despawn_recursive();
+    }
+    let material = material
+        .get_or_insert_with(|| {
+            materials.add(StandardMaterial {
+                base_color: GHOST_COLOR,
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..Default::default()
+            })
+        })
+        .clone();
+    let mut ghost_of = HashMap::new();
+    for (body, _, mesh) in bodies.iter() {
+        let Some(key) = keys.key(body) else {
+            continue;
+        };
+        let mut ghost = commands.spawn((
+            Ghost { key: key.clone() },
+            Name::new(format!("ghost {key}")),
+            SpatialBundle {
+                visibility: Visibility::Hidden,
+                ..Default::default()
+            },
+            NotShadowCaster,
+        ));
+        if let Some(mesh) = mesh {
+            ghost.insert((mesh.clone(), material.clone()));
+        }
+        for child in children
+            .get(body)
+            .iter()
+            .flat_map(|children| children.iter())
+        {
+            if let Ok((mesh, transform)) = visuals.get(*child) {
+                ghost.with_children(|ghost| {
+                    ghost.spawn((
+                        PbrBundle {
+                            mesh: mesh.clone(),
+                            material: material.clone(),
+                            transform: *transform,
+                            ..Default::default()
+                        },
+                        NotShadowCaster,
+                    ));
+                });
+            }
+        }
+        ghost_of.insert(body, ghost.id());
+    }
+    // recorded transforms are local, the ghosts need the hierarchy of their bodies
+    for (body, parent, _) in bodies.iter() {
+        if let (Some(ghost), Some(parent_ghost)) = (
+            ghost_of.get(&body),
+            parent.and_then(|parent| ghost_of.get(&parent.get())),
+        ) {
+            commands.entity(*parent_ghost).add_child(*ghost);
+        }
+    }
+    ghost_replay.shown = None;
+}
+
+fn restart_ghosts_on_reset(
+    simulation_time: Res<SimulationTime>,
+    mut resets: EventReader<ResetScene>,
+    mut ghost_replay: ResMut<GhostReplay>,
+) {
+    if resets.iter().count() > 0 {
+        ghost_replay.start = simulation_time.elapsed_seconds();
+        ghost_replay.shown = None;
+    }
+}
+
+/// Poses the ghosts as recorded at the current simulation time, ghosts of bodies missing in the
+/// recording are hidden.
+fn move_ghosts(
+    simulation_time: Res<SimulationTime>,
+    mut ghost_replay: ResMut<GhostReplay>,
+    mut ghosts: Query<(&Ghost, &mut Transform, &mut Visibility)>,
+) {
+    let index = ghost_replay
+        .recording
+        .index_at(simulation_time.elapsed_seconds() - ghost_replay.start);
+    if ghost_replay.shown == index {
+        return;
+    }
+    ghost_replay.shown = index;
+    let recorded: HashMap<_, _> = index
+        .filter(|_| ghost_replay.visible)
+        .map(|index| {
+            ghost_replay
+                .recording
+                .bodies(index)
+                .iter()
+                .map(|(key, transform)| (key.as_str(), *transform))
+                .collect()
+        })
+        .unwrap_or_default();
+    for (ghost, mut transform, mut visibility) in ghosts.iter_mut() {
+        match recorded.get(ghost.key.as_str()) {
+            Some(recorded) => {
+                *transform = *recorded;
+                *visibility = Visibility::Inherited;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
+fn ghost_replay_ui(
+    mut contexts: EguiContexts,
+    simulation_time: Res<SimulationTime>,
+    mut ghost_replay: ResMut<GhostReplay>,
+) {
+    let duration = ghost_replay.recording.duration();
+    egui::Window::new("Ghost")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            let mut visible = ghost_replay.visible;
+            if ui.checkbox(&mut visible, "show ghosts").changed() {
+                ghost_replay.visible = visible;
+                ghost_replay.shown = None;
+            }
+            let time = simulation_time.elapsed_seconds() - ghost_replay.start;
+            ui.label(format!(
+                "{:.2} s of {duration:.2} s",
+                time.clamp(0.0, duration)
+            ));
+            if ui.button("restart now").clicked() {
+                ghost_replay.start = simulation_time.elapsed_seconds();
+                ghost_replay.shown = None;
+            }
+        });
+}
@@ -0,0 +1,362 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    prelude::*,
+    render::mesh::{Indices, PrimitiveTopology, VertexAttributeValues},
+};
+use color_eyre::{eyre::WrapErr, Result};
+use serde_json::{json, Value};
+
+use crate::{
+    capture::{create_parent_directory, timestamped_path},
+    coordinate_frame::CoordinateFrame,
+    shortcuts::{dispatch_shortcuts, triggered, ShortcutAction},
+};
+
+const GLB_MAGIC: u32 = 0x4654_6c67;
+const GLB_VERSION: u32 = 2;
+const JSON_CHUNK: u32 = 0x4e4f_534a;
+const BINARY_CHUNK: u32 = 0x004e_4942;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+
+/// Writes the current posed scene as binary glTF, e.g. for rendering stills in Blender.
+///
+/// The entity hierarchy is kept, so robots arrive as their link trees with the joint angles of the
+/// moment of the export. Visible triangle meshes are exported with the base color, metallic and
+/// roughness of their materials, textures are not. The scene is rotated into the y up convention
+/// of glTF.
+pub struct GltfExportPlugin;
+
+impl Plugin for GltfExportPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ExportScene>()
+            .add_system(export_scene_on_shortcut.after(dispatch_shortcuts))
+            .add_system(export_scenes.after(export_scene_on_shortcut));
+    }
+}
+
+/// Exports the scene to a `.glb` file at the path.
+pub struct ExportScene(pub PathBuf);
+
+fn export_scene_on_shortcut(
+    mut actions: EventReader<ShortcutAction>,
+    mut exports: EventWriter<ExportScene>,
+) {
+    if triggered(&mut actions, ShortcutAction::ExportScene) {
+        exports.send(ExportScene(timestamped_path("scene", "glb")));
+    }
+}
+
+type SceneNode = (
+    &'static Transform,
+    Option<&'static Name>,
+    Option<&'static Handle<Mesh>>,
+    Option<&'static Handle<StandardMaterial>>,
+    Option<&'static ComputedVisibility>,
+);
+
+fn export_scenes(
+    mut exports: EventReader<ExportScene>,
+    frame: Res<CoordinateFrame>,
+    meshes: Res<Assets<Mesh>>,
+    materials: Res<Assets<StandardMaterial>>,
+    roots: Query<Entity, (With<Transform>, Without<Parent>)>,
+    nodes: Query<SceneNode>,
+    children: Query<&'static Children>,
+) {
+    for ExportScene(path) in exports.iter() {
+        let mut builder = GltfBuilder {
+            meshes: &meshes,
+            materials: &materials,
+            nodes: &nodes,
+            children: &children,
+            document: Document::default(),
+            mesh_indices: HashMap::new(),
+            material_indices: HashMap::new(),
+        };
+        let scene_roots: Vec<_> = roots
+            .iter()
+            .filter_map(|root| builder.add_node(root))
+            .collect();
+        let up = Quat::from_rotation_arc(frame.up(), Vec3::Y);
+        let mut scene = json!({ "name": "scene", "rotation": up.to_array() });
+        if !scene_roots.is_empty() {
+            scene["children"] = json!(scene_roots);
+        }
+        builder.document.nodes.push(scene);
+        let document = builder.document;
+        match document.save(path) {
+            Ok(()) => info!("Exported scene to {}", path.display()),
+            Err(error) => error!("{error:?}"),
+        }
+    }
+}
+
+/// glTF document under construction, all buffer views point into [`Self::binary`].
+#[derive(Default)]
+struct Document {
+    binary: Vec<u8>,
+    buffer_views: Vec<Value>,
+    accessors: Vec<Value>,
+    meshes: Vec<Value>,
+    materials: Vec<Value>,
+    nodes: Vec<Value>,
+}
+
+impl Document {
+    /// Appends `bytes` to the binary buffer and returns the index of their buffer view.
+    fn add_buffer_view(&mut self, bytes: &[u8], target: u32) -> usize {
+        let offset = self.binary.len();
+        self.binary.extend_from_slice(bytes);
+        // accessors need their components aligned
+        self.binary.resize(self.binary.len().next_multiple_of(4), 0);
+        self.buffer_views.push(json!({
+            "buffer": 0,
+            "byteOffset": offset,
+            "byteLength": bytes.len(),
+            "target": target,
+        }));
+        self.buffer_views.len() - 1
+    }
+
+    fn add_vectors<const N: usize>(&mut self, values: &[[f32; N]], bounds: bool) -> usize {
+        let bytes: Vec<u8> = values
+            .iter()
+            .flatten()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        let view = self.add_buffer_view(&bytes, ARRAY_BUFFER);
+        let mut accessor = json!({
+            "bufferView": view,
+            "componentType": FLOAT,
+            "count": values.len(),
+            "type": format!("VEC{N}"),
+        });
+        // positions require bounds
+        if bounds {
+            let mut min = [f32::INFINITY; N];
+            let mut max = [f32::NEG_INFINITY; N];
+            for value in values {
+                for axis in 0..N {
+                    min[axis] = min[axis].min(value[axis]);
+                    max[axis] = max[axis].max(value[axis]);
+                }
+            }
+            accessor["min"] = json!(min.to_vec());
+            accessor["max"] = json!(max.to_vec());
+        }
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    fn add_indices(&mut self, indices: &[u32]) -> usize {
+        let bytes: Vec<u8> = indices
+            .iter()
+            .flat_map(|index| index.to_le_bytes())
+            .collect();
+        let view = self.add_buffer_view(&bytes, ELEMENT_ARRAY_BUFFER);
+        self.accessors.push(json!({
+            "bufferView": view,
+            "componentType": UNSIGNED_INT,
+            "count": indices.len(),
+            "type": "SCALAR",
+        }));
+        self.accessors.len() - 1
+    }
+
+    fn save(self, path: &Path) -> Result<()> {
+        let mut document = json!({
+            "asset": { "version": "2.0", "generator": env!("CARGO_PKG_NAME") },
+            "scene": 0,
+            "scenes": [{ "nodes": [self.nodes.len() - 1] }],
+        });
+        // glTF forbids empty arrays
+        for (key, values) in [
+            ("nodes", self.nodes),
+            ("meshes", self.meshes),
+            ("materials", self.materials),
+            ("accessors", self.accessors),
+            ("bufferViews", self.buffer_views),
+        ] {
+            if !values.is_empty() {
+                document[key] = json!(values);
+            }
+        }
+        if !self.binary.is_empty() {
+            document["buffers"] = json!([{ "byteLength": self.binary.len() }]);
+        }
+        let mut json = serde_json::to_vec(&document)?;
+        json.resize(json.len().next_multiple_of(4), b' ');
+
+        let mut glb = Vec::new();
+        let total_length = 12
+            + 8
+            + json.len()
+            + if self.binary.is_empty() {
+                0
+            } else {
+                8 + self.binary.len()
+            };
+        for word in [GLB_MAGIC, GLB_VERSION, total_length as u32] {
+            glb.extend_from_slice(&word.to_le_bytes());
+        }
+        glb.extend_from_slice(&(json.len() as u32).to_le_bytes());
+        glb.extend_from_slice(&JSON_CHUNK.to_le_bytes());
+        glb.extend_from_slice(&json);
+        if !self.binary.is_empty() {
+            glb.extend_from_slice(&(self.binary.len() as u32).to_le_bytes());
+            glb.extend_from_slice(&BINARY_CHUNK.to_le_bytes());
+            glb.extend_from_slice(&self.binary);
+        }
+
+        create_parent_directory(path)?;
+        fs::write(path, glb).wrap_err_with(|| format!("failed to write {}", path.display()))
+    }
+}
+
+struct GltfBuilder<'a, 'w, 's> {
+    meshes: &'a Assets<Mesh>,
+    materials: &'a Assets<StandardMaterial>,
+    nodes: &'a Query<'w, 's, SceneNode>,
+    children: &'a Query<'w, 's, &'static Children>,
+    document: Document,
+    /// Exported mesh per mesh and material, `None` for meshes that cannot be exported
+    mesh_indices: HashMap<(Handle<Mesh>, Option<Handle<StandardMaterial>>), Option<usize>>,
+    material_indices: HashMap<Handle<StandardMaterial>, usize>,
+}
+
+impl GltfBuilder<'_, '_, '_> {
+    /// Adds `entity` and its descendants, `None` if it is hidden or nothing below it has a mesh.
+    fn add_node(&mut self, entity: Entity) -> Option<usize> {
+        let (nodes, children) = (self.nodes, self.children);
+        let (transform, name, mesh, material, visibility) = nodes.get(entity).ok()?;
+        if visibility.map_or(false, |visibility| !visibility.is_visible_in_hierarchy()) {
+            return None;
+        }
+        let children: Vec<_> = children
+            .get(entity)
+            .map(|children| {
+                children
+                    .iter()
+                    .filter_map(|&child| self.add_node(child))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let mesh = mesh.and_then(|mesh| self.add_mesh(mesh, material));
+        if mesh.is_none() && children.is_empty() {
+            return None;
+        }
+
+        let mut node = json!({
+            "name": name.map_or_else(|| format!("{entity:?}"), |name| name.to_string()),
+            "translation": transform.translation.to_array(),
+            "rotation": transform.rotation.to_array(),
+            "scale": transform.scale.to_array(),
+        });
+        if let Some(mesh) = mesh {
+            node["mesh"] = json!(mesh);
+        }
+        if !children.is_empty() {
+            node["children"] = json!(children);
+        }
+        self.document.nodes.push(node);
+        Some(self.document.nodes.len() - 1)
+    }
+
+    fn add_mesh(
+        &mut self,
+        handle: &Handle<Mesh>,
+        material: Option<&Handle<StandardMaterial>>,
+    ) -> Option<usize> {
+        let key = (handle.clone_weak(), material.map(Handle::clone_weak));
+        if let Some(&index) = self.mesh_indices.get(&key) {
+            return index;
+        }
+        let index = self.convert_mesh(handle, material);
+        self.mesh_indices.insert(key, index);
+        index
+    }
+
+    fn convert_mesh(
+        &mut self,
+        handle: &Handle<Mesh>,
+        material: Option<&Handle<StandardMaterial>>,
+    ) -> Option<usize> {
+        let mesh = self.meshes.get(handle)?;
+        // lines and points are gizmos
+        if mesh.primitive_topology() != PrimitiveTopology::TriangleList {
+            return None;
+        }
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            return None;
+        };
+        if positions.is_empty() {
+            return None;
+        }
+        let mut attributes = json!({
+            "POSITION": self.document.add_vectors(positions, true),
+        });
+        if let Some(VertexAttributeValues::Float32x3(normals)) =
+            mesh.attribute(Mesh::ATTRIBUTE_NORMAL)
+        {
+            attributes["NORMAL"] = json!(self.document.add_vectors(normals, false));
+        }
+        if let Some(VertexAttributeValues::Float32x2(uvs)) = mesh.attribute(Mesh::ATTRIBUTE_UV_0) {
+            attributes["TEXCOORD_0"] = json!(self.document.add_vectors(uvs, false));
+        }
+        let mut primitive = json!({ "attributes": attributes });
+        if let Some(indices) = mesh.indices() {
+            let indices: Vec<u32> = match indices {
+                Indices::U16(indices) => indices.iter().map(|&index| u32::from(index)).collect(),
+                Indices::U32(indices) => indices.clone(),
+            };
+            primitive["indices"] = json!(self.document.add_indices(&indices));
+        }
+        if let Some(material) = material.and_then(|material| self.add_material(material)) {
+            primitive["material"] = json!(material);
+        }
+        self.document
+            .meshes
+            .push(json!({ "primitives": [primitive] }));
+        Some(self.document.meshes.len() - 1)
+    }
+
+    fn add_material(&mut self, handle: &Handle<StandardMaterial>) -> Option<usize> {
+        if let Some(&index) = self.material_indices.get(handle) {
+            return Some(index);
+        }
+        let material = self.materials.get(handle)?;
+        let alpha_mode = match material.alpha_mode {
+            AlphaMode::Opaque => "OPAQUE",
+            AlphaMode::Mask(_) => "MASK",
+            _ => "BLEND",
+        };
+        let [red, green, blue, _] = material.emissive.as_linear_rgba_f32();
+        let mut value = json!({
+            "pbrMetallicRoughness": {
+                "baseColorFactor": material.base_color.as_linear_rgba_f32(),
+                "metallicFactor": material.metallic,
+                "roughnessFactor": material.perceptual_roughness,
+            },
+            "emissiveFactor": [red, green, blue],
+            "alphaMode": alpha_mode,
+            "doubleSided": material.double_sided,
+        });
+        if let AlphaMode::Mask(cutoff) = material.alpha_mode {
+            value["alphaCutoff"] = json!(cutoff);
+        }
+        self.document.materials.push(value);
+        let index = self.document.materials.len() - 1;
+        self.material_indices.insert(handle.clone_weak(), index);
+        Some(index)
+    }
+}
@@ -0,0 +1,166 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{arenas::Arenas, coordinate_frame::CoordinateFrame, field_dimensions::FieldDimensions};
+
+/// Height of the lower edge of the crossbar above the ground in meters
+pub const GOAL_HEIGHT: f32 = 0.8;
+/// Thickness of the net colliders in meters
+const NET_THICKNESS: f32 = 0.01;
+
+/// Goals on both ends of the field with posts, crossbar and a net the ball collides with.
+///
+/// The goals are rebuilt whenever the field dimensions change.
+pub struct GoalsPlugin;
+
+impl Plugin for GoalsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(spawn_goals.run_if(resource_changed::<FieldDimensions>()));
+    }
+}
+
+#[derive(Component)]
+pub struct Goal;
+
+fn spawn_goals(
+    mut commands: Commands,
+    frame: Res<CoordinateFrame>,
+    field_dimensions: Res<FieldDimensions>,
+    arenas: Res<Arenas>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    goals: Query<Entity, With<Goal>>,
+) {
+    for entity in goals.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let post_radius = field_dimensions.goal_post_diameter / 2.0;
+    let post_offset = field_dimensions.goal_inner_width / 2.0 + post_radius;
+    let post_height = GOAL_HEIGHT + field_dimensions.goal_post_diameter;
+    let depth = field_dimensions.goal_depth;
+
+    let post_mesh = meshes.add(Mesh::from(shape::Cylinder {
+        radius: post_radius,
+        height: post_height,
+        ..Default::default()
+    }));
+    let crossbar_length = 2.0 * post_offset;
+    let crossbar_mesh = meshes.add(Mesh::from(shape::Cylinder {
+        radius: post_radius,
+        height: crossbar_length,
+        ..Default::default()
+    }));
+    let post_material = materials.add(StandardMaterial {
+        base_color: Color::WHITE,
+        perceptual_roughness: 0.4,
+        ..Default::default()
+    });
+    let net_material = materials.add(StandardMaterial {
+        base_color: Color::rgba(1.0, 1.0, 1.0, 0.3),
+        alpha_mode: AlphaMode::Blend,
+        double_sided: true,
+        cull_mode: None,
+        ..Default::default()
+    });
+
+    // cuboids of the net as (center, half extents) in the frame of a goal opening towards -x
+    let net_parts = [
+        (
+            Vec3::new(depth, 0.0, GOAL_HEIGHT / 2.0),
+            Vec3::new(NET_THICKNESS, post_offset, GOAL_HEIGHT / 2.0),
+        ),
+        (
+            Vec3::new(depth / 2.0, post_offset, GOAL_HEIGHT / 2.0),
+            Vec3::new(depth / 2.0, NET_THICKNESS, GOAL_HEIGHT / 2.0),
+        ),
+        (
+            Vec3::new(depth / 2.0, -post_offset, GOAL_HEIGHT / 2.0),
+            Vec3::new(depth / 2.0, NET_THICKNESS, GOAL_HEIGHT / 2.0),
+        ),
+        (
+            Vec3::new(depth / 2.0, 0.0, GOAL_HEIGHT),
+            Vec3::new(depth / 2.0, post_offset, NET_THICKNESS),
+        ),
+    ];
+
+    for arena in arenas.iter() {
+        for (side, name) in [(1.0, "goal +x"), (-1.0, "goal -x")] {
+            let goal_line = side * (field_dimensions.length / 2.0 + post_radius);
+            let rotation = if side > 0.0 {
+                Quat::IDENTITY
+            } else {
+                Quat::from_rotation_z(PI)
+            };
+            commands
+                .spawn((
+                    TransformBundle::from(arena.transform_to_world(
+                        &frame,
+                        &field_dimensions,
+                        Transform {
+                            translation: Vec3::new(goal_line, 0.0, 0.0),
+                            rotation,
+                            ..Default::default()
+                        },
+                    )),
+                    VisibilityBundle::default(),
+                    RigidBody::Fixed,
+                    Goal,
+                    arena,
+                    Name::new(arena.name(name)),
+                ))
+                .with_children(|goal| {
+                    for y in [-post_offset, post_offset] {
+                        goal.spawn((
+                            PbrBundle {
+                                mesh: post_mesh.clone(),
+                                material: post_material.clone(),
+                                // cylinders are aligned with the y axis
+                                transform: Transform::from_xyz(0.0, y, post_height / 2.0)
+                                    .with_rotation(Quat::from_rotation_x(FRAC_PI_2)),
+                                ..Default::default()
+                            },
+                            Collider::cylinder(post_height / 2.0, post_radius),
+                            CollisionGroups::new(Group::GROUP_4, Group::ALL),
+                            Name::new("post"),
+                        ));
+                    }
+                    goal.spawn((
+                        PbrBundle {
+                            mesh: crossbar_mesh.clone(),
+                            material: post_material.clone(),
+                            transform: Transform::from_xyz(0.0, 0.0, GOAL_HEIGHT + post_radius),
+                            ..Default::default()
+                        },
+                        Collider::cylinder(crossbar_length / 2.0, post_radius),
+                        CollisionGroups::new(Group::GROUP_4, Group::ALL),
+                        Name::new("crossbar"),
+                    ));
+                    for (center, half_extents) in net_parts {
+                        goal.spawn((
+                            PbrBundle {
+                                mesh: meshes.add(Mesh::from(shape::Box::new(
+                                    half_extents.x * 2.0,
+                                    half_extents.y * 2.0,
+                                    half_extents.z * 2.0,
+                                ))),
+                                material: net_material.clone(),
+                                transform: Transform::from_translation(center),
+                                ..Default::default()
+                            },
+                            Collider::cuboid(half_extents.x, half_extents.y, half_extents.z),
+                            CollisionGroups::new(Group::GROUP_4, Group::ALL),
+                            // the net catches the ball instead of bouncing it back
+                            Restitution {
+                                coefficient: 0.0,
+                                combine_rule: CoefficientCombineRule::Min,
+                            },
+                            Name::new("net"),
+                        ));
+                    }
+                });
+        }
+    }
+}
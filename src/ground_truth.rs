@@ -0,0 +1,174 @@
+use std::{
+    collections::HashMap,
+    net::{Ipv4Addr, SocketAddr, UdpSocket},
+};
+
+use bevy::{prelude::*, transform::TransformSystem};
+use bevy_rapier3d::prelude::*;
+use color_eyre::{eyre::WrapErr, Result};
+use serde::Serialize;
+
+use crate::{
+    coordinate_frame::CoordinateFrame,
+    player::{Player, TeamColor},
+    simulation_time::SimulationTime,
+    Ball, RobotRoot,
+};
+
+/// Publishes the true poses and velocities of all robots and the ball over UDP.
+///
+/// Each datagram is a MessagePack encoded [`GroundTruthMessage`] with named fields, sent at
+/// [`GroundTruth::rate`] per simulated second. Positions are in field coordinates with the
+/// ground at zero height.
+pub struct GroundTruthPlugin;
+
+impl Plugin for GroundTruthPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            publish_ground_truth
+                .in_base_set(CoreSet::PostUpdate)
+                .after(TransformSystem::TransformPropagate)
+                .run_if(resource_exists::<GroundTruth>()),
+        );
+    }
+}
+
+#[derive(Resource)]
+pub struct GroundTruth {
+    /// Receiver of the messages, may be a broadcast address
+    pub address: SocketAddr,
+    /// Messages per simulated second
+    pub rate: f32,
+    socket: UdpSocket,
+    /// Simulated time the last message was sent at
+    last_sent: Option<f64>,
+}
+
+impl GroundTruth {
+    pub fn new(address: SocketAddr, rate: f32) -> Result<Self> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+            .wrap_err("failed to bind ground truth socket")?;
+        socket
+            .set_nonblocking(true)
+            .wrap_err("failed to make ground truth socket non-blocking")?;
+        socket
+            .set_broadcast(true)
+            .wrap_err("failed to enable broadcast on ground truth socket")?;
+        Ok(Self {
+            address,
+            rate,
+            socket,
+            last_sent: None,
+        })
+    }
+}
+
+#[derive(Serialize)]
+pub struct GroundTruthMessage {
+    /// Simulated time in seconds
+    pub time: f64,
+    pub ball: Option<BodyState>,
+    pub robots: Vec<RobotState>,
+}
+
+#[derive(Serialize)]
+pub struct RobotState {
+    pub team_color: TeamColor,
+    pub jersey_number: u8,
+    /// Position and heading on the field in meters and radians
+    pub pose: [f32; 3],
+    /// State of the root link
+    pub body: BodyState,
+}
+
+#[derive(Serialize)]
+pub struct BodyState {
+    /// Position in field coordinates in meters
+    pub position: [f32; 3],
+    /// Orientation as quaternion in x, y, z, w order
+    pub orientation: [f32; 4],
+    /// Linear velocity in m/s
+    pub linear_velocity: [f32; 3],
+    /// Angular velocity in rad/s
+    pub angular_velocity: [f32; 3],
+}
+
+/// Bodies without a [`Velocity`] get theirs from the difference to the previous message.
+#[allow(clippy::type_complexity)]
+fn publish_ground_truth(
+    simulation_time: Res<SimulationTime>,
+    mut ground_truth: ResMut<GroundTruth>,
+    frame: Res<CoordinateFrame>,
+    mut previous_transforms: Local<HashMap<Entity, Transform>>,
+    balls: Query<(Entity, &GlobalTransform, Option<&Velocity>), With<Ball>>,
+    robots: Query<(Entity, &Player, &GlobalTransform, Option<&Velocity>), With<RobotRoot>>,
+) {
+    let time = simulation_time.elapsed_seconds();
+    let interval = 1.0 / f64::from(ground_truth.rate);
+    let elapsed = match ground_truth.last_sent {
+        Some(last_sent) if time - last_sent < interval => return,
+        Some(last_sent) => (time - last_sent) as f32,
+        None => 0.0,
+    };
+    ground_truth.last_sent = Some(time);
+
+    let mut body_state =
+        |entity: Entity, transform: &GlobalTransform, velocity: Option<&Velocity>| {
+            let transform = frame.transform_to_field(transform.compute_transform());
+            let previous = previous_transforms.insert(entity, transform);
+            let (linear_velocity, angular_velocity) = match (velocity, previous) {
+                (Some(velocity), _) => (
+                    frame.vector_to_field(velocity.linvel),
+                    frame.vector_to_field(velocity.angvel),
+                ),
+                (None, Some(previous)) if elapsed > 0.0 => {
+                    let (axis, angle) =
+                        (transform.rotation * previous.rotation.inverse()).to_axis_angle();
+                    (
+                        (transform.translation - previous.translation) / elapsed,
+                        axis * angle / elapsed,
+                    )
+                }
+                (None, _) => (Vec3::ZERO, Vec3::ZERO),
+            };
+            BodyState {
+                position: transform.translation.to_array(),
+                orientation: transform.rotation.to_array(),
+                linear_velocity: linear_velocity.to_array(),
+                angular_velocity: angular_velocity.to_array(),
+            }
+        };
+    let ball = balls
+        .iter()
+        .next()
+        .map(|(entity, transform, velocity)| body_state(entity, transform, velocity));
+    let robots = robots
+        .iter()
+        .map(|(entity, player, transform, velocity)| {
+            let body = body_state(entity, transform, velocity);
+            let (yaw, _, _) = Quat::from_array(body.orientation).to_euler(EulerRot::ZYX);
+            RobotState {
+                team_color: player.team_color,
+                jersey_number: player.jersey_number,
+                pose: [body.position[0], body.position[1], yaw],
+                body,
+            }
+        })
+        .collect();
+    let message = GroundTruthMessage { time, ball, robots };
+
+    let bytes = match rmp_serde::to_vec_named(&message) {
+        Ok(bytes) => bytes,
+        Err(error) => {
+            error!("failed to serialize ground truth: {error:?}");
+            return;
+        }
+    };
+    if let Err(error) = ground_truth
+        .socket
+        .send_to(&bytes, ground_truth.address)
+        .wrap_err("failed to send ground truth")
+    {
+        error!("{error:?}");
+    }
+}
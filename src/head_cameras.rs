@@ -0,0 +1,232 @@
+use bevy::{prelude::*, render::mesh::PrimitiveTopology};
+
+use crate::{
+    coordinate_frame::CoordinateFrame,
+    shortcuts::{triggered, ShortcutAction},
+    RobotLink,
+};
+
+/// Visualizes the view frustums of the NAO head cameras and their footprint on the ground.
+pub struct HeadCamerasPlugin;
+
+impl Plugin for HeadCamerasPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraFrustums>()
+            .add_system(spawn_camera_frustums)
+            .add_system(toggle_camera_frustums)
+            .add_system(update_ground_footprints.after(toggle_camera_frustums));
+    }
+}
+
+/// Intrinsics of a head camera looking along the x axis of its link.
+pub struct HeadCamera {
+    pub link: &'static str,
+    pub horizontal_fov: f32,
+    pub image_width: u32,
+    pub image_height: u32,
+}
+
+/// Cameras as defined in the gazebo section of the NAO URDF
+pub const HEAD_CAMERAS: [HeadCamera; 2] = [
+    HeadCamera {
+        link: "CameraTop",
+        horizontal_fov: 0.982_122_2,
+        image_width: 640,
+        image_height: 480,
+    },
+    HeadCamera {
+        link: "CameraBottom",
+        horizontal_fov: 0.982_122_2,
+        image_width: 640,
+        image_height: 480,
+    },
+];
+
+impl HeadCamera {
+    /// Corners of the image plane at `distance` in the camera link frame, counter-clockwise
+    /// starting at the top left.
+    fn far_corners(&self, distance: f32) -> [Vec3; 4] {
+        let half_width = distance * (self.horizontal_fov / 2.0).tan();
+        let half_height = half_width * self.image_height as f32 / self.image_width as f32;
+        [
+            Vec3::new(distance, half_width, half_height),
+            Vec3::new(distance, half_width, -half_height),
+            Vec3::new(distance, -half_width, -half_height),
+            Vec3::new(distance, -half_width, half_height),
+        ]
+    }
+}
+
+#[derive(Resource)]
+pub struct CameraFrustums {
+    pub enabled: bool,
+    /// Distance of the far plane in meters
+    pub far: f32,
+}
+
+impl Default for CameraFrustums {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            far: 5.0,
+        }
+    }
+}
+
+#[derive(Component)]
+struct CameraFrustum;
+
+#[derive(Component)]
+struct GroundFootprint {
+    camera: Entity,
+    far_corners: [Vec3; 4],
+}
+
+fn spawn_camera_frustums(
+    mut commands: Commands,
+    settings: Res<CameraFrustums>,
+    links: Query<(Entity, &RobotLink), Added<RobotLink>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (entity, link) in links.iter() {
+        let Some(camera) = HEAD_CAMERAS.iter().find(|camera| camera.link == link.name) else {
+            continue;
+        };
+        let far_corners = camera.far_corners(settings.far);
+        let mut positions = Vec::new();
+        for (index, corner) in far_corners.iter().enumerate() {
+            let next_corner = far_corners[(index + 1) % far_corners.len()];
+            positions
+                .extend([Vec3::ZERO, *corner, *corner, next_corner].map(|point| point.to_array()));
+        }
+        let mut frustum_mesh = Mesh::new(PrimitiveTopology::LineList);
+        frustum_mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        // start with a degenerate line, the footprint is computed every frame
+        let mut footprint_mesh = Mesh::new(PrimitiveTopology::LineList);
+        footprint_mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, vec![[0.0; 3]; 2]);
+
+        let material = materials.add(StandardMaterial {
+            base_color: Color::YELLOW,
+            unlit: true,
+            ..Default::default()
+        });
+        let visibility = if settings.enabled {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        let frustum = commands
+            .spawn((
+                PbrBundle {
+                    mesh: meshes.add(frustum_mesh),
+                    material: material.clone(),
+                    visibility,
+                    ..Default::default()
+                },
+                CameraFrustum,
+                Name::new(format!("{} frustum", camera.link)),
+            ))
+            .id();
+        commands.entity(entity).add_child(frustum);
+        commands.spawn((
+            PbrBundle {
+                mesh: meshes.add(footprint_mesh),
+                material,
+                visibility: Visibility::Hidden,
+                ..Default::default()
+            },
+            GroundFootprint {
+                camera: entity,
+                far_corners,
+            },
+            Name::new(format!("{} footprint", camera.link)),
+        ));
+    }
+}
+
+fn toggle_camera_frustums(
+    mut actions: EventReader<ShortcutAction>,
+    mut settings: ResMut<CameraFrustums>,
+    mut frustums: Query<&mut Visibility, With<CameraFrustum>>,
+) {
+    if !triggered(&mut actions, ShortcutAction::ToggleCameraFrustums) {
+        return;
+    }
+    settings.enabled = !settings.enabled;
+    for mut visibility in frustums.iter_mut() {
+        *visibility = if settings.enabled {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+fn update_ground_footprints(
+    settings: Res<CameraFrustums>,
+    frame: Res<CoordinateFrame>,
+    cameras: Query<&GlobalTransform>,
+    mut footprints: Query<(&GroundFootprint, &Handle<Mesh>, &mut Visibility)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    for (footprint, mesh, mut visibility) in footprints.iter_mut() {
+        let Ok(camera_transform) = cameras.get(footprint.camera) else {
+            continue;
+        };
+        let far_corners = footprint
+            .far_corners
+            .map(|corner| frame.to_field(camera_transform.transform_point(corner)));
+        let polygon = ground_polygon(frame.to_field(camera_transform.translation()), far_corners);
+        if !settings.enabled || polygon.len() < 3 {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+        *visibility = Visibility::Inherited;
+
+        let mut positions = Vec::new();
+        for (index, point) in polygon.iter().enumerate() {
+            let next_point = polygon[(index + 1) % polygon.len()];
+            // slightly above the ground to avoid z-fighting
+            positions.extend(
+                [*point, next_point].map(|point| frame.to_world(point + Vec3::Z * 0.01).to_array()),
+            );
+        }
+        if let Some(mesh) = meshes.get_mut(mesh) {
+            mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        }
+    }
+}
+
+/// Intersection of the frustum spanned by `apex` and `far_corners` in field coordinates with the
+/// ground, as a convex polygon ordered by angle.
+fn ground_polygon(apex: Vec3, far_corners: [Vec3; 4]) -> Vec<Vec3> {
+    let edges = (0..far_corners.len()).flat_map(|index| {
+        let next_index = (index + 1) % far_corners.len();
+        [
+            (apex, far_corners[index]),
+            (far_corners[index], far_corners[next_index]),
+        ]
+    });
+    let mut points: Vec<_> = edges
+        .filter_map(|(start, end)| {
+            let (start_height, end_height) = (start.z, end.z);
+            if start_height * end_height > 0.0 || start_height == end_height {
+                return None;
+            }
+            let t = start_height / (start_height - end_height);
+            Some(start.lerp(end, t))
+        })
+        .collect();
+    if points.is_empty() {
+        return points;
+    }
+
+    let center = points.iter().sum::<Vec3>() / points.len() as f32;
+    points.sort_by(|a, b| {
+        let angle_a = (a.y - center.y).atan2(a.x - center.x);
+        let angle_b = (b.y - center.y).atan2(b.x - center.x);
+        angle_a.total_cmp(&angle_b)
+    });
+    points
+}
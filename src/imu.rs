@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+
+use bevy::{math::Affine3A, prelude::*};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    coordinate_frame::CoordinateFrame,
+    simulation_rng::SimulationRng,
+    simulation_time::{PhysicsSchedule, PHYSICS_TIMESTEP},
+    RobotLink,
+};
+
+/// Link the inertial measurement unit of the NAO is mounted in
+const TORSO_LINK: &str = "Torso_link";
+
+/// Simulates the inertial measurement unit in the torso of each robot.
+///
+/// After every physics step the angular velocity and linear acceleration of the torso are
+/// derived from its pose in the last steps, biased and disturbed by noise, and published in
+/// [`ImuReadings`].
+pub struct ImuPlugin;
+
+impl Plugin for ImuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ImuNoise>()
+            .init_resource::<ImuReadings>()
+            .add_system(add_imus)
+            .add_system(
+                update_imus
+                    .after(PhysicsSet::Writeback)
+                    .in_schedule(PhysicsSchedule),
+            );
+    }
+}
+
+#[derive(Component)]
+pub struct Imu {
+    /// Standard deviation of the accelerometer noise in m/s²
+    pub accelerometer_noise: f32,
+    /// Standard deviation of the gyroscope noise in rad/s
+    pub gyroscope_noise: f32,
+    /// Constant offset of the accelerometer in the torso frame in m/s²
+    pub accelerometer_bias: Vec3,
+    /// Constant offset of the gyroscope in the torso frame in rad/s
+    pub gyroscope_bias: Vec3,
+    previous: Option<ImuState>,
+}
+
+impl Default for Imu {
+    fn default() -> Self {
+        let noise = ImuNoise::default();
+        Self {
+            accelerometer_noise: noise.accelerometer,
+            gyroscope_noise: noise.gyroscope,
+            accelerometer_bias: Vec3::ZERO,
+            gyroscope_bias: Vec3::ZERO,
+            previous: None,
+        }
+    }
+}
+
+/// Noise of the IMUs added to robots from now on.
+#[derive(Clone, Copy, Debug, Resource)]
+pub struct ImuNoise {
+    /// Standard deviation of the accelerometer noise in m/s²
+    pub accelerometer: f32,
+    /// Standard deviation of the gyroscope noise in rad/s
+    pub gyroscope: f32,
+}
+
+impl Default for ImuNoise {
+    fn default() -> Self {
+        Self {
+            accelerometer: 0.05,
+            gyroscope: 0.005,
+        }
+    }
+}
+
+/// Pose of the torso in the previous step, needed for finite differences.
+#[derive(Clone, Copy)]
+struct ImuState {
+    position: Vec3,
+    rotation: Quat,
+    linear_velocity: Vec3,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ImuReading {
+    /// Specific force in the torso frame including gravity in m/s², reads upward at rest
+    pub accelerometer: Vec3,
+    /// Angular velocity in the torso frame in rad/s
+    pub gyroscope: Vec3,
+    /// Torso roll and pitch in radians
+    pub angles: Vec2,
+}
+
+/// Latest IMU reading of every robot, keyed by the robot's root entity.
+#[derive(Default, Resource)]
+pub struct ImuReadings(pub HashMap<Entity, ImuReading>);
+
+fn add_imus(
+    mut commands: Commands,
+    noise: Res<ImuNoise>,
+    links: Query<(Entity, &RobotLink), Added<RobotLink>>,
+) {
+    for (entity, link) in links.iter() {
+        if link.name == TORSO_LINK {
+            commands.entity(entity).insert(Imu {
+                accelerometer_noise: noise.accelerometer,
+                gyroscope_noise: noise.gyroscope,
+                ..Default::default()
+            });
+        }
+    }
+}
+
+pub fn update_imus(
+    configuration: Res<RapierConfiguration>,
+    frame: Res<CoordinateFrame>,
+    mut rng: ResMut<SimulationRng>,
+    mut readings: ResMut<ImuReadings>,
+    mut imus: Query<(Entity, &RobotLink, &mut Imu)>,
+    parents: Query<&Parent>,
+    transforms: Query<&Transform>,
+) {
+    for (entity, link, mut imu) in imus.iter_mut() {
+        let Some(pose) = global_pose(entity, &parents, &transforms) else {
+            continue;
+        };
+        let (_, rotation, position) = pose.to_scale_rotation_translation();
+        let (linear_velocity, linear_acceleration, angular_velocity) = match imu.previous {
+            Some(previous) => {
+                let linear_velocity = (position - previous.position) / PHYSICS_TIMESTEP;
+                let mut delta = rotation * previous.rotation.inverse();
+                // take the shorter way around
+                if delta.w < 0.0 {
+                    delta = -delta;
+                }
+                (
+                    linear_velocity,
+                    (linear_velocity - previous.linear_velocity) / PHYSICS_TIMESTEP,
+                    delta.to_scaled_axis() / PHYSICS_TIMESTEP,
+                )
+            }
+            None => (Vec3::ZERO, Vec3::ZERO, Vec3::ZERO),
+        };
+        imu.previous = Some(ImuState {
+            position,
+            rotation,
+            linear_velocity,
+        });
+
+        let to_torso = rotation.inverse();
+        let accelerometer = to_torso * (linear_acceleration - configuration.gravity)
+            + imu.accelerometer_bias
+            + rng.gaussian_vector(imu.accelerometer_noise);
+        let gyroscope = to_torso * angular_velocity
+            + imu.gyroscope_bias
+            + rng.gaussian_vector(imu.gyroscope_noise);
+        let (_, pitch, roll) = (frame.rotation().inverse() * rotation).to_euler(EulerRot::ZYX);
+
+        readings.0.insert(
+            link.robot,
+            ImuReading {
+                accelerometer,
+                gyroscope,
+                angles: Vec2::new(roll, pitch),
+            },
+        );
+    }
+}
+
+/// Pose in the world composed from local transforms, global transforms are only propagated once
+/// per frame but the IMU is sampled every physics step.
+pub fn global_pose(
+    entity: Entity,
+    parents: &Query<&Parent>,
+    transforms: &Query<&Transform>,
+) -> Option<Affine3A> {
+    let mut pose = transforms.get(entity).ok()?.compute_affine();
+    for ancestor in parents.iter_ancestors(entity) {
+        pose = transforms.get(ancestor).ok()?.compute_affine() * pose;
+    }
+    Some(pose)
+}
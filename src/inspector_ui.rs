@@ -1,241 +1,415 @@
-// use std::any::TypeId;
-
-// use bevy::{
-//     asset::{HandleId, ReflectAsset},
-//     prelude::*,
-//     render::camera::Viewport,
-// };
-// use bevy_egui::{egui, EguiContext};
-// use bevy_inspector_egui::{
-//     bevy_inspector::{
-//         by_type_id::{ui_for_asset, ui_for_resource},
-//         hierarchy::{hierarchy_ui, SelectedEntities},
-//         ui_for_entities_shared_components, ui_for_entity_with_children,
-//     },
-//     egui::Rect,
-// };
-// use bevy_reflect::TypeRegistry;
-// use egui_dock::{DockArea, NodeIndex, Tree};
-
-// use crate::MainCamera;
-
-// pub struct InspectorUiPlugin;
-
-// impl Plugin for InspectorUiPlugin {
-//     fn build(&self, app: &mut App) {
-//         app.insert_resource(InspectorSettings::default())
-//             .insert_resource(UiState::new())
-//             .add_system_to_stage(CoreStage::PreUpdate, inspector_ui.at_end())
-//             .add_system(set_camera_viewport);
-//     }
-// }
-
-// // pub fn ui_example_system(mut egui_context: ResMut<EguiContext>) {
-// //     egui::Window::new("Hello").show(egui_context.ctx_mut(), |ui| {
-// //         ui.label("world");
-// //     });
-// // }
-
-// #[derive(Default, Resource)]
-// pub struct InspectorSettings {
-//     pub enabled: bool,
-// }
-
-// pub fn inspector_ui(world: &mut World) {
-//     let inspector_settings = world.resource::<InspectorSettings>();
-//     if !inspector_settings.enabled {
-//         return;
-//     }
-//     let mut egui_context = world.resource_mut::<EguiContext>().ctx_mut().clone();
-
-//     world.resource_scope::<UiState, _>(|world, mut ui_state| ui_state.ui(world, &mut egui_context));
-// }
-
-// #[derive(Eq, PartialEq)]
-// enum InspectorSelection {
-//     Entities,
-//     Resource(TypeId, String),
-//     Asset(TypeId, String, HandleId),
-// }
-
-// #[derive(Debug)]
-// enum Window {
-//     GameView,
-//     Hierarchy,
-//     Resources,
-//     Assets,
-//     Inspector,
-// }
-
-// #[derive(Resource)]
-// struct UiState {
-//     tree: Tree<Window>,
-//     viewport_rect: Rect,
-//     selected_entities: SelectedEntities,
-//     selection: InspectorSelection,
-// }
-
-// impl UiState {
-//     pub fn new() -> Self {
-//         let mut tree = Tree::new(vec![Window::GameView]);
-//         let [game, _inspector] = tree.split_right(NodeIndex::root(), 0.75, vec![Window::Inspector]);
-//         let [game, _hierarchy] = tree.split_left(game, 0.2, vec![Window::Hierarchy]);
-//         let [_game, _bottom] = tree.split_below(game, 0.8, vec![Window::Resources, Window::Assets]);
-
-//         Self {
-//             tree,
-//             selected_entities: SelectedEntities::default(),
-//             selection: InspectorSelection::Entities,
-//             viewport_rect: egui::Rect::EVERYTHING,
-//         }
-//     }
-
-//     fn ui(&mut self, world: &mut World, ctx: &mut egui::Context) {
-//         let mut tab_viewer = TabViewer {
-//             world,
-//             viewport_rect: &mut self.viewport_rect,
-//             selected_entities: &mut self.selected_entities,
-//             selection: &mut self.selection,
-//         };
-//         DockArea::new(&mut self.tree).show(ctx, &mut tab_viewer);
-//     }
-// }
-
-// struct TabViewer<'a> {
-//     world: &'a mut World,
-//     selected_entities: &'a mut SelectedEntities,
-//     selection: &'a mut InspectorSelection,
-//     viewport_rect: &'a mut egui::Rect,
-// }
-
-// impl egui_dock::TabViewer for TabViewer<'_> {
-//     type Tab = Window;
-
-//     fn ui(&mut self, ui: &mut egui::Ui, window: &mut Self::Tab) {
-//         let type_registry = self.world.resource::<AppTypeRegistry>().0.clone();
-
-//         match window {
-//             Window::GameView => {
-//                 (*self.viewport_rect, _) =
-//                     ui.allocate_exact_size(ui.available_size(), egui::Sense::hover());
-//             }
-//             Window::Hierarchy => hierarchy_ui(self.world, ui, self.selected_entities),
-//             Window::Resources => select_resource(ui, &type_registry.read(), self.selection),
-//             Window::Assets => select_asset(ui, &type_registry.read(), self.world, self.selection),
-//             Window::Inspector => match *self.selection {
-//                 InspectorSelection::Entities => match self.selected_entities.as_slice() {
-//                     &[entity] => ui_for_entity_with_children(self.world, entity, ui),
-//                     entities => ui_for_entities_shared_components(self.world, entities, ui),
-//                 },
-//                 InspectorSelection::Resource(type_id, ref name) => {
-//                     ui.label(name);
-//                     ui_for_resource(self.world, type_id, ui, name, &type_registry.read())
-//                 }
-//                 InspectorSelection::Asset(type_id, ref name, handle) => {
-//                     ui.label(name);
-//                     ui_for_asset(self.world, type_id, handle, ui, &type_registry.read());
-//                 }
-//             },
-//         }
-//     }
-
-//     fn title(&mut self, window: &mut Self::Tab) -> egui::WidgetText {
-//         format!("{window:?}").into()
-//     }
-
-//     fn clear_background(&self, window: &Self::Tab) -> bool {
-//         !matches!(window, Window::GameView)
-//     }
-// }
-
-// fn select_resource(
-//     ui: &mut egui::Ui,
-//     type_registry: &TypeRegistry,
-//     selection: &mut InspectorSelection,
-// ) {
-//     let mut resources: Vec<_> = type_registry
-//         .iter()
-//         .filter(|registration| registration.data::<ReflectResource>().is_some())
-//         .map(|registration| (registration.short_name().to_owned(), registration.type_id()))
-//         .collect();
-//     resources.sort_by(|(name_a, _), (name_b, _)| name_a.cmp(name_b));
-
-//     for (resource_name, type_id) in resources {
-//         let selected = match *selection {
-//             InspectorSelection::Resource(selected, _) => selected == type_id,
-//             _ => false,
-//         };
-
-//         if ui.selectable_label(selected, &resource_name).clicked() {
-//             *selection = InspectorSelection::Resource(type_id, resource_name);
-//         }
-//     }
-// }
-
-// fn select_asset(
-//     ui: &mut egui::Ui,
-//     type_registry: &TypeRegistry,
-//     world: &World,
-//     selection: &mut InspectorSelection,
-// ) {
-//     let mut assets: Vec<_> = type_registry
-//         .iter()
-//         .filter_map(|registration| {
-//             let reflect_asset = registration.data::<ReflectAsset>()?;
-//             Some((
-//                 registration.short_name().to_owned(),
-//                 registration.type_id(),
-//                 reflect_asset,
-//             ))
-//         })
-//         .collect();
-//     assets.sort_by(|(name_a, ..), (name_b, ..)| name_a.cmp(name_b));
-
-//     for (asset_name, asset_type_id, reflect_asset) in assets {
-//         let mut handles: Vec<_> = reflect_asset.ids(world).collect();
-//         handles.sort();
-
-//         ui.collapsing(format!("{asset_name} ({})", handles.len()), |ui| {
-//             for handle in handles {
-//                 let selected = match *selection {
-//                     InspectorSelection::Asset(_, _, selected_id) => selected_id == handle,
-//                     _ => false,
-//                 };
-
-//                 if ui
-//                     .selectable_label(selected, format!("{:?}", handle))
-//                     .clicked()
-//                 {
-//                     *selection =
-//                         InspectorSelection::Asset(asset_type_id, asset_name.clone(), handle);
-//                 }
-//             }
-//         });
-//     }
-// }
-
-// fn set_camera_viewport(
-//     ui_state: Res<UiState>,
-//     windows: Res<Windows>,
-//     egui_settings: Res<bevy_egui::EguiSettings>,
-//     mut cameras: Query<&mut Camera, With<MainCamera>>,
-//     inspector_settings: Res<InspectorSettings>,
-// ) {
-//     let mut camera = cameras.single_mut();
-//     if !inspector_settings.enabled {
-//         camera.viewport = None;
-//         return;
-//     }
-
-//     let window = windows.primary();
-//     let scale_factor = window.scale_factor() * egui_settings.scale_factor;
-
-//     let viewport_pos = ui_state.viewport_rect.left_top().to_vec2() * scale_factor as f32;
-//     let viewport_size = ui_state.viewport_rect.size() * scale_factor as f32;
-
-//     camera.viewport = Some(Viewport {
-//         physical_position: UVec2::new(viewport_pos.x as u32, viewport_pos.y as u32),
-//         physical_size: UVec2::new(viewport_size.x as u32, viewport_size.y as u32),
-//         depth: 0.0..1.0,
-//     });
-// }
+use std::any::TypeId;
+
+use bevy::{
+    asset::{HandleId, ReflectAsset},
+    prelude::*,
+    render::{camera::Viewport, mesh::PrimitiveTopology, primitives::Aabb},
+    transform::TransformSystem,
+    window::PrimaryWindow,
+};
+use bevy_egui::{egui, EguiContext, EguiSet, EguiSettings};
+use bevy_inspector_egui::bevy_inspector::{
+    by_type_id::{ui_for_asset, ui_for_resource},
+    hierarchy::{hierarchy_ui, SelectedEntities},
+    ui_for_entities_shared_components, ui_for_entity_with_children,
+};
+use bevy_rapier3d::prelude::*;
+use bevy_reflect::TypeRegistry;
+use egui_dock::{DockArea, NodeIndex, Tree};
+
+use crate::{
+    pan_orbit_camera::PanOrbitCamera,
+    picking::Picking,
+    selection::{selectable_entity, Selection},
+    shortcuts::{dispatch_shortcuts, triggered, ShortcutAction},
+    Ball, RobotLink, RobotRoot,
+};
+
+/// Color of the boxes drawn around the selected entities
+const OUTLINE_COLOR: Color = Color::ORANGE;
+
+/// Docks the 3D view between an entity hierarchy, resource and asset lists, and an inspector for
+/// the selection.
+///
+/// While the dock is shown it covers the whole window, the tools that click into the 3D view are
+/// only available with the dock hidden. Clicking a link or the ball in the game view selects it
+/// for the inspector, the selected entities are outlined by their bounding boxes.
+pub struct InspectorUiPlugin;
+
+impl Plugin for InspectorUiPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InspectorSettings>()
+            .insert_resource(UiState::new())
+            .add_startup_system(create_outline_assets)
+            .add_system(toggle_inspector.after(dispatch_shortcuts))
+            .add_system(select_clicked_entity)
+            .add_system(update_selection_outlines.after(select_clicked_entity))
+            .add_system(
+                inspector_ui
+                    .in_base_set(CoreSet::PostUpdate)
+                    .before(EguiSet::ProcessOutput)
+                    .before(TransformSystem::TransformPropagate),
+            )
+            .add_system(
+                set_camera_viewport
+                    .in_base_set(CoreSet::PostUpdate)
+                    .after(inspector_ui),
+            );
+    }
+}
+
+#[derive(Default, Resource)]
+pub struct InspectorSettings {
+    pub enabled: bool,
+}
+
+fn toggle_inspector(
+    mut actions: EventReader<ShortcutAction>,
+    mut settings: ResMut<InspectorSettings>,
+) {
+    if triggered(&mut actions, ShortcutAction::ToggleInspector) {
+        settings.enabled = !settings.enabled;
+    }
+}
+
+fn inspector_ui(world: &mut World) {
+    if !world.resource::<InspectorSettings>().enabled {
+        return;
+    }
+    let Ok(egui_context) = world
+        .query_filtered::<&mut EguiContext, With<PrimaryWindow>>()
+        .get_single(world)
+    else {
+        return;
+    };
+    let mut egui_context = egui_context.clone();
+
+    world.resource_scope::<UiState, _>(|world, mut ui_state| {
+        ui_state.ui(world, egui_context.get_mut())
+    });
+}
+
+#[derive(Eq, PartialEq)]
+enum InspectorSelection {
+    Entities,
+    Resource(TypeId, String),
+    Asset(TypeId, String, HandleId),
+}
+
+#[derive(Debug)]
+enum Window {
+    GameView,
+    Hierarchy,
+    Resources,
+    Assets,
+    Inspector,
+}
+
+#[derive(Resource)]
+struct UiState {
+    tree: Tree<Window>,
+    viewport_rect: egui::Rect,
+    /// The game view was clicked since the last pick
+    game_view_clicked: bool,
+    selected_entities: SelectedEntities,
+    selection: InspectorSelection,
+}
+
+impl UiState {
+    fn new() -> Self {
+        let mut tree = Tree::new(vec![Window::GameView]);
+        let [game, _inspector] = tree.split_right(NodeIndex::root(), 0.75, vec![Window::Inspector]);
+        let [game, _hierarchy] = tree.split_left(game, 0.2, vec![Window::Hierarchy]);
+        let [_game, _bottom] = tree.split_below(game, 0.8, vec![Window::Resources, Window::Assets]);
+
+        Self {
+            tree,
+            selected_entities: SelectedEntities::default(),
+            selection: InspectorSelection::Entities,
+            viewport_rect: egui::Rect::NOTHING,
+            game_view_clicked: false,
+        }
+    }
+
+    fn ui(&mut self, world: &mut World, ctx: &mut egui::Context) {
+        let mut tab_viewer = TabViewer {
+            world,
+            viewport_rect: &mut self.viewport_rect,
+            game_view_clicked: &mut self.game_view_clicked,
+            selected_entities: &mut self.selected_entities,
+            selection: &mut self.selection,
+        };
+        DockArea::new(&mut self.tree).show(ctx, &mut tab_viewer);
+    }
+}
+
+struct TabViewer<'a> {
+    world: &'a mut World,
+    selected_entities: &'a mut SelectedEntities,
+    selection: &'a mut InspectorSelection,
+    viewport_rect: &'a mut egui::Rect,
+    game_view_clicked: &'a mut bool,
+}
+
+impl egui_dock::TabViewer for TabViewer<'_> {
+    type Tab = Window;
+
+    fn ui(&mut self, ui: &mut egui::Ui, window: &mut Self::Tab) {
+        let type_registry = self.world.resource::<AppTypeRegistry>().0.clone();
+        let type_registry = type_registry.read();
+
+        match window {
+            Window::GameView => {
+                let response;
+                (*self.viewport_rect, response) =
+                    ui.allocate_exact_size(ui.available_size(), egui::Sense::click());
+                if response.clicked() {
+                    *self.game_view_clicked = true;
+                }
+            }
+            Window::Hierarchy => {
+                let selected = hierarchy_ui(self.world, ui, self.selected_entities);
+                if selected {
+                    *self.selection = InspectorSelection::Entities;
+                }
+            }
+            Window::Resources => select_resource(ui, &type_registry, self.selection),
+            Window::Assets => select_asset(ui, &type_registry, self.world, self.selection),
+            Window::Inspector => match *self.selection {
+                InspectorSelection::Entities => match self.selected_entities.as_slice() {
+                    &[entity] => ui_for_entity_with_children(self.world, entity, ui),
+                    entities => ui_for_entities_shared_components(self.world, entities, ui),
+                },
+                InspectorSelection::Resource(type_id, ref name) => {
+                    ui.label(name);
+                    ui_for_resource(self.world, type_id, ui, name, &type_registry)
+                }
+                InspectorSelection::Asset(type_id, ref name, handle) => {
+                    ui.label(name);
+                    ui_for_asset(self.world, type_id, handle, ui, &type_registry);
+                }
+            },
+        }
+    }
+
+    fn title(&mut self, window: &mut Self::Tab) -> egui::WidgetText {
+        format!("{window:?}").into()
+    }
+
+    fn clear_background(&self, window: &Self::Tab) -> bool {
+        !matches!(window, Window::GameView)
+    }
+}
+
+fn select_resource(
+    ui: &mut egui::Ui,
+    type_registry: &TypeRegistry,
+    selection: &mut InspectorSelection,
+) {
+    let mut resources: Vec<_> = type_registry
+        .iter()
+        .filter(|registration| registration.data::<ReflectResource>().is_some())
+        .map(|registration| (registration.short_name().to_owned(), registration.type_id()))
+        .collect();
+    resources.sort_by(|(name_a, _), (name_b, _)| name_a.cmp(name_b));
+
+    for (resource_name, type_id) in resources {
+        let selected = match *selection {
+            InspectorSelection::Resource(selected, _) => selected == type_id,
+            _ => false,
+        };
+
+        if ui.selectable_label(selected, &resource_name).clicked() {
+            *selection = InspectorSelection::Resource(type_id, resource_name);
+        }
+    }
+}
+
+fn select_asset(
+    ui: &mut egui::Ui,
+    type_registry: &TypeRegistry,
+    world: &World,
+    selection: &mut InspectorSelection,
+) {
+    let mut assets: Vec<_> = type_registry
+        .iter()
+        .filter_map(|registration| {
+            let reflect_asset = registration.data::<ReflectAsset>()?;
+            Some((
+                registration.short_name().to_owned(),
+                registration.type_id(),
+                reflect_asset,
+            ))
+        })
+        .collect();
+    assets.sort_by(|(name_a, ..), (name_b, ..)| name_a.cmp(name_b));
+
+    for (asset_name, asset_type_id, reflect_asset) in assets {
+        let mut handles: Vec<_> = reflect_asset.ids(world).collect();
+        handles.sort();
+
+        ui.collapsing(format!("{asset_name} ({})", handles.len()), |ui| {
+            for handle in handles {
+                let selected = match *selection {
+                    InspectorSelection::Asset(_, _, selected_id) => selected_id == handle,
+                    _ => false,
+                };
+
+                if ui
+                    .selectable_label(selected, format!("{handle:?}"))
+                    .clicked()
+                {
+                    *selection =
+                        InspectorSelection::Asset(asset_type_id, asset_name.clone(), handle);
+                }
+            }
+        });
+    }
+}
+
+/// Restricts the 3D view to the game view tab of the dock.
+fn set_camera_viewport(
+    ui_state: Res<UiState>,
+    inspector_settings: Res<InspectorSettings>,
+    windows: Query<&bevy::window::Window, With<PrimaryWindow>>,
+    egui_settings: Res<EguiSettings>,
+    mut cameras: Query<&mut Camera, With<PanOrbitCamera>>,
+) {
+    let Ok(mut camera) = cameras.get_single_mut() else {
+        return;
+    };
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    if !inspector_settings.enabled || !ui_state.viewport_rect.is_positive() {
+        if camera.viewport.is_some() {
+            camera.viewport = None;
+        }
+        return;
+    }
+
+    let scale_factor = window.scale_factor() as f32 * egui_settings.scale_factor as f32;
+    let position = ui_state.viewport_rect.left_top().to_vec2() * scale_factor;
+    let size = ui_state.viewport_rect.size() * scale_factor;
+    camera.viewport = Some(Viewport {
+        physical_position: UVec2::new(position.x as u32, position.y as u32),
+        physical_size: UVec2::new(size.x as u32, size.y as u32).max(UVec2::ONE),
+        depth: 0.0..1.0,
+    });
+}
+
+/// Selects the link or ball under the cursor when the game view was clicked, clicking elsewhere
+/// clears the selection. The robot of a clicked link becomes the [`Selection`] as well.
+fn select_clicked_entity(
+    mut ui_state: ResMut<UiState>,
+    inspector_settings: Res<InspectorSettings>,
+    picking: Picking,
+    selection: Option<ResMut<Selection>>,
+    selectable: Query<(), Or<(With<RobotLink>, With<Ball>)>>,
+    parents: Query<&Parent>,
+    robots: Query<(), With<RobotRoot>>,
+) {
+    if !std::mem::take(&mut ui_state.game_view_clicked) || !inspector_settings.enabled {
+        return;
+    }
+    let picked = picking
+        .pick(QueryFilter::default())
+        .map(|(entity, _)| entity)
+        .filter(|&entity| selectable.contains(entity));
+    match picked {
+        Some(entity) => ui_state.selected_entities.select_replace(entity),
+        None => ui_state.selected_entities.clear(),
+    }
+    ui_state.selection = InspectorSelection::Entities;
+    if let Some(mut selection) = selection {
+        selection.entity = picked.map(|entity| selectable_entity(entity, &parents, &robots));
+    }
+}
+
+#[derive(Resource)]
+struct OutlineAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+#[derive(Component)]
+struct SelectionOutline;
+
+/// Edges of the cube from -0.5 to 0.5, scaled to each bounding box.
+fn create_outline_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let corner =
+        |index: usize| [0, 1, 2].map(|axis| if index & (1 << axis) == 0 { -0.5 } else { 0.5 });
+    let mut positions = Vec::new();
+    for index in 0..8 {
+        for axis in 0..3 {
+            // each edge once, from the corner with the lower coordinate along its axis
+            if index & (1 << axis) == 0 {
+                positions.extend([corner(index), corner(index | (1 << axis))]);
+            }
+        }
+    }
+    let mut mesh = Mesh::new(PrimitiveTopology::LineList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+
+    commands.insert_resource(OutlineAssets {
+        mesh: meshes.add(mesh),
+        material: materials.add(StandardMaterial {
+            base_color: OUTLINE_COLOR,
+            unlit: true,
+            ..Default::default()
+        }),
+    });
+}
+
+/// Outlines the meshes of the selected entities and their descendants, only while the inspector
+/// is shown.
+#[allow(clippy::too_many_arguments)]
+fn update_selection_outlines(
+    mut commands: Commands,
+    ui_state: Res<UiState>,
+    inspector_settings: Res<InspectorSettings>,
+    assets: Res<OutlineAssets>,
+    mut outlined: Local<Vec<Entity>>,
+    outlines: Query<Entity, With<SelectionOutline>>,
+    children: Query<&Children>,
+    bounds: Query<&Aabb, Without<SelectionOutline>>,
+) {
+    let selected = if inspector_settings.enabled {
+        ui_state.selected_entities.as_slice()
+    } else {
+        &[]
+    };
+    if *outlined == selected {
+        return;
+    }
+    *outlined = selected.to_vec();
+
+    for outline in outlines.iter() {
+        commands.entity(outline).despawn_recursive();
+    }
+    for &entity in selected {
+        for outlined_entity in std::iter::once(entity).chain(children.iter_descendants(entity)) {
+            let Ok(aabb) = bounds.get(outlined_entity) else {
+                continue;
+            };
+            let outline = commands
+                .spawn((
+                    PbrBundle {
+                        mesh: assets.mesh.clone(),
+                        material: assets.material.clone(),
+                        transform: Transform::from_translation(aabb.center.into())
+                            .with_scale(Vec3::from(aabb.half_extents) * 2.0),
+                        ..Default::default()
+                    },
+                    SelectionOutline,
+                    Name::new("selection outline"),
+                ))
+                .id();
+            commands.entity(outlined_entity).add_child(outline);
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    arenas::{in_main_arena, Arena},
+    coordinate_frame::CoordinateFrame,
+    pan_orbit_camera::PanOrbitCamera,
+    referee::GoalScored,
+    shortcuts::{triggered, ShortcutAction},
+    simulation_time::SimulationTime,
+    Ball,
+};
+
+/// Replays the last seconds before a goal in slow motion, then resumes the simulation.
+pub struct InstantReplayPlugin;
+
+impl Plugin for InstantReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InstantReplaySettings>()
+            .init_resource::<ReplayBuffer>()
+            .init_resource::<InstantReplay>()
+            .add_system(start_replay_on_goal)
+            .add_system(play_replay.after(start_replay_on_goal))
+            .add_system(record_replay_buffer.after(play_replay))
+            .add_system(follow_ball_during_replay.after(play_replay));
+    }
+}
+
+#[derive(Resource)]
+pub struct InstantReplaySettings {
+    pub enabled: bool,
+    /// Seconds of simulation kept in the replay buffer
+    pub duration: f64,
+    /// Playback speed relative to real time
+    pub speed: f32,
+    /// Slowly orbit the camera around the ball while replaying
+    pub cinematic_camera: bool,
+}
+
+impl Default for InstantReplaySettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            duration: 4.0,
+            speed: 0.25,
+            cinematic_camera: true,
+        }
+    }
+}
+
+struct ReplayFrame {
+    /// Simulation time in seconds
+    time: f64,
+    transforms: Vec<(Entity, Transform)>,
+}
+
+/// Transforms of all rigid bodies over the last [`InstantReplaySettings::duration`] seconds
+#[derive(Default, Resource)]
+pub struct ReplayBuffer {
+    frames: VecDeque<ReplayFrame>,
+}
+
+#[derive(Default, Resource)]
+pub struct InstantReplay {
+    frames: Vec<ReplayFrame>,
+    playback_time: f64,
+    /// Whether the simulation was paused before the replay, restored when it ends
+    was_paused: bool,
+}
+
+impl InstantReplay {
+    pub fn is_playing(&self) -> bool {
+        !self.frames.is_empty()
+    }
+}
+
+fn record_replay_buffer(
+    simulation_time: Res<SimulationTime>,
+    settings: Res<InstantReplaySettings>,
+    replay: Res<InstantReplay>,
+    mut buffer: ResMut<ReplayBuffer>,
+    bodies: Query<(Entity, &Transform), With<RigidBody>>,
+) {
+    if replay.is_playing() {
+        return;
+    }
+    let now = simulation_time.elapsed_seconds();
+    // nothing moved while the simulation is paused
+    if buffer
+        .frames
+        .back()
+        .map_or(false, |frame| frame.time == now)
+    {
+        return;
+    }
+    buffer.frames.push_back(ReplayFrame {
+        time: now,
+        transforms: bodies
+            .iter()
+            .map(|(entity, transform)| (entity, *transform))
+            .collect(),
+    });
+    while buffer
+        .frames
+        .front()
+        .map_or(false, |frame| now - frame.time > settings.duration)
+    {
+        buffer.frames.pop_front();
+    }
+}
+
+fn start_replay_on_goal(
+    mut goals: EventReader<GoalScored>,
+    settings: Res<InstantReplaySettings>,
+    mut buffer: ResMut<ReplayBuffer>,
+    mut replay: ResMut<InstantReplay>,
+    mut simulation_time: ResMut<SimulationTime>,
+) {
+    let goal_scored = goals.iter().count() > 0;
+    if !goal_scored || !settings.enabled || replay.is_playing() || buffer.frames.len() < 2 {
+        return;
+    }
+    replay.frames = buffer.frames.drain(..).collect();
+    replay.playback_time = replay.frames[0].time;
+    replay.was_paused = simulation_time.paused;
+    simulation_time.paused = true;
+}
+
+fn play_replay(
+    time: Res<Time>,
+    mut actions: EventReader<ShortcutAction>,
+    settings: Res<InstantReplaySettings>,
+    mut replay: ResMut<InstantReplay>,
+    mut simulation_time: ResMut<SimulationTime>,
+    mut transforms: Query<&mut Transform>,
+) {
+    let skip = triggered(&mut actions, ShortcutAction::SkipReplay);
+    if !replay.is_playing() {
+        return;
+    }
+    // the simulation stays paused until the replay ends, even when resumed in between
+    simulation_time.paused = true;
+    replay.playback_time += f64::from(time.delta_seconds() * settings.speed);
+    let last_frame = replay.frames.last().unwrap();
+    if skip || replay.playback_time >= last_frame.time {
+        // leave every body where the simulation was paused
+        for (entity, transform) in &last_frame.transforms {
+            if let Ok(mut current) = transforms.get_mut(*entity) {
+                *current = *transform;
+            }
+        }
+        replay.frames.clear();
+        simulation_time.paused = replay.was_paused;
+        return;
+    }
+
+    let next_index = replay
+        .frames
+        .iter()
+        .position(|frame| frame.time > replay.playback_time)
+        .unwrap_or(replay.frames.len() - 1)
+        .max(1);
+    let previous = &replay.frames[next_index - 1];
+    let next = &replay.frames[next_index];
+    let t = ((replay.playback_time - previous.time) / (next.time - previous.time)).clamp(0.0, 1.0)
+        as f32;
+    for (entity, from) in &previous.transforms {
+        let to = next
+            .transforms
+            .iter()
+            .find(|(next_entity, _)| next_entity == entity)
+            .map_or(from, |(_, to)| to);
+        if let Ok(mut current) = transforms.get_mut(*entity) {
+            current.translation = from.translation.lerp(to.translation, t);
+            current.rotation = from.rotation.slerp(to.rotation, t);
+        }
+    }
+}
+
+fn follow_ball_during_replay(
+    time: Res<Time>,
+    settings: Res<InstantReplaySettings>,
+    replay: Res<InstantReplay>,
+    frame: Res<CoordinateFrame>,
+    balls: Query<(&Transform, Option<&Arena>), (With<Ball>, Without<PanOrbitCamera>)>,
+    mut cameras: Query<(&mut PanOrbitCamera, &mut Transform)>,
+) {
+    if !replay.is_playing() || !settings.cinematic_camera {
+        return;
+    }
+    let Some((ball, _)) = balls.iter().find(|(_, arena)| in_main_arena(*arena)) else {
+        return;
+    };
+    for (mut pan_orbit, mut transform) in cameras.iter_mut() {
+        pan_orbit.focus = ball.translation;
+        transform.rotation =
+            Quat::from_axis_angle(frame.up(), 0.3 * time.delta_seconds()) * transform.rotation;
+        transform.translation =
+            pan_orbit.focus + transform.rotation * Vec3::new(0.0, 0.0, pan_orbit.radius);
+    }
+}
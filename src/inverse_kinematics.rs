@@ -0,0 +1,308 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use nalgebra::{DMatrix, DVector};
+
+use crate::{
+    joint_control::{apply_joint_commands, JointCommand},
+    motion_playback::play_motions,
+    NaoJoint, RobotLink,
+};
+
+/// Gauss-Newton iterations per frame, the previous targets are the starting point
+const MAXIMUM_ITERATIONS: usize = 30;
+/// Remaining weighted pose error at which the solver stops
+const TOLERANCE: f32 = 1e-4;
+/// Damping of the least squares steps, keeps singular poses like stretched knees stable
+const DAMPING: f32 = 0.05;
+
+/// Turns Cartesian targets of feet and hands into joint targets with damped least squares.
+///
+/// Targets are set with [`LimbTargets::set_foot_target`] and [`LimbTargets::set_hand_target`]
+/// and are resolved every frame until they are released, overriding controllers and motions for
+/// the joints of the limb. The solution follows the kinematics of the URDF, not the current
+/// physical state, so targets stay reachable while the robot sags under load.
+pub struct InverseKinematicsPlugin;
+
+impl Plugin for InverseKinematicsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LimbTargets>().add_system(
+            solve_limb_targets
+                .after(play_motions)
+                .before(apply_joint_commands),
+        );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Limb {
+    Leg(Side),
+    Arm(Side),
+}
+
+impl Limb {
+    /// Link the target applies to, named like the NAOqi effector frames
+    fn end_link(self) -> &'static str {
+        match self {
+            Limb::Leg(Side::Left) => "LLeg",
+            Limb::Leg(Side::Right) => "RLeg",
+            Limb::Arm(Side::Left) => "LArm",
+            Limb::Arm(Side::Right) => "RArm",
+        }
+    }
+
+    /// Weight of the orientation error relative to the position error in meters per radian.
+    ///
+    /// Arms have five joints and cannot reach arbitrary orientations, their position wins.
+    fn orientation_weight(self) -> f32 {
+        match self {
+            Limb::Leg(_) => 0.1,
+            Limb::Arm(_) => 0.01,
+        }
+    }
+}
+
+/// Poses of feet and hands in the frame of the root link of their robot.
+#[derive(Default, Resource)]
+pub struct LimbTargets {
+    targets: HashMap<(Entity, Limb), Transform>,
+}
+
+impl LimbTargets {
+    /// Moves the sole of a foot of `robot` to `target`.
+    pub fn set_foot_target(&mut self, robot: Entity, side: Side, target: Transform) {
+        self.targets.insert((robot, Limb::Leg(side)), target);
+    }
+
+    /// Moves a hand of `robot` to `target`, its orientation is only followed where the arm
+    /// allows.
+    pub fn set_hand_target(&mut self, robot: Entity, side: Side, target: Transform) {
+        self.targets.insert((robot, Limb::Arm(side)), target);
+    }
+
+    /// Stops driving the joints of the limb, they keep their last targets.
+    pub fn release(&mut self, robot: Entity, limb: Limb) {
+        self.targets.remove(&(robot, limb));
+    }
+
+    pub fn get(&self, robot: Entity, limb: Limb) -> Option<Transform> {
+        self.targets.get(&(robot, limb)).copied()
+    }
+}
+
+/// Link between the root link and the end of a limb.
+struct ChainLink {
+    entity: Entity,
+    local_transform: Transform,
+    joint: Option<(Vec3, Quat)>,
+    angle: f32,
+    range: Option<[f32; 2]>,
+}
+
+fn solve_limb_targets(
+    mut targets: ResMut<LimbTargets>,
+    robot_links: Query<(Entity, &RobotLink)>,
+    parents: Query<&Parent>,
+    mut links: Query<(&Transform, Option<&NaoJoint>, Option<&mut JointCommand>)>,
+) {
+    // robots may have been despawned
+    targets
+        .targets
+        .retain(|(robot, _), _| robot_links.contains(*robot));
+
+    for (&(robot, limb), target) in targets.targets.iter() {
+        let Some((end, _)) = robot_links
+            .iter()
+            .find(|(_, link)| link.robot == robot && link.name == limb.end_link())
+        else {
+            continue;
+        };
+        let mut chain: Vec<_> = std::iter::once(end)
+            .chain(parents.iter_ancestors(end))
+            .take_while(|&entity| entity != robot)
+            .filter_map(|entity| {
+                let (transform, joint, command) = links.get(entity).ok()?;
+                Some(ChainLink {
+                    entity,
+                    local_transform: *transform,
+                    joint: joint
+                        .map(|joint| (joint.axis.normalize_or_zero(), joint.origin_rotation)),
+                    angle: command.map_or_else(
+                        || joint.map_or(0.0, |joint| joint.angle(transform)),
+                        |command| command.clamped_position(),
+                    ),
+                    range: command.and_then(|command| command.range()),
+                })
+            })
+            .collect();
+        chain.reverse();
+
+        solve(&mut chain, *target, limb.orientation_weight());
+
+        for link in chain.iter().filter(|link| link.joint.is_some()) {
+            if let Ok((_, _, Some(mut command))) = links.get_mut(link.entity) {
+                command.position = link.angle;
+            }
+        }
+    }
+}
+
+/// Poses of all links of `chain` relative to the root link at the current angles.
+fn forward_kinematics(chain: &[ChainLink]) -> Vec<Transform> {
+    let mut pose = Transform::IDENTITY;
+    chain
+        .iter()
+        .map(|link| {
+            let mut local = link.local_transform;
+            if let Some((axis, origin_rotation)) = link.joint {
+                local.rotation = origin_rotation * Quat::from_axis_angle(axis, link.angle);
+            }
+            pose = pose.mul_transform(local);
+            pose
+        })
+        .collect()
+}
+
+/// Adjusts the angles of `chain` until its last link reaches `target`.
+fn solve(chain: &mut [ChainLink], target: Transform, orientation_weight: f32) {
+    let joints: Vec<_> = (0..chain.len())
+        .filter(|&index| chain[index].joint.is_some())
+        .collect();
+    if joints.is_empty() {
+        return;
+    }
+    for _ in 0..MAXIMUM_ITERATIONS {
+        let poses = forward_kinematics(chain);
+        let end = *poses.last().expect("chains contain at least the end link");
+
+        let position_error = target.translation - end.translation;
+        let (axis, angle) = (target.rotation * end.rotation.inverse()).to_axis_angle();
+        // the shorter way around
+        let angle = if angle > std::f32::consts::PI {
+            angle - std::f32::consts::TAU
+        } else {
+            angle
+        };
+        let rotation_error = axis * angle * orientation_weight;
+        let error = DVector::from_iterator(
+            6,
+            position_error
+                .to_array()
+                .into_iter()
+                .chain(rotation_error.to_array()),
+        );
+        if error.norm() < TOLERANCE {
+            break;
+        }
+
+        let mut jacobian = DMatrix::zeros(6, joints.len());
+        for (column, &index) in joints.iter().enumerate() {
+            let (axis, _) = chain[index].joint.expect("only joints are in the jacobian");
+            let axis = poses[index].rotation * axis;
+            let linear = axis.cross(end.translation - poses[index].translation);
+            let angular = axis * orientation_weight;
+            for (row, value) in linear
+                .to_array()
+                .into_iter()
+                .chain(angular.to_array())
+                .enumerate()
+            {
+                jacobian[(row, column)] = value;
+            }
+        }
+
+        let damped = &jacobian * jacobian.transpose() + DMatrix::identity(6, 6) * DAMPING.powi(2);
+        let Some(decomposition) = damped.cholesky() else {
+            break;
+        };
+        let step = jacobian.transpose() * decomposition.solve(&error);
+        for (&index, delta) in joints.iter().zip(step.iter()) {
+            let link = &mut chain[index];
+            link.angle += delta;
+            if let Some([lower, upper]) = link.range {
+                link.angle = link.angle.clamp(lower, upper);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_1_SQRT_2;
+
+    use super::*;
+
+    /// Left leg of the NAO from the pelvis to the foot, with the hip offset as first link.
+    fn leg(angles: [f32; 6]) -> Vec<ChainLink> {
+        let links = [
+            (
+                Vec3::new(0.0, 0.05, -0.085),
+                Vec3::new(0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2),
+            ),
+            (Vec3::ZERO, Vec3::X),
+            (Vec3::ZERO, Vec3::Y),
+            (Vec3::new(0.0, 0.0, -0.1), Vec3::Y),
+            (Vec3::new(0.0, 0.0, -0.1029), Vec3::Y),
+            (Vec3::ZERO, Vec3::X),
+        ];
+        let mut chain: Vec<_> = links
+            .into_iter()
+            .zip(angles)
+            .enumerate()
+            .map(|(index, ((translation, axis), angle))| ChainLink {
+                entity: Entity::from_raw(index as u32),
+                local_transform: Transform::from_translation(translation),
+                joint: Some((axis, Quat::IDENTITY)),
+                angle,
+                range: None,
+            })
+            .collect();
+        chain.push(ChainLink {
+            entity: Entity::from_raw(6),
+            local_transform: Transform::from_xyz(0.0, 0.0, -0.04519),
+            joint: None,
+            angle: 0.0,
+            range: None,
+        });
+        chain
+    }
+
+    fn end_pose(chain: &[ChainLink]) -> Transform {
+        *forward_kinematics(chain).last().unwrap()
+    }
+
+    #[test]
+    fn forward_kinematics_of_stretched_leg() {
+        let foot = end_pose(&leg([0.0; 6]));
+        assert!(foot
+            .translation
+            .abs_diff_eq(Vec3::new(0.0, 0.05, -0.085 - 0.1 - 0.1029 - 0.04519), 1e-6));
+        assert!(foot.rotation.abs_diff_eq(Quat::IDENTITY, 1e-6));
+
+        // bending the knee moves the foot backwards
+        let foot = end_pose(&leg([0.0, 0.0, 0.0, 1.0, 0.0, 0.0]));
+        assert!(foot.translation.x < 0.0);
+    }
+
+    #[test]
+    fn solve_reaches_pose_of_known_angles() {
+        let target = end_pose(&leg([0.1, 0.05, -0.5, 0.9, -0.4, -0.05]));
+        let mut chain = leg([0.0, 0.0, -0.2, 0.4, -0.2, 0.0]);
+        solve(&mut chain, target, 1.0);
+
+        let end = end_pose(&chain);
+        let position_error = (target.translation - end.translation).length();
+        // twice the vector part is the small rotation angle, more precise than the arc cosine
+        let rotation_error = 2.0 * (target.rotation * end.rotation.inverse()).xyz().length();
+        assert!(
+            position_error.hypot(rotation_error) < TOLERANCE,
+            "position error {position_error} m, rotation error {rotation_error} rad"
+        );
+    }
+}
@@ -0,0 +1,237 @@
+use bevy::prelude::*;
+use bevy_rapier3d::{prelude::*, rapier::dynamics::JointAxis};
+
+use crate::{
+    shortcuts::{dispatch_shortcuts, triggered, ShortcutAction, ShortcutsPlugin},
+    simulation_time::{PhysicsSchedule, PHYSICS_TIMESTEP},
+};
+
+/// Motor stiffness used for new joint commands
+pub const DEFAULT_STIFFNESS: f32 = 50.0;
+/// Motor damping used for new joint commands
+pub const DEFAULT_DAMPING: f32 = 5.0;
+/// Joint velocity below which dry friction fades out, keeps resting joints from chattering
+const STICTION_VELOCITY: f32 = 0.01;
+
+/// Drives every joint with a [`JointCommand`] toward its target position using the joint motor.
+///
+/// The stiffness of a command also scales the maximum force of the motor, an unpowered joint only
+/// keeps its URDF damping and friction and the limb falls under gravity.
+pub struct JointControlPlugin;
+
+impl Plugin for JointControlPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MotorPower>()
+            .add_system(apply_joint_commands)
+            .add_system(
+                apply_joint_friction
+                    .after(PhysicsSet::SyncBackendFlush)
+                    .before(PhysicsSet::StepSimulation)
+                    .in_schedule(PhysicsSchedule),
+            );
+        if app.is_plugin_added::<ShortcutsPlugin>() {
+            app.add_system(
+                toggle_motor_power
+                    .after(dispatch_shortcuts)
+                    .before(apply_joint_commands),
+            );
+        }
+    }
+}
+
+/// Switches the motors of all robots, while off every joint is limp regardless of its command.
+#[derive(Resource)]
+pub struct MotorPower {
+    pub enabled: bool,
+}
+
+impl Default for MotorPower {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// Position target of an actuated joint, lives on the child link next to its [`ImpulseJoint`].
+#[derive(Clone, Component, Debug)]
+pub struct JointCommand {
+    /// Target angle in radians, or target offset in meters for prismatic joints
+    pub position: f32,
+    pub stiffness: f32,
+    pub damping: f32,
+    axis: JointAxis,
+    /// Lower and upper limit the target position is clamped to
+    range: Option<[f32; 2]>,
+    /// Force or torque of the motor at full stiffness, from the URDF effort limit
+    max_force: f32,
+    /// Fraction of the maximum force the motor delivers, reduced while it overheats
+    available_force: f32,
+}
+
+impl JointCommand {
+    pub fn new(axis: JointAxis) -> Self {
+        Self {
+            position: 0.0,
+            stiffness: DEFAULT_STIFFNESS,
+            damping: DEFAULT_DAMPING,
+            axis,
+            range: None,
+            max_force: f32::MAX,
+            available_force: 1.0,
+        }
+    }
+
+    pub fn with_range(mut self, range: [f32; 2]) -> Self {
+        self.range = Some(range);
+        self
+    }
+
+    pub fn with_max_force(mut self, max_force: f32) -> Self {
+        self.max_force = max_force;
+        self
+    }
+
+    /// Sets stiffness and damping to `fraction` of their defaults, zero switches the motor off.
+    pub fn set_stiffness_fraction(&mut self, fraction: f32) {
+        let fraction = fraction.clamp(0.0, 1.0);
+        self.stiffness = fraction * DEFAULT_STIFFNESS;
+        self.damping = fraction * DEFAULT_DAMPING;
+    }
+
+    /// Stiffness relative to [`DEFAULT_STIFFNESS`] between 0 and 1.
+    pub fn stiffness_fraction(&self) -> f32 {
+        (self.stiffness / DEFAULT_STIFFNESS).clamp(0.0, 1.0)
+    }
+
+    pub fn available_force(&self) -> f32 {
+        self.available_force
+    }
+
+    pub fn set_available_force(&mut self, fraction: f32) {
+        self.available_force = fraction.clamp(0.0, 1.0);
+    }
+
+    /// Lower and upper limit of the joint, `None` for continuous joints
+    pub fn range(&self) -> Option<[f32; 2]> {
+        self.range
+    }
+
+    /// Target position clamped to the range of the joint.
+    pub fn clamped_position(&self) -> f32 {
+        match self.range {
+            Some([lower, upper]) => self.position.clamp(lower, upper),
+            None => self.position,
+        }
+    }
+}
+
+/// Viscous damping and dry friction of a joint from its URDF `<dynamics>` element.
+#[derive(Clone, Component, Debug, Default)]
+pub struct JointDynamics {
+    /// Added to the motor damping, in Nms/rad or Ns/m for prismatic joints
+    pub damping: f32,
+    /// Friction torque in Nm, or force in N for prismatic joints
+    pub friction: f32,
+}
+
+fn toggle_motor_power(mut actions: EventReader<ShortcutAction>, mut power: ResMut<MotorPower>) {
+    if triggered(&mut actions, ShortcutAction::ToggleMotorPower) {
+        power.enabled = !power.enabled;
+        info!(
+            "Motors {}",
+            if power.enabled {
+                "powered"
+            } else {
+                "switched off"
+            }
+        );
+    }
+}
+
+pub fn apply_joint_commands(
+    power: Res<MotorPower>,
+    mut joints: Query<(
+        Ref<JointCommand>,
+        Option<Ref<JointDynamics>>,
+        &mut ImpulseJoint,
+    )>,
+) {
+    for (command, dynamics, mut joint) in joints.iter_mut() {
+        let dynamics_changed = dynamics
+            .as_ref()
+            .map_or(false, |dynamics| dynamics.is_changed());
+        if !power.is_changed() && !command.is_changed() && !dynamics_changed {
+            continue;
+        }
+        let damping = dynamics.map_or(0.0, |dynamics| dynamics.damping);
+        // rapier drives the sine of half the joint angle toward the sine of the target, angular
+        // motors settle at twice their target
+        let target = match command.axis {
+            JointAxis::X | JointAxis::Y | JointAxis::Z => command.clamped_position(),
+            _ => command.clamped_position() / 2.0,
+        };
+        let (stiffness, motor_damping, max_force) = if power.enabled {
+            (
+                command.stiffness,
+                command.damping,
+                command.max_force * command.stiffness_fraction() * command.available_force,
+            )
+        } else {
+            (0.0, 0.0, 0.0)
+        };
+        joint
+            .data
+            .set_motor_position(command.axis, target, stiffness, motor_damping + damping)
+            .set_motor_max_force(command.axis, max_force);
+    }
+}
+
+/// Applies the dry friction of each joint as equal and opposite impulses on its two links.
+fn apply_joint_friction(
+    mut context: ResMut<RapierContext>,
+    joints: Query<(
+        Entity,
+        &ImpulseJoint,
+        &JointCommand,
+        &JointDynamics,
+        &GlobalTransform,
+    )>,
+) {
+    for (entity, joint, command, dynamics, transform) in joints.iter() {
+        if dynamics.friction <= 0.0 {
+            continue;
+        }
+        let bodies = context.entity2body();
+        let (Some(&child), Some(&parent)) = (bodies.get(&entity), bodies.get(&joint.parent)) else {
+            continue;
+        };
+        let (Some(child_body), Some(parent_body)) =
+            (context.bodies.get(child), context.bodies.get(parent))
+        else {
+            continue;
+        };
+        let (_, rotation, _) = transform.to_scale_rotation_translation();
+        let axis = rotation * joint.data.local_basis2() * Vec3::X;
+        let linear = matches!(command.axis, JointAxis::X);
+        let relative_velocity: Vec3 = if linear {
+            (child_body.linvel() - parent_body.linvel()).into()
+        } else {
+            (child_body.angvel() - parent_body.angvel()).into()
+        };
+        let relative_velocity = relative_velocity.dot(axis);
+        let impulse = -relative_velocity.signum()
+            * dynamics.friction
+            * PHYSICS_TIMESTEP
+            * (relative_velocity.abs() / STICTION_VELOCITY).min(1.0)
+            * axis;
+        for (handle, impulse) in [(child, impulse), (parent, -impulse)] {
+            let Some(body) = context.bodies.get_mut(handle) else {
+                continue;
+            };
+            if linear {
+                body.apply_impulse(impulse.into(), true);
+            } else {
+                body.apply_torque_impulse(impulse.into(), true);
+            }
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    f32::consts::TAU,
+};
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    imu::update_imus, simulation_rng::SimulationRng, simulation_time::PhysicsSchedule, NaoJoint,
+    RobotRoot,
+};
+
+/// Measures the joint positions of each robot like its encoders would, with noise, limited
+/// resolution and latency, and publishes them as [`MeasuredJointPositions`].
+pub struct JointEncodersPlugin;
+
+impl Plugin for JointEncodersPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<JointEncoders>()
+            .add_system(add_measured_joint_positions)
+            .add_system(
+                update_measured_joint_positions
+                    .after(PhysicsSet::Writeback)
+                    // sensors draw from the simulation RNG in a fixed order to stay reproducible
+                    .after(update_imus)
+                    .in_schedule(PhysicsSchedule),
+            );
+    }
+}
+
+#[derive(Resource)]
+pub struct JointEncoders {
+    /// Standard deviation of the measurement noise in radians
+    pub noise: f32,
+    /// Smallest angle step the encoders resolve in radians, zero disables quantization
+    pub resolution: f32,
+    /// Physics steps between sampling a position and reporting it
+    pub delay_steps: usize,
+}
+
+impl Default for JointEncoders {
+    fn default() -> Self {
+        Self {
+            noise: 0.001,
+            // 12 bit magnetic rotary encoders
+            resolution: TAU / 4096.0,
+            delay_steps: 1,
+        }
+    }
+}
+
+#[derive(Component, Debug, Default)]
+pub struct MeasuredJointPositions {
+    /// Latest reported position of each joint by name in radians
+    pub positions: HashMap<String, f32>,
+    /// Sampled positions not yet reported, oldest first
+    pending: VecDeque<HashMap<String, f32>>,
+}
+
+fn add_measured_joint_positions(
+    mut commands: Commands,
+    robots: Query<Entity, (Added<RobotRoot>, Without<MeasuredJointPositions>)>,
+) {
+    for robot in robots.iter() {
+        commands
+            .entity(robot)
+            .insert(MeasuredJointPositions::default());
+    }
+}
+
+pub fn update_measured_joint_positions(
+    encoders: Res<JointEncoders>,
+    mut rng: ResMut<SimulationRng>,
+    mut robots: Query<(Entity, &mut MeasuredJointPositions)>,
+    children: Query<&Children>,
+    joints: Query<(&NaoJoint, &Transform)>,
+) {
+    for (robot, mut measured) in robots.iter_mut() {
+        let sample = children
+            .iter_descendants(robot)
+            .filter_map(|link| joints.get(link).ok())
+            .map(|(joint, transform)| {
+                let position = joint.angle(transform) + rng.gaussian(encoders.noise);
+                (joint.name.clone(), quantize(position, encoders.resolution))
+            })
+            .collect();
+        measured.pending.push_back(sample);
+        while measured.pending.len() > encoders.delay_steps {
+            if let Some(positions) = measured.pending.pop_front() {
+                measured.positions = positions;
+            }
+        }
+    }
+}
+
+fn quantize(value: f32, resolution: f32) -> f32 {
+    if resolution <= 0.0 {
+        return value;
+    }
+    (value / resolution).round() * resolution
+}
@@ -0,0 +1,190 @@
+use std::f32::consts::TAU;
+
+use bevy::{
+    prelude::*,
+    render::{mesh::PrimitiveTopology, view::NoFrustumCulling},
+    transform::TransformSystem,
+};
+
+use crate::{
+    joint_control::JointCommand,
+    shortcuts::{triggered, ShortcutAction},
+    NaoJoint,
+};
+
+/// Line segments of the arcs
+const ARC_SEGMENTS: usize = 32;
+
+/// Draws the axis, the allowed range and the current angle of revolute joints.
+///
+/// Each joint gets a [`JointGizmo`] that is switched on in the inspector, or all joints are shown
+/// with [`JointGizmos::show_all`]. The range is drawn as a yellow arc around the axis starting at
+/// the zero position, the white line points at the current angle and the orange line at the
+/// target.
+pub struct JointGizmosPlugin;
+
+impl Plugin for JointGizmosPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<JointGizmos>()
+            .register_type::<JointGizmo>()
+            .add_startup_system(spawn_joint_lines)
+            .add_system(add_joint_gizmos)
+            .add_system(toggle_joint_gizmos)
+            .add_system(
+                update_joint_lines
+                    .in_base_set(CoreSet::PostUpdate)
+                    .after(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+#[derive(Resource)]
+pub struct JointGizmos {
+    /// Draws every joint regardless of its [`JointGizmo`]
+    pub show_all: bool,
+    /// Radius of the arcs in meters
+    pub radius: f32,
+}
+
+impl Default for JointGizmos {
+    fn default() -> Self {
+        Self {
+            show_all: false,
+            radius: 0.04,
+        }
+    }
+}
+
+/// Draws the gizmo of this joint.
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+pub struct JointGizmo {
+    pub visible: bool,
+}
+
+#[derive(Component)]
+struct JointLines;
+
+fn spawn_joint_lines(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    // start with a degenerate line, the gizmos are computed every frame
+    let mut mesh = Mesh::new(PrimitiveTopology::LineList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, vec![[0.0; 3]; 2]);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, vec![[0.0; 4]; 2]);
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(mesh),
+            material: materials.add(StandardMaterial {
+                base_color: Color::WHITE,
+                unlit: true,
+                ..Default::default()
+            }),
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        },
+        // the bounding box is only computed for the initial mesh
+        NoFrustumCulling,
+        JointLines,
+        Name::new("joint gizmos"),
+    ));
+}
+
+fn add_joint_gizmos(
+    mut commands: Commands,
+    joints: Query<Entity, (Added<NaoJoint>, Without<JointGizmo>)>,
+) {
+    for joint in joints.iter() {
+        commands.entity(joint).insert(JointGizmo::default());
+    }
+}
+
+fn toggle_joint_gizmos(
+    mut actions: EventReader<ShortcutAction>,
+    mut settings: ResMut<JointGizmos>,
+) {
+    if triggered(&mut actions, ShortcutAction::ToggleJointGizmos) {
+        settings.show_all = !settings.show_all;
+    }
+}
+
+fn update_joint_lines(
+    settings: Res<JointGizmos>,
+    joints: Query<(
+        &NaoJoint,
+        &JointGizmo,
+        &Transform,
+        &GlobalTransform,
+        &Parent,
+        Option<&JointCommand>,
+    )>,
+    parents: Query<&GlobalTransform>,
+    mut gizmos: Query<(&Handle<Mesh>, &mut Visibility), With<JointLines>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let Ok((mesh, mut visibility)) = gizmos.get_single_mut() else {
+        return;
+    };
+
+    let mut lines = Vec::new();
+    for (joint, gizmo, transform, global_transform, parent, command) in joints.iter() {
+        if !gizmo.visible && !settings.show_all {
+            continue;
+        }
+        let Ok(parent_transform) = parents.get(parent.get()) else {
+            continue;
+        };
+        let Some(axis) = joint.axis.try_normalize() else {
+            continue;
+        };
+        let center = global_transform.translation();
+        // the child link frame at the zero position, the axis is the same in both link frames
+        let zero_rotation = parent_transform.compute_transform().rotation * joint.origin_rotation;
+        let reference = axis.any_orthonormal_vector() * settings.radius;
+        let point =
+            |angle: f32| center + zero_rotation * Quat::from_axis_angle(axis, angle) * reference;
+
+        let world_axis = zero_rotation * axis * settings.radius * 1.5;
+        lines.push((center - world_axis, center + world_axis, Color::CYAN));
+
+        let range = command.and_then(JointCommand::range);
+        let [lower, upper] = range.unwrap_or([0.0, TAU]);
+        let color = if range.is_some() {
+            Color::YELLOW
+        } else {
+            Color::GRAY
+        };
+        for index in 0..ARC_SEGMENTS {
+            let angle = |index: usize| lower + (upper - lower) * index as f32 / ARC_SEGMENTS as f32;
+            lines.push((point(angle(index)), point(angle(index + 1)), color));
+        }
+        if range.is_some() {
+            lines.push((center, point(lower), color));
+            lines.push((center, point(upper), color));
+        }
+
+        lines.push((center, point(joint.angle(transform)), Color::WHITE));
+        if let Some(command) = command {
+            lines.push((center, point(command.clamped_position()), Color::ORANGE));
+        }
+    }
+    if lines.is_empty() {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+    *visibility = Visibility::Inherited;
+    if let Some(mesh) = meshes.get_mut(mesh) {
+        let positions: Vec<_> = lines
+            .iter()
+            .flat_map(|(from, to, _)| [from.to_array(), to.to_array()])
+            .collect();
+        let colors: Vec<_> = lines
+            .iter()
+            .flat_map(|(.., color)| [color.as_linear_rgba_f32(); 2])
+            .collect();
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    }
+}
@@ -0,0 +1,205 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    arenas::{Arena, Arenas},
+    coordinate_frame::CoordinateFrame,
+    field_dimensions::FieldDimensions,
+    picking::Picking,
+    shortcuts::{dispatch_shortcuts, triggered, ShortcutAction},
+    tools::ActiveTool,
+    Ball,
+};
+
+/// Kick tool: clicking the ball pushes it away from the click, clicking anywhere else passes it
+/// toward the clicked point. Holding shift kicks twice as hard.
+///
+/// Independent of the active tool, the ball can be kicked toward a goal with a hotkey or from the
+/// kick window, other modules do so by sending [`KickBallToward`].
+pub struct KickToolPlugin;
+
+impl Plugin for KickToolPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<KickTool>()
+            .add_event::<KickBallToward>()
+            .add_system(kick_tool_ui)
+            .add_system(kick_ball_on_click.after(kick_tool_ui))
+            .add_system(kick_at_goal_on_shortcut.after(dispatch_shortcuts))
+            .add_system(
+                kick_ball_toward_targets
+                    .after(kick_tool_ui)
+                    .after(kick_at_goal_on_shortcut),
+            );
+    }
+}
+
+/// Kicks the ball of the arena toward the point on its field with the speed and lift angle of the
+/// [`KickTool`].
+pub struct KickBallToward {
+    pub arena: Arena,
+    /// Target in the field frame of the arena
+    pub target: Vec2,
+}
+
+#[derive(Resource)]
+pub struct KickTool {
+    /// Speed added to the ball in m/s
+    pub speed: f32,
+    /// Elevation of the kick direction in radians, zero rolls the ball along the ground
+    pub lift_angle: f32,
+}
+
+impl Default for KickTool {
+    fn default() -> Self {
+        Self {
+            speed: 2.0,
+            lift_angle: 0.0,
+        }
+    }
+}
+
+fn kick_tool_ui(
+    mut contexts: EguiContexts,
+    active_tool: Res<ActiveTool>,
+    field_dimensions: Res<FieldDimensions>,
+    arenas: Res<Arenas>,
+    mut kick_tool: ResMut<KickTool>,
+    mut kicks: EventWriter<KickBallToward>,
+) {
+    if *active_tool != ActiveTool::Kick {
+        return;
+    }
+    egui::Window::new("Kick")
+        .anchor(egui::Align2::RIGHT_TOP, [-10.0, 60.0])
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.add(egui::Slider::new(&mut kick_tool.speed, 0.1..=10.0).text("speed [m/s]"));
+            ui.add(
+                egui::Slider::new(&mut kick_tool.lift_angle, 0.0..=1.2).text("lift angle [rad]"),
+            );
+            ui.horizontal(|ui| {
+                for (label, side) in [("toward goal -x", -1.0), ("toward goal +x", 1.0)] {
+                    if ui.button(label).clicked() {
+                        kicks.send(KickBallToward {
+                            arena: arenas.focused,
+                            target: goal_center(&field_dimensions, side),
+                        });
+                    }
+                }
+            });
+        });
+}
+
+/// Center of the goal line on the `side` (+1 or -1) of the field.
+fn goal_center(field_dimensions: &FieldDimensions, side: f32) -> Vec2 {
+    Vec2::new(side * field_dimensions.length / 2.0, 0.0)
+}
+
+fn kick_at_goal_on_shortcut(
+    mut actions: EventReader<ShortcutAction>,
+    field_dimensions: Res<FieldDimensions>,
+    arenas: Res<Arenas>,
+    mut kicks: EventWriter<KickBallToward>,
+) {
+    if triggered(&mut actions, ShortcutAction::KickBallAtGoal) {
+        kicks.send(KickBallToward {
+            arena: arenas.focused,
+            target: goal_center(&field_dimensions, 1.0),
+        });
+    }
+}
+
+fn kick_ball_toward_targets(
+    mut kicks: EventReader<KickBallToward>,
+    kick_tool: Res<KickTool>,
+    frame: Res<CoordinateFrame>,
+    field_dimensions: Res<FieldDimensions>,
+    mut balls: Query<(&GlobalTransform, &mut Velocity, Option<&Arena>), With<Ball>>,
+) {
+    for kick_toward in kicks.iter() {
+        for (transform, mut velocity, arena) in balls.iter_mut() {
+            if arena.copied().unwrap_or_default() != kick_toward.arena {
+                continue;
+            }
+            let position = (frame.to_field(transform.translation())
+                - kick_toward.arena.offset(&field_dimensions))
+            .truncate();
+            let Some(direction) = (kick_toward.target - position).try_normalize() else {
+                continue;
+            };
+            kick(
+                &mut velocity,
+                &frame,
+                direction.extend(0.0),
+                kick_tool.lift_angle,
+                kick_tool.speed,
+            );
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn kick_ball_on_click(
+    mut contexts: EguiContexts,
+    active_tool: Res<ActiveTool>,
+    mouse: Res<Input<MouseButton>>,
+    keys: Res<Input<KeyCode>>,
+    kick_tool: Res<KickTool>,
+    frame: Res<CoordinateFrame>,
+    picking: Picking,
+    mut balls: Query<(&GlobalTransform, &mut Velocity), With<Ball>>,
+) {
+    if *active_tool != ActiveTool::Kick
+        || !mouse.just_pressed(MouseButton::Left)
+        || contexts.ctx_mut().is_pointer_over_area()
+    {
+        return;
+    }
+    let Some((picked, point)) = picking.pick(QueryFilter::default()) else {
+        return;
+    };
+    let picked_ball = balls.contains(picked);
+    let Some((ball_transform, mut velocity)) = balls.iter_mut().min_by(|(a, _), (b, _)| {
+        let distance_a = a.translation().distance_squared(point);
+        let distance_b = b.translation().distance_squared(point);
+        distance_a.total_cmp(&distance_b)
+    }) else {
+        return;
+    };
+
+    let ball_position = ball_transform.translation();
+    let direction = frame.vector_to_field(if picked_ball {
+        ball_position - point
+    } else {
+        point - ball_position
+    });
+    let Some(direction) = Vec3::new(direction.x, direction.y, 0.0).try_normalize() else {
+        return;
+    };
+    let modifier = if keys.any_pressed([KeyCode::LShift, KeyCode::RShift]) {
+        2.0
+    } else {
+        1.0
+    };
+    kick(
+        &mut velocity,
+        &frame,
+        direction,
+        kick_tool.lift_angle,
+        kick_tool.speed * modifier,
+    );
+}
+
+/// Adds `speed` to the ball velocity along the horizontal `direction` in field coordinates raised
+/// by `lift_angle`.
+pub fn kick(
+    velocity: &mut Velocity,
+    frame: &CoordinateFrame,
+    direction: Vec3,
+    lift_angle: f32,
+    speed: f32,
+) {
+    let direction = direction * lift_angle.cos() + Vec3::Z * lift_angle.sin();
+    velocity.linvel += frame.vector_to_world(direction) * speed;
+}
@@ -0,0 +1,1046 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
+
+use arenas::{Arenas, ArenasPlugin};
+use arguments::Arguments;
+use asset_loading::AssetLoadingPlugin;
+use audio_events::AudioEventsPlugin;
+use ball_heatmap::BallHeatmapPlugin;
+use ball_model::BallModelPlugin;
+use bevy::{log::LogPlugin, prelude::*, scene::ScenePlugin};
+use bevy_egui::EguiPlugin;
+use bevy_inspector_egui::quick::WorldInspectorPlugin;
+use bevy_rapier3d::{
+    plugin::systems::writeback_rigid_bodies,
+    prelude::*,
+    rapier::dynamics::{JointAxesMask, JointAxis, MotorModel},
+    utils::iso_to_transform,
+};
+use bevy_stl::StlPlugin;
+use body_drag::BodyDragPlugin;
+use camera_streams::CameraStreamsPlugin;
+use capture::{Capture, CapturePlugin, StartVideo};
+use collada_loader::ColladaPlugin;
+use collision_group_colors::CollisionGroupColorsPlugin;
+use color_eyre::Result;
+use contact_forces::ContactForcesPlugin;
+use context_menu::ContextMenuPlugin;
+use coordinate_frame::CoordinateFrame;
+use diagnostics_overlay::DiagnosticsOverlayPlugin;
+use environment::{Environment, EnvironmentPlugin};
+use field_dimensions::{FieldDimensions, FieldDimensionsFile, FieldDimensionsPlugin};
+use field_grid::FieldGridPlugin;
+use field_markings::FieldMarkingsPlugin;
+use file_drop::FileDropPlugin;
+use force_sensitive_resistors::ForceSensitiveResistorsPlugin;
+use game_controller::GameControllerPlugin;
+use game_phase::GamePhasePlugin;
+use ghost_replay::{GhostReplay, GhostReplayPlugin};
+use gltf_export::GltfExportPlugin;
+use goals::GoalsPlugin;
+use ground_truth::{GroundTruth, GroundTruthPlugin};
+use head_cameras::HeadCamerasPlugin;
+use imu::ImuPlugin;
+use inspector_ui::InspectorUiPlugin;
+use instant_replay::InstantReplayPlugin;
+use inverse_kinematics::InverseKinematicsPlugin;
+use joint_control::{JointCommand, JointControlPlugin, JointDynamics};
+use joint_encoders::JointEncodersPlugin;
+use joint_gizmos::JointGizmosPlugin;
+use kick_tool::KickToolPlugin;
+use lola::LolaPlugin;
+use mass_gizmos::MassGizmosPlugin;
+use mesh_uris::{read_urdf, PackagePaths};
+use motion_playback::MotionPlaybackPlugin;
+use motor_thermals::MotorThermalsPlugin;
+use mouse_drag::MouseDragPlugin;
+
+use nalgebra::{Matrix3, SymmetricEigen};
+use pan_orbit_camera::PanOrbitCamera;
+use physics_log::PhysicsLogPlugin;
+use physics_tuning::PhysicsTuningPlugin;
+use placement::PlacementPlugin;
+use player::{Player, PlayerPlugin, RobotStatus};
+use plotting::PlottingPlugin;
+use pose_clipboard::PoseClipboardPlugin;
+use pose_tool::PoseToolPlugin;
+use push_tool::PushToolPlugin;
+use recording::{Recorder, RecordingPlugin, Replay};
+use referee::RefereePlugin;
+use robot_assets::{RobotAssetCache, RobotAssets};
+use robot_controller::RobotControllerPlugin;
+use robot_labels::RobotLabelsPlugin;
+use robot_spawn::{RobotSpawn, RobotSpawnPlugin};
+#[cfg(feature = "ros2")]
+use ros2_bridge::Ros2BridgePlugin;
+use scenario::{Scenario, ScenarioPlugin};
+use scene_reset::SceneResetPlugin;
+use selection::SelectionPlugin;
+use self_collision::{SelfCollisionFilter, SelfCollisionPlugin};
+use shortcuts::ShortcutsPlugin;
+use simulation_config::SimulationConfig;
+use simulation_rng::SimulationRng;
+use simulation_time::{PhysicsSchedule, SimulationTime, SimulationTimePlugin, PHYSICS_TIMESTEP};
+use snapshot::SnapshotPlugin;
+use sonar::SonarPlugin;
+use team_communication::{TeamCommunication, TeamCommunicationPlugin};
+use team_configuration::{RobotSetup, TeamConfiguration, TeamSetup};
+use teleop::TeleopPlugin;
+use tools::ToolsPlugin;
+use transform_gizmo::TransformGizmoPlugin;
+use urdf_reload::UrdfReloadPlugin;
+use urdf_rs::{JointType, Robot};
+use vision_dataset::{VisionDataset, VisionDatasetPlugin};
+use websocket_server::WebSocketServerPlugin;
+use world_labels::WorldLabelsPlugin;
+
+mod arenas;
+pub mod arguments;
+mod asset_loading;
+mod audio_events;
+mod ball_heatmap;
+mod ball_model;
+mod body_drag;
+mod camera_streams;
+mod capture;
+mod collada_loader;
+mod collision_group_colors;
+mod contact_forces;
+mod context_menu;
+mod coordinate_frame;
+mod diagnostics_overlay;
+mod environment;
+mod field_dimensions;
+mod field_grid;
+mod field_markings;
+mod file_drop;
+mod force_sensitive_resistors;
+mod game_controller;
+mod game_phase;
+mod ghost_replay;
+mod gltf_export;
+mod goals;
+mod ground_truth;
+mod head_cameras;
+mod imu;
+mod inspector_ui;
+mod instant_replay;
+mod inverse_kinematics;
+mod joint_control;
+mod joint_encoders;
+mod joint_gizmos;
+mod kick_tool;
+mod lola;
+mod mass_gizmos;
+mod mesh_colliders;
+mod mesh_uris;
+mod motion_playback;
+mod motor_thermals;
+mod mouse_drag;
+mod pan_orbit_camera;
+mod physics_log;
+mod physics_tuning;
+mod picking;
+mod placement;
+mod player;
+mod plotting;
+mod pose_clipboard;
+mod pose_tool;
+mod push_tool;
+mod recording;
+mod referee;
+mod robot_assets;
+mod robot_controller;
+mod robot_labels;
+mod robot_spawn;
+#[cfg(feature = "ros2")]
+mod ros2_bridge;
+mod scenario;
+mod scene_reset;
+mod selection;
+mod self_collision;
+mod shortcuts;
+pub mod simulation_config;
+mod simulation_rng;
+mod simulation_time;
+pub mod simulator_handle;
+mod snapshot;
+mod sonar;
+mod team_communication;
+mod team_configuration;
+mod teleop;
+mod tools;
+mod transform_gizmo;
+mod urdf_reload;
+mod vision_dataset;
+mod websocket_server;
+mod world_labels;
+mod xacro;
+
+pub use force_sensitive_resistors::ForceSensitiveResistors;
+pub use imu::ImuReading;
+pub use motor_thermals::MotorState;
+pub use player::TeamColor;
+pub use robot_controller::{JointTarget, RobotController, RobotControllerAppExt, SensorSnapshot};
+pub use sonar::SonarReadings;
+
+/// Where the ball is placed at startup in field coordinates
+pub const BALL_SPAWN_POSITION: Vec3 = Vec3::new(0.03, 0.0, 5.0);
+/// Thickness of the field collider in meters
+const FIELD_COLLIDER_THICKNESS: f32 = 0.5;
+
+/// Sets up the simulator as given on the command line, with window and user interface unless
+/// running headless.
+pub fn build_app(arguments: Arguments) -> Result<App> {
+    let config = SimulationConfig::from_arguments(&arguments)?;
+    let scenario = arguments
+        .scenario
+        .as_ref()
+        .map(Scenario::load)
+        .transpose()?;
+    let headless = arguments.headless;
+    let mut app = App::new();
+    let asset_plugin = AssetPlugin {
+        asset_folder: config.assets.directory.to_string_lossy().into_owned(),
+        watch_for_changes: true,
+    };
+    if headless {
+        add_headless_plugins(&mut app, asset_plugin);
+    } else {
+        add_interactive_plugins(&mut app, asset_plugin);
+    }
+    add_simulation(&mut app, &config)?;
+    let mut simulation_time = SimulationTime::default();
+    // without a window nothing limits the frame rate, the simulation runs as fast as possible
+    simulation_time.free_running = headless;
+    simulation_time.time_scale = config.world.timescale;
+    app.insert_resource(simulation_time);
+    if let Some(scenario) = scenario {
+        app.insert_resource(scenario);
+    }
+    if !headless {
+        let mut capture = Capture::default();
+        capture.video_fps = arguments.video_fps;
+        app.insert_resource(capture).insert_resource(Environment {
+            skybox: arguments.skybox,
+            ..Default::default()
+        });
+        if let Some(path) = arguments.video {
+            app.world.send_event(StartVideo(path));
+        }
+        if let Some(path) = &arguments.ghost {
+            app.insert_resource(GhostReplay::load(path)?);
+        }
+        if let Some(path) = arguments.vision_dataset {
+            app.insert_resource(VisionDataset::create(
+                path,
+                arguments.vision_dataset_interval,
+            )?);
+        }
+    }
+    if let Some(path) = &arguments.record {
+        app.insert_resource(Recorder::create(path)?);
+    }
+    if let Some(path) = &arguments.replay {
+        app.insert_resource(Replay::load(path)?);
+    }
+    Ok(app)
+}
+
+/// Adds the physics, robots, sensors, game control and network interfaces configured in
+/// `config`, independent of how the simulation is presented.
+fn add_simulation(app: &mut App, config: &SimulationConfig) -> Result<()> {
+    let mut team_configuration = TeamConfiguration::load(&config.robots.teams)?;
+    team_configuration.default_urdf = config.robots.default_urdf.clone();
+    if let Some(robots) = config.robots.per_team {
+        team_configuration.limit_robots_per_team(robots);
+    }
+    let field_dimensions = match &config.field.file {
+        Some(path) => FieldDimensions::load(path)?,
+        None => config.field.dimensions.clone(),
+    };
+    let frame = CoordinateFrame {
+        up_axis: config.world.up_axis,
+        ..Default::default()
+    };
+    // the simulation time plugin steps the physics itself
+    let physics =
+        RapierPhysicsPlugin::<SelfCollisionFilter>::default().with_default_system_setup(false);
+    app.add_plugin(physics)
+        .add_plugin(SimulationTimePlugin)
+        .add_plugin(AssetLoadingPlugin)
+        .add_plugin(FieldDimensionsPlugin)
+        .add_plugin(ArenasPlugin)
+        .add_plugin(GoalsPlugin)
+        .add_plugin(BallModelPlugin)
+        .add_plugin(PlayerPlugin)
+        .add_plugin(RobotSpawnPlugin)
+        .add_plugin(SceneResetPlugin)
+        .add_plugin(SelfCollisionPlugin)
+        .add_plugin(PhysicsLogPlugin)
+        .add_plugin(PhysicsTuningPlugin)
+        .add_plugin(RefereePlugin)
+        .add_plugin(JointControlPlugin)
+        .add_plugin(MotorThermalsPlugin)
+        .add_plugin(JointEncodersPlugin)
+        .add_plugin(ImuPlugin)
+        .add_plugin(ForceSensitiveResistorsPlugin)
+        .add_plugin(SonarPlugin)
+        .add_plugin(LolaPlugin)
+        .add_plugin(RobotControllerPlugin)
+        .add_plugin(MotionPlaybackPlugin)
+        .add_plugin(InverseKinematicsPlugin)
+        .add_plugin(GameControllerPlugin)
+        .add_plugin(GamePhasePlugin)
+        .add_plugin(PlacementPlugin)
+        .add_plugin(AudioEventsPlugin)
+        .add_plugin(TeamCommunicationPlugin)
+        .add_plugin(GroundTruthPlugin)
+        .add_plugin(WebSocketServerPlugin)
+        .add_plugin(ScenarioPlugin)
+        .add_plugin(SnapshotPlugin)
+        .add_plugin(RecordingPlugin)
+        .add_plugin(UrdfReloadPlugin)
+        .insert_resource(team_configuration)
+        .insert_resource(AdditionalRobots(config.robots.additional.clone()))
+        .insert_resource(Arenas {
+            count: config.world.arenas,
+            ..Default::default()
+        })
+        .insert_resource(RobotAssetCache::new(config.assets.directory.clone()))
+        .insert_resource(PackagePaths(config.assets.packages.clone()))
+        .insert_resource(frame)
+        .insert_resource(RapierConfiguration {
+            gravity: frame.gravity(),
+            // the simulation time plugin runs one schedule per step, each advancing by exactly
+            // one timestep
+            timestep_mode: TimestepMode::Fixed {
+                dt: PHYSICS_TIMESTEP,
+                substeps: 1,
+            },
+            ..Default::default()
+        })
+        .insert_resource(SimulationRng::from_seed(config.world.seed))
+        .insert_resource(field_dimensions)
+        .add_startup_system(setup_field)
+        .add_startup_system(setup_robots)
+        .add_system(
+            sync_link_transforms
+                .in_base_set(PhysicsSet::Writeback)
+                .after(writeback_rigid_bodies)
+                .in_schedule(PhysicsSchedule),
+        );
+    config.insert_resources(app);
+    if let Some(path) = &config.field.file {
+        app.insert_resource(FieldDimensionsFile(path.clone()));
+    }
+    #[cfg(feature = "ros2")]
+    app.add_plugin(Ros2BridgePlugin);
+    if let Some(address) = config.network.ground_truth {
+        app.insert_resource(GroundTruth::new(address, config.network.ground_truth_rate)?);
+    }
+    Ok(())
+}
+
+/// Runs without window, rendering and egui, e.g. in CI or on servers without a GPU.
+fn add_headless_plugins(app: &mut App, asset_plugin: AssetPlugin) {
+    app.add_plugins(MinimalPlugins)
+        .add_plugin(LogPlugin::default())
+        .add_plugin(TransformPlugin)
+        .add_plugin(HierarchyPlugin)
+        .add_plugin(asset_plugin)
+        // rapier initializes colliders of scenes in its physics systems
+        .add_plugin(ScenePlugin)
+        // spawning still creates meshes and materials, they are just never rendered
+        .add_asset::<Mesh>()
+        .add_asset::<StandardMaterial>()
+        .add_asset::<Image>();
+}
+
+fn add_interactive_plugins(app: &mut App, asset_plugin: AssetPlugin) {
+    app.add_plugins(DefaultPlugins.set(asset_plugin))
+        .add_plugin(RapierDebugRenderPlugin {
+            mode: DebugRenderMode::COLLIDER_SHAPES | DebugRenderMode::JOINTS,
+            //| DebugRenderMode::RIGID_BODY_AXES,
+            ..Default::default()
+        })
+        .add_plugin(StlPlugin)
+        .add_plugin(ColladaPlugin)
+        .add_plugin(EguiPlugin)
+        .add_plugin(WorldInspectorPlugin::new())
+        .add_plugin(ShortcutsPlugin)
+        .add_plugin(PanOrbitCamera::default())
+        .add_plugin(EnvironmentPlugin)
+        .add_plugin(CollisionGroupColorsPlugin)
+        .add_plugin(ContactForcesPlugin)
+        .add_plugin(MassGizmosPlugin)
+        .add_plugin(JointGizmosPlugin)
+        .add_plugin(WorldLabelsPlugin)
+        .add_plugin(FieldGridPlugin)
+        .add_plugin(FieldMarkingsPlugin)
+        .add_plugin(BallHeatmapPlugin)
+        .add_plugin(HeadCamerasPlugin)
+        .add_plugin(CameraStreamsPlugin)
+        .add_plugin(VisionDatasetPlugin)
+        .add_plugin(CapturePlugin)
+        .add_plugin(GltfExportPlugin)
+        .add_plugin(RobotLabelsPlugin)
+        .add_plugin(InstantReplayPlugin)
+        .add_plugin(GhostReplayPlugin)
+        .add_plugin(ToolsPlugin)
+        .add_plugin(MouseDragPlugin)
+        .add_plugin(BodyDragPlugin)
+        .add_plugin(KickToolPlugin)
+        .add_plugin(SelectionPlugin)
+        .add_plugin(TransformGizmoPlugin)
+        .add_plugin(PoseClipboardPlugin)
+        .add_plugin(FileDropPlugin)
+        .add_plugin(ContextMenuPlugin)
+        .add_plugin(PushToolPlugin)
+        .add_plugin(PoseToolPlugin)
+        .add_plugin(TeleopPlugin)
+        .add_plugin(InspectorUiPlugin)
+        .add_plugin(PlottingPlugin)
+        .add_plugin(DiagnosticsOverlayPlugin);
+    //.add_plugin(InspectableRapierPlugin)
+}
+
+fn setup_field(
+    mut commands: Commands,
+    frame: Res<CoordinateFrame>,
+    field_dimensions: Res<FieldDimensions>,
+    arenas: Res<Arenas>,
+    server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let ground_size = Vec2::new(
+        field_dimensions.length + field_dimensions.border_strip_width * 2.0,
+        field_dimensions.width + field_dimensions.border_strip_width * 2.0,
+    );
+    let ground_mesh = meshes.add(Mesh::from(shape::Quad::new(ground_size)));
+    let ground_material = materials.add(StandardMaterial {
+        // lines are rendered from the field dimensions, the texture only adds the grass
+        base_color: Color::rgb(0.1, 0.45, 0.1),
+        perceptual_roughness: 0.8,
+        normal_map_texture: Some(server.load("textures/field_quarter_normal.jpg")),
+        ..Default::default()
+    });
+    let ball_mesh = meshes.add(Mesh::from(shape::UVSphere {
+        radius: field_dimensions.ball_radius,
+        sectors: 30,
+        stacks: 30,
+    }));
+    let ball_material = materials.add(StandardMaterial {
+        base_color: Color::WHITE,
+        base_color_texture: Some(server.load("ball/football_base_color.jpg")),
+        metallic: 0.,
+        perceptual_roughness: 0.4,
+        normal_map_texture: Some(server.load("ball/football_normal.jpg")),
+        ..Default::default()
+    });
+
+    for arena in arenas.iter() {
+        commands
+            .spawn(PbrBundle {
+                mesh: ground_mesh.clone(),
+                material: ground_material.clone(),
+                transform: arena.transform_to_world(&frame, &field_dimensions, Transform::IDENTITY),
+                ..Default::default()
+            })
+            // thick enough that fast bodies cannot tunnel through, the surface stays 1 cm above
+            // the visual ground
+            .insert(Collider::compound(vec![(
+                Vec3::Z * (0.01 - FIELD_COLLIDER_THICKNESS / 2.0),
+                Quat::IDENTITY,
+                Collider::cuboid(
+                    ground_size.x / 2.0,
+                    ground_size.y / 2.0,
+                    FIELD_COLLIDER_THICKNESS / 2.0,
+                ),
+            )]))
+            .insert(CollisionGroups::new(Group::GROUP_1, Group::ALL))
+            .insert(Name::new(arena.name("field")))
+            .insert(Field)
+            .insert(arena)
+            .insert(RigidBody::Fixed);
+
+        commands
+            .spawn(RigidBody::Dynamic)
+            .insert(Name::new(arena.name("ball")))
+            .insert(Ball)
+            .insert(arena)
+            .insert(PbrBundle {
+                mesh: ball_mesh.clone(),
+                material: ball_material.clone(),
+                ..Default::default()
+            })
+            .insert(Collider::ball(field_dimensions.ball_radius))
+            // hard kicks move the ball further than its diameter within a step
+            .insert(Ccd::enabled())
+            .insert(CollisionGroups::new(Group::GROUP_3, Group::ALL))
+            .insert(Velocity::zero())
+            .insert(TransformBundle::from(Transform::from_translation(
+                arena.to_world(&frame, &field_dimensions, BALL_SPAWN_POSITION),
+            )));
+    }
+}
+
+/// Root link of a robot, its entity identifies the robot.
+#[derive(Component)]
+struct RobotRoot;
+
+#[derive(Component)]
+struct Ball;
+
+#[derive(Component)]
+struct Field;
+
+/// Link of a robot, link names are only unique within their robot.
+#[derive(Component)]
+struct RobotLink {
+    pub name: String,
+    /// Root link of the robot the link belongs to
+    pub robot: Entity,
+}
+
+#[derive(Component)]
+struct NaoJoint {
+    pub name: String,
+    /// Rotation axis in the child link frame
+    pub axis: Vec3,
+    /// Rotation of the child link relative to its parent at the zero position
+    pub origin_rotation: Quat,
+}
+
+impl NaoJoint {
+    /// Angle of the joint given the local transform of its child link.
+    pub fn angle(&self, transform: &Transform) -> f32 {
+        let relative = self.origin_rotation.inverse() * transform.rotation;
+        let relative = if relative.w < 0.0 {
+            -relative
+        } else {
+            relative
+        };
+        2.0 * relative.xyz().dot(self.axis).atan2(relative.w)
+    }
+
+    /// Local rotation of the child link at the given joint angle.
+    pub fn rotation(&self, angle: f32) -> Quat {
+        self.origin_rotation * Quat::from_axis_angle(self.axis, angle)
+    }
+}
+
+/// Recomputes the transforms of robot links from their rigid bodies, from the root down.
+///
+/// Rapier writes the pose of a body back relative to the global transform its parent had before
+/// the step. Links attached to a moving link would end up where their parent was, and the next
+/// step would move their bodies there.
+fn sync_link_transforms(
+    context: Res<RapierContext>,
+    robots: Query<Entity, With<RobotRoot>>,
+    children: Query<&Children>,
+    mut transforms: Query<(
+        &mut Transform,
+        &mut GlobalTransform,
+        Option<&RapierRigidBodyHandle>,
+    )>,
+) {
+    for robot in robots.iter() {
+        let Ok((transform, mut global_transform, _)) = transforms.get_mut(robot) else {
+            continue;
+        };
+        // robots are not attached to other entities, their root transform is global
+        let root = GlobalTransform::from(*transform);
+        global_transform.set_if_neq(root);
+        let mut stack = vec![(robot, root)];
+        while let Some((parent, parent_global_transform)) = stack.pop() {
+            for &child in children.get(parent).into_iter().flatten() {
+                let Ok((mut transform, mut global_transform, body)) = transforms.get_mut(child)
+                else {
+                    continue;
+                };
+                if let Some(body) = body.and_then(|handle| context.bodies.get(handle.0)) {
+                    let pose = iso_to_transform(body.position(), context.physics_scale());
+                    let (_, inverse_rotation, inverse_translation) = parent_global_transform
+                        .affine()
+                        .inverse()
+                        .to_scale_rotation_translation();
+                    let local = Transform {
+                        translation: inverse_rotation * pose.translation + inverse_translation,
+                        rotation: inverse_rotation * pose.rotation,
+                        scale: transform.scale,
+                    };
+                    transform.set_if_neq(local);
+                }
+                let child_global_transform = parent_global_transform.mul_transform(*transform);
+                global_transform.set_if_neq(child_global_transform);
+                stack.push((child, child_global_transform));
+            }
+        }
+    }
+}
+
+/// URDFs of robots spawned next to the field, outside the teams
+#[derive(Resource)]
+struct AdditionalRobots(Vec<PathBuf>);
+
+#[allow(clippy::too_many_arguments)]
+fn setup_robots(
+    mut commands: Commands,
+    mut assets: RobotAssets,
+    team_configuration: Res<TeamConfiguration>,
+    additional_robots: Res<AdditionalRobots>,
+    arenas: Res<Arenas>,
+    robot_spawn: Res<RobotSpawn>,
+    frame: Res<CoordinateFrame>,
+    field_dimensions: Res<FieldDimensions>,
+    packages: Res<PackagePaths>,
+) {
+    let mut urdfs = HashMap::new();
+    for arena in arenas.iter() {
+        for team in &team_configuration.teams {
+            for robot in &team.robots {
+                let path = team_configuration.urdf(robot);
+                if !urdfs.contains_key(path) {
+                    match read_urdf(path, &packages) {
+                        Ok(urdf) => {
+                            urdfs.insert(path.to_path_buf(), urdf);
+                        }
+                        Err(error) => {
+                            error!("{error:?}");
+                            continue;
+                        }
+                    }
+                }
+                let transform = arena.transform_to_world(
+                    &frame,
+                    &field_dimensions,
+                    robot_spawn.transform(robot.transform()),
+                );
+                if let Some(root) = spawn_player(
+                    &mut commands,
+                    &mut assets,
+                    &urdfs[path],
+                    team,
+                    robot,
+                    transform,
+                ) {
+                    commands.entity(root).insert(arena);
+                }
+            }
+        }
+    }
+
+    // lined up on the border strip along the negative side line
+    let side = -(field_dimensions.width + field_dimensions.border_strip_width) / 2.0;
+    for (index, path) in additional_robots.0.iter().enumerate() {
+        let urdf = match read_urdf(path, &packages) {
+            Ok(urdf) => urdf,
+            Err(error) => {
+                error!("{error:?}");
+                continue;
+            }
+        };
+        let position = Transform::from_xyz(index as f32 - 1.0, side, 0.0);
+        let transform = frame.transform_to_world(robot_spawn.transform(position));
+        if spawn_robot(&mut commands, &mut assets, &urdf, transform).is_none() {
+            error!("{} has no root link", path.display());
+        }
+    }
+}
+
+/// Spawns the robot of a player of `team` at `transform`, returns its root link.
+fn spawn_player(
+    commands: &mut Commands,
+    assets: &mut RobotAssets,
+    urdf: &Robot,
+    team: &TeamSetup,
+    robot: &RobotSetup,
+    transform: Transform,
+) -> Option<Entity> {
+    let root = spawn_robot(commands, assets, urdf, transform)?;
+    commands.entity(root).insert((
+        Player {
+            team_color: team.team_color,
+            jersey_number: robot.jersey_number,
+        },
+        TeamCommunication {
+            enabled: true,
+            team_number: team.team_number,
+            data: Vec::new(),
+            received: Vec::new(),
+        },
+    ));
+    Some(root)
+}
+
+/// Spawns the links of `urdf` connected by its joints with the root link placed at `transform`.
+///
+/// Returns the root link, `None` if the URDF has no root link.
+fn spawn_robot(
+    commands: &mut Commands,
+    assets: &mut RobotAssets,
+    urdf: &Robot,
+    transform: Transform,
+) -> Option<Entity> {
+    let (link_to_entity, root) = spawn_links(commands, assets, urdf, transform)?;
+    spawn_joints(commands, urdf, &link_to_entity);
+    add_link_visuals(commands, assets, urdf, &link_to_entity);
+    Some(root)
+}
+
+fn add_link_visuals(
+    commands: &mut Commands,
+    assets: &mut RobotAssets,
+    urdf: &Robot,
+    link_to_entity: &HashMap<String, Entity>,
+) {
+    for link in urdf.links.iter() {
+        let current_link = link_to_entity[&link.name];
+
+        if !link.visual.is_empty() {
+            link.visual.iter().for_each(|visual| {
+                let (mesh, scale): (Handle<Mesh>, _) = match &visual.geometry {
+                    urdf_rs::Geometry::Mesh { filename, scale } => (
+                        assets.server.load(filename),
+                        scale
+                            .map(|vec| Vec3::new(vec[0] as f32, vec[1] as f32, vec[2] as f32))
+                            .unwrap_or(Vec3::ONE),
+                    ),
+                    _ => (Default::default(), Vec3::ONE),
+                };
+                let material: Handle<StandardMaterial> = match &visual.material {
+                    Some(urdf_rs::Material {
+                        texture: Some(urdf_rs::Texture { filename }),
+                        ..
+                    }) => assets.server.load(filename),
+                    Some(urdf_rs::Material {
+                        color: Some(urdf_rs::Color { rgba }),
+                        ..
+                    }) => assets.color_material(Color::rgba(
+                        rgba.0[0] as f32,
+                        rgba.0[1] as f32,
+                        rgba.0[2] as f32,
+                        rgba.0[3] as f32,
+                    )),
+                    _ => assets.color_material(Color::rgb(1., 1., 1.)),
+                };
+
+                let position = visual.origin.xyz;
+                let rotation = visual.origin.rpy;
+
+                let origin =
+                    Transform::from_xyz(position[0] as f32, position[1] as f32, position[2] as f32)
+                        .with_rotation(Quat::from_euler(
+                            EulerRot::ZYX,
+                            rotation[2] as f32,
+                            rotation[1] as f32,
+                            rotation[0] as f32,
+                        ))
+                        .with_scale(scale);
+
+                let visual = commands
+                    .spawn(PbrBundle {
+                        mesh,
+                        material,
+                        transform: origin,
+                        ..Default::default()
+                    })
+                    .id();
+                commands.entity(current_link).add_child(visual);
+            });
+        }
+    }
+}
+
+fn spawn_joints(commands: &mut Commands, urdf: &Robot, link_to_entity: &HashMap<String, Entity>) {
+    // links with mass are rigid bodies, see `spawn_links`
+    let bodies: HashSet<_> = urdf
+        .links
+        .iter()
+        .filter(|link| link.inertial.mass.value > 0.0)
+        .map(|link| &link.name)
+        .collect();
+    for joint in urdf.joints.iter() {
+        let parent_id = link_to_entity[&joint.parent.link];
+        let child_id = link_to_entity[&joint.child.link];
+
+        commands.entity(parent_id).add_child(child_id);
+        let translation = joint.origin.xyz;
+        let translation = Vec3::new(
+            translation[0] as f32,
+            translation[1] as f32,
+            translation[2] as f32,
+        );
+        let rotation = joint.origin.rpy;
+        let rotation = Quat::from_euler(
+            EulerRot::ZYX,
+            rotation[2] as f32,
+            rotation[1] as f32,
+            rotation[0] as f32,
+        );
+        let axis = joint.axis.xyz;
+        let axis = Vec3::new(axis[0] as f32, axis[1] as f32, axis[2] as f32);
+        // joint frames with their x axis along the joint axis, matching at the zero position, so
+        // motor targets are URDF joint positions
+        let axis_basis = Quat::from_rotation_arc(Vec3::X, axis.try_normalize().unwrap_or(Vec3::X));
+        let mut child = commands.entity(child_id);
+        child.insert(Transform {
+            translation,
+            rotation,
+            ..Default::default()
+        });
+        if matches!(
+            joint.joint_type,
+            JointType::Revolute | JointType::Continuous
+        ) {
+            child.insert(NaoJoint {
+                name: joint.name.clone(),
+                axis,
+                origin_rotation: rotation,
+            });
+        }
+        // let joint = FixedJointBuilder::new()
+        //     .local_anchor1(translation)
+        //     .local_basis1(rotation);
+        // child.insert(ImpulseJoint::new(parent_id, joint));
+        let data: Option<GenericJoint> = match joint.joint_type {
+            JointType::Revolute | JointType::Continuous | JointType::Prismatic => {
+                let (locked_axes, motor_axis) = if matches!(joint.joint_type, JointType::Prismatic)
+                {
+                    (JointAxesMask::LOCKED_PRISMATIC_AXES, JointAxis::X)
+                } else {
+                    (JointAxesMask::LOCKED_REVOLUTE_AXES, JointAxis::AngX)
+                };
+                // force based motors drive light links carrying heavy ones, acceleration based
+                // motors only account for the inertia of the link they are attached to
+                let mut builder = GenericJointBuilder::new(locked_axes)
+                    .local_anchor1(translation)
+                    .local_basis1(rotation * axis_basis)
+                    .local_basis2(axis_basis)
+                    .motor_model(motor_axis, MotorModel::ForceBased);
+                let mut command = JointCommand::new(motor_axis);
+                // continuous joints rotate without limits, the motor still drives them
+                if !matches!(joint.joint_type, JointType::Continuous) {
+                    let range = [joint.limit.lower as f32, joint.limit.upper as f32];
+                    builder = builder.limits(motor_axis, range);
+                    command = command.with_range(range);
+                }
+                // the velocity limit is not modeled, rapier motors have no speed limit
+                if joint.limit.effort > 0.0 {
+                    command = command.with_max_force(joint.limit.effort as f32);
+                }
+                child.insert(command);
+                if let Some(dynamics) = &joint.dynamics {
+                    child.insert(JointDynamics {
+                        damping: dynamics.damping as f32,
+                        friction: dynamics.friction as f32,
+                    });
+                }
+                Some(builder.build())
+            }
+            JointType::Fixed => Some(
+                FixedJointBuilder::new()
+                    .local_anchor1(translation)
+                    .local_basis1(rotation)
+                    .into(),
+            ),
+            // the child moves freely, it is only attached to the parent in the hierarchy
+            JointType::Floating => None,
+            JointType::Planar => {
+                // the joint axis is the plane normal: translation along it and rotation around
+                // the in-plane axes are locked
+                let joint = GenericJointBuilder::new(
+                    JointAxesMask::X | JointAxesMask::ANG_Y | JointAxesMask::ANG_Z,
+                )
+                .local_axis1(axis)
+                .local_axis2(axis)
+                .local_anchor1(translation);
+                Some(joint.build())
+            }
+            JointType::Spherical => Some(
+                SphericalJointBuilder::new()
+                    .local_anchor1(translation)
+                    .into(),
+            ),
+        };
+        // rapier attaches joints of links without a rigid body to the nearest body above them,
+        // which would join that body to itself, such links just follow their parent
+        if let (Some(data), true) = (data, bodies.contains(&joint.child.link)) {
+            child.insert(ImpulseJoint::new(parent_id, data));
+        }
+    }
+}
+
+fn spawn_links(
+    commands: &mut Commands,
+    assets: &mut RobotAssets,
+    urdf: &Robot,
+    transform: Transform,
+) -> Option<(HashMap<String, Entity>, Entity)> {
+    let mut link_to_entity = HashMap::new();
+    let child_links: HashSet<_> = urdf.joints.iter().map(|joint| &joint.child.link).collect();
+    let root_name = urdf
+        .links
+        .iter()
+        .map(|link| &link.name)
+        .find(|name| !child_links.contains(name))?;
+    let root = commands.spawn_empty().id();
+
+    for link in &urdf.links {
+        let name = link.name.clone();
+
+        let shapes: Vec<_> = link
+            .collision
+            .iter()
+            .flat_map(|collision| {
+                let position = collision.origin.xyz;
+                let position =
+                    Vec3::new(position[0] as f32, position[1] as f32, position[2] as f32);
+                let rotation = collision.origin.rpy;
+                let rotation = Quat::from_euler(
+                    EulerRot::ZYX,
+                    rotation[2] as f32,
+                    rotation[1] as f32,
+                    rotation[0] as f32,
+                );
+                let collider = match &collision.geometry {
+                    urdf_rs::Geometry::Box { size } => Collider::cuboid(
+                        size[0] as f32 / 2.0,
+                        size[1] as f32 / 2.0,
+                        size[2] as f32 / 2.0,
+                    ),
+                    urdf_rs::Geometry::Cylinder { radius, length } => {
+                        Collider::cylinder(*length as f32 / 2.0, *radius as f32)
+                    }
+                    urdf_rs::Geometry::Sphere { radius } => Collider::ball(*radius as f32),
+                    urdf_rs::Geometry::Capsule { radius, length } => {
+                        Collider::capsule_z(*length as f32 / 2.0, *radius as f32)
+                    }
+                    urdf_rs::Geometry::Mesh { filename, scale } => {
+                        let scale = scale
+                            .map(|vec| Vec3::new(vec[0] as f32, vec[1] as f32, vec[2] as f32))
+                            .unwrap_or(Vec3::ONE);
+                        let parts = assets
+                            .mesh_collider(filename, scale)
+                            .unwrap_or_else(|error| {
+                                error!("Skipping collision mesh of link {name}: {error:?}");
+                                Vec::new()
+                            });
+                        return parts
+                            .into_iter()
+                            .map(|(part_position, part_rotation, collider)| {
+                                (
+                                    position + rotation * part_position,
+                                    rotation * part_rotation,
+                                    collider,
+                                )
+                            })
+                            .collect();
+                    }
+                };
+                vec![(position, rotation, collider)]
+            })
+            .collect();
+
+        let inertial = &link.inertial;
+        let center_of_mass = Vec3::new(
+            inertial.origin.xyz[0] as f32,
+            inertial.origin.xyz[1] as f32,
+            inertial.origin.xyz[2] as f32,
+        );
+
+        let i = &inertial.inertia;
+        let inertia_matrix = Matrix3::new(
+            i.ixx as f32,
+            i.ixy as f32,
+            i.ixz as f32, //
+            i.ixy as f32,
+            i.iyy as f32,
+            i.iyz as f32, //
+            i.ixz as f32,
+            i.iyz as f32,
+            i.izz as f32, //
+        );
+
+        let mass_properties = if inertia_matrix != Matrix3::zeros() {
+            let evd = SymmetricEigen::new(inertia_matrix);
+
+            let principal_inertia =
+                Vec3::new(evd.eigenvalues[0], evd.eigenvalues[1], evd.eigenvalues[2]);
+            let column = |index: usize| {
+                let column = evd.eigenvectors.column(index);
+                Vec3::new(column[0], column[1], column[2])
+            };
+            let mut basis = Mat3::from_cols(column(0), column(1), column(2));
+            // eigenvectors may form a left-handed basis, which is a reflection and no rotation
+            if basis.determinant() < 0.0 {
+                basis.z_axis = -basis.z_axis;
+            }
+            let principal_inertia_local_frame =
+                if basis.is_finite() && (basis.determinant() - 1.0).abs() < 1e-3 {
+                    Quat::from_mat3(&basis).normalize()
+                } else {
+                    Quat::IDENTITY
+                };
+
+            Some(AdditionalMassProperties::MassProperties(MassProperties {
+                local_center_of_mass: center_of_mass,
+                mass: inertial.mass.value as f32,
+                principal_inertia_local_frame,
+                principal_inertia,
+            }))
+        } else {
+            None
+        };
+
+        let is_root_link = name == *root_name;
+        let mut link = if is_root_link {
+            commands.entity(root)
+        } else {
+            commands.spawn_empty()
+        };
+        link.insert((
+            RobotLink {
+                name: name.clone(),
+                robot: root,
+            },
+            TransformBundle::default(),
+            VisibilityBundle::default(),
+        ));
+        if is_root_link {
+            link.insert((
+                RobotRoot,
+                Player::default(),
+                RobotStatus::default(),
+                TransformBundle::from(transform),
+            ));
+        }
+        link_to_entity.insert(name, link.id());
+        if inertial.mass.value > 0.0 {
+            // physics only steps once the assets are loaded, until then the links stay put
+            link.insert((
+                RigidBody::Dynamic,
+                AdditionalMassProperties::Mass(inertial.mass.value as f32),
+            ));
+        }
+        if let Some(mass_properties) = mass_properties {
+            link.insert(mass_properties);
+        }
+        if !shapes.is_empty() {
+            // the URDF inertia is the whole mass of the body, collision shapes add none
+            link.insert((
+                Collider::compound(shapes),
+                ColliderMassProperties::Density(0.0),
+            ))
+            .insert(CollisionGroups::new(
+                Group::GROUP_2,
+                Group::GROUP_1 | Group::GROUP_2 | Group::GROUP_3,
+            ));
+        }
+    }
+    Some((link_to_entity, root))
+}
@@ -0,0 +1,385 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::Write,
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Condvar, Mutex,
+    },
+    thread,
+};
+
+use bevy::prelude::*;
+use color_eyre::{eyre::WrapErr, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    arenas::{in_main_arena, Arena},
+    force_sensitive_resistors::ForceSensitiveResistors,
+    imu::ImuReadings,
+    joint_control::JointCommand,
+    joint_encoders::MeasuredJointPositions,
+    motor_thermals::MotorState,
+    player::Player,
+    sonar::{SonarReadings, SONAR_MAXIMUM_DISTANCE},
+    NaoJoint, RobotRoot,
+};
+
+/// Joints in the order LoLA uses for all per-joint arrays
+const JOINT_NAMES: [&str; JOINT_COUNT] = [
+    "HeadYaw",
+    "HeadPitch",
+    "LShoulderPitch",
+    "LShoulderRoll",
+    "LElbowYaw",
+    "LElbowRoll",
+    "LWristYaw",
+    "LHipYawPitch",
+    "LHipRoll",
+    "LHipPitch",
+    "LKneePitch",
+    "LAnklePitch",
+    "LAnkleRoll",
+    "RHipRoll",
+    "RHipPitch",
+    "RKneePitch",
+    "RAnklePitch",
+    "RAnkleRoll",
+    "RShoulderPitch",
+    "RShoulderRoll",
+    "RElbowYaw",
+    "RElbowRoll",
+    "RWristYaw",
+    "LHand",
+    "RHand",
+];
+const JOINT_COUNT: usize = 25;
+/// Both hip yaw pitch joints are driven by a single motor on the NAO
+const COUPLED_JOINTS: [(&str, &str); 1] = [("RHipYawPitch", "LHipYawPitch")];
+/// LoLA reports the force sensitive resistors in kilograms
+const STANDARD_GRAVITY: f32 = 9.81;
+
+/// Emulates LoLA, the low level interface of the NAO, on a Unix socket so unmodified robot
+/// software can control a simulated robot.
+///
+/// Each frame a sensor frame of the robot with [`Lola::jersey_number`] is offered to the
+/// connected client, its answering actuator frame sets the [`JointCommand`]s of the robot.
+pub struct LolaPlugin;
+
+impl Plugin for LolaPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Lola>()
+            .add_startup_system(start_lola_server)
+            .add_system(send_sensor_frames)
+            .add_system(apply_actuator_frames);
+    }
+}
+
+#[derive(Resource)]
+pub struct Lola {
+    pub socket_path: PathBuf,
+    /// Jersey number of the robot controlled through LoLA
+    pub jersey_number: u8,
+}
+
+impl Default for Lola {
+    fn default() -> Self {
+        Self {
+            socket_path: PathBuf::from("/tmp/robocup"),
+            jersey_number: 1,
+        }
+    }
+}
+
+/// Channels to the thread serving the socket, the mutex only makes the receiver `Sync`.
+#[derive(Resource)]
+struct LolaChannels {
+    sensor_frames: Arc<LatestSensorFrame>,
+    actuator_frames: Mutex<Receiver<ActuatorFrame>>,
+}
+
+impl Drop for LolaChannels {
+    fn drop(&mut self) {
+        self.sensor_frames.close();
+    }
+}
+
+/// Holds only the newest sensor frame not yet sent, so the client always gets fresh data.
+#[derive(Default)]
+struct LatestSensorFrame {
+    state: Mutex<SensorFrameState>,
+    updated: Condvar,
+}
+
+#[derive(Default)]
+struct SensorFrameState {
+    frame: Option<SensorFrame>,
+    /// Set when the simulation quits, no more frames follow
+    closed: bool,
+}
+
+impl LatestSensorFrame {
+    /// Replaces the frame not yet sent.
+    fn publish(&self, frame: SensorFrame) {
+        self.state.lock().unwrap().frame = Some(frame);
+        self.updated.notify_one();
+    }
+
+    fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.updated.notify_one();
+    }
+
+    fn clear(&self) {
+        self.state.lock().unwrap().frame = None;
+    }
+
+    /// Waits for a frame newer than the last one taken, `None` once the simulation quits.
+    fn take(&self) -> Option<SensorFrame> {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if state.closed {
+                return None;
+            }
+            if let Some(frame) = state.frame.take() {
+                return Some(frame);
+            }
+            state = self.updated.wait(state).unwrap();
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct SensorFrame {
+    stiffness: [f32; JOINT_COUNT],
+    position: [f32; JOINT_COUNT],
+    temperature: [f32; JOINT_COUNT],
+    current: [f32; JOINT_COUNT],
+    /// Charge, status, current and temperature
+    battery: [f32; 4],
+    accelerometer: [f32; 3],
+    gyroscope: [f32; 3],
+    /// Torso roll and pitch
+    angles: [f32; 2],
+    sonar: [f32; 2],
+    #[serde(rename = "FSR")]
+    fsr: [f32; 8],
+    touch: [f32; 14],
+    status: [i32; JOINT_COUNT],
+    robot_config: [String; 4],
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ActuatorFrame {
+    position: [f32; JOINT_COUNT],
+    stiffness: [f32; JOINT_COUNT],
+}
+
+fn start_lola_server(mut commands: Commands, lola: Res<Lola>) {
+    let listener = match bind(&lola.socket_path) {
+        Ok(listener) => listener,
+        Err(error) => {
+            error!("{error:?}");
+            return;
+        }
+    };
+    let sensor_frames = Arc::new(LatestSensorFrame::default());
+    let (actuator_sender, actuator_receiver) = mpsc::channel();
+    let server_sensor_frames = sensor_frames.clone();
+    thread::spawn(move || serve(listener, &server_sensor_frames, actuator_sender));
+    info!("LoLA listening on {}", lola.socket_path.display());
+    commands.insert_resource(LolaChannels {
+        sensor_frames,
+        actuator_frames: Mutex::new(actuator_receiver),
+    });
+}
+
+fn bind(socket_path: &Path) -> Result<UnixListener> {
+    // a socket left over from a previous run prevents binding
+    if socket_path.exists() {
+        fs::remove_file(socket_path)
+            .wrap_err_with(|| format!("failed to remove stale socket {}", socket_path.display()))?;
+    }
+    UnixListener::bind(socket_path)
+        .wrap_err_with(|| format!("failed to bind LoLA socket {}", socket_path.display()))
+}
+
+fn serve(
+    listener: UnixListener,
+    sensor_frames: &LatestSensorFrame,
+    actuator_frames: Sender<ActuatorFrame>,
+) {
+    for stream in listener.incoming() {
+        let mut stream = match stream {
+            Ok(stream) => stream,
+            Err(error) => {
+                error!("Failed to accept LoLA client: {error}");
+                continue;
+            }
+        };
+        info!("LoLA client connected");
+        match handle_client(&mut stream, sensor_frames, &actuator_frames) {
+            Ok(()) => return,
+            Err(error) => warn!("LoLA client disconnected: {error:?}"),
+        }
+    }
+}
+
+/// Exchanges frames with a client until it disconnects, returns `Ok` when the simulation quits.
+fn handle_client(
+    stream: &mut UnixStream,
+    sensor_frames: &LatestSensorFrame,
+    actuator_frames: &Sender<ActuatorFrame>,
+) -> Result<()> {
+    // a frame produced while nobody was connected is outdated
+    sensor_frames.clear();
+    loop {
+        let Some(sensor_frame) = sensor_frames.take() else {
+            return Ok(());
+        };
+        let buffer =
+            rmp_serde::to_vec_named(&sensor_frame).wrap_err("failed to serialize sensor frame")?;
+        stream
+            .write_all(&buffer)
+            .wrap_err("failed to send sensor frame")?;
+        let actuator_frame =
+            rmp_serde::from_read(&mut *stream).wrap_err("failed to receive actuator frame")?;
+        if actuator_frames.send(actuator_frame).is_err() {
+            return Ok(());
+        }
+    }
+}
+
+fn send_sensor_frames(
+    lola: Res<Lola>,
+    channels: Option<Res<LolaChannels>>,
+    imu_readings: Res<ImuReadings>,
+    robots: Query<
+        (
+            Entity,
+            &Player,
+            Option<&MeasuredJointPositions>,
+            Option<&ForceSensitiveResistors>,
+            Option<&SonarReadings>,
+            Option<&Arena>,
+        ),
+        With<RobotRoot>,
+    >,
+    children: Query<&Children>,
+    joints: Query<(
+        &NaoJoint,
+        &Transform,
+        Option<&JointCommand>,
+        Option<&MotorState>,
+    )>,
+) {
+    let Some(channels) = channels else {
+        return;
+    };
+    let Some((robot, _, measured_positions, force_sensitive_resistors, sonar_readings, _)) =
+        robots.iter().find(|(_, player, .., arena)| {
+            in_main_arena(*arena) && player.jersey_number == lola.jersey_number
+        })
+    else {
+        return;
+    };
+
+    let mut position = [0.0; JOINT_COUNT];
+    let mut stiffness = [0.0; JOINT_COUNT];
+    let mut temperature = [30.0; JOINT_COUNT];
+    let mut current = [0.0; JOINT_COUNT];
+    for (joint, transform, command, motor) in children
+        .iter_descendants(robot)
+        .filter_map(|link| joints.get(link).ok())
+    {
+        let Some(index) = JOINT_NAMES.iter().position(|name| *name == joint.name) else {
+            continue;
+        };
+        position[index] = measured_positions
+            .and_then(|measured| measured.positions.get(&joint.name).copied())
+            .unwrap_or_else(|| joint.angle(transform));
+        stiffness[index] = command.map_or(0.0, JointCommand::stiffness_fraction);
+        if let Some(motor) = motor {
+            temperature[index] = motor.temperature;
+            current[index] = motor.current;
+        }
+    }
+
+    let imu = imu_readings.0.get(&robot).copied().unwrap_or_default();
+    let mut fsr = [0.0; 8];
+    if let Some(sensors) = force_sensitive_resistors {
+        for (weight, force) in fsr
+            .iter_mut()
+            .zip(sensors.left.iter().chain(sensors.right.iter()))
+        {
+            *weight = force / STANDARD_GRAVITY;
+        }
+    }
+
+    let frame = SensorFrame {
+        stiffness,
+        position,
+        temperature,
+        current,
+        battery: [1.0, 0.0, 0.0, 30.0],
+        accelerometer: imu.accelerometer.to_array(),
+        gyroscope: imu.gyroscope.to_array(),
+        angles: imu.angles.to_array(),
+        sonar: sonar_readings.map_or([SONAR_MAXIMUM_DISTANCE; 2], |readings| {
+            [readings.left, readings.right]
+        }),
+        fsr,
+        touch: [0.0; 14],
+        status: [0; JOINT_COUNT],
+        robot_config: [
+            "P0000000000000000000".to_string(),
+            "6.0.0".to_string(),
+            "P0000000000000000000".to_string(),
+            "6.0.0".to_string(),
+        ],
+    };
+    channels.sensor_frames.publish(frame);
+}
+
+fn apply_actuator_frames(
+    lola: Res<Lola>,
+    channels: Option<Res<LolaChannels>>,
+    robots: Query<(Entity, &Player, Option<&Arena>), With<RobotRoot>>,
+    children: Query<&Children>,
+    mut joints: Query<(&NaoJoint, &mut JointCommand)>,
+) {
+    let Some(channels) = channels else {
+        return;
+    };
+    let Some(frame) = channels.actuator_frames.lock().unwrap().try_iter().last() else {
+        return;
+    };
+    let Some((robot, ..)) = robots.iter().find(|(_, player, arena)| {
+        in_main_arena(*arena) && player.jersey_number == lola.jersey_number
+    }) else {
+        return;
+    };
+
+    let mut targets: HashMap<_, _> = JOINT_NAMES
+        .iter()
+        .zip(frame.position.iter().zip(frame.stiffness.iter()))
+        .map(|(name, (position, stiffness))| (*name, (*position, *stiffness)))
+        .collect();
+    for (coupled, driver) in COUPLED_JOINTS {
+        let target = targets[driver];
+        targets.insert(coupled, target);
+    }
+    for link in children.iter_descendants(robot) {
+        let Ok((joint, mut command)) = joints.get_mut(link) else {
+            continue;
+        };
+        if let Some((position, stiffness)) = targets.get(joint.name.as_str()) {
+            command.position = *position;
+            command.set_stiffness_fraction(*stiffness);
+        }
+    }
+}
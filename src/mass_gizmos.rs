@@ -0,0 +1,292 @@
+use bevy::{
+    prelude::*,
+    render::{mesh::PrimitiveTopology, view::NoFrustumCulling},
+    transform::TransformSystem,
+};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    coordinate_frame::CoordinateFrame,
+    shortcuts::{triggered, ShortcutAction},
+    world_labels::WorldLabel,
+    Field, RobotLink, RobotRoot,
+};
+
+/// Half size of the crosses marking centers of mass in meters
+const MARKER_SIZE: f32 = 0.01;
+
+/// Draws the center of mass, mass and principal inertia axes of every link as given by the URDF,
+/// and the center of mass of every robot projected onto its support polygon.
+///
+/// Axes are as long as the radius of gyration around them, so implausible inertia tensors stick
+/// out of the links. The projected center of mass is green inside the convex hull of the contacts
+/// with the field and red outside.
+pub struct MassGizmosPlugin;
+
+impl Plugin for MassGizmosPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MassGizmos>()
+            .add_startup_system(spawn_mass_lines)
+            .add_system(spawn_mass_labels)
+            .add_system(toggle_mass_gizmos)
+            .add_system(
+                update_mass_lines
+                    .in_base_set(CoreSet::PostUpdate)
+                    .after(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+#[derive(Default, Resource)]
+pub struct MassGizmos {
+    pub enabled: bool,
+}
+
+#[derive(Component)]
+struct MassLines;
+
+#[derive(Component)]
+struct MassLabel;
+
+fn spawn_mass_lines(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    // start with a degenerate line, the gizmos are computed every frame
+    let mut mesh = Mesh::new(PrimitiveTopology::LineList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, vec![[0.0; 3]; 2]);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, vec![[0.0; 4]; 2]);
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(mesh),
+            material: materials.add(StandardMaterial {
+                base_color: Color::WHITE,
+                unlit: true,
+                ..Default::default()
+            }),
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        },
+        // the bounding box is only computed for the initial mesh
+        NoFrustumCulling,
+        MassLines,
+        Name::new("mass gizmos"),
+    ));
+}
+
+/// Mass properties of a link as given by the URDF, links without inertia tensor have their
+/// center of mass at the link origin.
+fn link_mass(properties: &AdditionalMassProperties) -> MassProperties {
+    match *properties {
+        AdditionalMassProperties::MassProperties(properties) => properties,
+        AdditionalMassProperties::Mass(mass) => MassProperties {
+            mass,
+            ..Default::default()
+        },
+    }
+}
+
+fn spawn_mass_labels(
+    mut commands: Commands,
+    settings: Res<MassGizmos>,
+    links: Query<
+        (Entity, &AdditionalMassProperties),
+        (With<RobotLink>, Added<AdditionalMassProperties>),
+    >,
+) {
+    for (link, properties) in links.iter() {
+        let properties = link_mass(properties);
+        let visibility = if settings.enabled {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        let label = commands
+            .spawn((
+                TransformBundle::from(Transform::from_translation(properties.local_center_of_mass)),
+                VisibilityBundle {
+                    visibility,
+                    ..Default::default()
+                },
+                WorldLabel::new(format!("{:.3} kg", properties.mass)),
+                MassLabel,
+            ))
+            .id();
+        commands.entity(link).add_child(label);
+    }
+}
+
+fn toggle_mass_gizmos(
+    mut actions: EventReader<ShortcutAction>,
+    mut settings: ResMut<MassGizmos>,
+    mut labels: Query<&mut Visibility, With<MassLabel>>,
+) {
+    if !triggered(&mut actions, ShortcutAction::ToggleMassGizmos) {
+        return;
+    }
+    settings.enabled = !settings.enabled;
+    for mut visibility in labels.iter_mut() {
+        *visibility = if settings.enabled {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_mass_lines(
+    settings: Res<MassGizmos>,
+    frame: Res<CoordinateFrame>,
+    context: Res<RapierContext>,
+    fields: Query<Entity, With<Field>>,
+    robots: Query<Entity, With<RobotRoot>>,
+    children: Query<&Children>,
+    links: Query<(&GlobalTransform, &AdditionalMassProperties), With<RobotLink>>,
+    mut gizmos: Query<(&Handle<Mesh>, &mut Visibility), With<MassLines>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let Ok((mesh, mut visibility)) = gizmos.get_single_mut() else {
+        return;
+    };
+    if !settings.enabled {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+
+    let mut lines = Vec::new();
+    for robot in robots.iter() {
+        let mut total_mass = 0.0;
+        let mut weighted_center = Vec3::ZERO;
+        let mut contacts = Vec::new();
+        for link in std::iter::once(robot).chain(children.iter_descendants(robot)) {
+            let Ok((transform, properties)) = links.get(link) else {
+                continue;
+            };
+            let properties = link_mass(properties);
+            let center = transform.transform_point(properties.local_center_of_mass);
+            total_mass += properties.mass;
+            weighted_center += center * properties.mass;
+
+            lines.extend(cross(center, Color::WHITE));
+            if properties.mass > 0.0 {
+                let rotation = transform.compute_transform().rotation
+                    * properties.principal_inertia_local_frame;
+                let axes = [Vec3::X, Vec3::Y, Vec3::Z];
+                let colors = [Color::RED, Color::GREEN, Color::BLUE];
+                for ((axis, inertia), color) in axes
+                    .into_iter()
+                    .zip(properties.principal_inertia.to_array())
+                    .zip(colors)
+                {
+                    let radius_of_gyration = (inertia.max(0.0) / properties.mass).sqrt();
+                    let axis = rotation * axis * radius_of_gyration;
+                    lines.push((center - axis, center + axis, color));
+                }
+            }
+
+            for field in fields.iter() {
+                let Some(pair) = context.contact_pair(link, field) else {
+                    continue;
+                };
+                let link_is_first = pair.collider1() == link;
+                for manifold in pair.manifolds() {
+                    for contact in manifold.points().filter(|contact| contact.dist() <= 0.0) {
+                        let local_point = if link_is_first {
+                            contact.local_p1()
+                        } else {
+                            contact.local_p2()
+                        };
+                        contacts.push(
+                            frame
+                                .to_field(transform.transform_point(local_point))
+                                .truncate(),
+                        );
+                    }
+                }
+            }
+        }
+        if total_mass <= 0.0 {
+            continue;
+        }
+
+        let world_center = weighted_center / total_mass;
+        let center = frame.to_field(world_center).truncate();
+        let on_ground = |point: Vec2| frame.to_world(point.extend(0.0));
+        let support_polygon = convex_hull(contacts);
+        for (index, corner) in support_polygon.iter().enumerate() {
+            let next = support_polygon[(index + 1) % support_polygon.len()];
+            lines.push((on_ground(*corner), on_ground(next), Color::YELLOW));
+        }
+        let color = if contains(&support_polygon, center) {
+            Color::GREEN
+        } else {
+            Color::RED
+        };
+        lines.push((world_center, on_ground(center), color));
+        lines.extend(cross(on_ground(center), color));
+    }
+    if lines.is_empty() {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+    *visibility = Visibility::Inherited;
+    if let Some(mesh) = meshes.get_mut(mesh) {
+        let positions: Vec<_> = lines
+            .iter()
+            .flat_map(|(from, to, _)| [from.to_array(), to.to_array()])
+            .collect();
+        let colors: Vec<_> = lines
+            .iter()
+            .flat_map(|(.., color)| [color.as_linear_rgba_f32(); 2])
+            .collect();
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    }
+}
+
+/// Three axis aligned lines crossing at `center`.
+fn cross(center: Vec3, color: Color) -> [(Vec3, Vec3, Color); 3] {
+    [Vec3::X, Vec3::Y, Vec3::Z].map(|axis| {
+        let offset = axis * MARKER_SIZE;
+        (center - offset, center + offset, color)
+    })
+}
+
+/// Counterclockwise convex hull of `points` by Andrew's monotone chain.
+pub fn convex_hull(mut points: Vec<Vec2>) -> Vec<Vec2> {
+    points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+    let turns_left = |hull: &[Vec2], point: Vec2| {
+        let [.., a, b] = hull else {
+            return true;
+        };
+        (*b - *a).perp_dot(point - *a) > 0.0
+    };
+    let mut hull: Vec<Vec2> = Vec::new();
+    for pass in [points.clone(), points.into_iter().rev().collect()] {
+        let start = hull.len();
+        for point in pass {
+            while hull.len() >= start + 2 && !turns_left(&hull, point) {
+                hull.pop();
+            }
+            hull.push(point);
+        }
+        // the last point of each chain starts the other one
+        hull.pop();
+    }
+    hull
+}
+
+/// Whether `point` lies inside the counterclockwise convex `polygon`.
+fn contains(polygon: &[Vec2], point: Vec2) -> bool {
+    polygon.len() >= 3
+        && polygon.iter().enumerate().all(|(index, corner)| {
+            let next = polygon[(index + 1) % polygon.len()];
+            (next - *corner).perp_dot(point - *corner) >= 0.0
+        })
+}
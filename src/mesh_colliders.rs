@@ -0,0 +1,236 @@
+use std::{collections::HashMap, fs, io::Cursor, path::Path};
+
+use bevy::{prelude::*, render::mesh::VertexAttributeValues};
+use bevy_rapier3d::prelude::*;
+use clap::ValueEnum;
+use collada::document::ColladaDocument;
+use color_eyre::{
+    eyre::{bail, eyre, WrapErr},
+    Result,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::collada_loader::mesh_from_document;
+
+/// Directory in the assets the convex decompositions are cached in
+const CACHE_DIRECTORY: &str = ".cache/colliders";
+/// Edge length of the cubes vertices are merged in when simplifying meshes in meters
+const SIMPLIFICATION_CELL_SIZE: f32 = 0.005;
+
+/// How closely mesh colliders follow their meshes, coarser colliders make physics steps faster.
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Reflect, Resource, ValueEnum,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColliderFidelity {
+    /// One convex hull around the whole mesh
+    ConvexHull,
+    /// Convex decomposition of the mesh with nearby vertices merged
+    #[default]
+    Simplified,
+    /// Convex decomposition of the full resolution mesh
+    Full,
+}
+
+/// Position, rotation and collider of one convex part of a mesh collider
+pub type ColliderPart = (Vec3, Quat, Collider);
+
+/// Convex part as stored in the cache, the hull is recomputed from its points.
+#[derive(Deserialize, Serialize)]
+struct CachedPart {
+    translation: [f32; 3],
+    rotation: [f32; 4],
+    points: Vec<[f32; 3]>,
+}
+
+/// Loads the STL or Collada mesh `filename` in `assets_directory` and approximates it by convex
+/// shapes.
+///
+/// The vertices are scaled by `scale` before the decomposition. Returns the position and rotation
+/// of each convex part in the mesh frame, ready to be put into a compound collider (compounds
+/// cannot be nested). Decompositions are cached on disk by the hash of the file contents, the
+/// scale and the fidelity, so only changed meshes are decomposed again.
+pub fn load_mesh_collider(
+    assets_directory: &Path,
+    filename: &str,
+    scale: Vec3,
+    fidelity: ColliderFidelity,
+) -> Result<Vec<ColliderPart>> {
+    let path = assets_directory.join(filename);
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+    if !matches!(extension.as_deref(), Some("stl" | "dae")) {
+        bail!("unsupported collision mesh format {}", path.display());
+    }
+    let content = fs::read(&path).wrap_err_with(|| format!("failed to read {}", path.display()))?;
+    let cache_path = assets_directory.join(CACHE_DIRECTORY).join(format!(
+        "{:016x}.msgpack",
+        cache_key(&content, scale, fidelity)
+    ));
+    if let Some(parts) = read_cached_parts(&cache_path) {
+        return Ok(parts);
+    }
+    let (vertices, indices) = if extension.as_deref() == Some("dae") {
+        read_collada(&content)
+    } else {
+        read_stl(content)
+    }
+    .wrap_err_with(|| format!("failed to parse {}", path.display()))?;
+    let vertices: Vec<_> = vertices.into_iter().map(|vertex| vertex * scale).collect();
+    if indices.is_empty() {
+        bail!("collision mesh {} has no faces", path.display());
+    }
+    let decomposition = match fidelity {
+        ColliderFidelity::ConvexHull => Collider::convex_hull(&vertices)
+            .ok_or_else(|| eyre!("collision mesh {} is degenerate", path.display()))?,
+        ColliderFidelity::Simplified => {
+            let (vertices, indices) = simplify(&vertices, &indices, SIMPLIFICATION_CELL_SIZE);
+            if indices.is_empty() {
+                bail!("collision mesh {} vanished when simplified", path.display());
+            }
+            Collider::convex_decomposition(&vertices, &indices)
+        }
+        ColliderFidelity::Full => Collider::convex_decomposition(&vertices, &indices),
+    };
+    let parts = match decomposition.raw.as_compound() {
+        Some(compound) => compound
+            .shapes()
+            .iter()
+            .map(|(isometry, shape)| {
+                (
+                    isometry.translation.vector.into(),
+                    isometry.rotation.into(),
+                    Collider::from(shape.clone()),
+                )
+            })
+            .collect(),
+        None => vec![(Vec3::ZERO, Quat::IDENTITY, decomposition)],
+    };
+    if let Err(error) = write_cached_parts(&cache_path, &parts) {
+        warn!("Not caching collision mesh {filename}: {error:?}");
+    }
+    Ok(parts)
+}
+
+/// Vertices and triangles of an STL file.
+fn read_stl(content: Vec<u8>) -> Result<(Vec<Vec3>, Vec<[u32; 3]>)> {
+    let mesh = stl_io::read_stl(&mut Cursor::new(content))?;
+    let vertices = mesh
+        .vertices
+        .iter()
+        .map(|vertex| Vec3::new(vertex[0], vertex[1], vertex[2]))
+        .collect();
+    let indices = mesh
+        .faces
+        .iter()
+        .map(|face| face.vertices.map(|index| index as u32))
+        .collect();
+    Ok((vertices, indices))
+}
+
+/// Vertices and triangles of a Collada file, read like the visuals by the [`ColladaPlugin`].
+///
+/// [`ColladaPlugin`]: crate::collada_loader::ColladaPlugin
+fn read_collada(content: &[u8]) -> Result<(Vec<Vec3>, Vec<[u32; 3]>)> {
+    let document =
+        ColladaDocument::from_str(std::str::from_utf8(content)?).map_err(|error| eyre!(error))?;
+    let mesh = mesh_from_document(&document).ok_or_else(|| eyre!("no geometry"))?;
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        bail!("no vertex positions");
+    };
+    // the mesh is an unindexed triangle list
+    let vertices: Vec<_> = positions.iter().copied().map(Vec3::from).collect();
+    let indices = (0..vertices.len() as u32 / 3)
+        .map(|triangle| [0, 1, 2].map(|corner| triangle * 3 + corner))
+        .collect();
+    Ok((vertices, indices))
+}
+
+/// Merges all vertices within the same cube of edge length `cell_size` into their mean and drops
+/// the triangles collapsing in the process.
+fn simplify(vertices: &[Vec3], indices: &[[u32; 3]], cell_size: f32) -> (Vec<Vec3>, Vec<[u32; 3]>) {
+    let mut cells = HashMap::new();
+    let mut sums: Vec<(Vec3, f32)> = Vec::new();
+    let remap: Vec<u32> = vertices
+        .iter()
+        .map(|vertex| {
+            let cell = (*vertex / cell_size).floor().as_ivec3();
+            let index = *cells.entry(cell).or_insert_with(|| {
+                sums.push((Vec3::ZERO, 0.0));
+                sums.len() as u32 - 1
+            });
+            let (sum, count) = &mut sums[index as usize];
+            *sum += *vertex;
+            *count += 1.0;
+            index
+        })
+        .collect();
+    let vertices = sums.into_iter().map(|(sum, count)| sum / count).collect();
+    let indices = indices
+        .iter()
+        .map(|triangle| triangle.map(|index| remap[index as usize]))
+        .filter(|[a, b, c]| a != b && b != c && c != a)
+        .collect();
+    (vertices, indices)
+}
+
+/// FNV-1a hash of the mesh file, the scale and the fidelity, stable across runs and Rust
+/// versions.
+fn cache_key(content: &[u8], scale: Vec3, fidelity: ColliderFidelity) -> u64 {
+    let scale_bytes = scale.to_array().map(f32::to_le_bytes);
+    content
+        .iter()
+        .chain(scale_bytes.iter().flatten())
+        .chain(&[fidelity as u8])
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3)
+        })
+}
+
+/// Parts of a cached decomposition, `None` if there is none or it is unreadable.
+fn read_cached_parts(path: &Path) -> Option<Vec<ColliderPart>> {
+    let content = fs::read(path).ok()?;
+    let cached: Vec<CachedPart> = rmp_serde::from_slice(&content).ok()?;
+    cached
+        .into_iter()
+        .map(|part| {
+            let points: Vec<_> = part.points.into_iter().map(Vec3::from_array).collect();
+            Some((
+                Vec3::from_array(part.translation),
+                Quat::from_array(part.rotation),
+                Collider::convex_hull(&points)?,
+            ))
+        })
+        .collect()
+}
+
+fn write_cached_parts(path: &Path, parts: &[ColliderPart]) -> Result<()> {
+    let cached = parts
+        .iter()
+        .map(|(translation, rotation, collider)| {
+            let Some(polyhedron) = collider.as_convex_polyhedron() else {
+                bail!("decomposition contains a non-convex part");
+            };
+            Ok(CachedPart {
+                translation: translation.to_array(),
+                rotation: rotation.to_array(),
+                points: polyhedron
+                    .raw
+                    .points()
+                    .iter()
+                    .map(|point| [point.x, point.y, point.z])
+                    .collect(),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)
+            .wrap_err_with(|| format!("failed to create {}", directory.display()))?;
+    }
+    let content = rmp_serde::to_vec(&cached).wrap_err("failed to serialize decomposition")?;
+    fs::write(path, content).wrap_err_with(|| format!("failed to write {}", path.display()))
+}
@@ -0,0 +1,91 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use color_eyre::{eyre::WrapErr, Result};
+use urdf_rs::{Geometry, Robot};
+
+use crate::xacro::expand_xacro;
+
+/// Directories of the ROS packages referenced by `package://` URIs, by package name.
+#[derive(Clone, Debug, Default, Resource)]
+pub struct PackagePaths(pub HashMap<String, PathBuf>);
+
+/// Reads the URDF at `path` with its mesh and texture URIs resolved, `.xacro` files are expanded
+/// first.
+pub fn read_urdf(path: &Path, packages: &PackagePaths) -> Result<Robot> {
+    let is_xacro = path
+        .extension()
+        .map_or(false, |extension| extension.eq_ignore_ascii_case("xacro"));
+    let mut urdf = if is_xacro {
+        let expanded = expand_xacro(path, packages)?;
+        urdf_rs::read_from_string(&expanded)
+    } else {
+        urdf_rs::read_file(path)
+    }
+    .wrap_err_with(|| format!("failed to read URDF {}", path.display()))?;
+    resolve_mesh_uris(&mut urdf, path.parent(), packages);
+    Ok(urdf)
+}
+
+/// Rewrites the mesh and texture filenames of `urdf` to paths the asset server and the collider
+/// loader understand.
+///
+/// `package://` URIs are resolved through `packages`, packages without a path are looked up in the
+/// assets directory. `file://` URIs become plain paths. Relative paths are resolved against the
+/// `directory` of the URDF if the file exists there, otherwise they stay relative to the assets
+/// directory.
+pub fn resolve_mesh_uris(urdf: &mut Robot, directory: Option<&Path>, packages: &PackagePaths) {
+    let geometries = urdf.links.iter_mut().flat_map(|link| {
+        link.visual
+            .iter_mut()
+            .map(|visual| &mut visual.geometry)
+            .chain(
+                link.collision
+                    .iter_mut()
+                    .map(|collision| &mut collision.geometry),
+            )
+    });
+    for geometry in geometries {
+        if let Geometry::Mesh { filename, .. } = geometry {
+            *filename = resolve_uri(filename, directory, packages);
+        }
+    }
+    let textures = urdf
+        .links
+        .iter_mut()
+        .flat_map(|link| link.visual.iter_mut())
+        .filter_map(|visual| visual.material.as_mut()?.texture.as_mut())
+        .chain(
+            urdf.materials
+                .iter_mut()
+                .filter_map(|material| material.texture.as_mut()),
+        );
+    for texture in textures {
+        texture.filename = resolve_uri(&texture.filename, directory, packages);
+    }
+}
+
+fn resolve_uri(uri: &str, directory: Option<&Path>, packages: &PackagePaths) -> String {
+    if let Some(path) = uri.strip_prefix("package://") {
+        let (package, path) = path.split_once('/').unwrap_or((path, ""));
+        let package_directory = packages
+            .0
+            .get(package)
+            .cloned()
+            .unwrap_or_else(|| PathBuf::from(package));
+        return package_directory.join(path).to_string_lossy().into_owned();
+    }
+    if let Some(path) = uri.strip_prefix("file://") {
+        return path.to_string();
+    }
+    if let Some(directory) = directory {
+        let path = directory.join(uri);
+        if Path::new(uri).is_relative() && path.exists() {
+            return path.to_string_lossy().into_owned();
+        }
+    }
+    uri.to_string()
+}
@@ -0,0 +1,274 @@
+use std::{collections::HashMap, fs, path::Path, sync::Arc};
+
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts, EguiPlugin};
+use color_eyre::{eyre::WrapErr, Result};
+use serde::Deserialize;
+
+use crate::{
+    joint_control::{apply_joint_commands, JointCommand},
+    robot_controller::step_robot_controllers,
+    selection::Selection,
+    shortcuts::{dispatch_shortcuts, triggered, ShortcutAction, ShortcutsPlugin},
+    simulation_time::SimulationTime,
+    NaoJoint, RobotRoot,
+};
+
+/// Plays keyframe motions like stand-ups and kicks through the joint motors of a robot.
+///
+/// Motions are JSON files, see [`Motion`]. The "Motion" window loads a motion onto the selected
+/// robot and plays, pauses, rewinds and loops it. Playback follows simulation time and overrides
+/// the targets of the robot's controller for the joints the motion names.
+pub struct MotionPlaybackPlugin;
+
+impl Plugin for MotionPlaybackPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            play_motions
+                .after(step_robot_controllers)
+                .before(apply_joint_commands),
+        );
+        if app.is_plugin_added::<EguiPlugin>() {
+            app.init_resource::<MotionPlayer>()
+                .add_system(motion_player_ui);
+            if app.is_plugin_added::<ShortcutsPlugin>() {
+                app.add_system(toggle_motion_player.after(dispatch_shortcuts));
+            }
+        }
+    }
+}
+
+/// Timestamped joint targets, e.g.
+///
+/// ```json
+/// {
+///   "interpolation": "smooth",
+///   "keyframes": [
+///     { "time": 0.0, "positions": { "LKneePitch": 0.0, "RKneePitch": 0.0 } },
+///     { "time": 1.5, "positions": { "LKneePitch": 1.2, "RKneePitch": 1.2 }, "stiffness": 0.8 }
+///   ]
+/// }
+/// ```
+///
+/// Each joint is interpolated between the keyframes naming it and holds its first and last
+/// position before and after them.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Motion {
+    #[serde(default)]
+    pub interpolation: Interpolation,
+    pub keyframes: Vec<Keyframe>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Interpolation {
+    #[default]
+    Linear,
+    /// Cosine ease in and out, joints rest at every keyframe
+    Smooth,
+    /// Jumps to the next keyframe when it is reached
+    Step,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Keyframe {
+    /// Time since the start of the motion in seconds
+    pub time: f32,
+    /// Joint positions by joint name in radians
+    pub positions: HashMap<String, f32>,
+    /// Fraction of the default motor stiffness between 0 and 1, kept until the next keyframe
+    #[serde(default = "full_stiffness")]
+    pub stiffness: f32,
+}
+
+fn full_stiffness() -> f32 {
+    1.0
+}
+
+impl Motion {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .wrap_err_with(|| format!("failed to read motion {}", path.display()))?;
+        let mut motion: Self = serde_json::from_str(&content)
+            .wrap_err_with(|| format!("failed to parse motion {}", path.display()))?;
+        motion.keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Ok(motion)
+    }
+
+    /// Time of the last keyframe in seconds.
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |keyframe| keyframe.time)
+    }
+
+    /// Position of `joint` at `time`, `None` if no keyframe names the joint.
+    pub fn position(&self, joint: &str, time: f32) -> Option<f32> {
+        let mut previous = None;
+        for keyframe in &self.keyframes {
+            let Some(&position) = keyframe.positions.get(joint) else {
+                continue;
+            };
+            if keyframe.time <= time {
+                previous = Some((keyframe.time, position));
+                continue;
+            }
+            let Some((previous_time, previous_position)) = previous else {
+                return Some(position);
+            };
+            let progress = (time - previous_time) / (keyframe.time - previous_time);
+            let progress = match self.interpolation {
+                Interpolation::Linear => progress,
+                Interpolation::Smooth => (1.0 - (progress * std::f32::consts::PI).cos()) / 2.0,
+                Interpolation::Step => 0.0,
+            };
+            return Some(previous_position + (position - previous_position) * progress);
+        }
+        previous.map(|(_, position)| position)
+    }
+
+    /// Stiffness of the last keyframe reached at `time`.
+    pub fn stiffness(&self, time: f32) -> f32 {
+        self.keyframes
+            .iter()
+            .take_while(|keyframe| keyframe.time <= time)
+            .last()
+            .or(self.keyframes.first())
+            .map_or(1.0, |keyframe| keyframe.stiffness)
+    }
+}
+
+/// Motion played on a robot, lives on its root link.
+#[derive(Component)]
+pub struct MotionPlayback {
+    pub motion: Arc<Motion>,
+    /// Playback position in seconds
+    pub time: f32,
+    pub playing: bool,
+    /// Restarts the motion when it ends instead of holding the last keyframe
+    pub looping: bool,
+}
+
+impl MotionPlayback {
+    pub fn new(motion: Arc<Motion>) -> Self {
+        Self {
+            motion,
+            time: 0.0,
+            playing: true,
+            looping: false,
+        }
+    }
+}
+
+pub fn play_motions(
+    simulation_time: Res<SimulationTime>,
+    mut last_time: Local<f64>,
+    mut robots: Query<(Entity, &mut MotionPlayback)>,
+    children: Query<&Children>,
+    mut joints: Query<(&NaoJoint, &mut JointCommand)>,
+) {
+    let now = simulation_time.elapsed_seconds();
+    let delta = (now - std::mem::replace(&mut *last_time, now)) as f32;
+    for (robot, mut playback) in robots.iter_mut() {
+        let duration = playback.motion.duration();
+        if playback.playing {
+            playback.time += delta;
+            if playback.time > duration {
+                if playback.looping && duration > 0.0 {
+                    playback.time %= duration;
+                } else {
+                    playback.time = duration;
+                    playback.playing = false;
+                }
+            }
+        }
+        let stiffness = playback.motion.stiffness(playback.time);
+        for link in children.iter_descendants(robot) {
+            let Ok((joint, mut command)) = joints.get_mut(link) else {
+                continue;
+            };
+            let Some(position) = playback.motion.position(&joint.name, playback.time) else {
+                continue;
+            };
+            command.position = position;
+            command.set_stiffness_fraction(stiffness);
+        }
+    }
+}
+
+#[derive(Default, Resource)]
+struct MotionPlayer {
+    enabled: bool,
+    path: String,
+    error: Option<String>,
+}
+
+fn toggle_motion_player(
+    mut actions: EventReader<ShortcutAction>,
+    mut player: ResMut<MotionPlayer>,
+) {
+    if triggered(&mut actions, ShortcutAction::ToggleMotionPlayer) {
+        player.enabled = !player.enabled;
+    }
+}
+
+fn motion_player_ui(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    mut player: ResMut<MotionPlayer>,
+    selection: Res<Selection>,
+    robots: Query<(), With<RobotRoot>>,
+    mut playbacks: Query<&mut MotionPlayback>,
+) {
+    if !player.enabled {
+        return;
+    }
+    let player = &mut *player;
+    let robot = selection.entity.filter(|&entity| robots.contains(entity));
+    egui::Window::new("Motion").show(contexts.ctx_mut(), |ui| {
+        let Some(robot) = robot else {
+            ui.label("Select a robot");
+            return;
+        };
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut player.path);
+            if ui.button("load").clicked() {
+                match Motion::load(&player.path) {
+                    Ok(motion) => {
+                        commands
+                            .entity(robot)
+                            .insert(MotionPlayback::new(Arc::new(motion)));
+                        player.error = None;
+                    }
+                    Err(error) => {
+                        error!("{error:?}");
+                        player.error = Some(format!("{error:#}"));
+                    }
+                }
+            }
+        });
+        if let Some(error) = &player.error {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+        let Ok(mut playback) = playbacks.get_mut(robot) else {
+            return;
+        };
+        let duration = playback.motion.duration();
+        ui.horizontal(|ui| {
+            let label = if playback.playing { "pause" } else { "play" };
+            if ui.button(label).clicked() {
+                if !playback.playing && playback.time >= duration {
+                    playback.time = 0.0;
+                }
+                playback.playing = !playback.playing;
+            }
+            if ui.button("rewind").clicked() {
+                playback.time = 0.0;
+            }
+            ui.checkbox(&mut playback.looping, "loop");
+            if ui.button("stop").clicked() {
+                commands.entity(robot).remove::<MotionPlayback>();
+            }
+        });
+        ui.add(egui::Slider::new(&mut playback.time, 0.0..=duration).text("time [s]"));
+    });
+}
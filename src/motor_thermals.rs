@@ -0,0 +1,136 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    joint_control::{apply_joint_commands, JointCommand},
+    simulation_time::{PhysicsSchedule, PHYSICS_TIMESTEP},
+    NaoJoint,
+};
+
+/// Index of the angular x axis of the joint frame in the impulses of a joint, motors drive it
+const MOTOR_IMPULSE_INDEX: usize = 3;
+/// Smallest change of the available force worth updating the joint motor for
+const DERATING_STEP: f32 = 0.01;
+
+/// Estimates the current and winding temperature of every joint motor from the torque it applies
+/// and reduces the available torque of overheating motors like the NAO firmware does.
+///
+/// Motors heat with the copper losses of their current and cool toward the ambient temperature.
+/// The readings are published as [`MotorState`] next to the [`JointCommand`] of each joint, so
+/// behaviors that would burn the ankles of a real robot show up as derated joints instead.
+pub struct MotorThermalsPlugin;
+
+impl Plugin for MotorThermalsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MotorThermals>()
+            .add_system(add_motor_states)
+            .add_system(
+                update_motor_states
+                    .after(PhysicsSet::Writeback)
+                    .in_schedule(PhysicsSchedule),
+            )
+            .add_system(derate_hot_motors.before(apply_joint_commands));
+    }
+}
+
+#[derive(Resource)]
+pub struct MotorThermals {
+    /// Joint torque per motor current including the gearbox in Nm/A
+    pub torque_constant: f32,
+    /// Current drawn by a motor without load in A
+    pub idle_current: f32,
+    /// Winding resistance in Ω
+    pub winding_resistance: f32,
+    /// Thermal resistance between winding and ambient in K/W
+    pub thermal_resistance: f32,
+    /// Heat capacity of the motor in J/K
+    pub heat_capacity: f32,
+    /// Temperature of the surrounding air in °C
+    pub ambient_temperature: f32,
+    /// Temperature above which the available torque is reduced in °C
+    pub derating_temperature: f32,
+    /// Temperature at which the motor delivers no torque anymore in °C
+    pub shutdown_temperature: f32,
+}
+
+impl Default for MotorThermals {
+    fn default() -> Self {
+        Self {
+            torque_constant: 0.9,
+            idle_current: 0.05,
+            winding_resistance: 4.0,
+            thermal_resistance: 20.0,
+            heat_capacity: 8.0,
+            ambient_temperature: 30.0,
+            derating_temperature: 70.0,
+            shutdown_temperature: 90.0,
+        }
+    }
+}
+
+impl MotorThermals {
+    /// Fraction of the maximum torque a motor at `temperature` delivers.
+    pub fn available_force(&self, temperature: f32) -> f32 {
+        let range = self.shutdown_temperature - self.derating_temperature;
+        (1.0 - (temperature - self.derating_temperature) / range).clamp(0.0, 1.0)
+    }
+}
+
+/// Simulated electrical and thermal state of a joint motor.
+#[derive(Clone, Copy, Component, Debug)]
+pub struct MotorState {
+    /// Motor current in A
+    pub current: f32,
+    /// Winding temperature in °C
+    pub temperature: f32,
+}
+
+fn add_motor_states(
+    mut commands: Commands,
+    thermals: Res<MotorThermals>,
+    joints: Query<Entity, (Added<JointCommand>, With<NaoJoint>, Without<MotorState>)>,
+) {
+    for joint in joints.iter() {
+        commands.entity(joint).insert(MotorState {
+            current: 0.0,
+            temperature: thermals.ambient_temperature,
+        });
+    }
+}
+
+fn update_motor_states(
+    thermals: Res<MotorThermals>,
+    context: Res<RapierContext>,
+    mut joints: Query<(Entity, &mut MotorState)>,
+) {
+    for (entity, mut state) in joints.iter_mut() {
+        let torque = context
+            .entity2impulse_joint()
+            .get(&entity)
+            .and_then(|handle| context.impulse_joints.get(*handle))
+            .map_or(0.0, |joint| {
+                joint.impulses[MOTOR_IMPULSE_INDEX] / PHYSICS_TIMESTEP
+            });
+        let current = torque.abs() / thermals.torque_constant + thermals.idle_current;
+        let heating = thermals.winding_resistance * current.powi(2);
+        let cooling =
+            (state.temperature - thermals.ambient_temperature) / thermals.thermal_resistance;
+        state.current = current;
+        state.temperature += (heating - cooling) / thermals.heat_capacity * PHYSICS_TIMESTEP;
+    }
+}
+
+fn derate_hot_motors(
+    thermals: Res<MotorThermals>,
+    mut joints: Query<(&MotorState, &mut JointCommand)>,
+) {
+    for (state, mut command) in joints.iter_mut() {
+        let available_force = thermals.available_force(state.temperature);
+        // only touch the command on noticeable changes, every change resets the motor
+        if (available_force - command.available_force()).abs() >= DERATING_STEP
+            || (available_force == 0.0) != (command.available_force() == 0.0)
+        {
+            command.set_available_force(available_force);
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use bevy::prelude::*;
+use bevy_egui::EguiContexts;
+use bevy_rapier3d::{
+    prelude::*,
+    rapier::dynamics::{JointAxesMask, JointAxis},
+};
+
+use crate::{coordinate_frame::CoordinateFrame, picking::Picking, tools::ActiveTool};
+
+/// Drag tool: grab a dynamic body and pull it around with a spring attached to the cursor.
+///
+/// The body is dragged in the horizontal plane through the grab point, holding shift moves it
+/// vertically instead. Releasing the mouse button drops it back into the simulation.
+pub struct MouseDragPlugin;
+
+impl Plugin for MouseDragPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MouseDragSettings>()
+            .init_resource::<MouseDrag>()
+            .add_system(start_drag)
+            .add_system(update_drag.after(start_drag));
+    }
+}
+
+#[derive(Resource)]
+pub struct MouseDragSettings {
+    /// Stiffness of the spring between cursor and body, independent of the body's mass
+    pub stiffness: f32,
+    pub damping: f32,
+}
+
+impl Default for MouseDragSettings {
+    fn default() -> Self {
+        Self {
+            stiffness: 200.0,
+            damping: 20.0,
+        }
+    }
+}
+
+#[derive(Default, Resource)]
+struct MouseDrag {
+    /// Kinematic body following the cursor, the dragged body is jointed to it
+    anchor: Option<Entity>,
+    target: Vec3,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn start_drag(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    active_tool: Res<ActiveTool>,
+    mouse: Res<Input<MouseButton>>,
+    settings: Res<MouseDragSettings>,
+    picking: Picking,
+    bodies: Query<&GlobalTransform>,
+    mut drag: ResMut<MouseDrag>,
+) {
+    if *active_tool != ActiveTool::Drag
+        || !mouse.just_pressed(MouseButton::Left)
+        || contexts.ctx_mut().is_pointer_over_area()
+    {
+        return;
+    }
+    let Some((body, grab_point)) = picking.pick(QueryFilter::only_dynamic()) else {
+        return;
+    };
+    let Ok(body_transform) = bodies.get(body) else {
+        return;
+    };
+
+    let local_anchor = body_transform
+        .affine()
+        .inverse()
+        .transform_point3(grab_point);
+    let spring = [JointAxis::X, JointAxis::Y, JointAxis::Z].into_iter().fold(
+        GenericJointBuilder::new(JointAxesMask::empty()).local_anchor1(local_anchor),
+        |joint, axis| joint.motor_position(axis, 0.0, settings.stiffness, settings.damping),
+    );
+    let anchor = commands
+        .spawn((
+            RigidBody::KinematicPositionBased,
+            TransformBundle::from(Transform::from_translation(grab_point)),
+            ImpulseJoint::new(body, spring),
+            Name::new("drag anchor"),
+        ))
+        .id();
+    drag.anchor = Some(anchor);
+    drag.target = grab_point;
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_drag(
+    mut commands: Commands,
+    active_tool: Res<ActiveTool>,
+    mouse: Res<Input<MouseButton>>,
+    keys: Res<Input<KeyCode>>,
+    frame: Res<CoordinateFrame>,
+    picking: Picking,
+    mut drag: ResMut<MouseDrag>,
+    mut anchors: Query<&mut Transform>,
+) {
+    let Some(anchor) = drag.anchor else {
+        return;
+    };
+    if *active_tool != ActiveTool::Drag || !mouse.pressed(MouseButton::Left) {
+        commands.entity(anchor).despawn_recursive();
+        drag.anchor = None;
+        return;
+    }
+    let Some(ray) = picking.cursor_ray() else {
+        return;
+    };
+
+    let up = frame.up();
+    let plane_normal = if keys.any_pressed([KeyCode::LShift, KeyCode::RShift]) {
+        // vertical plane facing the camera
+        (ray.direction - up * ray.direction.dot(up)).normalize_or_zero()
+    } else {
+        up
+    };
+    let Some(distance) = ray.intersect_plane(drag.target, plane_normal) else {
+        return;
+    };
+    drag.target = ray.get_point(distance);
+    if let Ok(mut transform) = anchors.get_mut(anchor) {
+        transform.translation = drag.target;
+    }
+}
@@ -1,8 +1,19 @@
 use bevy::{
     input::mouse::{MouseMotion, MouseWheel},
     prelude::*,
+    render::primitives::Aabb,
     window::PrimaryWindow,
 };
+use bevy_egui::EguiContexts;
+
+use crate::{
+    body_drag::BodyDrag, coordinate_frame::CoordinateFrame, field_dimensions::FieldDimensions,
+    player::Player, selection::Selection, shortcuts::ShortcutAction, tools::ActiveTool, Ball,
+    RobotRoot,
+};
+
+/// Radius of the sphere framed for entities without bounding box in meters
+const DEFAULT_FRAMED_RADIUS: f32 = 0.5;
 
 /// Tags an entity as capable of panning and orbiting.
 #[derive(Component)]
@@ -11,6 +22,10 @@ pub struct PanOrbitCamera {
     pub focus: Vec3,
     pub radius: f32,
     pub upside_down: bool,
+    /// Entity the focus point follows, panning stops following
+    pub follow: Option<Entity>,
+    /// Time constant in seconds the focus point catches up with the followed entity
+    pub follow_smoothing: f32,
 }
 
 impl Default for PanOrbitCamera {
@@ -19,56 +34,196 @@ impl Default for PanOrbitCamera {
             focus: Vec3::ZERO,
             radius: 5.0,
             upside_down: false,
+            follow: None,
+            follow_smoothing: 0.3,
+        }
+    }
+}
+
+/// Mouse buttons, directions and sensitivities of the camera controls, editable in the inspector.
+#[derive(Reflect, Resource)]
+#[reflect(Resource)]
+pub struct CameraSettings {
+    pub orbit_button: MouseButton,
+    pub pan_button: MouseButton,
+    pub invert_orbit_x: bool,
+    pub invert_orbit_y: bool,
+    pub invert_pan_x: bool,
+    pub invert_pan_y: bool,
+    pub invert_zoom: bool,
+    /// Scales orbiting, 1 turns the camera once around per window width of mouse motion
+    pub orbit_sensitivity: f32,
+    /// Scales panning, 1 keeps the focus point under the mouse
+    pub pan_sensitivity: f32,
+    /// Fraction of the radius zoomed per scroll step
+    pub zoom_sensitivity: f32,
+    /// Keyboard panning speed in radii per second
+    pub keyboard_pan_speed: f32,
+    /// Keyboard orbiting speed in rad/s
+    pub keyboard_orbit_speed: f32,
+    /// Closest distance to the focus point in meters, zooming to zero gets the camera stuck
+    pub minimum_radius: f32,
+    /// Farthest distance to the focus point in meters
+    pub maximum_radius: f32,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            orbit_button: MouseButton::Right,
+            pan_button: MouseButton::Left,
+            invert_orbit_x: false,
+            invert_orbit_y: false,
+            invert_pan_x: false,
+            invert_pan_y: false,
+            invert_zoom: false,
+            orbit_sensitivity: 1.0,
+            pan_sensitivity: 1.0,
+            zoom_sensitivity: 0.2,
+            keyboard_pan_speed: 1.0,
+            keyboard_orbit_speed: 1.5,
+            minimum_radius: 0.05,
+            maximum_radius: 50.0,
         }
     }
 }
 
+/// Eye and focus point of the camera at startup in field coordinates.
+#[derive(Clone, Copy, Debug, Resource)]
+pub struct InitialCameraPose {
+    pub eye: Vec3,
+    pub focus: Vec3,
+}
+
+impl Default for InitialCameraPose {
+    fn default() -> Self {
+        Self {
+            eye: Vec3::new(1.0, -1.0, 1.4),
+            focus: Vec3::Z,
+        }
+    }
+}
+
+impl CameraSettings {
+    fn clamp_radius(&self, radius: f32) -> f32 {
+        radius.clamp(
+            self.minimum_radius,
+            self.maximum_radius.max(self.minimum_radius),
+        )
+    }
+}
+
 impl Plugin for PanOrbitCamera {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(spawn_camera)
-            .add_system(pan_orbit_camera);
+        app.init_resource::<ActiveTool>()
+            .init_resource::<CameraSettings>()
+            .init_resource::<InitialCameraPose>()
+            .register_type::<CameraSettings>()
+            .add_startup_system(spawn_camera)
+            .add_system(pan_orbit_camera)
+            .add_system(follow_target.after(pan_orbit_camera))
+            .add_system(apply_camera_presets)
+            .add_system(apply_follow_shortcuts.before(follow_target))
+            .add_system(keyboard_camera.before(follow_target))
+            .add_system(frame_selection.before(follow_target));
     }
 }
 
-fn spawn_camera(mut commands: Commands) {
-    let translation = Vec3::new(1.0, -1.0, 0.4);
-    let radius = translation.length();
+fn spawn_camera(
+    mut commands: Commands,
+    frame: Res<CoordinateFrame>,
+    initial_pose: Res<InitialCameraPose>,
+) {
+    let translation = frame.to_world(initial_pose.eye);
+    let focus = frame.to_world(initial_pose.focus);
 
     commands.spawn((
         Camera3dBundle {
-            transform: Transform::from_translation(translation).looking_at(Vec3::ZERO, Vec3::Z),
+            transform: Transform::from_translation(translation).looking_at(focus, frame.up()),
             ..Default::default()
         },
         PanOrbitCamera {
-            radius,
+            focus,
+            radius: translation.distance(focus),
             ..Default::default()
         },
     ));
 }
 
-/// Pan the camera with middle mouse click, zoom with scroll wheel, orbit with right mouse click.
-fn pan_orbit_camera(
+/// Eye and focus point of the camera presets in field coordinates on the ground
+const CAMERA_PRESETS: [(Vec3, Vec3); 9] = [
+    // overview from the side line
+    (Vec3::new(0.0, -7.0, 5.0), Vec3::ZERO),
+    // top down
+    (Vec3::new(0.0, -0.5, 11.0), Vec3::ZERO),
+    // behind the own goal
+    (Vec3::new(-6.5, 0.0, 2.5), Vec3::ZERO),
+    // behind the opponent goal
+    (Vec3::new(6.5, 0.0, 2.5), Vec3::ZERO),
+    // low side line view
+    (Vec3::new(0.0, -5.0, 1.5), Vec3::ZERO),
+    // own penalty area
+    (Vec3::new(-3.0, -3.0, 2.0), Vec3::new(-3.5, 0.0, 0.0)),
+    // opponent penalty area
+    (Vec3::new(3.0, -3.0, 2.0), Vec3::new(3.5, 0.0, 0.0)),
+    // own corner
+    (Vec3::new(-5.5, -4.0, 3.0), Vec3::ZERO),
+    // opponent corner
+    (Vec3::new(5.5, 4.0, 3.0), Vec3::ZERO),
+];
+
+fn apply_camera_presets(
+    mut actions: EventReader<ShortcutAction>,
+    frame: Res<CoordinateFrame>,
+    mut query: Query<(&mut PanOrbitCamera, &mut Transform)>,
+) {
+    for action in actions.iter() {
+        let ShortcutAction::CameraPreset(index) = action else {
+            continue;
+        };
+        let Some((eye, focus)) = CAMERA_PRESETS.get(*index) else {
+            continue;
+        };
+        let (eye, focus) = (frame.to_world(*eye), frame.to_world(*focus));
+        for (mut pan_orbit, mut transform) in query.iter_mut() {
+            *transform = Transform::from_translation(eye).looking_at(focus, frame.up());
+            pan_orbit.focus = focus;
+            pan_orbit.radius = eye.distance(focus);
+            pan_orbit.follow = None;
+        }
+    }
+}
+
+/// Pan, orbit and zoom the camera with the mouse as configured in the [`CameraSettings`].
+#[allow(clippy::too_many_arguments)]
+pub fn pan_orbit_camera(
     windows: Query<&Window, With<PrimaryWindow>>,
+    settings: Res<CameraSettings>,
     mut ev_motion: EventReader<MouseMotion>,
     mut ev_scroll: EventReader<MouseWheel>,
     input_mouse: Res<Input<MouseButton>>,
+    active_tool: Res<ActiveTool>,
+    body_drag: Res<BodyDrag>,
+    frame: Res<CoordinateFrame>,
     mut query: Query<(&mut PanOrbitCamera, &mut Transform, &Projection)>,
 ) {
-    // change input mapping for orbit and panning here
-    let orbit_button = MouseButton::Right;
-    let pan_button = MouseButton::Left;
+    let orbit_button = settings.orbit_button;
+    let pan_button = settings.pan_button;
 
     let mut pan = Vec2::ZERO;
     let mut rotation_move = Vec2::ZERO;
     let mut scroll: f32 = 0.0;
     let mut orbit_button_changed = false;
 
-    if input_mouse.pressed(orbit_button) {
+    if body_drag.is_dragging() {
+        // the mouse moves a body, not the camera
+        ev_motion.clear();
+    } else if input_mouse.pressed(orbit_button) {
         for ev in ev_motion.iter() {
             rotation_move += ev.delta;
         }
-    } else if input_mouse.pressed(pan_button) {
-        // Pan only if we're not rotating at the moment
+    } else if input_mouse.pressed(pan_button) && *active_tool == ActiveTool::Camera {
+        // Pan only if we're not rotating at the moment and no other tool uses the button
         for ev in ev_motion.iter() {
             pan += ev.delta;
         }
@@ -76,6 +231,15 @@ fn pan_orbit_camera(
     for ev in ev_scroll.iter() {
         scroll += ev.y;
     }
+    let invert = |inverted: bool| if inverted { -1.0 } else { 1.0 };
+    rotation_move *= settings.orbit_sensitivity
+        * Vec2::new(
+            invert(settings.invert_orbit_x),
+            invert(settings.invert_orbit_y),
+        );
+    pan *= settings.pan_sensitivity
+        * Vec2::new(invert(settings.invert_pan_x), invert(settings.invert_pan_y));
+    scroll *= invert(settings.invert_zoom);
     if input_mouse.just_released(orbit_button) || input_mouse.just_pressed(orbit_button) {
         orbit_button_changed = true;
     }
@@ -101,7 +265,7 @@ fn pan_orbit_camera(
                 }
             };
             let delta_y = rotation_move.y / window.y * std::f32::consts::PI;
-            let yaw = Quat::from_rotation_z(-delta_x);
+            let yaw = Quat::from_axis_angle(frame.up(), -delta_x);
             let pitch = Quat::from_rotation_x(-delta_y);
             transform.rotation = yaw * transform.rotation; // rotate around global y axis
             transform.rotation *= pitch; // rotate around local x axis
@@ -118,11 +282,11 @@ fn pan_orbit_camera(
             // make panning proportional to distance away from focus point
             let translation = (right + up) * pan_orbit.radius;
             pan_orbit.focus += translation;
+            pan_orbit.follow = None;
         } else if scroll.abs() > 0.0 {
             any = true;
-            pan_orbit.radius -= scroll * pan_orbit.radius * 0.2;
-            // dont allow zoom to reach zero or you get stuck
-            pan_orbit.radius = f32::max(pan_orbit.radius, 0.05);
+            pan_orbit.radius -= scroll * pan_orbit.radius * settings.zoom_sensitivity;
+            pan_orbit.radius = settings.clamp_radius(pan_orbit.radius);
         }
 
         if any {
@@ -140,6 +304,199 @@ fn pan_orbit_camera(
     ev_motion.clear();
 }
 
+/// Pans with WASD or the arrow keys and orbits with Q and E, unless they drive a selected robot.
+#[allow(clippy::too_many_arguments)]
+fn keyboard_camera(
+    mut contexts: EguiContexts,
+    time: Res<Time>,
+    settings: Res<CameraSettings>,
+    keys: Res<Input<KeyCode>>,
+    frame: Res<CoordinateFrame>,
+    selection: Option<Res<Selection>>,
+    robots: Query<(), With<RobotRoot>>,
+    mut cameras: Query<(&mut PanOrbitCamera, &mut Transform)>,
+) {
+    if contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+    let robot_selected = selection
+        .and_then(|selection| selection.entity)
+        .map_or(false, |entity| robots.contains(entity));
+    if robot_selected {
+        return;
+    }
+    let pressed = |codes: &[KeyCode]| {
+        if keys.any_pressed(codes.iter().copied()) {
+            1.0
+        } else {
+            0.0
+        }
+    };
+    let axis = |positive: &[KeyCode], negative: &[KeyCode]| pressed(positive) - pressed(negative);
+    let forward = axis(&[KeyCode::W, KeyCode::Up], &[KeyCode::S, KeyCode::Down]);
+    let right = axis(&[KeyCode::D, KeyCode::Right], &[KeyCode::A, KeyCode::Left]);
+    let orbit = axis(&[KeyCode::E], &[KeyCode::Q]);
+    if forward == 0.0 && right == 0.0 && orbit == 0.0 {
+        return;
+    }
+
+    let delta = time.delta_seconds();
+    for (mut pan_orbit, mut transform) in cameras.iter_mut() {
+        if forward != 0.0 || right != 0.0 {
+            // pan on the ground plane in the direction the camera looks
+            let rotation = frame.rotation().inverse() * transform.rotation;
+            let (yaw, _, _) = rotation.to_euler(EulerRot::ZYX);
+            let heading = Quat::from_rotation_z(yaw);
+            let direction =
+                frame.vector_to_world(heading * Vec3::new(right, forward, 0.0).normalize_or_zero());
+            let radius = pan_orbit.radius;
+            pan_orbit.focus += direction * settings.keyboard_pan_speed * radius * delta;
+            pan_orbit.follow = None;
+        }
+        transform.rotation =
+            Quat::from_axis_angle(frame.up(), orbit * settings.keyboard_orbit_speed * delta)
+                * transform.rotation;
+        transform.translation =
+            pan_orbit.focus + transform.rotation * Vec3::new(0.0, 0.0, pan_orbit.radius);
+    }
+}
+
+/// Moves the focus to the selected entity and the radius to fit it into the view.
+#[allow(clippy::too_many_arguments)]
+fn frame_selection(
+    mut actions: EventReader<ShortcutAction>,
+    settings: Res<CameraSettings>,
+    frame: Res<CoordinateFrame>,
+    selection: Option<Res<Selection>>,
+    field_dimensions: Res<FieldDimensions>,
+    children: Query<&Children>,
+    bounds: Query<(&GlobalTransform, Option<&Aabb>)>,
+    mut cameras: Query<(&mut PanOrbitCamera, &mut Transform, &Projection)>,
+) {
+    if !actions
+        .iter()
+        .any(|action| *action == ShortcutAction::FrameSelection)
+    {
+        return;
+    }
+    let selected = selection.and_then(|selection| selection.entity);
+    let (center, radius) = match selected {
+        Some(entity) => {
+            let boxes: Vec<_> = std::iter::once(entity)
+                .chain(children.iter_descendants(entity))
+                .filter_map(|entity| bounds.get(entity).ok())
+                .filter_map(|(transform, aabb)| {
+                    let aabb = aabb?;
+                    // the box is rotated, its bounding sphere is not
+                    let center = transform.transform_point(aabb.center.into());
+                    let extent = transform.affine().matrix3 * aabb.half_extents;
+                    Some((center, extent.length()))
+                })
+                .collect();
+            if boxes.is_empty() {
+                match bounds.get(entity) {
+                    Ok((transform, _)) => (transform.translation(), DEFAULT_FRAMED_RADIUS),
+                    Err(_) => return,
+                }
+            } else {
+                let minimum = boxes
+                    .iter()
+                    .map(|(center, radius)| *center - Vec3::splat(*radius))
+                    .reduce(Vec3::min)
+                    .unwrap();
+                let maximum = boxes
+                    .iter()
+                    .map(|(center, radius)| *center + Vec3::splat(*radius))
+                    .reduce(Vec3::max)
+                    .unwrap();
+                (
+                    (minimum + maximum) / 2.0,
+                    (maximum - minimum).length() / 2.0,
+                )
+            }
+        }
+        None => {
+            let half_size = Vec2::new(
+                field_dimensions.length / 2.0 + field_dimensions.border_strip_width,
+                field_dimensions.width / 2.0 + field_dimensions.border_strip_width,
+            );
+            (frame.to_world(Vec3::ZERO), half_size.length())
+        }
+    };
+
+    for (mut pan_orbit, mut transform, projection) in cameras.iter_mut() {
+        let fov = match projection {
+            Projection::Perspective(projection) => {
+                projection.fov.min(projection.fov * projection.aspect_ratio)
+            }
+            Projection::Orthographic(_) => std::f32::consts::FRAC_PI_4,
+        };
+        pan_orbit.focus = center;
+        pan_orbit.radius = settings.clamp_radius(radius / (fov / 2.0).sin());
+        pan_orbit.follow = None;
+        transform.translation =
+            pan_orbit.focus + transform.rotation * Vec3::new(0.0, 0.0, pan_orbit.radius);
+    }
+}
+
+fn apply_follow_shortcuts(
+    mut actions: EventReader<ShortcutAction>,
+    balls: Query<Entity, With<Ball>>,
+    players: Query<(Entity, &Player)>,
+    mut cameras: Query<&mut PanOrbitCamera>,
+) {
+    for action in actions.iter() {
+        for mut pan_orbit in cameras.iter_mut() {
+            match *action {
+                ShortcutAction::FollowBall => {
+                    let ball = balls.iter().next();
+                    pan_orbit.follow = if pan_orbit.follow == ball { None } else { ball };
+                }
+                ShortcutAction::FollowRobot(jersey_number) => {
+                    let mut robots: Vec<_> = players
+                        .iter()
+                        .filter(|(_, player)| player.jersey_number == jersey_number)
+                        .collect();
+                    robots.sort_by_key(|(_, player)| player.team_color as u8);
+                    // the next team's robot, after the last one following stops
+                    let current = robots
+                        .iter()
+                        .position(|(robot, _)| pan_orbit.follow == Some(*robot));
+                    let next = current.map_or(0, |index| index + 1);
+                    pan_orbit.follow = robots.get(next).map(|(robot, _)| *robot);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Moves the camera along with the followed entity, keeping its orientation and distance.
+fn follow_target(
+    time: Res<Time>,
+    targets: Query<&GlobalTransform>,
+    mut cameras: Query<(&mut PanOrbitCamera, &mut Transform)>,
+) {
+    for (mut pan_orbit, mut transform) in cameras.iter_mut() {
+        let Some(target) = pan_orbit.follow else {
+            continue;
+        };
+        let Ok(target_transform) = targets.get(target) else {
+            pan_orbit.follow = None;
+            continue;
+        };
+        // exponential smoothing, independent of the frame rate
+        let catch_up = if pan_orbit.follow_smoothing > 0.0 {
+            1.0 - (-time.delta_seconds() / pan_orbit.follow_smoothing).exp()
+        } else {
+            1.0
+        };
+        let offset = (target_transform.translation() - pan_orbit.focus) * catch_up;
+        pan_orbit.focus += offset;
+        transform.translation += offset;
+    }
+}
+
 fn get_primary_window_size(windows: &Query<&Window, With<PrimaryWindow>>) -> Vec2 {
     let window = windows.get_single().expect("could not find primary window");
     Vec2::new(window.width(), window.height())
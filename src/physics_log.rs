@@ -0,0 +1,199 @@
+use std::collections::HashSet;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    joint_control::JointCommand,
+    player::Player,
+    simulation_time::{PhysicsSchedule, SimulationTime},
+    NaoJoint, RobotLink,
+};
+
+/// Logs collisions, strong contact forces and joints leaving their limits or lagging far behind
+/// their targets, so physics anomalies show up before a robot visibly explodes.
+///
+/// Records are logged with structured fields under the `physics` target, e.g.
+/// `RUST_LOG=physics=info` shows only them.
+pub struct PhysicsLogPlugin;
+
+impl Plugin for PhysicsLogPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PhysicsLog>()
+            .add_system(enable_collider_events)
+            .add_system(log_collisions)
+            .add_system(log_contact_forces)
+            .add_system(
+                check_joints
+                    .after(PhysicsSet::Writeback)
+                    .in_schedule(PhysicsSchedule),
+            );
+    }
+}
+
+#[derive(Resource)]
+pub struct PhysicsLog {
+    /// Collisions are logged if both colliders are members of these groups, by default all but
+    /// the field, whose contacts with the feet never stop
+    pub collision_groups: Group,
+    /// Contacts exceeding this total force in newtons are logged
+    pub contact_force_threshold: f32,
+    /// How far a joint may exceed its limits before a warning in radians or meters
+    pub joint_limit_tolerance: f32,
+    /// Difference between target and actual joint position causing a warning in radians or
+    /// meters
+    pub position_error_threshold: f32,
+}
+
+impl Default for PhysicsLog {
+    fn default() -> Self {
+        Self {
+            collision_groups: Group::ALL.difference(Group::GROUP_1),
+            contact_force_threshold: 200.0,
+            joint_limit_tolerance: 0.05,
+            position_error_threshold: 0.5,
+        }
+    }
+}
+
+/// Rapier only reports events of colliders asking for them.
+fn enable_collider_events(
+    mut commands: Commands,
+    settings: Res<PhysicsLog>,
+    added_colliders: Query<Entity, Added<Collider>>,
+    colliders: Query<Entity, With<Collider>>,
+) {
+    let colliders: Vec<_> = if settings.is_changed() {
+        colliders.iter().collect()
+    } else {
+        added_colliders.iter().collect()
+    };
+    for collider in colliders {
+        commands.entity(collider).insert((
+            ActiveEvents::COLLISION_EVENTS | ActiveEvents::CONTACT_FORCE_EVENTS,
+            ContactForceEventThreshold(settings.contact_force_threshold),
+        ));
+    }
+}
+
+/// Names colliders by their entity name or link name and the robot they belong to.
+#[derive(SystemParam)]
+struct ColliderNames<'w, 's> {
+    names: Query<'w, 's, &'static Name>,
+    links: Query<'w, 's, &'static RobotLink>,
+    players: Query<'w, 's, &'static Player>,
+    parents: Query<'w, 's, &'static Parent>,
+}
+
+impl ColliderNames<'_, '_> {
+    fn name(&self, entity: Entity) -> String {
+        let name = match (self.names.get(entity), self.links.get(entity)) {
+            (Ok(name), _) => name.to_string(),
+            (_, Ok(link)) => link.name.clone(),
+            _ => format!("{entity:?}"),
+        };
+        let player = std::iter::once(entity)
+            .chain(self.parents.iter_ancestors(entity))
+            .find_map(|candidate| self.players.get(candidate).ok());
+        match player {
+            Some(player) => format!("{:?} {}/{name}", player.team_color, player.jersey_number),
+            None => name,
+        }
+    }
+}
+
+fn log_collisions(
+    settings: Res<PhysicsLog>,
+    simulation_time: Res<SimulationTime>,
+    mut collisions: EventReader<CollisionEvent>,
+    groups: Query<&CollisionGroups>,
+    names: ColliderNames,
+) {
+    let in_groups = |entity: Entity| {
+        groups.get(entity).map_or(false, |groups| {
+            settings.collision_groups.intersects(groups.memberships)
+        })
+    };
+    for collision in collisions.iter() {
+        let (first, second, started) = match *collision {
+            CollisionEvent::Started(first, second, _) => (first, second, true),
+            CollisionEvent::Stopped(first, second, _) => (first, second, false),
+        };
+        if !in_groups(first) || !in_groups(second) {
+            continue;
+        }
+        info!(
+            target: "physics",
+            time = simulation_time.elapsed_seconds(),
+            first = %names.name(first),
+            second = %names.name(second),
+            "collision {}",
+            if started { "started" } else { "stopped" }
+        );
+    }
+}
+
+fn log_contact_forces(
+    simulation_time: Res<SimulationTime>,
+    mut contact_forces: EventReader<ContactForceEvent>,
+    names: ColliderNames,
+) {
+    for contact_force in contact_forces.iter() {
+        info!(
+            target: "physics",
+            time = simulation_time.elapsed_seconds(),
+            first = %names.name(contact_force.collider1),
+            second = %names.name(contact_force.collider2),
+            force = contact_force.total_force_magnitude,
+            "strong contact"
+        );
+    }
+}
+
+/// Warns once each time a joint leaves its limits or falls far behind its target.
+fn check_joints(
+    settings: Res<PhysicsLog>,
+    simulation_time: Res<SimulationTime>,
+    mut beyond_limits: Local<HashSet<Entity>>,
+    mut lagging: Local<HashSet<Entity>>,
+    joints: Query<(Entity, &NaoJoint, &JointCommand, &Transform)>,
+    names: ColliderNames,
+) {
+    for (entity, joint, command, transform) in joints.iter() {
+        let position = joint.angle(transform);
+        let limit_exceeded = command.range().map_or(false, |[lower, upper]| {
+            position < lower - settings.joint_limit_tolerance
+                || position > upper + settings.joint_limit_tolerance
+        });
+        if limit_exceeded && beyond_limits.insert(entity) {
+            warn!(
+                target: "physics",
+                time = simulation_time.elapsed_seconds(),
+                link = %names.name(entity),
+                joint = %joint.name,
+                position,
+                range = ?command.range(),
+                "joint beyond its limits"
+            );
+        } else if !limit_exceeded {
+            beyond_limits.remove(&entity);
+        }
+
+        let error = (command.clamped_position() - position).abs();
+        let error_exceeded = error > settings.position_error_threshold;
+        if error_exceeded && lagging.insert(entity) {
+            warn!(
+                target: "physics",
+                time = simulation_time.elapsed_seconds(),
+                link = %names.name(entity),
+                joint = %joint.name,
+                position,
+                target_position = command.clamped_position(),
+                error,
+                "joint far from its target"
+            );
+        } else if !error_exceeded {
+            lagging.remove(&entity);
+        }
+    }
+}
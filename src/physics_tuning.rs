@@ -0,0 +1,147 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts, EguiPlugin};
+use bevy_rapier3d::{prelude::*, rapier::dynamics::IntegrationParameters};
+use serde::Deserialize;
+
+use crate::{
+    shortcuts::{dispatch_shortcuts, triggered, ShortcutAction, ShortcutsPlugin},
+    Ball, Field,
+};
+
+/// Adjusts solver parameters and the contact materials of field and ball at runtime.
+///
+/// The "Physics" window edits a copy of [`PhysicsTuning`] that only takes effect when applied, so
+/// several parameters can be changed together without simulating the steps in between.
+pub struct PhysicsTuningPlugin;
+
+impl Plugin for PhysicsTuningPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PhysicsTuning>()
+            .add_system(apply_physics_tuning);
+        if app.is_plugin_added::<EguiPlugin>() {
+            app.init_resource::<PhysicsTuningPanel>()
+                .add_system(physics_tuning_ui.before(apply_physics_tuning));
+            if app.is_plugin_added::<ShortcutsPlugin>() {
+                app.add_system(toggle_physics_tuning_panel.after(dispatch_shortcuts));
+            }
+        }
+    }
+}
+
+/// Configuration files may leave out fields, those keep their default.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Reflect, Resource)]
+#[serde(default)]
+pub struct PhysicsTuning {
+    /// Solver substeps per physics step
+    pub substeps: usize,
+    /// Fraction of the penetration resolved per step, between 0 and 1
+    pub erp: f32,
+    pub max_velocity_iterations: usize,
+    pub field_friction: f32,
+    pub field_restitution: f32,
+    pub ball_friction: f32,
+    pub ball_restitution: f32,
+}
+
+impl Default for PhysicsTuning {
+    fn default() -> Self {
+        let parameters = IntegrationParameters::default();
+        Self {
+            substeps: 1,
+            erp: parameters.erp,
+            // rapier's default leaves the joint chains of the robots sagging apart
+            max_velocity_iterations: 16,
+            field_friction: Friction::default().coefficient,
+            field_restitution: 0.0,
+            ball_friction: Friction::default().coefficient,
+            ball_restitution: 0.7,
+        }
+    }
+}
+
+fn apply_physics_tuning(
+    mut commands: Commands,
+    tuning: Res<PhysicsTuning>,
+    mut configuration: ResMut<RapierConfiguration>,
+    mut context: ResMut<RapierContext>,
+    fields: Query<Entity, With<Field>>,
+    balls: Query<Entity, With<Ball>>,
+) {
+    if !tuning.is_changed() {
+        return;
+    }
+    if let TimestepMode::Fixed { substeps, .. } = &mut configuration.timestep_mode {
+        *substeps = tuning.substeps.max(1);
+    }
+    context.integration_parameters.erp = tuning.erp;
+    context.integration_parameters.max_velocity_iterations = tuning.max_velocity_iterations;
+    for field in fields.iter() {
+        commands.entity(field).insert((
+            Friction::coefficient(tuning.field_friction),
+            Restitution::coefficient(tuning.field_restitution),
+        ));
+    }
+    for ball in balls.iter() {
+        commands.entity(ball).insert((
+            Friction::coefficient(tuning.ball_friction),
+            Restitution::coefficient(tuning.ball_restitution),
+        ));
+    }
+}
+
+#[derive(Default, Resource)]
+struct PhysicsTuningPanel {
+    enabled: bool,
+    /// Edited values, `None` until the window is opened
+    draft: Option<PhysicsTuning>,
+}
+
+fn toggle_physics_tuning_panel(
+    mut actions: EventReader<ShortcutAction>,
+    mut panel: ResMut<PhysicsTuningPanel>,
+) {
+    if triggered(&mut actions, ShortcutAction::TogglePhysicsTuning) {
+        panel.enabled = !panel.enabled;
+    }
+}
+
+fn physics_tuning_ui(
+    mut contexts: EguiContexts,
+    mut panel: ResMut<PhysicsTuningPanel>,
+    mut tuning: ResMut<PhysicsTuning>,
+) {
+    if !panel.enabled {
+        return;
+    }
+    let draft = panel.draft.get_or_insert(*tuning);
+    egui::Window::new("Physics").show(contexts.ctx_mut(), |ui| {
+        ui.label("Solver");
+        ui.add(egui::Slider::new(&mut draft.substeps, 1..=16).text("substeps"));
+        ui.add(egui::Slider::new(&mut draft.erp, 0.0..=1.0).text("ERP"));
+        ui.add(
+            egui::Slider::new(&mut draft.max_velocity_iterations, 1..=32)
+                .text("max velocity iterations"),
+        );
+        ui.separator();
+        ui.label("Field");
+        ui.add(egui::Slider::new(&mut draft.field_friction, 0.0..=2.0).text("friction"));
+        ui.add(egui::Slider::new(&mut draft.field_restitution, 0.0..=1.0).text("restitution"));
+        ui.separator();
+        ui.label("Ball");
+        ui.add(egui::Slider::new(&mut draft.ball_friction, 0.0..=2.0).text("friction"));
+        ui.add(egui::Slider::new(&mut draft.ball_restitution, 0.0..=1.0).text("restitution"));
+        ui.separator();
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(*draft != *tuning, egui::Button::new("apply"))
+                .clicked()
+            {
+                *tuning = *draft;
+            }
+            if ui.button("reset").clicked() {
+                *draft = PhysicsTuning::default();
+                *tuning = *draft;
+            }
+        });
+    });
+}
@@ -0,0 +1,48 @@
+use bevy::{ecs::system::SystemParam, prelude::*, window::PrimaryWindow};
+use bevy_rapier3d::prelude::*;
+
+use crate::pan_orbit_camera::PanOrbitCamera;
+
+/// Maximum distance from the camera at which entities can be picked
+const MAXIMUM_PICK_DISTANCE: f32 = 100.0;
+
+/// Casts rays from the viewport camera through the cursor into the physics world.
+#[derive(SystemParam)]
+pub struct Picking<'w, 's> {
+    windows: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+    cameras: Query<'w, 's, (&'static Camera, &'static GlobalTransform), With<PanOrbitCamera>>,
+    rapier_context: Res<'w, RapierContext>,
+}
+
+impl<'w, 's> Picking<'w, 's> {
+    /// Ray from the camera through the cursor, `None` if the cursor is outside the viewport.
+    pub fn cursor_ray(&self) -> Option<Ray> {
+        let window = self.windows.get_single().ok()?;
+        let (camera, camera_transform) = self.cameras.get_single().ok()?;
+        let cursor = window.cursor_position()?;
+        // the cursor starts at the bottom left of the window, the viewport rect at the top left
+        let (viewport_min, viewport_max) = camera.logical_viewport_rect()?;
+        let position = Vec2::new(
+            cursor.x - viewport_min.x,
+            cursor.y - (window.height() - viewport_max.y),
+        );
+        let viewport_size = viewport_max - viewport_min;
+        if position.cmplt(Vec2::ZERO).any() || position.cmpgt(viewport_size).any() {
+            return None;
+        }
+        camera.viewport_to_world(camera_transform, position)
+    }
+
+    /// First collider under the cursor and the world position where it was hit.
+    pub fn pick(&self, filter: QueryFilter) -> Option<(Entity, Vec3)> {
+        let ray = self.cursor_ray()?;
+        let (entity, time_of_impact) = self.rapier_context.cast_ray(
+            ray.origin,
+            ray.direction,
+            MAXIMUM_PICK_DISTANCE,
+            true,
+            filter,
+        )?;
+        Some((entity, ray.get_point(time_of_impact)))
+    }
+}
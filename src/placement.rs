@@ -0,0 +1,327 @@
+use std::{collections::HashMap, f32::consts::PI};
+
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts, EguiPlugin};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    arenas::Arena,
+    coordinate_frame::CoordinateFrame,
+    field_dimensions::FieldDimensions,
+    game_controller::GameControllerState,
+    player::{Player, RobotStatus, TeamColor},
+    robot_spawn::RobotSpawn,
+    team_configuration::TeamConfiguration,
+    Ball, RobotRoot,
+};
+
+/// Distance between penalized robots waiting next to the field in meters
+const PENALIZED_SPACING: f32 = 0.4;
+
+/// Places robots like the referees of an SPL game would, at positions computed from the
+/// [`FieldDimensions`].
+///
+/// [`ManualPlacement`] puts all unpenalized robots at their kick-off positions on their own half.
+/// With [`PenaltyPlacement::enabled`] robots are taken off the field when they are penalized,
+/// by the GameController or by hand, and put back on the touchline when the penalty ends. The
+/// own half of a team is the one it starts on.
+pub struct PlacementPlugin;
+
+impl Plugin for PlacementPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ManualPlacement>()
+            .init_resource::<PenaltyPlacement>()
+            .add_system(place_robots_manually)
+            .add_system(follow_penalties);
+        if app.is_plugin_added::<EguiPlugin>() {
+            app.add_system(placement_ui.before(place_robots_manually));
+        }
+    }
+}
+
+/// Moves all unpenalized robots to their manual placement positions for a kick-off.
+pub struct ManualPlacement;
+
+#[derive(Resource)]
+pub struct PenaltyPlacement {
+    pub enabled: bool,
+}
+
+impl Default for PenaltyPlacement {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// Manual placement position of a robot on the field of a team defending the goal at
+/// `own_goal_side`.
+///
+/// The goalkeeper stands in its goal, the kicking team places a striker at the center circle and
+/// the remaining field players on its penalty mark line, the other team lines up its field
+/// players on its penalty area line.
+fn manual_placement_position(
+    field_dimensions: &FieldDimensions,
+    own_goal_side: f32,
+    goalkeeper: bool,
+    kicking: bool,
+    index: usize,
+    count: usize,
+) -> Vec2 {
+    let half_length = field_dimensions.length / 2.0;
+    if goalkeeper {
+        return Vec2::new(own_goal_side * half_length, 0.0);
+    }
+    if kicking && index == 0 {
+        return Vec2::new(
+            own_goal_side * field_dimensions.center_circle_diameter / 2.0,
+            0.0,
+        );
+    }
+    let (x, index, count) = if kicking {
+        (
+            half_length - field_dimensions.penalty_marker_distance,
+            index - 1,
+            count - 1,
+        )
+    } else {
+        (
+            half_length - field_dimensions.penalty_area_length,
+            index,
+            count,
+        )
+    };
+    // spread evenly over the width of the penalty area
+    let y = field_dimensions.penalty_area_width * ((index as f32 + 0.5) / count as f32 - 0.5);
+    Vec2::new(own_goal_side * x, y)
+}
+
+/// Where a penalized robot waits next to the field, on the side line `side` of its own half.
+fn penalized_position(
+    field_dimensions: &FieldDimensions,
+    own_goal_side: f32,
+    side: f32,
+    jersey_number: u8,
+) -> Vec2 {
+    let x = field_dimensions.length / 2.0
+        - field_dimensions.penalty_marker_distance
+        - PENALIZED_SPACING * f32::from(jersey_number.saturating_sub(1));
+    let y = field_dimensions.width / 2.0 + field_dimensions.border_strip_width / 2.0;
+    Vec2::new(own_goal_side * x.max(0.0), side * y)
+}
+
+/// Where a robot returns from a penalty, on the touchline `side` in line with its own penalty
+/// mark.
+fn return_position(field_dimensions: &FieldDimensions, own_goal_side: f32, side: f32) -> Vec2 {
+    Vec2::new(
+        own_goal_side * (field_dimensions.length / 2.0 - field_dimensions.penalty_marker_distance),
+        side * field_dimensions.width / 2.0,
+    )
+}
+
+/// Yaw looking from `position` onto the field, towards the opponent goal on the field.
+fn facing_field(position: Vec2, own_goal_side: f32, field_dimensions: &FieldDimensions) -> f32 {
+    if position.y.abs() >= field_dimensions.width / 2.0 {
+        -position.y.signum() * PI / 2.0
+    } else if own_goal_side < 0.0 {
+        0.0
+    } else {
+        PI
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn place_robots_manually(
+    mut commands: Commands,
+    mut placements: EventReader<ManualPlacement>,
+    frame: Res<CoordinateFrame>,
+    field_dimensions: Res<FieldDimensions>,
+    robot_spawn: Res<RobotSpawn>,
+    team_configuration: Res<TeamConfiguration>,
+    state: Res<GameControllerState>,
+    mut robots: Query<
+        (
+            Entity,
+            &Player,
+            &RobotStatus,
+            Option<&Arena>,
+            &mut Transform,
+        ),
+        With<RobotRoot>,
+    >,
+    children: Query<&Children>,
+    bodies: Query<(), With<RigidBody>>,
+) {
+    if placements.iter().count() == 0 {
+        return;
+    }
+    let mut groups: HashMap<(Arena, TeamColor), Vec<_>> = HashMap::new();
+    for (entity, player, status, arena, transform) in robots.iter_mut() {
+        if !status.penalized {
+            groups
+                .entry((arena.copied().unwrap_or_default(), player.team_color))
+                .or_default()
+                .push((entity, player.jersey_number, transform));
+        }
+    }
+    for ((arena, team_color), mut robots) in groups {
+        let Some(team) = team_configuration
+            .teams
+            .iter()
+            .find(|team| team.team_color == team_color)
+        else {
+            continue;
+        };
+        let Some(own_goal_side) = team.own_goal_side() else {
+            continue;
+        };
+        let goalkeeper = state
+            .teams
+            .iter()
+            .find(|team| team.field_player_color == team_color)
+            .map_or(1, |team| team.goalkeeper);
+        let kicking = state.kicking_team == team.team_number;
+        robots.sort_by_key(|(_, jersey_number, _)| *jersey_number);
+        let field_player_count = robots
+            .iter()
+            .filter(|(_, jersey_number, _)| *jersey_number != goalkeeper)
+            .count();
+        let mut field_player_index = 0;
+        for (robot, jersey_number, mut transform) in robots {
+            let is_goalkeeper = jersey_number == goalkeeper;
+            let position = manual_placement_position(
+                &field_dimensions,
+                own_goal_side,
+                is_goalkeeper,
+                kicking,
+                field_player_index,
+                field_player_count,
+            );
+            if !is_goalkeeper {
+                field_player_index += 1;
+            }
+            let orientation = facing_field(position, own_goal_side, &field_dimensions);
+            *transform = place(
+                &mut commands,
+                robot,
+                &children,
+                &bodies,
+                &frame,
+                &field_dimensions,
+                &robot_spawn,
+                arena,
+                position,
+                orientation,
+            );
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn follow_penalties(
+    mut commands: Commands,
+    placement: Res<PenaltyPlacement>,
+    frame: Res<CoordinateFrame>,
+    field_dimensions: Res<FieldDimensions>,
+    robot_spawn: Res<RobotSpawn>,
+    team_configuration: Res<TeamConfiguration>,
+    mut penalized: Local<HashMap<Entity, bool>>,
+    mut robots: Query<
+        (
+            Entity,
+            &Player,
+            &RobotStatus,
+            Option<&Arena>,
+            &mut Transform,
+        ),
+        (With<RobotRoot>, Changed<RobotStatus>),
+    >,
+    balls: Query<(&GlobalTransform, Option<&Arena>), With<Ball>>,
+    children: Query<&Children>,
+    bodies: Query<(), With<RigidBody>>,
+) {
+    for (robot, player, status, arena, mut transform) in robots.iter_mut() {
+        let was_penalized = penalized.insert(robot, status.penalized);
+        if !placement.enabled || was_penalized.map_or(true, |was| was == status.penalized) {
+            continue;
+        }
+        let Some(own_goal_side) = team_configuration
+            .teams
+            .iter()
+            .find(|team| team.team_color == player.team_color)
+            .and_then(|team| team.own_goal_side())
+        else {
+            continue;
+        };
+        let arena = arena.copied().unwrap_or_default();
+        // robots are taken off and put back on the side away from the ball
+        let ball_y = balls
+            .iter()
+            .find(|(_, ball_arena)| ball_arena.copied().unwrap_or_default() == arena)
+            .map_or(0.0, |(ball, _)| {
+                (frame.to_field(ball.translation()) - arena.offset(&field_dimensions)).y
+            });
+        let side = if ball_y > 0.0 { -1.0 } else { 1.0 };
+        let position = if status.penalized {
+            penalized_position(&field_dimensions, own_goal_side, side, player.jersey_number)
+        } else {
+            return_position(&field_dimensions, own_goal_side, side)
+        };
+        let orientation = facing_field(position, own_goal_side, &field_dimensions);
+        *transform = place(
+            &mut commands,
+            robot,
+            &children,
+            &bodies,
+            &frame,
+            &field_dimensions,
+            &robot_spawn,
+            arena,
+            position,
+            orientation,
+        );
+    }
+}
+
+/// Transform of a robot standing upright at `position` in the field frame of `arena`, its bodies
+/// are brought to rest.
+#[allow(clippy::too_many_arguments)]
+fn place(
+    commands: &mut Commands,
+    robot: Entity,
+    children: &Query<&Children>,
+    bodies: &Query<(), With<RigidBody>>,
+    frame: &CoordinateFrame,
+    field_dimensions: &FieldDimensions,
+    robot_spawn: &RobotSpawn,
+    arena: Arena,
+    position: Vec2,
+    orientation: f32,
+) -> Transform {
+    for entity in std::iter::once(robot).chain(children.iter_descendants(robot)) {
+        if bodies.contains(entity) {
+            commands.entity(entity).insert(Velocity::zero());
+        }
+    }
+    let field_transform = Transform::from_translation(position.extend(0.0))
+        .with_rotation(Quat::from_rotation_z(orientation));
+    arena.transform_to_world(
+        frame,
+        field_dimensions,
+        robot_spawn.transform(field_transform),
+    )
+}
+
+fn placement_ui(
+    mut contexts: EguiContexts,
+    mut placements: EventWriter<ManualPlacement>,
+    mut placement: ResMut<PenaltyPlacement>,
+) {
+    egui::Window::new("Placement")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            if ui.button("manual placement").clicked() {
+                placements.send(ManualPlacement);
+            }
+            ui.checkbox(&mut placement.enabled, "move penalized robots");
+        });
+}
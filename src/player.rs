@@ -0,0 +1,107 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    coordinate_frame::CoordinateFrame,
+    selection::{Selection, SelectionPlugin},
+    shortcuts::{triggered, ShortcutAction, ShortcutsPlugin},
+    RobotRoot,
+};
+
+/// Keeps the [`RobotStatus`] of all robots up to date.
+pub struct PlayerPlugin;
+
+impl Plugin for PlayerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(detect_fallen_robots);
+        if app.is_plugin_added::<ShortcutsPlugin>() && app.is_plugin_added::<SelectionPlugin>() {
+            app.add_system(penalize_selected_robot);
+        }
+    }
+}
+
+/// Team colors as used by the SPL GameController
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TeamColor {
+    #[default]
+    Blue,
+    Red,
+    Yellow,
+    Black,
+    White,
+    Green,
+    Orange,
+    Purple,
+    Brown,
+    Gray,
+}
+
+impl TeamColor {
+    pub fn color(self) -> Color {
+        match self {
+            TeamColor::Blue => Color::rgb(0.1, 0.3, 1.0),
+            TeamColor::Red => Color::RED,
+            TeamColor::Yellow => Color::YELLOW,
+            TeamColor::Black => Color::BLACK,
+            TeamColor::White => Color::WHITE,
+            TeamColor::Green => Color::GREEN,
+            TeamColor::Orange => Color::ORANGE,
+            TeamColor::Purple => Color::PURPLE,
+            TeamColor::Brown => Color::rgb(0.55, 0.35, 0.15),
+            TeamColor::Gray => Color::GRAY,
+        }
+    }
+}
+
+#[derive(Clone, Component, Debug)]
+pub struct Player {
+    pub team_color: TeamColor,
+    pub jersey_number: u8,
+}
+
+impl Default for Player {
+    fn default() -> Self {
+        Self {
+            team_color: TeamColor::default(),
+            jersey_number: 1,
+        }
+    }
+}
+
+#[derive(Clone, Component, Debug, Default)]
+pub struct RobotStatus {
+    pub penalized: bool,
+    pub fallen: bool,
+    /// State of charge between 0 and 1, if the robot reports one
+    pub battery: Option<f32>,
+}
+
+fn detect_fallen_robots(
+    frame: Res<CoordinateFrame>,
+    mut robots: Query<(&GlobalTransform, &mut RobotStatus), With<RobotRoot>>,
+) {
+    for (transform, mut status) in robots.iter_mut() {
+        // more than 60 degrees away from upright
+        let fallen = transform.up().dot(frame.up()) < 0.5;
+        if status.fallen != fallen {
+            status.fallen = fallen;
+        }
+    }
+}
+
+fn penalize_selected_robot(
+    mut actions: EventReader<ShortcutAction>,
+    selection: Res<Selection>,
+    mut robots: Query<&mut RobotStatus>,
+) {
+    if !triggered(&mut actions, ShortcutAction::PenalizeSelected) {
+        return;
+    }
+    if let Some(mut status) = selection
+        .entity
+        .and_then(|entity| robots.get_mut(entity).ok())
+    {
+        status.penalized = !status.penalized;
+    }
+}
@@ -0,0 +1,368 @@
+use std::{
+    collections::{BTreeSet, HashMap, HashSet, VecDeque},
+    fmt,
+};
+
+use bevy::{prelude::*, transform::TransformSystem};
+use bevy_egui::{
+    egui::{
+        self,
+        plot::{Legend, Line, Plot, PlotPoints},
+    },
+    EguiContexts,
+};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    coordinate_frame::CoordinateFrame,
+    player::{Player, TeamColor},
+    shortcuts::{dispatch_shortcuts, triggered, ShortcutAction},
+    simulation_time::{SimulationTime, PHYSICS_TIMESTEP},
+    Ball, NaoJoint, RobotRoot,
+};
+
+/// Longest time window that can be shown in seconds, older samples are dropped
+const MAXIMUM_WINDOW: f64 = 60.0;
+
+/// Plots joint and physics signals over a scrolling window of simulation time.
+///
+/// Signals are picked into the plot of the "Plots" window, pinning it moves them into a plot of
+/// their own so several plots can be watched side by side.
+pub struct PlottingPlugin;
+
+impl Plugin for PlottingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Plots>()
+            .add_system(toggle_plots.after(dispatch_shortcuts))
+            .add_system(
+                record_signals
+                    .in_base_set(CoreSet::PostUpdate)
+                    .after(TransformSystem::TransformPropagate),
+            )
+            .add_system(plots_ui);
+    }
+}
+
+type RobotId = (TeamColor, u8);
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Signal {
+    /// Angle in radians
+    JointPosition { robot: RobotId, joint: String },
+    /// Motor torque in the last physics step in Nm
+    JointTorque { robot: RobotId, joint: String },
+    /// Forward lean of the torso in radians
+    TorsoPitch { robot: RobotId },
+    /// Ball speed in m/s
+    BallSpeed,
+}
+
+impl fmt::Display for Signal {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Signal::JointPosition {
+                robot: (color, jersey),
+                joint,
+            } => write!(formatter, "{color:?} {jersey} {joint} position"),
+            Signal::JointTorque {
+                robot: (color, jersey),
+                joint,
+            } => write!(formatter, "{color:?} {jersey} {joint} torque"),
+            Signal::TorsoPitch {
+                robot: (color, jersey),
+            } => write!(formatter, "{color:?} {jersey} torso pitch"),
+            Signal::BallSpeed => write!(formatter, "ball speed"),
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct Plots {
+    pub enabled: bool,
+    /// Length of the shown time window in seconds
+    pub window: f64,
+    /// Signals of the plot in the "Plots" window
+    current: Vec<Signal>,
+    pinned: Vec<PinnedPlot>,
+    histories: HashMap<Signal, VecDeque<[f64; 2]>>,
+    last_sample_time: Option<f64>,
+    next_plot_id: usize,
+    picker: SignalPicker,
+}
+
+impl Default for Plots {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            window: 10.0,
+            current: Vec::new(),
+            pinned: Vec::new(),
+            histories: HashMap::new(),
+            last_sample_time: None,
+            next_plot_id: 0,
+            picker: SignalPicker::default(),
+        }
+    }
+}
+
+struct PinnedPlot {
+    id: usize,
+    signals: Vec<Signal>,
+    open: bool,
+}
+
+#[derive(Default)]
+struct SignalPicker {
+    kind: SignalKind,
+    robot: Option<RobotId>,
+    joint: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum SignalKind {
+    #[default]
+    JointPosition,
+    JointTorque,
+    TorsoPitch,
+    BallSpeed,
+}
+
+impl SignalPicker {
+    fn signal(&self) -> Option<Signal> {
+        Some(match self.kind {
+            SignalKind::JointPosition => Signal::JointPosition {
+                robot: self.robot?,
+                joint: self.joint.clone()?,
+            },
+            SignalKind::JointTorque => Signal::JointTorque {
+                robot: self.robot?,
+                joint: self.joint.clone()?,
+            },
+            SignalKind::TorsoPitch => Signal::TorsoPitch { robot: self.robot? },
+            SignalKind::BallSpeed => Signal::BallSpeed,
+        })
+    }
+}
+
+fn toggle_plots(mut actions: EventReader<ShortcutAction>, mut plots: ResMut<Plots>) {
+    if triggered(&mut actions, ShortcutAction::TogglePlots) {
+        plots.enabled = !plots.enabled;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn record_signals(
+    mut plots: ResMut<Plots>,
+    simulation_time: Res<SimulationTime>,
+    frame: Res<CoordinateFrame>,
+    context: Res<RapierContext>,
+    robots: Query<(Entity, &Player, &GlobalTransform), With<RobotRoot>>,
+    children: Query<&Children>,
+    joints: Query<(Entity, &NaoJoint, &Transform)>,
+    balls: Query<&Velocity, With<Ball>>,
+) {
+    let now = simulation_time.elapsed_seconds();
+    if plots.last_sample_time == Some(now) {
+        return;
+    }
+    plots.last_sample_time = Some(now);
+
+    let plots = &mut *plots;
+    let signals: HashSet<_> = plots
+        .current
+        .iter()
+        .chain(plots.pinned.iter().flat_map(|plot| plot.signals.iter()))
+        .cloned()
+        .collect();
+    plots.histories.retain(|signal, _| signals.contains(signal));
+
+    let find_robot = |(team_color, jersey_number): RobotId| {
+        robots.iter().find(|(_, player, _)| {
+            player.team_color == team_color && player.jersey_number == jersey_number
+        })
+    };
+    let find_joint = |robot: RobotId, name: &str| {
+        let (entity, ..) = find_robot(robot)?;
+        children
+            .iter_descendants(entity)
+            .filter_map(|link| joints.get(link).ok())
+            .find(|(_, joint, _)| joint.name == name)
+    };
+
+    for signal in signals {
+        let value = match &signal {
+            Signal::JointPosition { robot, joint } => {
+                find_joint(*robot, joint).map(|(_, joint, transform)| joint.angle(transform))
+            }
+            Signal::JointTorque { robot, joint } => {
+                find_joint(*robot, joint).and_then(|(entity, ..)| {
+                    let handle = context.entity2impulse_joint().get(&entity)?;
+                    let joint = context.impulse_joints.get(*handle)?;
+                    // motors drive the angular x axis of the joint frame
+                    Some(joint.impulses[3] / PHYSICS_TIMESTEP)
+                })
+            }
+            Signal::TorsoPitch { robot } => find_robot(*robot).map(|(_, _, transform)| {
+                let rotation = frame
+                    .transform_to_field(transform.compute_transform())
+                    .rotation;
+                let (_, pitch, _) = rotation.to_euler(EulerRot::ZYX);
+                pitch
+            }),
+            Signal::BallSpeed => balls.iter().next().map(|velocity| velocity.linvel.length()),
+        };
+        let Some(value) = value else {
+            continue;
+        };
+        let history = plots.histories.entry(signal).or_default();
+        history.push_back([now, f64::from(value)]);
+        while history
+            .front()
+            .map_or(false, |[time, _]| *time < now - MAXIMUM_WINDOW)
+        {
+            history.pop_front();
+        }
+    }
+}
+
+fn plots_ui(
+    mut contexts: EguiContexts,
+    mut plots: ResMut<Plots>,
+    simulation_time: Res<SimulationTime>,
+    robots: Query<&Player, With<RobotRoot>>,
+    joints: Query<&NaoJoint>,
+) {
+    if !plots.enabled {
+        return;
+    }
+    let plots = &mut *plots;
+    let context = contexts.ctx_mut();
+    let now = simulation_time.elapsed_seconds();
+    let window = plots.window;
+
+    let mut robot_ids: Vec<_> = robots
+        .iter()
+        .map(|player| (player.team_color, player.jersey_number))
+        .collect();
+    robot_ids.sort_by_key(|(color, jersey)| (format!("{color:?}"), *jersey));
+    let joint_names: BTreeSet<_> = joints.iter().map(|joint| joint.name.clone()).collect();
+
+    egui::Window::new("Plots").show(context, |ui| {
+        ui.add(egui::Slider::new(&mut plots.window, 1.0..=MAXIMUM_WINDOW).text("window [s]"));
+        signal_picker_ui(ui, &mut plots.picker, &robot_ids, &joint_names);
+        ui.horizontal(|ui| {
+            if ui.button("add").clicked() {
+                if let Some(signal) = plots.picker.signal() {
+                    if !plots.current.contains(&signal) {
+                        plots.current.push(signal);
+                    }
+                }
+            }
+            if ui.button("clear").clicked() {
+                plots.current.clear();
+            }
+            if ui.button("pin").clicked() && !plots.current.is_empty() {
+                plots.pinned.push(PinnedPlot {
+                    id: plots.next_plot_id,
+                    signals: std::mem::take(&mut plots.current),
+                    open: true,
+                });
+                plots.next_plot_id += 1;
+            }
+        });
+        show_plot(
+            ui,
+            "current plot",
+            &plots.current,
+            &plots.histories,
+            now,
+            window,
+        );
+    });
+
+    for pinned in plots.pinned.iter_mut() {
+        egui::Window::new(format!("Plot {}", pinned.id))
+            .open(&mut pinned.open)
+            .show(context, |ui| {
+                show_plot(
+                    ui,
+                    pinned.id,
+                    &pinned.signals,
+                    &plots.histories,
+                    now,
+                    window,
+                );
+            });
+    }
+    plots.pinned.retain(|plot| plot.open);
+}
+
+fn signal_picker_ui(
+    ui: &mut egui::Ui,
+    picker: &mut SignalPicker,
+    robot_ids: &[RobotId],
+    joint_names: &BTreeSet<String>,
+) {
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_source("signal kind")
+            .selected_text(format!("{:?}", picker.kind))
+            .show_ui(ui, |ui| {
+                for kind in [
+                    SignalKind::JointPosition,
+                    SignalKind::JointTorque,
+                    SignalKind::TorsoPitch,
+                    SignalKind::BallSpeed,
+                ] {
+                    ui.selectable_value(&mut picker.kind, kind, format!("{kind:?}"));
+                }
+            });
+        if picker.kind == SignalKind::BallSpeed {
+            return;
+        }
+        let robot_label = |(color, jersey): RobotId| format!("{color:?} {jersey}");
+        egui::ComboBox::from_id_source("signal robot")
+            .selected_text(picker.robot.map_or("robot".to_string(), robot_label))
+            .show_ui(ui, |ui| {
+                for robot in robot_ids {
+                    ui.selectable_value(&mut picker.robot, Some(*robot), robot_label(*robot));
+                }
+            });
+        if picker.kind == SignalKind::TorsoPitch {
+            return;
+        }
+        egui::ComboBox::from_id_source("signal joint")
+            .selected_text(picker.joint.as_deref().unwrap_or("joint"))
+            .show_ui(ui, |ui| {
+                for joint in joint_names {
+                    ui.selectable_value(&mut picker.joint, Some(joint.clone()), joint);
+                }
+            });
+    });
+}
+
+fn show_plot(
+    ui: &mut egui::Ui,
+    id: impl std::hash::Hash,
+    signals: &[Signal],
+    histories: &HashMap<Signal, VecDeque<[f64; 2]>>,
+    now: f64,
+    window: f64,
+) {
+    Plot::new(id)
+        .height(200.0)
+        .legend(Legend::default())
+        .include_x(now - window)
+        .include_x(now)
+        .show(ui, |plot_ui| {
+            for signal in signals {
+                let points: Vec<_> = histories
+                    .get(signal)
+                    .into_iter()
+                    .flatten()
+                    .filter(|[time, _]| *time >= now - window)
+                    .copied()
+                    .collect();
+                plot_ui.line(Line::new(PlotPoints::from(points)).name(signal.to_string()));
+            }
+        });
+}
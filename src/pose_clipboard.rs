@@ -0,0 +1,57 @@
+use bevy::prelude::*;
+use bevy_egui::EguiContexts;
+use serde_json::{json, Map, Value};
+
+use crate::{
+    selection::Selection,
+    shortcuts::{triggered, ShortcutAction},
+    NaoJoint,
+};
+
+/// Copies the pose of the selected entity as JSON to the clipboard.
+pub struct PoseClipboardPlugin;
+
+impl Plugin for PoseClipboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(copy_selected_pose);
+    }
+}
+
+fn copy_selected_pose(
+    mut contexts: EguiContexts,
+    mut actions: EventReader<ShortcutAction>,
+    selection: Res<Selection>,
+    entities: Query<(&GlobalTransform, Option<&Name>)>,
+    children: Query<&Children>,
+    joints: Query<(&NaoJoint, &Transform)>,
+) {
+    if !triggered(&mut actions, ShortcutAction::CopySelectedPose) {
+        return;
+    }
+    let Some(entity) = selection.entity else {
+        return;
+    };
+    let Ok((transform, name)) = entities.get(entity) else {
+        return;
+    };
+
+    let (_, rotation, translation) = transform.to_scale_rotation_translation();
+    let joint_angles: Map<String, Value> = children
+        .iter_descendants(entity)
+        .filter_map(|descendant| joints.get(descendant).ok())
+        .map(|(joint, transform)| (joint.name.clone(), json!(joint.angle(transform))))
+        .collect();
+    let mut pose = json!({
+        "name": name.map(|name| name.as_str()),
+        "translation": translation.to_array(),
+        "rotation": rotation.to_array(),
+    });
+    if !joint_angles.is_empty() {
+        pose["joint_angles"] = Value::Object(joint_angles);
+    }
+
+    let text = serde_json::to_string_pretty(&pose).expect("pose is always serializable");
+    contexts
+        .ctx_mut()
+        .output_mut(|output| output.copied_text = text);
+}
@@ -0,0 +1,175 @@
+use bevy::{math::Affine3A, prelude::*};
+use bevy_egui::EguiContexts;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    joint_control::JointCommand, picking::Picking, tools::ActiveTool, NaoJoint, RobotLink,
+};
+
+/// Passes over the chain per frame, each pass rotates every joint once
+const SOLVER_ITERATIONS: usize = 10;
+
+/// Pose tool: grab a hand, foot or any other link of a robot and drag it around, the joints from
+/// the torso to the grabbed link follow using cyclic coordinate descent.
+///
+/// The resulting joint angles become the [`JointCommand`] targets and are also applied to the link
+/// transforms directly, copying the pose of the robot afterwards yields the joint angles for a
+/// motion.
+pub struct PoseToolPlugin;
+
+impl Plugin for PoseToolPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PoseDrag>()
+            .add_system(start_pose_drag)
+            .add_system(update_pose_drag.after(start_pose_drag));
+    }
+}
+
+#[derive(Default, Resource)]
+struct PoseDrag {
+    active: Option<ActivePoseDrag>,
+}
+
+struct ActivePoseDrag {
+    /// Topmost link of the robot, it stays in place
+    root: Entity,
+    /// Links from below the root down to the grabbed link
+    chain: Vec<Entity>,
+    /// Grab point in the frame of the grabbed link
+    local_grab_point: Vec3,
+    /// Normal of the plane through the grab point the cursor is projected onto
+    plane_normal: Vec3,
+    target: Vec3,
+}
+
+fn start_pose_drag(
+    mut contexts: EguiContexts,
+    active_tool: Res<ActiveTool>,
+    mouse: Res<Input<MouseButton>>,
+    picking: Picking,
+    parents: Query<&Parent>,
+    links: Query<&GlobalTransform, With<RobotLink>>,
+    mut drag: ResMut<PoseDrag>,
+) {
+    if *active_tool != ActiveTool::Pose
+        || !mouse.just_pressed(MouseButton::Left)
+        || contexts.ctx_mut().is_pointer_over_area()
+    {
+        return;
+    }
+    let (Some(ray), Some((grabbed, grab_point))) =
+        (picking.cursor_ray(), picking.pick(QueryFilter::default()))
+    else {
+        return;
+    };
+    let Ok(grabbed_transform) = links.get(grabbed) else {
+        return;
+    };
+
+    let mut chain: Vec<_> = std::iter::once(grabbed)
+        .chain(parents.iter_ancestors(grabbed))
+        .collect();
+    let Some(root) = chain.pop() else {
+        return;
+    };
+    chain.reverse();
+    drag.active = Some(ActivePoseDrag {
+        root,
+        chain,
+        local_grab_point: grabbed_transform
+            .affine()
+            .inverse()
+            .transform_point3(grab_point),
+        plane_normal: ray.direction,
+        target: grab_point,
+    });
+}
+
+fn update_pose_drag(
+    active_tool: Res<ActiveTool>,
+    mouse: Res<Input<MouseButton>>,
+    picking: Picking,
+    mut drag: ResMut<PoseDrag>,
+    roots: Query<&GlobalTransform>,
+    mut links: Query<(&mut Transform, Option<&NaoJoint>, Option<&mut JointCommand>)>,
+) {
+    let Some(active) = &mut drag.active else {
+        return;
+    };
+    if *active_tool != ActiveTool::Pose || !mouse.pressed(MouseButton::Left) {
+        drag.active = None;
+        return;
+    }
+    let Some(ray) = picking.cursor_ray() else {
+        return;
+    };
+    if let Some(distance) = ray.intersect_plane(active.target, active.plane_normal) {
+        active.target = ray.get_point(distance);
+    }
+    let Ok(root_transform) = roots.get(active.root) else {
+        drag.active = None;
+        return;
+    };
+
+    let Ok(mut local_transforms) = active
+        .chain
+        .iter()
+        .map(|&link| links.get(link).map(|(transform, ..)| *transform))
+        .collect::<Result<Vec<_>, _>>()
+    else {
+        drag.active = None;
+        return;
+    };
+    for _ in 0..SOLVER_ITERATIONS {
+        for index in (0..active.chain.len()).rev() {
+            let Ok((_, Some(joint), _)) = links.get(active.chain[index]) else {
+                continue;
+            };
+            let world_transforms = forward_kinematics(root_transform.affine(), &local_transforms);
+            let joint_frame = world_transforms[index];
+            let effector = world_transforms
+                .last()
+                .expect("chain contains at least the grabbed link")
+                .transform_point3(active.local_grab_point);
+            let pivot = Vec3::from(joint_frame.translation);
+            let axis = joint_frame
+                .transform_vector3(joint.axis)
+                .normalize_or_zero();
+            let Some(delta) = angle_around_axis(effector - pivot, active.target - pivot, axis)
+            else {
+                continue;
+            };
+            let transform = &mut local_transforms[index];
+            transform.rotation = joint.rotation(joint.angle(transform) + delta);
+        }
+    }
+
+    for (&link, local_transform) in active.chain.iter().zip(local_transforms) {
+        let Ok((mut transform, Some(joint), command)) = links.get_mut(link) else {
+            continue;
+        };
+        transform.rotation = local_transform.rotation;
+        if let Some(mut command) = command {
+            command.position = joint.angle(&local_transform);
+        }
+    }
+}
+
+/// World transforms of all links in the chain below `root`.
+fn forward_kinematics(root: Affine3A, local_transforms: &[Transform]) -> Vec<Affine3A> {
+    local_transforms
+        .iter()
+        .scan(root, |world, local| {
+            *world = *world * local.compute_affine();
+            Some(*world)
+        })
+        .collect()
+}
+
+/// Signed angle around `axis` rotating `from` towards `to`, both projected onto the plane
+/// perpendicular to `axis`.
+fn angle_around_axis(from: Vec3, to: Vec3, axis: Vec3) -> Option<f32> {
+    let from = (from - axis * from.dot(axis)).try_normalize()?;
+    let to = (to - axis * to.dot(axis)).try_normalize()?;
+    Some(from.cross(to).dot(axis).atan2(from.dot(to)))
+}
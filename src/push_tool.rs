@@ -0,0 +1,173 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    coordinate_frame::CoordinateFrame, picking::Picking, selection::selectable_entity,
+    tools::ActiveTool, RobotLink, RobotRoot,
+};
+
+/// Push tool: clicking a robot applies an impulse at the clicked point to test push recovery.
+///
+/// Every push is reported as [`PushApplied`] event and logged, so experiments can be repeated
+/// with the same disturbance.
+pub struct PushToolPlugin;
+
+impl Plugin for PushToolPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PushTool>()
+            .add_event::<PushApplied>()
+            .add_system(push_tool_ui)
+            .add_system(push_robot_on_click.after(push_tool_ui))
+            .add_system(log_pushes.after(push_robot_on_click));
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PushDirection {
+    /// Along the view direction of the camera, projected onto the ground
+    Camera,
+    /// Along [`PushTool::direction`] in field coordinates
+    Fixed,
+}
+
+#[derive(Resource)]
+pub struct PushTool {
+    /// Magnitude of the impulse in Ns
+    pub impulse: f32,
+    pub direction_mode: PushDirection,
+    pub direction: Vec3,
+}
+
+impl Default for PushTool {
+    fn default() -> Self {
+        Self {
+            impulse: 5.0,
+            direction_mode: PushDirection::Camera,
+            direction: Vec3::X,
+        }
+    }
+}
+
+/// An impulse applied to a robot by the push tool.
+#[derive(Clone, Debug)]
+pub struct PushApplied {
+    pub robot: Entity,
+    /// Rigid body the impulse was applied to
+    pub body: Entity,
+    /// Point of application in world coordinates
+    pub point: Vec3,
+    /// Impulse in world coordinates in Ns
+    pub impulse: Vec3,
+    /// Elapsed simulation time in seconds
+    pub time: f64,
+}
+
+fn push_tool_ui(
+    mut contexts: EguiContexts,
+    active_tool: Res<ActiveTool>,
+    mut push_tool: ResMut<PushTool>,
+) {
+    if *active_tool != ActiveTool::Push {
+        return;
+    }
+    egui::Window::new("Push")
+        .anchor(egui::Align2::RIGHT_TOP, [-10.0, 60.0])
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.add(egui::Slider::new(&mut push_tool.impulse, 0.1..=50.0).text("impulse [Ns]"));
+            ui.horizontal(|ui| {
+                ui.radio_value(
+                    &mut push_tool.direction_mode,
+                    PushDirection::Camera,
+                    "camera",
+                );
+                ui.radio_value(&mut push_tool.direction_mode, PushDirection::Fixed, "fixed");
+            });
+            if push_tool.direction_mode == PushDirection::Fixed {
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut push_tool.direction.x).speed(0.01));
+                    ui.add(egui::DragValue::new(&mut push_tool.direction.y).speed(0.01));
+                    ui.add(egui::DragValue::new(&mut push_tool.direction.z).speed(0.01));
+                    ui.label("direction");
+                });
+            }
+        });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn push_robot_on_click(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    active_tool: Res<ActiveTool>,
+    mouse: Res<Input<MouseButton>>,
+    time: Res<Time>,
+    push_tool: Res<PushTool>,
+    frame: Res<CoordinateFrame>,
+    picking: Picking,
+    parents: Query<&Parent>,
+    robots: Query<(), With<RobotRoot>>,
+    bodies: Query<(&GlobalTransform, Option<&ReadMassProperties>), With<RigidBody>>,
+    mut pushes: EventWriter<PushApplied>,
+) {
+    if *active_tool != ActiveTool::Push
+        || !mouse.just_pressed(MouseButton::Left)
+        || contexts.ctx_mut().is_pointer_over_area()
+    {
+        return;
+    }
+    let Some(ray) = picking.cursor_ray() else {
+        return;
+    };
+    let Some((body, point)) = picking.pick(QueryFilter::default()) else {
+        return;
+    };
+    let robot = selectable_entity(body, &parents, &robots);
+    if !robots.contains(robot) {
+        return;
+    }
+    let Ok((body_transform, mass_properties)) = bodies.get(body) else {
+        return;
+    };
+
+    let direction = match push_tool.direction_mode {
+        PushDirection::Camera => {
+            let up = frame.up();
+            ray.direction - up * ray.direction.dot(up)
+        }
+        PushDirection::Fixed => frame.vector_to_world(push_tool.direction),
+    };
+    let Some(direction) = direction.try_normalize() else {
+        return;
+    };
+    let impulse = direction * push_tool.impulse;
+    let center_of_mass = match mass_properties {
+        Some(mass_properties) => {
+            body_transform.transform_point(mass_properties.0.local_center_of_mass)
+        }
+        None => body_transform.translation(),
+    };
+    commands.entity(body).insert(ExternalImpulse {
+        impulse,
+        torque_impulse: (point - center_of_mass).cross(impulse),
+    });
+    pushes.send(PushApplied {
+        robot,
+        body,
+        point,
+        impulse,
+        time: time.elapsed_seconds_f64(),
+    });
+}
+
+fn log_pushes(mut pushes: EventReader<PushApplied>, links: Query<&RobotLink>) {
+    for push in pushes.iter() {
+        let body = links
+            .get(push.body)
+            .map_or_else(|_| format!("{:?}", push.body), |link| link.name.clone());
+        info!(
+            "Pushed {body} at {} with {} Ns at t = {:.3} s",
+            push.point, push.impulse, push.time
+        );
+    }
+}
@@ -0,0 +1,380 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{BufWriter, Cursor, Write},
+    path::Path,
+};
+
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts, EguiPlugin};
+use bevy_rapier3d::prelude::*;
+use color_eyre::{eyre::WrapErr, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game_phase::GamePhaseChanged,
+    instant_replay::InstantReplaySettings,
+    joint_control::JointCommand,
+    referee::{BallOut, GoalScored},
+    simulation_time::{PhysicsSchedule, SimulationTime, PHYSICS_TIMESTEP},
+    snapshot::BodyKeys,
+};
+
+/// Records every physics step to a file and plays such recordings back instead of simulating.
+///
+/// A recording holds the transforms of all rigid bodies, the joint targets and the referee and
+/// game phase events. Bodies are matched by the same keys as snapshots, so a recording must be
+/// replayed with the same team configuration it was recorded with.
+pub struct RecordingPlugin;
+
+impl Plugin for RecordingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            record_events
+                .in_base_set(CoreSet::PostUpdate)
+                .run_if(resource_exists::<Recorder>()),
+        )
+        .add_system(
+            record_step
+                .after(PhysicsSet::Writeback)
+                .in_schedule(PhysicsSchedule)
+                .run_if(resource_exists::<Recorder>()),
+        )
+        .add_system(flush_recording.run_if(resource_exists::<Recorder>()))
+        .add_startup_system(start_replay.run_if(resource_exists::<Replay>()))
+        .add_system(play_replay.run_if(resource_exists::<Replay>()));
+        if app.is_plugin_added::<EguiPlugin>() {
+            app.add_system(
+                replay_ui
+                    .before(play_replay)
+                    .run_if(resource_exists::<Replay>()),
+            );
+        }
+    }
+}
+
+/// One physics step, keys are indices into the key table built from all previous steps.
+#[derive(Deserialize, Serialize)]
+struct RecordedStep {
+    step: u64,
+    /// Keys first used in this step, appended to the key table
+    new_keys: Vec<String>,
+    /// Key, local translation and rotation of each body
+    bodies: Vec<(u32, [f32; 3], [f32; 4])>,
+    /// Key and target position of each commanded joint
+    joints: Vec<(u32, f32)>,
+    events: Vec<String>,
+}
+
+/// Appends the simulation state to a file after every physics step.
+#[derive(Resource)]
+pub struct Recorder {
+    writer: BufWriter<File>,
+    keys: HashMap<String, u32>,
+    /// Events raised since the last step
+    pending_events: Vec<String>,
+}
+
+impl Recorder {
+    pub fn create(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if let Some(directory) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(directory)
+                .wrap_err_with(|| format!("failed to create {}", directory.display()))?;
+        }
+        let file =
+            File::create(path).wrap_err_with(|| format!("failed to create {}", path.display()))?;
+        Ok(Self {
+            writer: BufWriter::new(file),
+            keys: HashMap::new(),
+            pending_events: Vec::new(),
+        })
+    }
+
+    fn intern(&mut self, key: String, new_keys: &mut Vec<String>) -> u32 {
+        let next = self.keys.len() as u32;
+        *self.keys.entry(key.clone()).or_insert_with(|| {
+            new_keys.push(key);
+            next
+        })
+    }
+}
+
+fn record_events(
+    mut recorder: ResMut<Recorder>,
+    mut goals: EventReader<GoalScored>,
+    mut outs: EventReader<BallOut>,
+    mut phase_changes: EventReader<GamePhaseChanged>,
+) {
+    let events = goals
+        .iter()
+        .map(|goal| format!("{goal:?}"))
+        .chain(outs.iter().map(|out| format!("{out:?}")))
+        .chain(phase_changes.iter().map(|change| format!("{change:?}")))
+        .collect::<Vec<_>>();
+    recorder.pending_events.extend(events);
+}
+
+fn record_step(
+    mut recorder: ResMut<Recorder>,
+    simulation_time: Res<SimulationTime>,
+    keys: BodyKeys,
+    bodies: Query<(Entity, &Transform), With<RigidBody>>,
+    joints: Query<(Entity, &JointCommand), With<ImpulseJoint>>,
+) {
+    let mut new_keys = Vec::new();
+    let bodies = bodies
+        .iter()
+        .filter_map(|(entity, transform)| {
+            let key = recorder.intern(keys.key(entity)?, &mut new_keys);
+            Some((
+                key,
+                transform.translation.to_array(),
+                transform.rotation.to_array(),
+            ))
+        })
+        .collect();
+    let joints = joints
+        .iter()
+        .filter_map(|(entity, command)| {
+            let key = recorder.intern(keys.key(entity)?, &mut new_keys);
+            Some((key, command.position))
+        })
+        .collect();
+    let step = RecordedStep {
+        // the step counter advances after the schedule ran
+        step: simulation_time.elapsed_steps() + 1,
+        new_keys,
+        bodies,
+        joints,
+        events: std::mem::take(&mut recorder.pending_events),
+    };
+    if let Err(error) = rmp_serde::encode::write(&mut recorder.writer, &step) {
+        error!("failed to record step: {error:?}");
+    }
+}
+
+/// Writes the buffered steps once per frame, so a crash loses at most one frame.
+fn flush_recording(mut recorder: ResMut<Recorder>) {
+    if let Err(error) = recorder.writer.flush() {
+        error!("failed to write recording: {error:?}");
+    }
+}
+
+struct ReplayStep {
+    step: u64,
+    bodies: Vec<(String, Transform)>,
+    joints: Vec<(String, f32)>,
+    events: Vec<String>,
+}
+
+/// Steps of a recording file.
+pub struct Recording {
+    steps: Vec<ReplayStep>,
+}
+
+impl Recording {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content =
+            fs::read(path).wrap_err_with(|| format!("failed to read {}", path.display()))?;
+        let length = content.len() as u64;
+        let mut reader = Cursor::new(content);
+        let mut key_table = Vec::new();
+        let mut steps = Vec::new();
+        while reader.position() < length {
+            let recorded: RecordedStep = rmp_serde::decode::from_read(&mut reader)
+                .wrap_err_with(|| format!("failed to parse {}", path.display()))?;
+            key_table.extend(recorded.new_keys);
+            let key = |index: u32| key_table.get(index as usize).cloned().unwrap_or_default();
+            steps.push(ReplayStep {
+                step: recorded.step,
+                bodies: recorded
+                    .bodies
+                    .into_iter()
+                    .map(|(index, translation, rotation)| {
+                        let transform = Transform::from_translation(Vec3::from_array(translation))
+                            .with_rotation(Quat::from_array(rotation));
+                        (key(index), transform)
+                    })
+                    .collect(),
+                joints: recorded
+                    .joints
+                    .into_iter()
+                    .map(|(index, position)| (key(index), position))
+                    .collect(),
+                events: recorded.events,
+            });
+        }
+        Ok(Self { steps })
+    }
+
+    /// Length of the recording in seconds
+    pub fn duration(&self) -> f64 {
+        self.steps
+            .last()
+            .map_or(0.0, |step| step_seconds(step.step))
+    }
+
+    /// Index of the last step at or before `time` seconds since the start of the recording.
+    pub fn index_at(&self, time: f64) -> Option<usize> {
+        let index = self
+            .steps
+            .partition_point(|step| step_seconds(step.step) <= time);
+        // before the first step the first one is shown
+        Some(index.saturating_sub(1)).filter(|_| !self.steps.is_empty())
+    }
+
+    /// Key and local transform of each body in the step at `index`.
+    pub fn bodies(&self, index: usize) -> &[(String, Transform)] {
+        &self.steps[index].bodies
+    }
+}
+
+/// A recording played back in place of the physics simulation.
+#[derive(Resource)]
+pub struct Replay {
+    recording: Recording,
+    /// Seconds since the start of the recording
+    pub time: f64,
+    pub playing: bool,
+    /// Index of the step currently shown, `None` after a jump
+    shown: Option<usize>,
+}
+
+impl Replay {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self {
+            recording: Recording::load(path)?,
+            time: 0.0,
+            playing: true,
+            shown: None,
+        })
+    }
+
+    /// Length of the recording in seconds
+    pub fn duration(&self) -> f64 {
+        self.recording.duration()
+    }
+}
+
+fn step_seconds(step: u64) -> f64 {
+    step as f64 * f64::from(PHYSICS_TIMESTEP)
+}
+
+/// Bodies only follow the recording, nothing may move them on its own.
+///
+/// Only the physics pipeline is switched off, the simulation time keeps running because the
+/// recording is played back along it and pausing the simulation pauses the playback.
+fn start_replay(
+    mut rapier_configuration: ResMut<RapierConfiguration>,
+    instant_replay: Option<ResMut<InstantReplaySettings>>,
+) {
+    rapier_configuration.physics_pipeline_active = false;
+    if let Some(mut instant_replay) = instant_replay {
+        instant_replay.enabled = false;
+    }
+}
+
+fn play_replay(
+    time: Res<Time>,
+    simulation_time: Res<SimulationTime>,
+    mut replay: ResMut<Replay>,
+    keys: BodyKeys,
+    mut bodies: Query<(Entity, &mut Transform), With<RigidBody>>,
+    mut joints: Query<(Entity, &mut JointCommand), With<ImpulseJoint>>,
+) {
+    if replay.playing && !simulation_time.paused {
+        let duration = replay.duration();
+        replay.time = (replay.time + f64::from(time.delta_seconds() * simulation_time.time_scale))
+            .min(duration);
+        if replay.time >= duration {
+            replay.playing = false;
+        }
+    }
+    let Some(index) = replay.recording.index_at(replay.time) else {
+        return;
+    };
+    if replay.shown == Some(index) {
+        return;
+    }
+    // events are logged while playing forward, jumps skip them
+    if let Some(shown) = replay.shown.filter(|shown| *shown < index) {
+        for step in &replay.recording.steps[shown + 1..=index] {
+            for event in &step.events {
+                info!("{:.2} s: {event}", step_seconds(step.step));
+            }
+        }
+    }
+    replay.shown = Some(index);
+
+    let step = &replay.recording.steps[index];
+    let recorded_bodies: HashMap<_, _> = step.bodies.iter().cloned().collect();
+    for (entity, mut transform) in bodies.iter_mut() {
+        if let Some(recorded) = keys.key(entity).and_then(|key| recorded_bodies.get(&key)) {
+            *transform = *recorded;
+        }
+    }
+    let recorded_joints: HashMap<_, _> = step.joints.iter().cloned().collect();
+    for (entity, mut command) in joints.iter_mut() {
+        if let Some(position) = keys.key(entity).and_then(|key| recorded_joints.get(&key)) {
+            command.position = *position;
+        }
+    }
+}
+
+fn replay_ui(mut contexts: EguiContexts, mut replay: ResMut<Replay>) {
+    let duration = replay.duration();
+    egui::Window::new("Replay")
+        .anchor(egui::Align2::CENTER_BOTTOM, [0.0, -10.0])
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                let label = if replay.playing { "pause" } else { "play" };
+                if ui.button(label).clicked() {
+                    if !replay.playing && replay.time >= duration {
+                        replay.time = 0.0;
+                        replay.shown = None;
+                    }
+                    replay.playing = !replay.playing;
+                }
+                ui.spacing_mut().slider_width = 500.0;
+                let mut time = replay.time;
+                let scrubbed = ui
+                    .add(egui::Slider::new(&mut time, 0.0..=duration).suffix(" s"))
+                    .changed();
+                if scrubbed {
+                    replay.time = time;
+                    replay.shown = None;
+                }
+            });
+            let events: Vec<_> = replay
+                .recording
+                .steps
+                .iter()
+                .flat_map(|step| {
+                    step.events
+                        .iter()
+                        .map(|event| (step_seconds(step.step), event.clone()))
+                })
+                .collect();
+            if events.is_empty() {
+                return;
+            }
+            ui.collapsing("Events", |ui| {
+                egui::ScrollArea::vertical()
+                    .max_height(150.0)
+                    .show(ui, |ui| {
+                        for (time, event) in events {
+                            if ui.link(format!("{time:.2} s: {event}")).clicked() {
+                                replay.time = time;
+                                replay.shown = None;
+                            }
+                        }
+                    });
+            });
+        });
+}
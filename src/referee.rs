@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+
+use bevy::{prelude::*, transform::TransformSystem};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    arenas::{in_main_arena, Arena},
+    coordinate_frame::CoordinateFrame,
+    field_dimensions::FieldDimensions,
+    goals::GOAL_HEIGHT,
+    instant_replay::InstantReplay,
+    player::{Player, TeamColor},
+    team_configuration::TeamConfiguration,
+    Ball,
+};
+
+/// Watches the ball against the field dimensions, raises goal and ball out events, keeps the
+/// [`GameScore`] and puts the ball back at the restart point.
+///
+/// The ball is put back once a running instant replay of the goal is over. Only the main arena is
+/// refereed.
+pub struct RefereePlugin;
+
+impl Plugin for RefereePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<GoalScored>()
+            .add_event::<BallOut>()
+            .init_resource::<GameScore>()
+            .init_resource::<PendingRestart>()
+            .init_resource::<LastTouch>()
+            .add_systems(
+                (
+                    track_last_touch,
+                    restart_ball,
+                    detect_goals_and_ball_out,
+                    announce_ball_out,
+                )
+                    .chain()
+                    .in_base_set(CoreSet::PostUpdate)
+                    .after(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct GoalScored {
+    /// Team that scored, `None` if it can not be attributed to a team
+    pub team: Option<TeamColor>,
+}
+
+#[derive(Clone, Debug)]
+pub struct BallOut {
+    pub restart: Restart,
+    /// Team of the robot that touched the ball last
+    pub last_touch: Option<TeamColor>,
+}
+
+/// Where play continues after the ball left the field.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Restart {
+    /// Kick-off at the center mark after a goal
+    Center,
+    /// On the sideline where the ball crossed it, the x coordinate in meters
+    KickIn { x: f32, side: f32 },
+    /// At the corner of the goal line `goal_side` and the sideline `side`
+    Corner { goal_side: f32, side: f32 },
+    /// At the corner of the goal box in front of the goal line `goal_side` on `side`
+    GoalKick { goal_side: f32, side: f32 },
+}
+
+/// Goals scored by each team.
+#[derive(Debug, Default, Resource)]
+pub struct GameScore {
+    pub goals: HashMap<TeamColor, u32>,
+}
+
+#[derive(Default, Resource)]
+struct PendingRestart(Option<Restart>);
+
+#[derive(Default, Resource)]
+struct LastTouch(Option<TeamColor>);
+
+fn track_last_touch(
+    context: Res<RapierContext>,
+    mut last_touch: ResMut<LastTouch>,
+    balls: Query<(Entity, Option<&Arena>), With<Ball>>,
+    parents: Query<&Parent>,
+    players: Query<&Player>,
+) {
+    for (ball, _) in balls.iter().filter(|(_, arena)| in_main_arena(*arena)) {
+        for pair in context.contacts_with(ball) {
+            if !pair.has_any_active_contacts() {
+                continue;
+            }
+            let other = if pair.collider1() == ball {
+                pair.collider2()
+            } else {
+                pair.collider1()
+            };
+            let robot = std::iter::once(other)
+                .chain(parents.iter_ancestors(other))
+                .find_map(|entity| players.get(entity).ok());
+            if let Some(player) = robot {
+                last_touch.0 = Some(player.team_color);
+            }
+        }
+    }
+}
+
+fn restart_ball(
+    frame: Res<CoordinateFrame>,
+    field_dimensions: Res<FieldDimensions>,
+    replay: Option<Res<InstantReplay>>,
+    mut pending: ResMut<PendingRestart>,
+    mut last_touch: ResMut<LastTouch>,
+    mut balls: Query<(&mut Transform, &mut Velocity, Option<&Arena>), With<Ball>>,
+) {
+    if replay.map_or(false, |replay| replay.is_playing()) {
+        return;
+    }
+    let Some(restart) = pending.0.take() else {
+        return;
+    };
+    let half_length = field_dimensions.length / 2.0;
+    let half_width = field_dimensions.width / 2.0;
+    let position = match restart {
+        Restart::Center => Vec2::ZERO,
+        Restart::KickIn { x, side } => {
+            Vec2::new(x.clamp(-half_length, half_length), side * half_width)
+        }
+        Restart::Corner { goal_side, side } => {
+            Vec2::new(goal_side * half_length, side * half_width)
+        }
+        Restart::GoalKick { goal_side, side } => Vec2::new(
+            goal_side * (half_length - field_dimensions.goal_box_area_length),
+            side * field_dimensions.goal_box_area_width / 2.0,
+        ),
+    };
+    for (mut transform, mut velocity, _) in
+        balls.iter_mut().filter(|(.., arena)| in_main_arena(*arena))
+    {
+        *transform = Transform::from_translation(
+            frame.to_world(position.extend(field_dimensions.ball_radius)),
+        );
+        *velocity = Velocity::zero();
+    }
+    last_touch.0 = None;
+}
+
+#[allow(clippy::too_many_arguments)]
+fn detect_goals_and_ball_out(
+    frame: Res<CoordinateFrame>,
+    field_dimensions: Res<FieldDimensions>,
+    team_configuration: Res<TeamConfiguration>,
+    last_touch: Res<LastTouch>,
+    mut pending: ResMut<PendingRestart>,
+    mut score: ResMut<GameScore>,
+    mut goals: EventWriter<GoalScored>,
+    mut outs: EventWriter<BallOut>,
+    balls: Query<(&Transform, Option<&Arena>), With<Ball>>,
+) {
+    if pending.0.is_some() {
+        return;
+    }
+    let Some((ball, _)) = balls.iter().find(|(_, arena)| in_main_arena(*arena)) else {
+        return;
+    };
+    let radius = field_dimensions.ball_radius;
+    let position = frame.to_field(ball.translation);
+    // the ball is out once it completely crossed a line
+    let beyond_goal_line = position.x.abs() > field_dimensions.length / 2.0 + radius;
+    let beyond_sideline = position.y.abs() > field_dimensions.width / 2.0 + radius;
+    let goal_side = position.x.signum();
+    let side = position.y.signum();
+
+    if beyond_goal_line {
+        let in_goal =
+            position.y.abs() < field_dimensions.goal_inner_width / 2.0 && position.z < GOAL_HEIGHT;
+        let defending_team = defending_team(&team_configuration, goal_side);
+        if in_goal {
+            let team = attacking_team(&team_configuration, goal_side);
+            if let Some(team) = team {
+                *score.goals.entry(team).or_default() += 1;
+            }
+            info!("Goal scored by {team:?}");
+            goals.send(GoalScored { team });
+            pending.0 = Some(Restart::Center);
+            return;
+        }
+        let restart = if last_touch.0.is_some() && last_touch.0 == defending_team {
+            Restart::Corner { goal_side, side }
+        } else {
+            Restart::GoalKick { goal_side, side }
+        };
+        outs.send(BallOut {
+            restart,
+            last_touch: last_touch.0,
+        });
+        pending.0 = Some(restart);
+    } else if beyond_sideline {
+        let restart = Restart::KickIn {
+            x: position.x,
+            side,
+        };
+        outs.send(BallOut {
+            restart,
+            last_touch: last_touch.0,
+        });
+        pending.0 = Some(restart);
+    }
+}
+
+fn announce_ball_out(mut outs: EventReader<BallOut>) {
+    for out in outs.iter() {
+        info!(
+            "Ball out, {:?} after last touch by {:?}",
+            out.restart, out.last_touch
+        );
+    }
+}
+
+/// Team defending the goal at `goal_side`.
+fn defending_team(team_configuration: &TeamConfiguration, goal_side: f32) -> Option<TeamColor> {
+    team_configuration
+        .teams
+        .iter()
+        .find(|team| team.own_goal_side() == Some(goal_side))
+        .map(|team| team.team_color)
+}
+
+fn attacking_team(team_configuration: &TeamConfiguration, goal_side: f32) -> Option<TeamColor> {
+    defending_team(team_configuration, -goal_side)
+}
@@ -0,0 +1,70 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use color_eyre::Result;
+
+use crate::mesh_colliders::{load_mesh_collider, ColliderFidelity, ColliderPart};
+
+/// Colliders and materials shared by all robots, so spawning many robots of the same URDF
+/// decomposes each collision mesh and creates each material only once.
+#[derive(Resource)]
+pub struct RobotAssetCache {
+    /// Directory relative mesh filenames in URDFs are resolved against, same as the asset server
+    assets_directory: PathBuf,
+    /// Convex parts by mesh filename, scale and fidelity
+    colliders: HashMap<(String, [u32; 3], ColliderFidelity), Vec<ColliderPart>>,
+    /// Plain color materials by color
+    materials: HashMap<[u32; 4], Handle<StandardMaterial>>,
+}
+
+impl RobotAssetCache {
+    pub fn new(assets_directory: PathBuf) -> Self {
+        Self {
+            assets_directory,
+            colliders: HashMap::new(),
+            materials: HashMap::new(),
+        }
+    }
+}
+
+/// Everything needed to spawn robots.
+#[derive(SystemParam)]
+pub struct RobotAssets<'w> {
+    pub server: Res<'w, AssetServer>,
+    pub materials: ResMut<'w, Assets<StandardMaterial>>,
+    fidelity: Res<'w, ColliderFidelity>,
+    cache: ResMut<'w, RobotAssetCache>,
+}
+
+impl RobotAssets<'_> {
+    /// Convex parts of the collision mesh `filename`, see [`load_mesh_collider`].
+    pub fn mesh_collider(&mut self, filename: &str, scale: Vec3) -> Result<Vec<ColliderPart>> {
+        let key = (
+            filename.to_string(),
+            scale.to_array().map(f32::to_bits),
+            *self.fidelity,
+        );
+        if let Some(parts) = self.cache.colliders.get(&key) {
+            // colliders share their shapes, cloning them is cheap
+            return Ok(parts.clone());
+        }
+        let parts = load_mesh_collider(
+            &self.cache.assets_directory,
+            filename,
+            scale,
+            *self.fidelity,
+        )?;
+        self.cache.colliders.insert(key, parts.clone());
+        Ok(parts)
+    }
+
+    pub fn color_material(&mut self, color: Color) -> Handle<StandardMaterial> {
+        let key = color.as_rgba_f32().map(f32::to_bits);
+        let materials = &mut self.materials;
+        self.cache
+            .materials
+            .entry(key)
+            .or_insert_with(|| materials.add(color.into()))
+            .clone()
+    }
+}
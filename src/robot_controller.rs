@@ -0,0 +1,269 @@
+use std::{collections::HashMap, f32::consts::TAU};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    force_sensitive_resistors::ForceSensitiveResistors,
+    imu::{ImuReading, ImuReadings},
+    joint_control::{apply_joint_commands, JointCommand},
+    joint_encoders::MeasuredJointPositions,
+    motor_thermals::MotorState,
+    player::Player,
+    simulation_time::SimulationTime,
+    sonar::SonarReadings,
+    team_configuration::TeamConfiguration,
+    NaoJoint, RobotRoot,
+};
+
+/// Runs the [`RobotController`] of every robot that names one in the team configuration.
+///
+/// Controllers are registered by name with [`RobotControllerAppExt::register_robot_controller`],
+/// the "hold_pose" and "sine_wave" reference controllers are always available.
+pub struct RobotControllerPlugin;
+
+impl Plugin for RobotControllerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RobotControllers>()
+            .register_robot_controller("hold_pose", HoldPose::default)
+            .register_robot_controller("sine_wave", SineWave::default)
+            .add_system(attach_robot_controllers)
+            .add_system(
+                step_robot_controllers
+                    .after(attach_robot_controllers)
+                    .before(apply_joint_commands),
+            );
+    }
+}
+
+/// Sensor values of a robot as seen by its controller.
+#[derive(Clone, Debug)]
+pub struct SensorSnapshot {
+    /// Simulation time in seconds
+    pub time: f64,
+    /// Measured joint positions by joint name in radians
+    pub joint_positions: HashMap<String, f32>,
+    /// Current and temperature of the joint motors by joint name
+    pub motors: HashMap<String, MotorState>,
+    pub imu: ImuReading,
+    pub force_sensitive_resistors: ForceSensitiveResistors,
+    pub sonar: SonarReadings,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct JointTarget {
+    /// Target position in radians
+    pub position: f32,
+    /// Fraction of the default motor stiffness between 0 and 1
+    pub stiffness: f32,
+}
+
+/// Gait or behavior code driving the joints of a robot from its sensors.
+pub trait RobotController: Send + Sync + 'static {
+    /// Computes joint targets by joint name, joints without a target keep their last command.
+    fn step(&mut self, sensors: &SensorSnapshot) -> HashMap<String, JointTarget>;
+}
+
+type ControllerFactory = Box<dyn Fn() -> Box<dyn RobotController> + Send + Sync>;
+
+/// Controllers that can be assigned to robots, by name.
+#[derive(Default, Resource)]
+pub struct RobotControllers {
+    factories: HashMap<String, ControllerFactory>,
+}
+
+pub trait RobotControllerAppExt {
+    /// Makes a controller available to robots under `name`, `create` is called once per robot.
+    fn register_robot_controller<C: RobotController>(
+        &mut self,
+        name: &str,
+        create: impl Fn() -> C + Send + Sync + 'static,
+    ) -> &mut Self;
+}
+
+impl RobotControllerAppExt for App {
+    fn register_robot_controller<C: RobotController>(
+        &mut self,
+        name: &str,
+        create: impl Fn() -> C + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.init_resource::<RobotControllers>()
+            .world
+            .resource_mut::<RobotControllers>()
+            .factories
+            .insert(name.to_string(), Box::new(move || Box::new(create())));
+        self
+    }
+}
+
+/// Controller driving a robot.
+#[derive(Component)]
+pub struct Controlled(pub Box<dyn RobotController>);
+
+fn attach_robot_controllers(
+    mut commands: Commands,
+    controllers: Res<RobotControllers>,
+    team_configuration: Res<TeamConfiguration>,
+    robots: Query<(Entity, &Player), (Added<Player>, With<RobotRoot>)>,
+) {
+    for (entity, player) in robots.iter() {
+        let Some(name) = team_configuration
+            .teams
+            .iter()
+            .filter(|team| team.team_color == player.team_color)
+            .flat_map(|team| team.robots.iter())
+            .find(|robot| robot.jersey_number == player.jersey_number)
+            .and_then(|robot| robot.controller.as_ref())
+        else {
+            continue;
+        };
+        match controllers.factories.get(name) {
+            Some(create) => {
+                commands.entity(entity).insert(Controlled(create()));
+            }
+            None => error!("unknown robot controller {name:?}"),
+        }
+    }
+}
+
+/// Sensor values of robots as seen by their controllers.
+#[allow(clippy::type_complexity)]
+#[derive(SystemParam)]
+pub struct Sensors<'w, 's> {
+    simulation_time: Res<'w, SimulationTime>,
+    imu_readings: Res<'w, ImuReadings>,
+    robots: Query<
+        'w,
+        's,
+        (
+            Option<&'static MeasuredJointPositions>,
+            Option<&'static ForceSensitiveResistors>,
+            Option<&'static SonarReadings>,
+        ),
+    >,
+    children: Query<'w, 's, &'static Children>,
+    joints: Query<'w, 's, (&'static NaoJoint, &'static Transform)>,
+    motors: Query<'w, 's, (&'static NaoJoint, &'static MotorState)>,
+}
+
+impl Sensors<'_, '_> {
+    /// Sensor values of `robot`, joint positions are exact if it has no joint encoders.
+    pub fn snapshot(&self, robot: Entity) -> SensorSnapshot {
+        let (measured, force_sensitive_resistors, sonar) =
+            self.robots.get(robot).unwrap_or_default();
+        let joint_positions = match measured {
+            Some(measured) => measured.positions.clone(),
+            None => self
+                .children
+                .iter_descendants(robot)
+                .filter_map(|link| self.joints.get(link).ok())
+                .map(|(joint, transform)| (joint.name.clone(), joint.angle(transform)))
+                .collect(),
+        };
+        let motors = self
+            .children
+            .iter_descendants(robot)
+            .filter_map(|link| self.motors.get(link).ok())
+            .map(|(joint, motor)| (joint.name.clone(), *motor))
+            .collect();
+        SensorSnapshot {
+            time: self.simulation_time.elapsed_seconds(),
+            joint_positions,
+            motors,
+            imu: self.imu_readings.0.get(&robot).copied().unwrap_or_default(),
+            force_sensitive_resistors: force_sensitive_resistors.cloned().unwrap_or_default(),
+            sonar: sonar.cloned().unwrap_or_default(),
+        }
+    }
+}
+
+pub fn step_robot_controllers(
+    sensors: Sensors,
+    mut robots: Query<(Entity, &mut Controlled)>,
+    children: Query<&Children>,
+    mut commands: Query<(&NaoJoint, &mut JointCommand)>,
+) {
+    for (robot, mut controller) in robots.iter_mut() {
+        let targets = controller.0.step(&sensors.snapshot(robot));
+        apply_joint_targets(robot, &targets, &children, &mut commands);
+    }
+}
+
+/// Commands the joints of `robot` named in `targets`.
+pub fn apply_joint_targets(
+    robot: Entity,
+    targets: &HashMap<String, JointTarget>,
+    children: &Query<&Children>,
+    commands: &mut Query<(&NaoJoint, &mut JointCommand)>,
+) {
+    for link in children.iter_descendants(robot) {
+        let Ok((joint, mut command)) = commands.get_mut(link) else {
+            continue;
+        };
+        if let Some(target) = targets.get(&joint.name) {
+            command.position = target.position;
+            command.set_stiffness_fraction(target.stiffness);
+        }
+    }
+}
+
+/// Holds the joint positions measured in the first step.
+#[derive(Default)]
+pub struct HoldPose {
+    pose: Option<HashMap<String, f32>>,
+}
+
+impl RobotController for HoldPose {
+    fn step(&mut self, sensors: &SensorSnapshot) -> HashMap<String, JointTarget> {
+        self.pose
+            .get_or_insert_with(|| sensors.joint_positions.clone())
+            .iter()
+            .map(|(name, position)| {
+                (
+                    name.clone(),
+                    JointTarget {
+                        position: *position,
+                        stiffness: 1.0,
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+/// Swings every joint sinusoidally around the pose measured in the first step.
+pub struct SineWave {
+    /// Amplitude in radians
+    pub amplitude: f32,
+    /// Frequency in Hz
+    pub frequency: f32,
+    center: Option<HashMap<String, f32>>,
+}
+
+impl Default for SineWave {
+    fn default() -> Self {
+        Self {
+            amplitude: 0.2,
+            frequency: 0.5,
+            center: None,
+        }
+    }
+}
+
+impl RobotController for SineWave {
+    fn step(&mut self, sensors: &SensorSnapshot) -> HashMap<String, JointTarget> {
+        let offset = self.amplitude * (TAU * self.frequency * sensors.time as f32).sin();
+        self.center
+            .get_or_insert_with(|| sensors.joint_positions.clone())
+            .iter()
+            .map(|(name, center)| {
+                (
+                    name.clone(),
+                    JointTarget {
+                        position: center + offset,
+                        stiffness: 1.0,
+                    },
+                )
+            })
+            .collect()
+    }
+}
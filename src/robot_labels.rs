@@ -0,0 +1,114 @@
+use bevy::prelude::*;
+
+use crate::{
+    coordinate_frame::CoordinateFrame,
+    player::{Player, RobotStatus},
+    shortcuts::{triggered, ShortcutAction},
+    world_labels::WorldLabel,
+};
+
+/// Shows team color, jersey number and status above every robot.
+pub struct RobotLabelsPlugin;
+
+impl Plugin for RobotLabelsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RobotLabels>()
+            .add_system(spawn_robot_labels)
+            .add_system(toggle_robot_labels)
+            .add_system(update_robot_labels);
+    }
+}
+
+#[derive(Resource)]
+pub struct RobotLabels {
+    pub enabled: bool,
+    pub show_status: bool,
+}
+
+impl Default for RobotLabels {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            show_status: true,
+        }
+    }
+}
+
+#[derive(Component)]
+struct RobotLabel {
+    robot: Entity,
+}
+
+fn spawn_robot_labels(
+    mut commands: Commands,
+    settings: Res<RobotLabels>,
+    frame: Res<CoordinateFrame>,
+    robots: Query<Entity, Added<Player>>,
+) {
+    for robot in robots.iter() {
+        let visibility = if settings.enabled {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        let label = commands
+            .spawn((
+                TransformBundle::default(),
+                VisibilityBundle {
+                    visibility,
+                    ..Default::default()
+                },
+                WorldLabel {
+                    offset: frame.up() * 0.45,
+                    ..WorldLabel::new("")
+                },
+                RobotLabel { robot },
+            ))
+            .id();
+        commands.entity(robot).add_child(label);
+    }
+}
+
+fn toggle_robot_labels(
+    mut actions: EventReader<ShortcutAction>,
+    mut settings: ResMut<RobotLabels>,
+    mut labels: Query<&mut Visibility, With<RobotLabel>>,
+) {
+    if !triggered(&mut actions, ShortcutAction::ToggleRobotLabels) {
+        return;
+    }
+    settings.enabled = !settings.enabled;
+    for mut visibility in labels.iter_mut() {
+        *visibility = if settings.enabled {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+fn update_robot_labels(
+    settings: Res<RobotLabels>,
+    robots: Query<(&Player, &RobotStatus)>,
+    mut labels: Query<(&RobotLabel, &mut WorldLabel)>,
+) {
+    for (label, mut world_label) in labels.iter_mut() {
+        let Ok((player, status)) = robots.get(label.robot) else {
+            continue;
+        };
+        let mut text = format!("#{}", player.jersey_number);
+        if settings.show_status {
+            if status.penalized {
+                text.push_str("\npenalized");
+            }
+            if status.fallen {
+                text.push_str("\nfallen");
+            }
+            if let Some(battery) = status.battery {
+                text.push_str(&format!("\nbattery {:.0}%", battery * 100.0));
+            }
+        }
+        world_label.text = text;
+        world_label.color = player.team_color.color();
+    }
+}
@@ -0,0 +1,154 @@
+use bevy::{ecs::query::ReadOnlyWorldQuery, prelude::*};
+
+use crate::{joint_control::JointCommand, NaoJoint, RobotRoot};
+
+/// Places new robots above the ground and brings their joints into a named preset, so they do not
+/// start intersecting the field.
+pub struct RobotSpawnPlugin;
+
+impl Plugin for RobotSpawnPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RobotSpawn>()
+            .add_system(apply_joint_preset);
+    }
+}
+
+/// How robots are spawned, their placement on the field comes from the team configuration.
+#[derive(Clone, Debug, Resource)]
+pub struct RobotSpawn {
+    /// Offset of the root link from its resting position of the preset in meters
+    pub position: Vec3,
+    /// Rotation of the root link applied on top of the orientation on the field
+    pub orientation: Quat,
+    /// Name of the joint preset, one of [`JOINT_PRESETS`]
+    pub preset: String,
+}
+
+impl Default for RobotSpawn {
+    fn default() -> Self {
+        Self {
+            position: Vec3::ZERO,
+            orientation: Quat::IDENTITY,
+            preset: "stand".to_string(),
+        }
+    }
+}
+
+impl RobotSpawn {
+    /// Transform of the root link in field coordinates of a robot placed at `field_transform` on
+    /// the ground.
+    pub fn transform(&self, field_transform: Transform) -> Transform {
+        let height = self.joint_preset().map_or(0.0, |preset| preset.root_height);
+        let mut transform = field_transform;
+        transform.translation.z = height;
+        transform.translation += self.position;
+        transform.rotation *= self.orientation;
+        transform
+    }
+
+    pub fn joint_preset(&self) -> Option<&'static JointPreset> {
+        JOINT_PRESETS
+            .iter()
+            .find(|preset| preset.name == self.preset)
+    }
+}
+
+pub struct JointPreset {
+    pub name: &'static str,
+    /// Height of the root link above the soles in meters
+    pub root_height: f32,
+    /// Joint angles in radians, joints not listed stay at zero
+    pub positions: &'static [(&'static str, f32)],
+}
+
+pub const JOINT_PRESETS: &[JointPreset] = &[
+    JointPreset {
+        name: "zero",
+        root_height: 0.333,
+        positions: &[],
+    },
+    JointPreset {
+        name: "stand",
+        root_height: 0.31,
+        positions: &[
+            ("LHipPitch", -0.45),
+            ("RHipPitch", -0.45),
+            ("LKneePitch", 0.9),
+            ("RKneePitch", 0.9),
+            ("LAnklePitch", -0.45),
+            ("RAnklePitch", -0.45),
+            ("LShoulderPitch", 1.5),
+            ("RShoulderPitch", 1.5),
+            ("LShoulderRoll", 0.15),
+            ("RShoulderRoll", -0.15),
+            ("LElbowYaw", -1.2),
+            ("RElbowYaw", 1.2),
+            ("LElbowRoll", -0.5),
+            ("RElbowRoll", 0.5),
+        ],
+    },
+    JointPreset {
+        name: "sit",
+        root_height: 0.227,
+        positions: &[
+            ("LHipPitch", -0.87),
+            ("RHipPitch", -0.87),
+            ("LKneePitch", 2.11),
+            ("RKneePitch", 2.11),
+            ("LAnklePitch", -1.18),
+            ("RAnklePitch", -1.18),
+            ("LShoulderPitch", 1.5),
+            ("RShoulderPitch", 1.5),
+            ("LShoulderRoll", 0.1),
+            ("RShoulderRoll", -0.1),
+            ("LElbowYaw", -1.2),
+            ("RElbowYaw", 1.2),
+            ("LElbowRoll", -0.5),
+            ("RElbowRoll", 0.5),
+        ],
+    },
+];
+
+/// Moves the joints of new robots into the preset and makes it their command.
+fn apply_joint_preset(
+    spawn: Res<RobotSpawn>,
+    robots: Query<Entity, Added<RobotRoot>>,
+    children: Query<&Children>,
+    mut joints: Query<(&NaoJoint, &mut Transform, Option<&mut JointCommand>)>,
+) {
+    if robots.is_empty() {
+        return;
+    }
+    let Some(preset) = spawn.joint_preset() else {
+        error!("unknown joint preset {:?}", spawn.preset);
+        return;
+    };
+    for robot in robots.iter() {
+        set_joint_positions(robot, preset.positions, &children, &mut joints);
+    }
+}
+
+/// Moves the named joints of `robot` to their angles and makes them their command.
+pub fn set_joint_positions<F: ReadOnlyWorldQuery>(
+    robot: Entity,
+    positions: &[(&str, f32)],
+    children: &Query<&Children>,
+    joints: &mut Query<(&NaoJoint, &mut Transform, Option<&mut JointCommand>), F>,
+) {
+    for (name, angle) in positions {
+        let joint = children.iter_descendants(robot).find(|link| {
+            joints
+                .get(*link)
+                .map_or(false, |(joint, ..)| joint.name == *name)
+        });
+        let Some((joint, mut transform, command)) =
+            joint.and_then(|link| joints.get_mut(link).ok())
+        else {
+            continue;
+        };
+        transform.rotation = joint.rotation(*angle);
+        if let Some(mut command) = command {
+            command.position = *angle;
+        }
+    }
+}
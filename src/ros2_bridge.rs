@@ -0,0 +1,345 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use bevy::{prelude::*, transform::TransformSystem};
+use color_eyre::{eyre::WrapErr, Result};
+use futures::{FutureExt, Stream, StreamExt};
+use r2r::{
+    builtin_interfaces::msg::Time,
+    geometry_msgs::msg::{Quaternion, Transform as RosTransform, TransformStamped, Vector3},
+    rosgraph_msgs::msg::Clock,
+    sensor_msgs::msg::JointState,
+    std_msgs::msg::Header,
+    tf2_msgs::msg::TFMessage,
+    trajectory_msgs::msg::JointTrajectory,
+    Node, Publisher, QosProfile,
+};
+
+use crate::{
+    coordinate_frame::CoordinateFrame, joint_control::JointCommand, player::Player,
+    simulation_time::SimulationTime, NaoJoint, RobotLink, RobotRoot,
+};
+
+/// Frame all robots are located in
+const FIELD_FRAME: &str = "field";
+/// How long the bridge thread waits for ROS messages per spin
+const SPIN_TIMEOUT: Duration = Duration::from_millis(1);
+
+/// Connects the simulator to ROS 2, enabled by the `ros2` feature.
+///
+/// Publishes `/clock` from the simulation time and `/tf` with every link of every robot. Each
+/// robot gets a namespace of its team color and jersey number, e.g. `/blue_1`, with its
+/// `joint_states` and a `joint_trajectory` topic whose trajectories are followed by setting the
+/// joint commands. Frames are prefixed with the namespace as well, e.g. `blue_1/Head`.
+pub struct Ros2BridgePlugin;
+
+impl Plugin for Ros2BridgePlugin {
+    fn build(&self, app: &mut App) {
+        let (outgoing_sender, outgoing_receiver) = mpsc::channel();
+        let (incoming_sender, incoming_receiver) = mpsc::channel();
+        thread::spawn(move || {
+            if let Err(error) = run_node(outgoing_receiver, incoming_sender) {
+                error!("{error:?}");
+            }
+        });
+        app.insert_resource(Ros2Bridge {
+            outgoing: Mutex::new(outgoing_sender),
+            incoming: Mutex::new(incoming_receiver),
+        })
+        .add_system(subscribe_robots)
+        .add_system(receive_trajectories.after(subscribe_robots))
+        .add_system(follow_trajectories.after(receive_trajectories))
+        .add_system(
+            publish_robot_states
+                .in_base_set(CoreSet::PostUpdate)
+                .after(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+/// Channels to the thread owning the ROS node, the mutexes only make them `Sync`.
+#[derive(Resource)]
+struct Ros2Bridge {
+    outgoing: Mutex<Sender<Outgoing>>,
+    incoming: Mutex<Receiver<(String, JointTrajectory)>>,
+}
+
+impl Ros2Bridge {
+    fn send(&self, message: Outgoing) {
+        // a failed node already logged why
+        let _ = self.outgoing.lock().unwrap().send(message);
+    }
+}
+
+enum Outgoing {
+    Subscribe {
+        namespace: String,
+    },
+    Clock(Clock),
+    Tf(TFMessage),
+    JointState {
+        namespace: String,
+        state: JointState,
+    },
+}
+
+/// Trajectory a robot is following, started at the simulated time `start`.
+#[derive(Component)]
+struct ActiveTrajectory {
+    start: f64,
+    /// Joint positions when the trajectory was received, the start of the first segment
+    start_positions: HashMap<String, f32>,
+    trajectory: JointTrajectory,
+}
+
+fn namespace(player: &Player) -> String {
+    format!("{:?}_{}", player.team_color, player.jersey_number).to_lowercase()
+}
+
+fn stamp(seconds: f64) -> Time {
+    Time {
+        sec: seconds.floor() as i32,
+        nanosec: (seconds.fract() * 1e9) as u32,
+    }
+}
+
+fn subscribe_robots(bridge: Res<Ros2Bridge>, robots: Query<&Player, Added<Player>>) {
+    for player in robots.iter() {
+        bridge.send(Outgoing::Subscribe {
+            namespace: namespace(player),
+        });
+    }
+}
+
+fn receive_trajectories(
+    mut commands: Commands,
+    bridge: Res<Ros2Bridge>,
+    simulation_time: Res<SimulationTime>,
+    robots: Query<(Entity, &Player)>,
+    children: Query<&Children>,
+    joints: Query<(&NaoJoint, &JointCommand)>,
+) {
+    let trajectories: Vec<_> = bridge.incoming.lock().unwrap().try_iter().collect();
+    for (robot_namespace, trajectory) in trajectories {
+        let Some((robot, _)) = robots
+            .iter()
+            .find(|(_, player)| namespace(player) == robot_namespace)
+        else {
+            continue;
+        };
+        let start_positions = children
+            .iter_descendants(robot)
+            .filter_map(|link| joints.get(link).ok())
+            .map(|(joint, command)| (joint.name.clone(), command.position))
+            .collect();
+        commands.entity(robot).insert(ActiveTrajectory {
+            start: simulation_time.elapsed_seconds(),
+            start_positions,
+            trajectory,
+        });
+    }
+}
+
+/// Interpolates the joint commands linearly between the trajectory points.
+fn follow_trajectories(
+    mut commands: Commands,
+    simulation_time: Res<SimulationTime>,
+    robots: Query<(Entity, &ActiveTrajectory)>,
+    children: Query<&Children>,
+    mut joints: Query<(&NaoJoint, &mut JointCommand)>,
+) {
+    for (robot, active) in robots.iter() {
+        let elapsed = simulation_time.elapsed_seconds() - active.start;
+        let points = &active.trajectory.points;
+        let point_time = |index: usize| {
+            let duration = &points[index].time_from_start;
+            f64::from(duration.sec) + f64::from(duration.nanosec) * 1e-9
+        };
+        let next = (0..points.len()).find(|index| point_time(*index) > elapsed);
+        if next.is_none() {
+            commands.entity(robot).remove::<ActiveTrajectory>();
+        }
+        for link in children.iter_descendants(robot) {
+            let Ok((joint, mut command)) = joints.get_mut(link) else {
+                continue;
+            };
+            let Some(index) = active
+                .trajectory
+                .joint_names
+                .iter()
+                .position(|name| *name == joint.name)
+            else {
+                continue;
+            };
+            let position_at = |point: usize| {
+                points[point]
+                    .positions
+                    .get(index)
+                    .map(|position| *position as f32)
+            };
+            let position = match next {
+                // past the end the last point is held
+                None => points.len().checked_sub(1).and_then(position_at),
+                Some(next) => {
+                    let (from_time, from) = match next.checked_sub(1) {
+                        Some(previous) => (point_time(previous), position_at(previous)),
+                        None => (0.0, active.start_positions.get(&joint.name).copied()),
+                    };
+                    let to = position_at(next);
+                    let t = ((elapsed - from_time) / (point_time(next) - from_time)) as f32;
+                    from.zip(to)
+                        .map(|(from, to)| from + (to - from) * t.clamp(0.0, 1.0))
+                }
+            };
+            if let Some(position) = position {
+                command.position = position;
+            }
+        }
+    }
+}
+
+fn publish_robot_states(
+    bridge: Res<Ros2Bridge>,
+    simulation_time: Res<SimulationTime>,
+    frame: Res<CoordinateFrame>,
+    robots: Query<(Entity, &Player, &RobotLink, &GlobalTransform), With<RobotRoot>>,
+    children: Query<&Children>,
+    links: Query<(&RobotLink, &Transform, Option<&NaoJoint>)>,
+) {
+    let time = stamp(simulation_time.elapsed_seconds());
+    bridge.send(Outgoing::Clock(Clock {
+        clock: time.clone(),
+    }));
+    let header = |frame_id: String| Header {
+        stamp: time.clone(),
+        frame_id,
+    };
+
+    let mut transforms = Vec::new();
+    for (robot, player, root_link, root_transform) in robots.iter() {
+        let namespace = namespace(player);
+        let root_frame = format!("{namespace}/{}", root_link.name);
+        let root_transform = frame.transform_to_field(root_transform.compute_transform());
+        transforms.push(TransformStamped {
+            header: header(FIELD_FRAME.to_string()),
+            child_frame_id: root_frame.clone(),
+            transform: ros_transform(root_transform.translation, root_transform.rotation),
+        });
+
+        let mut state = JointState {
+            header: header(root_frame.clone()),
+            ..Default::default()
+        };
+        for link in children.iter_descendants(robot) {
+            let Ok((link, transform, joint)) = links.get(link) else {
+                continue;
+            };
+            // links are children of the root link, not of their parent link
+            transforms.push(TransformStamped {
+                header: header(root_frame.clone()),
+                child_frame_id: format!("{namespace}/{}", link.name),
+                transform: ros_transform(transform.translation, transform.rotation),
+            });
+            if let Some(joint) = joint {
+                state.name.push(joint.name.clone());
+                state.position.push(f64::from(joint.angle(transform)));
+            }
+        }
+        bridge.send(Outgoing::JointState { namespace, state });
+    }
+    bridge.send(Outgoing::Tf(TFMessage { transforms }));
+}
+
+fn ros_transform(translation: Vec3, rotation: Quat) -> RosTransform {
+    RosTransform {
+        translation: Vector3 {
+            x: f64::from(translation.x),
+            y: f64::from(translation.y),
+            z: f64::from(translation.z),
+        },
+        rotation: Quaternion {
+            x: f64::from(rotation.x),
+            y: f64::from(rotation.y),
+            z: f64::from(rotation.z),
+            w: f64::from(rotation.w),
+        },
+    }
+}
+
+type TrajectoryStream = Box<dyn Stream<Item = JointTrajectory> + Unpin>;
+
+/// Spins the ROS node, publishing what the systems send and forwarding received trajectories.
+fn run_node(
+    outgoing: Receiver<Outgoing>,
+    incoming: Sender<(String, JointTrajectory)>,
+) -> Result<()> {
+    let context = r2r::Context::create().wrap_err("failed to create ROS context")?;
+    let mut node = Node::create(context, "mio", "").wrap_err("failed to create ROS node")?;
+    let clock: Publisher<Clock> = node
+        .create_publisher("/clock", QosProfile::default())
+        .wrap_err("failed to create /clock publisher")?;
+    let tf: Publisher<TFMessage> = node
+        .create_publisher("/tf", QosProfile::default())
+        .wrap_err("failed to create /tf publisher")?;
+    let mut joint_states: HashMap<String, Publisher<JointState>> = HashMap::new();
+    let mut trajectories: Vec<(String, TrajectoryStream)> = Vec::new();
+
+    loop {
+        node.spin_once(SPIN_TIMEOUT);
+        for message in outgoing.try_iter() {
+            let result = match message {
+                Outgoing::Subscribe { namespace } => match open_robot_topics(&mut node, &namespace)
+                {
+                    Ok((subscription, publisher)) => {
+                        trajectories.push((namespace.clone(), subscription));
+                        joint_states.insert(namespace, publisher);
+                        Ok(())
+                    }
+                    Err(error) => {
+                        error!("{error:?}");
+                        Ok(())
+                    }
+                },
+                Outgoing::Clock(message) => clock.publish(&message),
+                Outgoing::Tf(message) => tf.publish(&message),
+                Outgoing::JointState { namespace, state } => match joint_states.get(&namespace) {
+                    Some(publisher) => publisher.publish(&state),
+                    None => Ok(()),
+                },
+            };
+            if let Err(error) = result {
+                warn!("Failed to publish ROS message: {error}");
+            }
+        }
+        for (namespace, stream) in &mut trajectories {
+            while let Some(Some(trajectory)) = stream.next().now_or_never() {
+                if incoming.send((namespace.clone(), trajectory)).is_err() {
+                    // the simulation quit
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+fn open_robot_topics(
+    node: &mut Node,
+    namespace: &str,
+) -> Result<(TrajectoryStream, Publisher<JointState>)> {
+    let topic = format!("/{namespace}/joint_trajectory");
+    let subscription = node
+        .subscribe::<JointTrajectory>(&topic, QosProfile::default())
+        .wrap_err_with(|| format!("failed to subscribe to {topic}"))?;
+    let topic = format!("/{namespace}/joint_states");
+    let publisher = node
+        .create_publisher(&topic, QosProfile::default())
+        .wrap_err_with(|| format!("failed to create {topic} publisher"))?;
+    Ok((Box::new(subscription), publisher))
+}
@@ -0,0 +1,388 @@
+use std::{
+    path::Path,
+    process,
+    sync::{Arc, Mutex},
+};
+
+use bevy::{app::AppExit, prelude::*};
+use bevy_rapier3d::prelude::*;
+use color_eyre::{eyre::WrapErr, Result};
+use rhai::{Engine, EvalAltResult, FnPtr, AST};
+use serde::{de::IntoDeserializer, Deserialize};
+
+use crate::{
+    arenas::{in_main_arena, Arena},
+    coordinate_frame::CoordinateFrame,
+    field_dimensions::FieldDimensions,
+    joint_control::JointCommand,
+    kick_tool::kick,
+    player::{Player, TeamColor},
+    simulation_time::SimulationTime,
+    Ball, NaoJoint, RobotRoot,
+};
+
+/// Runs a Rhai script that sets up and checks a test scenario.
+///
+/// The script body runs once after startup, callbacks registered with `at(time, || ...)` run
+/// when the simulation time reaches `time` seconds. Available functions:
+///
+/// - `place_ball(x, y)`, `kick_ball(vx, vy, vz)`, `kick_ball_toward(x, y, speed)`, `ball_x()`,
+///   `ball_y()`
+/// - `place_robot(color, jersey, x, y, orientation)`, `push_robot(color, jersey, ix, iy, iz)`,
+///   `robot_x(color, jersey)`, `robot_y(color, jersey)`
+/// - `set_joint(color, jersey, joint, position)`
+/// - `expect(condition, message)` records a failure if `condition` is false
+/// - `finish()` quits, with exit code 1 if any expectation failed
+///
+/// With several arenas, commands apply to the ball and robots of every arena in its own field
+/// frame, while queries and expectations see the main arena.
+pub struct ScenarioPlugin;
+
+impl Plugin for ScenarioPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(run_scenario.run_if(resource_exists::<Scenario>()));
+    }
+}
+
+#[derive(Resource)]
+pub struct Scenario {
+    engine: Engine,
+    ast: AST,
+    state: Arc<Mutex<ScenarioState>>,
+    started: bool,
+    /// Pending callbacks sorted by time
+    timers: Vec<(f64, FnPtr)>,
+}
+
+/// Shared between the script functions and the systems applying their effects.
+#[derive(Default)]
+struct ScenarioState {
+    ball: Vec3,
+    robots: Vec<(RobotId, Vec3)>,
+    commands: Vec<ScenarioCommand>,
+    new_timers: Vec<(f64, FnPtr)>,
+    failures: Vec<String>,
+}
+
+type RobotId = (TeamColor, u8);
+
+enum ScenarioCommand {
+    PlaceBall(Vec2),
+    KickBall(Vec3),
+    KickBallToward {
+        target: Vec2,
+        speed: f32,
+    },
+    PlaceRobot {
+        robot: RobotId,
+        position: Vec2,
+        orientation: f32,
+    },
+    PushRobot {
+        robot: RobotId,
+        impulse: Vec3,
+    },
+    SetJoint {
+        robot: RobotId,
+        joint: String,
+        position: f32,
+    },
+    Finish,
+}
+
+impl Scenario {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let state = Arc::new(Mutex::new(ScenarioState::default()));
+        let engine = create_engine(&state);
+        let ast = engine
+            .compile_file(path.to_path_buf())
+            .wrap_err_with(|| format!("failed to compile scenario {}", path.display()))?;
+        Ok(Self {
+            engine,
+            ast,
+            state,
+            started: false,
+            timers: Vec::new(),
+        })
+    }
+}
+
+fn create_engine(state: &Arc<Mutex<ScenarioState>>) -> Engine {
+    let mut engine = Engine::new();
+
+    let push_command = |state: &Arc<Mutex<ScenarioState>>| {
+        let state = state.clone();
+        move |command: ScenarioCommand| state.lock().unwrap().commands.push(command)
+    };
+
+    let command = push_command(state);
+    engine.register_fn("place_ball", move |x: f64, y: f64| {
+        command(ScenarioCommand::PlaceBall(Vec2::new(x as f32, y as f32)))
+    });
+    let command = push_command(state);
+    engine.register_fn("kick_ball", move |x: f64, y: f64, z: f64| {
+        command(ScenarioCommand::KickBall(Vec3::new(
+            x as f32, y as f32, z as f32,
+        )))
+    });
+    let command = push_command(state);
+    engine.register_fn("kick_ball_toward", move |x: f64, y: f64, speed: f64| {
+        command(ScenarioCommand::KickBallToward {
+            target: Vec2::new(x as f32, y as f32),
+            speed: speed as f32,
+        })
+    });
+    let command = push_command(state);
+    engine.register_fn(
+        "place_robot",
+        move |color: &str,
+              jersey: i64,
+              x: f64,
+              y: f64,
+              orientation: f64|
+              -> Result<(), Box<EvalAltResult>> {
+            command(ScenarioCommand::PlaceRobot {
+                robot: robot_id(color, jersey)?,
+                position: Vec2::new(x as f32, y as f32),
+                orientation: orientation as f32,
+            });
+            Ok(())
+        },
+    );
+    let command = push_command(state);
+    engine.register_fn(
+        "push_robot",
+        move |color: &str, jersey: i64, x: f64, y: f64, z: f64| -> Result<(), Box<EvalAltResult>> {
+            command(ScenarioCommand::PushRobot {
+                robot: robot_id(color, jersey)?,
+                impulse: Vec3::new(x as f32, y as f32, z as f32),
+            });
+            Ok(())
+        },
+    );
+    let command = push_command(state);
+    engine.register_fn(
+        "set_joint",
+        move |color: &str,
+              jersey: i64,
+              joint: &str,
+              position: f64|
+              -> Result<(), Box<EvalAltResult>> {
+            command(ScenarioCommand::SetJoint {
+                robot: robot_id(color, jersey)?,
+                joint: joint.to_string(),
+                position: position as f32,
+            });
+            Ok(())
+        },
+    );
+    let command = push_command(state);
+    engine.register_fn("finish", move || command(ScenarioCommand::Finish));
+
+    let shared = state.clone();
+    engine.register_fn("at", move |time: f64, callback: FnPtr| {
+        shared.lock().unwrap().new_timers.push((time, callback))
+    });
+    let shared = state.clone();
+    engine.register_fn("expect", move |condition: bool, message: &str| {
+        if !condition {
+            error!("Scenario expectation failed: {message}");
+            shared.lock().unwrap().failures.push(message.to_string());
+        }
+    });
+
+    let shared = state.clone();
+    engine.register_fn("ball_x", move || f64::from(shared.lock().unwrap().ball.x));
+    let shared = state.clone();
+    engine.register_fn("ball_y", move || f64::from(shared.lock().unwrap().ball.y));
+    let shared = state.clone();
+    engine.register_fn("robot_x", move |color: &str, jersey: i64| {
+        robot_position(&shared, color, jersey).map(|position| f64::from(position.x))
+    });
+    let shared = state.clone();
+    engine.register_fn("robot_y", move |color: &str, jersey: i64| {
+        robot_position(&shared, color, jersey).map(|position| f64::from(position.y))
+    });
+
+    engine
+}
+
+fn robot_id(color: &str, jersey: i64) -> Result<RobotId, Box<EvalAltResult>> {
+    let color = TeamColor::deserialize(color.into_deserializer())
+        .map_err(|error: serde::de::value::Error| error.to_string())?;
+    let jersey = u8::try_from(jersey).map_err(|_| format!("invalid jersey number {jersey}"))?;
+    Ok((color, jersey))
+}
+
+fn robot_position(
+    state: &Mutex<ScenarioState>,
+    color: &str,
+    jersey: i64,
+) -> Result<Vec3, Box<EvalAltResult>> {
+    let robot = robot_id(color, jersey)?;
+    state
+        .lock()
+        .unwrap()
+        .robots
+        .iter()
+        .find(|(id, _)| *id == robot)
+        .map(|(_, position)| *position)
+        .ok_or_else(|| format!("no robot {color} {jersey}").into())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_scenario(
+    mut commands: Commands,
+    mut scenario: ResMut<Scenario>,
+    simulation_time: Res<SimulationTime>,
+    frame: Res<CoordinateFrame>,
+    field_dimensions: Res<FieldDimensions>,
+    mut balls: Query<(&mut Transform, &mut Velocity, Option<&Arena>), With<Ball>>,
+    mut robots: Query<
+        (Entity, &Player, &mut Transform, Option<&Arena>),
+        (With<RobotRoot>, Without<Ball>),
+    >,
+    children: Query<&Children>,
+    mut joints: Query<(&NaoJoint, &mut JointCommand)>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let scenario = &mut *scenario;
+    {
+        let mut state = scenario.state.lock().unwrap();
+        if let Some((transform, ..)) = balls.iter().find(|(.., arena)| in_main_arena(*arena)) {
+            state.ball = frame.to_field(transform.translation);
+        }
+        state.robots = robots
+            .iter()
+            .filter(|(.., arena)| in_main_arena(*arena))
+            .map(|(_, player, transform, _)| {
+                (
+                    (player.team_color, player.jersey_number),
+                    frame.to_field(transform.translation),
+                )
+            })
+            .collect();
+    }
+
+    if !scenario.started {
+        scenario.started = true;
+        if let Err(error) = scenario.engine.run_ast(&scenario.ast) {
+            error!("Scenario failed: {error}");
+        }
+    }
+    let now = simulation_time.elapsed_seconds();
+    loop {
+        let new_timers = std::mem::take(&mut scenario.state.lock().unwrap().new_timers);
+        scenario.timers.extend(new_timers);
+        scenario
+            .timers
+            .sort_by(|(left, _), (right, _)| left.total_cmp(right));
+        if scenario
+            .timers
+            .first()
+            .map_or(true, |(time, _)| *time > now)
+        {
+            break;
+        }
+        let (_, callback) = scenario.timers.remove(0);
+        if let Err(error) = callback.call::<()>(&scenario.engine, &scenario.ast, ()) {
+            error!("Scenario callback failed: {error}");
+        }
+    }
+
+    let (pending, failures) = {
+        let mut state = scenario.state.lock().unwrap();
+        (std::mem::take(&mut state.commands), state.failures.len())
+    };
+    for command in pending {
+        match command {
+            ScenarioCommand::PlaceBall(position) => {
+                for (mut transform, mut velocity, arena) in balls.iter_mut() {
+                    let arena = arena.copied().unwrap_or_default();
+                    transform.translation = arena.to_world(
+                        &frame,
+                        &field_dimensions,
+                        position.extend(field_dimensions.ball_radius),
+                    );
+                    *velocity = Velocity::zero();
+                }
+            }
+            ScenarioCommand::KickBall(linear_velocity) => {
+                for (_, mut velocity, _) in balls.iter_mut() {
+                    velocity.linvel = frame.vector_to_world(linear_velocity);
+                }
+            }
+            ScenarioCommand::KickBallToward { target, speed } => {
+                for (transform, mut velocity, arena) in balls.iter_mut() {
+                    let arena = arena.copied().unwrap_or_default();
+                    let position = (frame.to_field(transform.translation)
+                        - arena.offset(&field_dimensions))
+                    .truncate();
+                    if let Some(direction) = (target - position).try_normalize() {
+                        kick(&mut velocity, &frame, direction.extend(0.0), 0.0, speed);
+                    }
+                }
+            }
+            ScenarioCommand::PlaceRobot {
+                robot,
+                position,
+                orientation,
+            } => {
+                for (_, _, mut transform, arena) in robots
+                    .iter_mut()
+                    .filter(|(_, player, ..)| is_robot(player, robot))
+                {
+                    let arena = arena.copied().unwrap_or_default();
+                    let mut field_transform = frame.transform_to_field(*transform);
+                    field_transform.translation.x = position.x;
+                    field_transform.translation.y = position.y;
+                    field_transform.rotation = Quat::from_rotation_z(orientation);
+                    *transform =
+                        arena.transform_to_world(&frame, &field_dimensions, field_transform);
+                }
+            }
+            ScenarioCommand::PushRobot { robot, impulse } => {
+                for (entity, ..) in robots
+                    .iter()
+                    .filter(|(_, player, ..)| is_robot(player, robot))
+                {
+                    commands.entity(entity).insert(ExternalImpulse {
+                        impulse: frame.vector_to_world(impulse),
+                        torque_impulse: Vec3::ZERO,
+                    });
+                }
+            }
+            ScenarioCommand::SetJoint {
+                robot,
+                joint,
+                position,
+            } => {
+                for (entity, ..) in robots
+                    .iter()
+                    .filter(|(_, player, ..)| is_robot(player, robot))
+                {
+                    for link in children.iter_descendants(entity) {
+                        if let Ok((nao_joint, mut command)) = joints.get_mut(link) {
+                            if nao_joint.name == joint {
+                                command.position = position;
+                            }
+                        }
+                    }
+                }
+            }
+            ScenarioCommand::Finish => {
+                if failures > 0 {
+                    error!("Scenario finished with {failures} failed expectation(s)");
+                    process::exit(1);
+                }
+                info!("Scenario finished successfully");
+                exit.send(AppExit);
+            }
+        }
+    }
+}
+
+fn is_robot(player: &Player, (team_color, jersey_number): RobotId) -> bool {
+    player.team_color == team_color && player.jersey_number == jersey_number
+}
@@ -0,0 +1,123 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    coordinate_frame::CoordinateFrame,
+    joint_control::JointCommand,
+    robot_spawn::{set_joint_positions, RobotSpawn},
+    shortcuts::{dispatch_shortcuts, triggered, ShortcutAction, ShortcutsPlugin},
+    Ball, NaoJoint, RobotRoot,
+};
+
+/// Depth below the field surface at which a ball is considered to have tunneled through it in
+/// meters
+const FALLEN_BALL_DEPTH: f32 = 1.0;
+
+/// Returns robots and balls to where they were spawned on [`ResetScene`], at rest and with the
+/// joints in the spawn preset, so experiments can be repeated without restarting.
+///
+/// Balls that end up below the field despite continuous collision detection are respawned on
+/// their own.
+pub struct SceneResetPlugin;
+
+impl Plugin for SceneResetPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ResetScene>()
+            .add_system(record_spawn_poses)
+            .add_system(reset_scene.after(record_spawn_poses))
+            .add_system(respawn_fallen_balls.after(record_spawn_poses));
+        if app.is_plugin_added::<ShortcutsPlugin>() {
+            app.add_system(
+                send_reset_on_shortcut
+                    .after(dispatch_shortcuts)
+                    .before(reset_scene),
+            );
+        }
+    }
+}
+
+/// Moves robots and balls back to their [`SpawnPose`].
+pub struct ResetScene;
+
+/// Pose of a top-level body when it was spawned.
+#[derive(Component)]
+pub struct SpawnPose(pub Transform);
+
+fn record_spawn_poses(
+    mut commands: Commands,
+    bodies: Query<
+        (Entity, &Transform),
+        (
+            Or<(Added<RobotRoot>, Added<RigidBody>)>,
+            Without<Parent>,
+            Without<SpawnPose>,
+        ),
+    >,
+) {
+    for (entity, transform) in bodies.iter() {
+        commands.entity(entity).insert(SpawnPose(*transform));
+    }
+}
+
+fn send_reset_on_shortcut(
+    mut actions: EventReader<ShortcutAction>,
+    mut resets: EventWriter<ResetScene>,
+) {
+    if triggered(&mut actions, ShortcutAction::ResetScene) {
+        resets.send(ResetScene);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn reset_scene(
+    mut commands: Commands,
+    mut resets: EventReader<ResetScene>,
+    spawn: Res<RobotSpawn>,
+    mut roots: Query<(Entity, &SpawnPose, &mut Transform), Or<(With<RobotRoot>, With<Ball>)>>,
+    robots: Query<(), With<RobotRoot>>,
+    children: Query<&Children>,
+    bodies: Query<(), With<RigidBody>>,
+    mut joints: Query<(&NaoJoint, &mut Transform, Option<&mut JointCommand>), Without<SpawnPose>>,
+) {
+    if resets.iter().count() == 0 {
+        return;
+    }
+    for (root, SpawnPose(pose), mut transform) in roots.iter_mut() {
+        *transform = *pose;
+        for entity in std::iter::once(root).chain(children.iter_descendants(root)) {
+            if bodies.contains(entity) {
+                commands.entity(entity).insert(Velocity::zero());
+            }
+        }
+        if !robots.contains(root) {
+            continue;
+        }
+        // joints missing in the preset rest at zero
+        for link in children.iter_descendants(root) {
+            if let Ok((joint, mut transform, command)) = joints.get_mut(link) {
+                transform.rotation = joint.rotation(0.0);
+                if let Some(mut command) = command {
+                    command.position = 0.0;
+                }
+            }
+        }
+        if let Some(preset) = spawn.joint_preset() {
+            set_joint_positions(root, preset.positions, &children, &mut joints);
+        }
+    }
+}
+
+fn respawn_fallen_balls(
+    mut commands: Commands,
+    frame: Res<CoordinateFrame>,
+    mut balls: Query<(Entity, &SpawnPose, &mut Transform), With<Ball>>,
+) {
+    for (ball, SpawnPose(pose), mut transform) in balls.iter_mut() {
+        if frame.height(transform.translation) > -FALLEN_BALL_DEPTH {
+            continue;
+        }
+        warn!("Ball fell through the field, respawning it");
+        *transform = *pose;
+        commands.entity(ball).insert(Velocity::zero());
+    }
+}
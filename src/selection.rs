@@ -0,0 +1,63 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{shortcuts::ShortcutAction, RobotRoot};
+
+/// Tracks the entity the user is currently working with.
+pub struct SelectionPlugin;
+
+impl Plugin for SelectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Selection>()
+            .add_system(clear_despawned_selection)
+            .add_system(selection_ui.after(clear_despawned_selection));
+    }
+}
+
+#[derive(Default, Resource)]
+pub struct Selection {
+    pub entity: Option<Entity>,
+}
+
+/// Entity to select when `entity` is clicked: links select the robot they belong to.
+pub fn selectable_entity(
+    entity: Entity,
+    parents: &Query<&Parent>,
+    robots: &Query<(), With<RobotRoot>>,
+) -> Entity {
+    std::iter::once(entity)
+        .chain(parents.iter_ancestors(entity))
+        .find(|&candidate| robots.contains(candidate))
+        .unwrap_or(entity)
+}
+
+fn clear_despawned_selection(mut selection: ResMut<Selection>, entities: Query<Entity>) {
+    if let Some(entity) = selection.entity {
+        if !entities.contains(entity) {
+            selection.entity = None;
+        }
+    }
+}
+
+fn selection_ui(
+    mut contexts: EguiContexts,
+    selection: Res<Selection>,
+    names: Query<&Name>,
+    mut actions: EventWriter<ShortcutAction>,
+) {
+    let Some(entity) = selection.entity else {
+        return;
+    };
+    egui::Window::new("Selection")
+        .anchor(egui::Align2::LEFT_BOTTOM, [10.0, -10.0])
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            match names.get(entity) {
+                Ok(name) => ui.label(name.as_str()),
+                Err(_) => ui.label(format!("{entity:?}")),
+            };
+            if ui.button("Copy pose").clicked() {
+                actions.send(ShortcutAction::CopySelectedPose);
+            }
+        });
+}
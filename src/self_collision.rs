@@ -0,0 +1,189 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_rapier3d::{parry::query::intersection_test, prelude::*};
+
+use crate::{
+    arenas::{arena_of, Arena},
+    joint_control::JointCommand,
+    simulation_time::PhysicsSchedule,
+    RobotLink, RobotRoot,
+};
+
+/// Lets the links of a robot collide with each other according to the [`SelfCollisionMatrix`].
+pub struct SelfCollisionPlugin;
+
+impl Plugin for SelfCollisionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SelfCollisionMatrix>()
+            .add_system(add_self_collision_links)
+            .add_system(
+                find_overlapping_links
+                    .after(PhysicsSet::SyncBackendFlush)
+                    .before(PhysicsSet::StepSimulation)
+                    .in_schedule(PhysicsSchedule),
+            );
+    }
+}
+
+/// Which links of the same robot collide.
+///
+/// Links rigidly attached by fixed joints form one body and never collide with each other.
+#[derive(Clone, Debug, Resource)]
+pub struct SelfCollisionMatrix {
+    /// Whether links of the same robot collide at all
+    pub enabled: bool,
+    /// Whether bodies connected by a movable joint collide
+    pub adjacent: bool,
+    /// Whether links already overlapping when their robot spawns collide, the coarse collision
+    /// shapes of some robots intersect in their resting poses
+    pub overlapping: bool,
+    /// Further pairs of link names that never collide
+    pub disabled_pairs: Vec<[String; 2]>,
+}
+
+impl Default for SelfCollisionMatrix {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            adjacent: false,
+            overlapping: false,
+            disabled_pairs: Vec::new(),
+        }
+    }
+}
+
+impl SelfCollisionMatrix {
+    fn collide(
+        &self,
+        (name1, link1): (&str, &SelfCollisionLink),
+        (name2, link2): (&str, &SelfCollisionLink),
+    ) -> bool {
+        if link1.robot != link2.robot {
+            return true;
+        }
+        if !self.enabled || link1.body == link2.body {
+            return false;
+        }
+        let adjacent =
+            link1.parent_body == Some(link2.body) || link2.parent_body == Some(link1.body);
+        if adjacent && !self.adjacent {
+            return false;
+        }
+        !self.disabled_pairs.iter().any(|[first, second]| {
+            (first == name1 && second == name2) || (first == name2 && second == name1)
+        })
+    }
+}
+
+/// Where a link sits in the kinematic tree of its robot.
+#[derive(Clone, Component, Debug)]
+pub struct SelfCollisionLink {
+    robot: Entity,
+    /// Link the joint moving this link is attached to, the root for links not moved by any joint
+    body: Entity,
+    /// Body the moving joint of `body` is attached to
+    parent_body: Option<Entity>,
+    /// Links of the same robot whose colliders overlapped this one before its first step
+    overlapping: Vec<Entity>,
+}
+
+/// Contact filter deciding whether two links of the same robot collide, bodies of different
+/// arenas never do.
+#[derive(SystemParam)]
+pub struct SelfCollisionFilter<'w, 's> {
+    matrix: Res<'w, SelfCollisionMatrix>,
+    links: Query<'w, 's, (&'static RobotLink, &'static SelfCollisionLink)>,
+    parents: Query<'w, 's, &'static Parent>,
+    arenas: Query<'w, 's, &'static Arena>,
+}
+
+impl BevyPhysicsHooks for SelfCollisionFilter<'_, '_> {
+    fn filter_contact_pair(&self, context: PairFilterContextView) -> Option<SolverFlags> {
+        if arena_of(context.collider1(), &self.parents, &self.arenas)
+            != arena_of(context.collider2(), &self.parents, &self.arenas)
+        {
+            return None;
+        }
+        let (Ok((name1, link1)), Ok((name2, link2))) = (
+            self.links.get(context.collider1()),
+            self.links.get(context.collider2()),
+        ) else {
+            return Some(SolverFlags::COMPUTE_IMPULSES);
+        };
+        if !self.matrix.overlapping && link1.overlapping.contains(&context.collider2()) {
+            return None;
+        }
+        self.matrix
+            .collide((&name1.name, link1), (&name2.name, link2))
+            .then_some(SolverFlags::COMPUTE_IMPULSES)
+    }
+}
+
+fn add_self_collision_links(
+    mut commands: Commands,
+    robots: Query<Entity, Added<RobotRoot>>,
+    children: Query<&Children>,
+    parents: Query<&Parent, With<RobotLink>>,
+    links: Query<(Option<&JointCommand>, Option<&Collider>), With<RobotLink>>,
+) {
+    let body = |link: Entity| {
+        let mut link = link;
+        while let (Ok((None, _)), Ok(parent)) = (links.get(link), parents.get(link)) {
+            link = parent.get();
+        }
+        link
+    };
+    for robot in robots.iter() {
+        for link in std::iter::once(robot).chain(children.iter_descendants(robot)) {
+            let Ok((_, Some(_))) = links.get(link) else {
+                continue;
+            };
+            let link_body = body(link);
+            let parent_body = parents.get(link_body).ok().map(|parent| body(parent.get()));
+            commands.entity(link).insert((
+                SelfCollisionLink {
+                    robot,
+                    body: link_body,
+                    parent_body,
+                    overlapping: Vec::new(),
+                },
+                ActiveHooks::FILTER_CONTACT_PAIRS,
+            ));
+        }
+    }
+}
+
+/// Records which links of new robots overlap in the pose they spawned in, rapier would otherwise
+/// push them apart with the whole penetration in the first step.
+fn find_overlapping_links(
+    context: Res<RapierContext>,
+    mut links: Query<(Entity, &RapierColliderHandle, &mut SelfCollisionLink)>,
+) {
+    let colliders: Vec<_> = links
+        .iter_mut()
+        .filter(|(_, _, link)| link.is_added())
+        .filter_map(|(entity, handle, link)| {
+            Some((entity, link.robot, context.colliders.get(handle.0)?))
+        })
+        .collect();
+    let mut overlaps = Vec::new();
+    for (index, (entity1, robot1, collider1)) in colliders.iter().enumerate() {
+        for (entity2, robot2, collider2) in &colliders[index + 1..] {
+            let overlapping = robot1 == robot2
+                && intersection_test(
+                    collider1.position(),
+                    collider1.shape(),
+                    collider2.position(),
+                    collider2.shape(),
+                )
+                .unwrap_or(false);
+            if overlapping {
+                overlaps.extend([(*entity1, *entity2), (*entity2, *entity1)]);
+            }
+        }
+    }
+    for (entity, other) in overlaps {
+        if let Ok((_, _, mut link)) = links.get_mut(entity) {
+            link.overlapping.push(other);
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_egui::EguiContexts;
+use bevy_rapier3d::prelude::*;
+
+/// Translates key presses into [`ShortcutAction`] events according to the [`Shortcuts`] map.
+///
+/// Modules react to the actions instead of checking keys themselves, so all bindings can be
+/// changed in one place.
+pub struct ShortcutsPlugin;
+
+impl Plugin for ShortcutsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Shortcuts>()
+            .add_event::<ShortcutAction>()
+            .add_system(dispatch_shortcuts)
+            .add_system(toggle_gizmos.after(dispatch_shortcuts));
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ShortcutAction {
+    TogglePause,
+    /// Pauses the simulation and advances it by a single frame
+    StepSimulation,
+    SlowDown,
+    SpeedUp,
+    /// Returns robots and balls to their spawn poses
+    ResetScene,
+    /// Toggles the physics debug rendering
+    ToggleGizmos,
+    CameraPreset(usize),
+    /// Starts or stops following the ball with the camera
+    FollowBall,
+    /// Follows a robot with this jersey number with the camera, cycling through the teams
+    FollowRobot(u8),
+    /// Zooms the camera to fit the selected entity, or the whole field without selection
+    FrameSelection,
+    /// Moves the camera over to the next arena
+    NextArena,
+    PenalizeSelected,
+    CopySelectedPose,
+    ToggleCollisionGroupColors,
+    ToggleFieldGrid,
+    ToggleBallHeatmap,
+    /// Shows or hides frame rate, physics timings and scene size
+    ToggleDiagnosticsOverlay,
+    ExportBallHeatmap,
+    ToggleCameraFrustums,
+    ToggleRobotLabels,
+    /// Shows or hides the arrows of the contact forces between robots and the field
+    ToggleContactForces,
+    /// Shows or hides centers of mass, inertia axes and support polygons
+    ToggleMassGizmos,
+    /// Shows the axes and ranges of all joints, not only of those enabled in the inspector
+    ToggleJointGizmos,
+    SkipReplay,
+    QuickSave,
+    QuickLoad,
+    /// Kicks the ball toward the goal in positive x direction
+    KickBallAtGoal,
+    /// Shows or hides the docked inspector
+    ToggleInspector,
+    TogglePlots,
+    /// Shows or hides the window playing keyframe motions on the selected robot
+    ToggleMotionPlayer,
+    /// Switches the motors of all robots off or on again, unpowered robots collapse
+    ToggleMotorPower,
+    /// Shows or hides the window tuning solver parameters and contact materials
+    TogglePhysicsTuning,
+    /// Kicks the ball in front of the selected robot
+    TeleopKick,
+    /// Tips the selected robot over
+    TeleopFall,
+    /// Saves the view of the main camera as PNG
+    Screenshot,
+    /// Writes the posed scene as glTF
+    ExportScene,
+    /// Starts or stops recording a video of the main camera
+    ToggleVideoRecording,
+}
+
+#[derive(Resource)]
+pub struct Shortcuts {
+    pub bindings: HashMap<KeyCode, ShortcutAction>,
+}
+
+impl Default for Shortcuts {
+    fn default() -> Self {
+        let camera_presets = [
+            KeyCode::Key1,
+            KeyCode::Key2,
+            KeyCode::Key3,
+            KeyCode::Key4,
+            KeyCode::Key5,
+            KeyCode::Key6,
+            KeyCode::Key7,
+            KeyCode::Key8,
+            KeyCode::Key9,
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(index, key)| (key, ShortcutAction::CameraPreset(index)));
+        let follow_robots = [
+            KeyCode::Numpad1,
+            KeyCode::Numpad2,
+            KeyCode::Numpad3,
+            KeyCode::Numpad4,
+            KeyCode::Numpad5,
+            KeyCode::Numpad6,
+            KeyCode::Numpad7,
+            KeyCode::Numpad8,
+            KeyCode::Numpad9,
+        ]
+        .into_iter()
+        .zip(1..)
+        .map(|(key, jersey_number)| (key, ShortcutAction::FollowRobot(jersey_number)));
+        let bindings = [
+            (KeyCode::Space, ShortcutAction::TogglePause),
+            (KeyCode::Period, ShortcutAction::StepSimulation),
+            (KeyCode::LBracket, ShortcutAction::SlowDown),
+            (KeyCode::RBracket, ShortcutAction::SpeedUp),
+            (KeyCode::R, ShortcutAction::ResetScene),
+            (KeyCode::G, ShortcutAction::ToggleGizmos),
+            (KeyCode::P, ShortcutAction::PenalizeSelected),
+            (KeyCode::C, ShortcutAction::CopySelectedPose),
+            (KeyCode::F1, ShortcutAction::ToggleCollisionGroupColors),
+            (KeyCode::F2, ShortcutAction::ToggleFieldGrid),
+            (KeyCode::F3, ShortcutAction::ToggleDiagnosticsOverlay),
+            (KeyCode::H, ShortcutAction::ToggleBallHeatmap),
+            (KeyCode::F4, ShortcutAction::ExportBallHeatmap),
+            (KeyCode::F5, ShortcutAction::ToggleCameraFrustums),
+            (KeyCode::F6, ShortcutAction::ToggleRobotLabels),
+            (KeyCode::N, ShortcutAction::ToggleContactForces),
+            (KeyCode::M, ShortcutAction::ToggleMassGizmos),
+            (KeyCode::J, ShortcutAction::ToggleJointGizmos),
+            (KeyCode::L, ShortcutAction::ToggleMotionPlayer),
+            (KeyCode::O, ShortcutAction::ToggleMotorPower),
+            (KeyCode::T, ShortcutAction::TogglePhysicsTuning),
+            (KeyCode::K, ShortcutAction::KickBallAtGoal),
+            (KeyCode::F7, ShortcutAction::QuickSave),
+            (KeyCode::F8, ShortcutAction::QuickLoad),
+            (KeyCode::F9, ShortcutAction::ToggleInspector),
+            (KeyCode::F10, ShortcutAction::TogglePlots),
+            (KeyCode::X, ShortcutAction::TeleopKick),
+            (KeyCode::V, ShortcutAction::TeleopFall),
+            (KeyCode::F, ShortcutAction::FrameSelection),
+            (KeyCode::Tab, ShortcutAction::NextArena),
+            (KeyCode::F11, ShortcutAction::ToggleVideoRecording),
+            (KeyCode::F12, ShortcutAction::Screenshot),
+            (KeyCode::B, ShortcutAction::ExportScene),
+            (KeyCode::Escape, ShortcutAction::SkipReplay),
+            (KeyCode::Key0, ShortcutAction::FollowBall),
+        ]
+        .into_iter()
+        .chain(camera_presets)
+        .chain(follow_robots)
+        .collect();
+        Self { bindings }
+    }
+}
+
+pub fn dispatch_shortcuts(
+    mut contexts: EguiContexts,
+    keys: Res<Input<KeyCode>>,
+    shortcuts: Res<Shortcuts>,
+    mut actions: EventWriter<ShortcutAction>,
+) {
+    // typing into a text field must not trigger shortcuts
+    if contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+    for key in keys.get_just_pressed() {
+        if let Some(action) = shortcuts.bindings.get(key) {
+            actions.send(*action);
+        }
+    }
+}
+
+/// Whether `action` was triggered since the last call, consumes all pending actions.
+pub fn triggered(actions: &mut EventReader<ShortcutAction>, action: ShortcutAction) -> bool {
+    actions.iter().fold(false, |triggered, candidate| {
+        triggered || *candidate == action
+    })
+}
+
+fn toggle_gizmos(
+    mut actions: EventReader<ShortcutAction>,
+    mut debug_render: ResMut<DebugRenderContext>,
+) {
+    if triggered(&mut actions, ShortcutAction::ToggleGizmos) {
+        debug_render.enabled = !debug_render.enabled;
+    }
+}
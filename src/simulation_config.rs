@@ -0,0 +1,333 @@
+use std::{
+    collections::HashMap,
+    fs,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use color_eyre::{eyre::WrapErr, Result};
+use serde::Deserialize;
+
+use crate::{
+    arguments::Arguments,
+    coordinate_frame::UpAxis,
+    field_dimensions::FieldDimensions,
+    game_controller::GameController,
+    imu::ImuNoise,
+    joint_encoders::JointEncoders,
+    lola::Lola,
+    mesh_colliders::ColliderFidelity,
+    pan_orbit_camera::{CameraSettings, InitialCameraPose},
+    physics_tuning::PhysicsTuning,
+    robot_spawn::RobotSpawn,
+    sonar::SonarNoise,
+    team_communication::TeamSockets,
+    websocket_server::WebSocketServer,
+};
+
+/// Configuration loaded without `--config` if it exists in the working directory
+const DEFAULT_CONFIG_PATH: &str = "simulation.toml";
+
+/// Everything a simulation run is set up with, loaded from a TOML file with command line
+/// arguments taking precedence.
+///
+/// Sections and fields may be left out, those keep their default. Relative paths are resolved
+/// against the working directory. The values are applied at startup, the resource only shows what
+/// the simulation was started with.
+#[derive(Clone, Debug, Default, Deserialize, Reflect, Resource)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct SimulationConfig {
+    pub assets: AssetsConfig,
+    pub world: WorldConfig,
+    pub field: FieldConfig,
+    pub physics: PhysicsTuning,
+    pub robots: RobotsConfig,
+    pub sensors: SensorsConfig,
+    pub network: NetworkConfig,
+    pub camera: CameraConfig,
+}
+
+#[derive(Clone, Debug, Deserialize, Reflect)]
+#[serde(default)]
+pub struct AssetsConfig {
+    /// Directory meshes and textures are loaded from
+    pub directory: PathBuf,
+    /// Directories of ROS packages referenced by `package://` URIs in URDFs by name
+    #[reflect(ignore)]
+    pub packages: HashMap<String, PathBuf>,
+}
+
+impl Default for AssetsConfig {
+    fn default() -> Self {
+        Self {
+            directory: PathBuf::from("assets"),
+            packages: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Reflect)]
+#[serde(default)]
+pub struct WorldConfig {
+    pub up_axis: UpAxis,
+    /// Independent matches simulated side by side
+    pub arenas: usize,
+    /// Simulated seconds per real second
+    pub timescale: f32,
+    /// Seed of all randomized systems
+    pub seed: u64,
+}
+
+impl Default for WorldConfig {
+    fn default() -> Self {
+        Self {
+            up_axis: UpAxis::default(),
+            arenas: 1,
+            timescale: 1.0,
+            seed: 0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Reflect)]
+#[serde(default)]
+pub struct FieldConfig {
+    /// JSON file replacing the dimensions below, reloaded when it changes
+    pub file: Option<PathBuf>,
+    #[serde(flatten)]
+    pub dimensions: FieldDimensions,
+}
+
+#[derive(Clone, Debug, Deserialize, Reflect)]
+#[serde(default)]
+pub struct RobotsConfig {
+    /// Robots to spawn per team and where, in JSON
+    pub teams: PathBuf,
+    /// Spawn only the first robots of each team
+    pub per_team: Option<usize>,
+    /// URDF or xacro of robots that do not name one in the team configuration
+    pub default_urdf: PathBuf,
+    /// URDFs or xacros of robots to spawn next to the field outside the teams
+    pub additional: Vec<PathBuf>,
+    pub collider_fidelity: ColliderFidelity,
+    /// Joint preset robots are spawned in
+    pub spawn_preset: String,
+    /// Offset of the root link from its resting position in the preset in meters
+    pub spawn_offset: [f32; 3],
+}
+
+impl Default for RobotsConfig {
+    fn default() -> Self {
+        let spawn = RobotSpawn::default();
+        Self {
+            teams: PathBuf::from("assets/teams.json"),
+            per_team: None,
+            default_urdf: PathBuf::from("assets/NAO.urdf"),
+            additional: Vec::new(),
+            collider_fidelity: ColliderFidelity::default(),
+            spawn_preset: spawn.preset,
+            spawn_offset: spawn.position.to_array(),
+        }
+    }
+}
+
+/// Standard deviations of the sensor noise.
+#[derive(Clone, Debug, Deserialize, Reflect)]
+#[serde(default)]
+pub struct SensorsConfig {
+    /// In radians
+    pub joint_encoder_noise: f32,
+    /// Smallest angle step the encoders resolve in radians, zero disables quantization
+    pub joint_encoder_resolution: f32,
+    /// Physics steps between sampling a joint position and reporting it
+    pub joint_encoder_delay_steps: usize,
+    /// In m/s²
+    pub accelerometer_noise: f32,
+    /// In rad/s
+    pub gyroscope_noise: f32,
+    /// In meters
+    pub sonar_noise: f32,
+}
+
+impl Default for SensorsConfig {
+    fn default() -> Self {
+        let encoders = JointEncoders::default();
+        let imu = ImuNoise::default();
+        Self {
+            joint_encoder_noise: encoders.noise,
+            joint_encoder_resolution: encoders.resolution,
+            joint_encoder_delay_steps: encoders.delay_steps,
+            accelerometer_noise: imu.accelerometer,
+            gyroscope_noise: imu.gyroscope,
+            sonar_noise: SonarNoise::default().0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Reflect)]
+#[serde(default)]
+pub struct NetworkConfig {
+    pub game_controller_data_port: u16,
+    pub game_controller_return_port: u16,
+    /// Team communication of team `n` uses port `team_port_base + n`
+    pub team_port_base: u16,
+    pub websocket_port: u16,
+    /// Unix socket a robot controller connects to over LoLA
+    pub lola_socket: PathBuf,
+    /// Address ground truth robot and ball poses are published to, e.g. 127.0.0.1:10700
+    #[reflect(ignore)]
+    pub ground_truth: Option<SocketAddr>,
+    /// Ground truth messages per simulated second
+    pub ground_truth_rate: f32,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        let game_controller = GameController::default();
+        Self {
+            game_controller_data_port: game_controller.data_port,
+            game_controller_return_port: game_controller.return_port,
+            team_port_base: TeamSockets::default().port_base,
+            websocket_port: WebSocketServer::default().port,
+            lola_socket: Lola::default().socket_path,
+            ground_truth: None,
+            ground_truth_rate: 30.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Reflect)]
+#[serde(default)]
+pub struct CameraConfig {
+    /// Position of the camera at startup in field coordinates
+    pub eye: [f32; 3],
+    /// Point the camera looks at and orbits around at startup in field coordinates
+    pub focus: [f32; 3],
+    pub orbit_sensitivity: f32,
+    pub pan_sensitivity: f32,
+    pub zoom_sensitivity: f32,
+}
+
+impl Default for CameraConfig {
+    fn default() -> Self {
+        let pose = InitialCameraPose::default();
+        let settings = CameraSettings::default();
+        Self {
+            eye: pose.eye.to_array(),
+            focus: pose.focus.to_array(),
+            orbit_sensitivity: settings.orbit_sensitivity,
+            pan_sensitivity: settings.pan_sensitivity,
+            zoom_sensitivity: settings.zoom_sensitivity,
+        }
+    }
+}
+
+impl SimulationConfig {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .wrap_err_with(|| format!("failed to read configuration {}", path.display()))?;
+        toml::from_str(&content)
+            .wrap_err_with(|| format!("failed to parse configuration {}", path.display()))
+    }
+
+    /// Loads the configuration given in the arguments or [`DEFAULT_CONFIG_PATH`] if it exists,
+    /// and overrides it with the arguments given.
+    pub fn from_arguments(arguments: &Arguments) -> Result<Self> {
+        let mut config = match &arguments.config {
+            Some(path) => Self::load(path)?,
+            None if Path::new(DEFAULT_CONFIG_PATH).exists() => Self::load(DEFAULT_CONFIG_PATH)?,
+            None => Self::default(),
+        };
+        config.override_with(arguments);
+        Ok(config)
+    }
+
+    fn override_with(&mut self, arguments: &Arguments) {
+        fn set<T: Clone>(value: &mut T, argument: &Option<T>) {
+            if let Some(argument) = argument {
+                *value = argument.clone();
+            }
+        }
+        set(&mut self.assets.directory, &arguments.assets);
+        self.assets
+            .packages
+            .extend(arguments.packages.iter().cloned());
+        set(&mut self.world.up_axis, &arguments.up_axis);
+        set(&mut self.world.arenas, &arguments.arenas);
+        set(&mut self.world.timescale, &arguments.timescale);
+        set(&mut self.world.seed, &arguments.seed);
+        if arguments.field_dimensions.is_some() {
+            self.field.file = arguments.field_dimensions.clone();
+        }
+        set(&mut self.robots.teams, &arguments.teams);
+        if arguments.robots.is_some() {
+            self.robots.per_team = arguments.robots;
+        }
+        set(&mut self.robots.default_urdf, &arguments.urdf);
+        self.robots
+            .additional
+            .extend(arguments.additional_robots.iter().cloned());
+        set(
+            &mut self.robots.collider_fidelity,
+            &arguments.collider_fidelity,
+        );
+        if arguments.ground_truth.is_some() {
+            self.network.ground_truth = arguments.ground_truth;
+        }
+        set(
+            &mut self.network.ground_truth_rate,
+            &arguments.ground_truth_rate,
+        );
+    }
+
+    /// Inserts the resources of physics, robot spawning, sensors, network interfaces and camera
+    /// configured here, and the configuration itself.
+    pub fn insert_resources(&self, app: &mut App) {
+        let mut game_controller = GameController::default();
+        game_controller.data_port = self.network.game_controller_data_port;
+        game_controller.return_port = self.network.game_controller_return_port;
+        let mut team_sockets = TeamSockets::default();
+        team_sockets.port_base = self.network.team_port_base;
+        let mut websocket_server = WebSocketServer::default();
+        websocket_server.port = self.network.websocket_port;
+        app.insert_resource(self.physics)
+            .insert_resource(self.robots.collider_fidelity)
+            .insert_resource(RobotSpawn {
+                position: Vec3::from_array(self.robots.spawn_offset),
+                preset: self.robots.spawn_preset.clone(),
+                ..Default::default()
+            })
+            .insert_resource(JointEncoders {
+                noise: self.sensors.joint_encoder_noise,
+                resolution: self.sensors.joint_encoder_resolution,
+                delay_steps: self.sensors.joint_encoder_delay_steps,
+            })
+            .insert_resource(ImuNoise {
+                accelerometer: self.sensors.accelerometer_noise,
+                gyroscope: self.sensors.gyroscope_noise,
+            })
+            .insert_resource(SonarNoise(self.sensors.sonar_noise))
+            .insert_resource(game_controller)
+            .insert_resource(team_sockets)
+            .insert_resource(websocket_server)
+            .insert_resource(Lola {
+                socket_path: self.network.lola_socket.clone(),
+                ..Default::default()
+            })
+            .insert_resource(InitialCameraPose {
+                eye: Vec3::from_array(self.camera.eye),
+                focus: Vec3::from_array(self.camera.focus),
+            })
+            .insert_resource(CameraSettings {
+                orbit_sensitivity: self.camera.orbit_sensitivity,
+                pan_sensitivity: self.camera.pan_sensitivity,
+                zoom_sensitivity: self.camera.zoom_sensitivity,
+                ..Default::default()
+            })
+            .insert_resource(self.clone())
+            .register_type::<SimulationConfig>();
+    }
+}
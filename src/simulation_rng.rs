@@ -0,0 +1,46 @@
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rand_distr::Normal;
+
+/// Source of randomness for all randomized systems, seeded so runs can be reproduced.
+///
+/// Systems draw from this resource instead of thread-local generators, which makes the random
+/// sequence depend only on the seed and the order systems run in.
+#[derive(Resource)]
+pub struct SimulationRng {
+    pub seed: u64,
+    pub rng: ChaCha8Rng,
+}
+
+impl SimulationRng {
+    pub fn from_seed(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+
+    /// Zero mean Gaussian noise, no noise for a non-positive standard deviation
+    pub fn gaussian(&mut self, standard_deviation: f32) -> f32 {
+        if standard_deviation <= 0.0 {
+            return 0.0;
+        }
+        Normal::new(0.0, standard_deviation).map_or(0.0, |normal| self.rng.sample(normal))
+    }
+
+    /// Independent [`Self::gaussian`] noise on each axis
+    pub fn gaussian_vector(&mut self, standard_deviation: f32) -> Vec3 {
+        Vec3::new(
+            self.gaussian(standard_deviation),
+            self.gaussian(standard_deviation),
+            self.gaussian(standard_deviation),
+        )
+    }
+}
+
+impl Default for SimulationRng {
+    fn default() -> Self {
+        Self::from_seed(0)
+    }
+}
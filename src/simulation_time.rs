@@ -0,0 +1,259 @@
+use std::time::{Duration, Instant};
+
+use bevy::{ecs::schedule::ScheduleLabel, prelude::*, transform::TransformSystem};
+use bevy_egui::{egui, EguiContexts, EguiPlugin};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    asset_loading::SimulationState,
+    scene_reset::ResetScene,
+    self_collision::SelfCollisionFilter,
+    shortcuts::{dispatch_shortcuts, ShortcutAction, ShortcutsPlugin},
+};
+
+/// Simulated seconds per physics step, independent of the frame rate
+pub const PHYSICS_TIMESTEP: f32 = 1.0 / 60.0;
+const MINIMUM_TIME_SCALE: f32 = 0.1;
+const MAXIMUM_TIME_SCALE: f32 = 10.0;
+/// Limits catching up after a slow frame so the simulation does not fall further behind
+const MAXIMUM_STEPS_PER_FRAME: u32 = 20;
+
+/// Advances the physics simulation in fixed steps of [`PHYSICS_TIMESTEP`] decoupled from the
+/// frame rate, so runs with the same inputs produce identical trajectories.
+///
+/// The simulation can be paused, single-stepped and sped up or slowed down.
+pub struct SimulationTimePlugin;
+
+impl Plugin for SimulationTimePlugin {
+    fn build(&self, app: &mut App) {
+        let mut schedule = Schedule::new();
+        schedule.configure_sets(
+            (
+                PhysicsSet::SyncBackend,
+                PhysicsSet::SyncBackendFlush,
+                PhysicsSet::StepSimulation,
+                PhysicsSet::Writeback,
+            )
+                .chain(),
+        );
+        for (stage, set) in PHYSICS_STAGES.into_iter().enumerate() {
+            schedule
+                .add_systems(
+                    RapierPhysicsPlugin::<SelfCollisionFilter>::get_systems(set.clone())
+                        .in_base_set(set.clone())
+                        .in_set(PhysicsStage(stage)),
+                )
+                .add_system(
+                    start_stage_timer
+                        .in_base_set(set.clone())
+                        .before(PhysicsStage(stage)),
+                )
+                .add_system(
+                    (move |timings: ResMut<PhysicsTimings>| stop_stage_timer(stage, timings))
+                        .in_base_set(set)
+                        .after(PhysicsStage(stage)),
+                );
+        }
+        app.add_schedule(PhysicsSchedule, schedule)
+            .init_resource::<SimulationTime>()
+            .init_resource::<PhysicsTimings>()
+            .add_system(
+                run_physics_schedule
+                    .in_base_set(CoreSet::PostUpdate)
+                    .before(TransformSystem::TransformPropagate)
+                    .run_if(in_state(SimulationState::Running)),
+            );
+        if app.is_plugin_added::<ShortcutsPlugin>() {
+            app.add_system(control_simulation_time.after(dispatch_shortcuts));
+        }
+        if app.is_plugin_added::<EguiPlugin>() {
+            app.add_system(simulation_time_ui);
+        }
+    }
+}
+
+/// Base sets of the Rapier systems in the order they run in a step
+const PHYSICS_STAGES: [PhysicsSet; 4] = [
+    PhysicsSet::SyncBackend,
+    PhysicsSet::SyncBackendFlush,
+    PhysicsSet::StepSimulation,
+    PhysicsSet::Writeback,
+];
+
+/// Rapier systems of the physics stage with this index in [`PHYSICS_STAGES`], for timing them.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, SystemSet)]
+struct PhysicsStage(usize);
+
+/// Schedule containing the Rapier systems, run once per physics step.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, ScheduleLabel)]
+pub struct PhysicsSchedule;
+
+#[derive(Resource)]
+pub struct SimulationTime {
+    pub paused: bool,
+    /// Simulated seconds per real second
+    pub time_scale: f32,
+    /// Simulates one step per frame regardless of real time, as fast as the machine allows
+    pub free_running: bool,
+    /// Steps to simulate while paused
+    pending_steps: u32,
+    /// Scaled real time not yet simulated
+    accumulator: f32,
+    elapsed_steps: u64,
+}
+
+impl Default for SimulationTime {
+    fn default() -> Self {
+        Self {
+            paused: false,
+            time_scale: 1.0,
+            free_running: false,
+            pending_steps: 0,
+            accumulator: 0.0,
+            elapsed_steps: 0,
+        }
+    }
+}
+
+impl SimulationTime {
+    /// Advances the paused simulation by a single step.
+    pub fn step(&mut self) {
+        self.pending_steps += 1;
+    }
+
+    pub fn elapsed_steps(&self) -> u64 {
+        self.elapsed_steps
+    }
+
+    /// Simulated time since startup in seconds
+    pub fn elapsed_seconds(&self) -> f64 {
+        self.elapsed_steps as f64 * f64::from(PHYSICS_TIMESTEP)
+    }
+
+    /// Number of physics steps to simulate in a frame that took `delta` seconds.
+    fn steps_for(&mut self, delta: f32) -> u32 {
+        if self.paused {
+            self.accumulator = 0.0;
+            return std::mem::take(&mut self.pending_steps);
+        }
+        if self.free_running {
+            return 1;
+        }
+        self.accumulator += delta * self.time_scale;
+        let steps = (self.accumulator / PHYSICS_TIMESTEP) as u32;
+        if steps > MAXIMUM_STEPS_PER_FRAME {
+            self.accumulator = 0.0;
+            return MAXIMUM_STEPS_PER_FRAME;
+        }
+        self.accumulator -= steps as f32 * PHYSICS_TIMESTEP;
+        steps
+    }
+}
+
+/// Wall clock durations of the physics steps simulated in the last frame.
+#[derive(Default, Resource)]
+pub struct PhysicsTimings {
+    pub steps: u32,
+    /// Duration of all steps of the frame
+    pub total: Duration,
+    /// Duration of each stage summed over the steps of the frame, named by [`Self::STAGE_NAMES`]
+    pub stages: [Duration; PHYSICS_STAGES.len()],
+    stage_start: Option<Instant>,
+}
+
+impl PhysicsTimings {
+    pub const STAGE_NAMES: [&'static str; PHYSICS_STAGES.len()] = [
+        "sync backend",
+        "sync backend flush",
+        "step simulation",
+        "writeback",
+    ];
+}
+
+fn start_stage_timer(mut timings: ResMut<PhysicsTimings>) {
+    timings.stage_start = Some(Instant::now());
+}
+
+fn stop_stage_timer(stage: usize, mut timings: ResMut<PhysicsTimings>) {
+    if let Some(start) = timings.stage_start.take() {
+        timings.stages[stage] += start.elapsed();
+    }
+}
+
+pub fn run_physics_schedule(world: &mut World) {
+    let delta = world.resource::<Time>().delta_seconds();
+    let steps = world.resource_mut::<SimulationTime>().steps_for(delta);
+    *world.resource_mut::<PhysicsTimings>() = PhysicsTimings {
+        steps,
+        ..Default::default()
+    };
+    let start = Instant::now();
+    for _ in 0..steps {
+        world.run_schedule(PhysicsSchedule);
+        world.resource_mut::<SimulationTime>().elapsed_steps += 1;
+    }
+    world.resource_mut::<PhysicsTimings>().total = start.elapsed();
+}
+
+fn control_simulation_time(
+    mut actions: EventReader<ShortcutAction>,
+    mut simulation_time: ResMut<SimulationTime>,
+) {
+    for action in actions.iter() {
+        match action {
+            ShortcutAction::TogglePause => simulation_time.paused = !simulation_time.paused,
+            ShortcutAction::StepSimulation => {
+                simulation_time.paused = true;
+                simulation_time.step();
+            }
+            ShortcutAction::SlowDown => {
+                simulation_time.time_scale =
+                    (simulation_time.time_scale / 2.0).max(MINIMUM_TIME_SCALE);
+            }
+            ShortcutAction::SpeedUp => {
+                simulation_time.time_scale =
+                    (simulation_time.time_scale * 2.0).min(MAXIMUM_TIME_SCALE);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn simulation_time_ui(
+    mut contexts: EguiContexts,
+    mut simulation_time: ResMut<SimulationTime>,
+    mut resets: EventWriter<ResetScene>,
+) {
+    egui::Window::new("Simulation time")
+        .anchor(egui::Align2::CENTER_TOP, [0.0, 10.0])
+        .resizable(false)
+        .title_bar(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                let label = if simulation_time.paused {
+                    "resume"
+                } else {
+                    "pause"
+                };
+                if ui.button(label).clicked() {
+                    simulation_time.paused = !simulation_time.paused;
+                }
+                if ui.button("step").clicked() {
+                    simulation_time.paused = true;
+                    simulation_time.step();
+                }
+                if ui.button("reset").clicked() {
+                    resets.send(ResetScene);
+                }
+                ui.add(
+                    egui::Slider::new(
+                        &mut simulation_time.time_scale,
+                        MINIMUM_TIME_SCALE..=MAXIMUM_TIME_SCALE,
+                    )
+                    .logarithmic(true)
+                    .text("x"),
+                );
+                ui.label(format!("{:.2} s", simulation_time.elapsed_seconds()));
+            });
+        });
+}
@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+
+use bevy::{ecs::system::SystemState, prelude::*};
+use bevy_rapier3d::prelude::*;
+use color_eyre::Result;
+
+use crate::{
+    add_headless_plugins, add_simulation,
+    arenas::Arena,
+    asset_loading::SimulationState,
+    coordinate_frame::CoordinateFrame,
+    field_dimensions::FieldDimensions,
+    joint_control::JointCommand,
+    player::{Player, RobotStatus, TeamColor},
+    robot_controller::{apply_joint_targets, JointTarget, SensorSnapshot, Sensors},
+    scene_reset::ResetScene,
+    simulation_config::SimulationConfig,
+    simulation_time::SimulationTime,
+    Ball, NaoJoint, RobotRoot,
+};
+
+/// Joint targets by robot and joint name, joints left out keep their last target.
+pub type Actions = HashMap<RobotId, HashMap<String, JointTarget>>;
+
+/// Identifies a robot by the arena it plays in and its player number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RobotId {
+    pub arena: usize,
+    pub team_color: TeamColor,
+    pub jersey_number: u8,
+}
+
+/// State of the simulation after a step.
+#[derive(Clone, Debug)]
+pub struct Observations {
+    /// Simulation time in seconds
+    pub time: f64,
+    pub robots: HashMap<RobotId, RobotObservation>,
+    /// Balls by arena
+    pub balls: HashMap<usize, BallObservation>,
+}
+
+#[derive(Clone, Debug)]
+pub struct RobotObservation {
+    /// Pose of the root link in the field coordinates of its arena
+    pub pose: Transform,
+    pub penalized: bool,
+    pub fallen: bool,
+    pub sensors: SensorSnapshot,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct BallObservation {
+    /// Position in the field coordinates of its arena
+    pub position: Vec3,
+    /// Linear velocity in field coordinates in m/s
+    pub velocity: Vec3,
+}
+
+/// Headless simulation advanced explicitly, one physics step per [`SimulatorHandle::step`], e.g.
+/// as environment for reinforcement learning.
+///
+/// Time only passes while stepping, every step advances the simulation by exactly
+/// [`PHYSICS_TIMESTEP`](crate::simulation_time::PHYSICS_TIMESTEP). Robots naming a controller in
+/// the team configuration keep being driven by it, overriding the actions for their joints.
+/// Network interfaces are opened as configured, handles running side by side in one process need
+/// distinct ports. Several robots and balls are best simulated in one handle with
+/// [`WorldConfig::arenas`](crate::simulation_config::WorldConfig::arenas).
+pub struct SimulatorHandle {
+    app: App,
+}
+
+impl SimulatorHandle {
+    /// Sets up the simulation and waits until its assets are loaded.
+    pub fn new(config: &SimulationConfig) -> Result<Self> {
+        let mut app = App::new();
+        add_headless_plugins(
+            &mut app,
+            AssetPlugin {
+                asset_folder: config.assets.directory.to_string_lossy().into_owned(),
+                watch_for_changes: false,
+            },
+        );
+        add_simulation(&mut app, config)?;
+        let mut simulation_time = SimulationTime::default();
+        simulation_time.paused = true;
+        app.insert_resource(simulation_time);
+        app.setup();
+        while app.world.resource::<State<SimulationState>>().0 != SimulationState::Running {
+            app.update();
+        }
+        Ok(Self { app })
+    }
+
+    /// Commands the joints as given in `actions` and advances the simulation by one physics step.
+    pub fn step(&mut self, actions: &Actions) -> Observations {
+        let mut state: SystemState<(
+            Query<(Entity, &Player, Option<&Arena>), With<RobotRoot>>,
+            Query<&Children>,
+            Query<(&NaoJoint, &mut JointCommand)>,
+        )> = SystemState::new(&mut self.app.world);
+        let (robots, children, mut commands) = state.get_mut(&mut self.app.world);
+        for (robot, player, arena) in robots.iter() {
+            if let Some(targets) = actions.get(&robot_id(player, arena)) {
+                apply_joint_targets(robot, targets, &children, &mut commands);
+            }
+        }
+        self.app.world.resource_mut::<SimulationTime>().step();
+        self.app.update();
+        self.observe()
+    }
+
+    /// Moves robots and balls back to where they were spawned, without advancing the physics.
+    pub fn reset(&mut self) -> Observations {
+        self.app.world.send_event(ResetScene);
+        self.app.update();
+        self.observe()
+    }
+
+    /// Current state of the simulation, without advancing it.
+    pub fn observe(&mut self) -> Observations {
+        let time = self
+            .app
+            .world
+            .resource::<SimulationTime>()
+            .elapsed_seconds();
+        let mut state: SystemState<(
+            Res<CoordinateFrame>,
+            Res<FieldDimensions>,
+            Sensors,
+            Query<(Entity, &Player, &RobotStatus, Option<&Arena>, &Transform), With<RobotRoot>>,
+            Query<(Option<&Arena>, &Transform, &Velocity), With<Ball>>,
+        )> = SystemState::new(&mut self.app.world);
+        let (frame, field_dimensions, sensors, robots, balls) = state.get_mut(&mut self.app.world);
+        let robots = robots
+            .iter()
+            .map(|(robot, player, status, arena, transform)| {
+                let mut pose = frame.transform_to_field(*transform);
+                pose.translation -= arena.copied().unwrap_or_default().offset(&field_dimensions);
+                let observation = RobotObservation {
+                    pose,
+                    penalized: status.penalized,
+                    fallen: status.fallen,
+                    sensors: sensors.snapshot(robot),
+                };
+                (robot_id(player, arena), observation)
+            })
+            .collect();
+        let balls = balls
+            .iter()
+            .map(|(arena, transform, velocity)| {
+                let arena = arena.copied().unwrap_or_default();
+                let observation = BallObservation {
+                    position: frame.to_field(transform.translation)
+                        - arena.offset(&field_dimensions),
+                    velocity: frame.vector_to_field(velocity.linvel),
+                };
+                (arena.0, observation)
+            })
+            .collect();
+        Observations {
+            time,
+            robots,
+            balls,
+        }
+    }
+
+    /// The simulated world, for everything not covered by the observations.
+    pub fn world(&mut self) -> &mut World {
+        &mut self.app.world
+    }
+}
+
+fn robot_id(player: &Player, arena: Option<&Arena>) -> RobotId {
+    RobotId {
+        arena: arena.copied().unwrap_or_default().0,
+        team_color: player.team_color,
+        jersey_number: player.jersey_number,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation_time::PHYSICS_TIMESTEP;
+
+    #[test]
+    fn step_advances_time_by_one_physics_step() {
+        let mut handle = SimulatorHandle::new(&SimulationConfig::default()).unwrap();
+        let start = handle.observe().time;
+        for step in 1..=3 {
+            let observations = handle.step(&Actions::new());
+            let expected = start + f64::from(step) * f64::from(PHYSICS_TIMESTEP);
+            assert!((observations.time - expected).abs() < 1e-9);
+            assert!(!observations.robots.is_empty());
+        }
+        let time = handle.observe().time;
+        assert_eq!(handle.reset().time, time);
+    }
+
+    #[test]
+    fn joint_targets_move_the_links() {
+        let mut handle = SimulatorHandle::new(&SimulationConfig::default()).unwrap();
+        let robot = *handle.observe().robots.keys().next().unwrap();
+        let target = JointTarget {
+            position: 0.5,
+            stiffness: 1.0,
+        };
+        let actions = Actions::from([(robot, HashMap::from([("HeadYaw".to_string(), target)]))]);
+        for _ in 0..100 {
+            handle.step(&actions);
+        }
+        let position = handle.observe().robots[&robot].sensors.joint_positions["HeadYaw"];
+        assert!(
+            (position - target.position).abs() < 0.05,
+            "HeadYaw at {position}"
+        );
+    }
+}
@@ -0,0 +1,244 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_rapier3d::prelude::*;
+use color_eyre::{eyre::WrapErr, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    arenas::Arena,
+    joint_control::JointCommand,
+    player::Player,
+    shortcuts::{ShortcutAction, ShortcutsPlugin},
+    RobotLink,
+};
+
+/// File written by quick-save and read by quick-load
+const QUICK_SAVE_PATH: &str = "snapshots/quicksave.json";
+
+/// Saves the state of all rigid bodies and joints to a file and restores it later.
+///
+/// Bodies are matched by name, robot links additionally by the team color and jersey number of
+/// their robot, so a snapshot can be restored as long as the same robots exist.
+pub struct SnapshotPlugin;
+
+impl Plugin for SnapshotPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SaveSnapshot>()
+            .add_event::<LoadSnapshot>()
+            .add_system(save_snapshots)
+            .add_system(load_snapshots);
+        if app.is_plugin_added::<ShortcutsPlugin>() {
+            app.add_system(
+                quick_save_and_load
+                    .before(save_snapshots)
+                    .before(load_snapshots),
+            );
+        }
+    }
+}
+
+/// Request to write the current simulation state to the file at the path.
+pub struct SaveSnapshot(pub PathBuf);
+
+/// Request to restore the simulation state from the file at the path.
+pub struct LoadSnapshot(pub PathBuf);
+
+#[derive(Deserialize, Serialize)]
+struct Snapshot {
+    bodies: Vec<BodySnapshot>,
+    joints: Vec<JointSnapshot>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct BodySnapshot {
+    key: String,
+    /// Local transform relative to the parent
+    translation: [f32; 3],
+    rotation: [f32; 4],
+    linear_velocity: Option<[f32; 3]>,
+    angular_velocity: Option<[f32; 3]>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct JointSnapshot {
+    key: String,
+    target: Option<f32>,
+    /// Impulses the solver applied in the last step, restoring them avoids a jolt after loading
+    impulses: [f32; 6],
+}
+
+/// Stable names for bodies that survive despawning and respawning entities.
+#[derive(SystemParam)]
+pub struct BodyKeys<'w, 's> {
+    parents: Query<'w, 's, &'static Parent>,
+    names: Query<'w, 's, &'static Name>,
+    links: Query<'w, 's, &'static RobotLink>,
+    players: Query<'w, 's, &'static Player>,
+    arenas: Query<'w, 's, &'static Arena>,
+}
+
+impl BodyKeys<'_, '_> {
+    pub fn key(&self, entity: Entity) -> Option<String> {
+        let (name, root) = match self.links.get(entity) {
+            Ok(link) => (link.name.clone(), link.robot),
+            Err(_) => (
+                self.names.get(entity).ok()?.to_string(),
+                self.parents.iter_ancestors(entity).last().unwrap_or(entity),
+            ),
+        };
+        Some(match self.players.get(root) {
+            // every arena spawns the same teams, robots are told apart by their arena
+            Ok(player) => self
+                .arenas
+                .get(root)
+                .copied()
+                .unwrap_or_default()
+                .name(&format!(
+                    "{:?} {}/{name}",
+                    player.team_color, player.jersey_number
+                )),
+            Err(_) => name,
+        })
+    }
+}
+
+fn quick_save_and_load(
+    mut actions: EventReader<ShortcutAction>,
+    mut saves: EventWriter<SaveSnapshot>,
+    mut loads: EventWriter<LoadSnapshot>,
+) {
+    for action in actions.iter() {
+        match action {
+            ShortcutAction::QuickSave => saves.send(SaveSnapshot(QUICK_SAVE_PATH.into())),
+            ShortcutAction::QuickLoad => loads.send(LoadSnapshot(QUICK_SAVE_PATH.into())),
+            _ => {}
+        }
+    }
+}
+
+fn save_snapshots(
+    mut requests: EventReader<SaveSnapshot>,
+    keys: BodyKeys,
+    context: Res<RapierContext>,
+    bodies: Query<(Entity, &Transform, Option<&Velocity>), With<RigidBody>>,
+    joints: Query<(Entity, Option<&JointCommand>), With<ImpulseJoint>>,
+) {
+    for SaveSnapshot(path) in requests.iter() {
+        let snapshot = Snapshot {
+            bodies: bodies
+                .iter()
+                .filter_map(|(entity, transform, velocity)| {
+                    Some(BodySnapshot {
+                        key: keys.key(entity)?,
+                        translation: transform.translation.to_array(),
+                        rotation: transform.rotation.to_array(),
+                        linear_velocity: velocity.map(|velocity| velocity.linvel.to_array()),
+                        angular_velocity: velocity.map(|velocity| velocity.angvel.to_array()),
+                    })
+                })
+                .collect(),
+            joints: joints
+                .iter()
+                .filter_map(|(entity, command)| {
+                    let impulses = context
+                        .entity2impulse_joint()
+                        .get(&entity)
+                        .and_then(|handle| context.impulse_joints.get(*handle))
+                        .map_or([0.0; 6], |joint| joint.impulses.into());
+                    Some(JointSnapshot {
+                        key: keys.key(entity)?,
+                        target: command.map(|command| command.position),
+                        impulses,
+                    })
+                })
+                .collect(),
+        };
+        match write_snapshot(path, &snapshot) {
+            Ok(()) => info!("Saved snapshot to {}", path.display()),
+            Err(error) => error!("{error:?}"),
+        }
+    }
+}
+
+fn write_snapshot(path: &Path, snapshot: &Snapshot) -> Result<()> {
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)
+            .wrap_err_with(|| format!("failed to create {}", directory.display()))?;
+    }
+    let content =
+        serde_json::to_string_pretty(snapshot).wrap_err("failed to serialize snapshot")?;
+    fs::write(path, content).wrap_err_with(|| format!("failed to write {}", path.display()))
+}
+
+fn read_snapshot(path: &Path) -> Result<Snapshot> {
+    let content =
+        fs::read_to_string(path).wrap_err_with(|| format!("failed to read {}", path.display()))?;
+    serde_json::from_str(&content).wrap_err_with(|| format!("failed to parse {}", path.display()))
+}
+
+fn load_snapshots(
+    mut commands: Commands,
+    mut requests: EventReader<LoadSnapshot>,
+    keys: BodyKeys,
+    mut context: ResMut<RapierContext>,
+    mut bodies: Query<(Entity, &mut Transform), With<RigidBody>>,
+    mut joints: Query<(Entity, Option<&mut JointCommand>), With<ImpulseJoint>>,
+) {
+    for LoadSnapshot(path) in requests.iter() {
+        let snapshot = match read_snapshot(path) {
+            Ok(snapshot) => snapshot,
+            Err(error) => {
+                error!("{error:?}");
+                continue;
+            }
+        };
+        let saved_bodies: HashMap<_, _> = snapshot
+            .bodies
+            .into_iter()
+            .map(|body| (body.key.clone(), body))
+            .collect();
+        let saved_joints: HashMap<_, _> = snapshot
+            .joints
+            .into_iter()
+            .map(|joint| (joint.key.clone(), joint))
+            .collect();
+
+        let mut restored = 0;
+        for (entity, mut transform) in bodies.iter_mut() {
+            let Some(saved) = keys.key(entity).and_then(|key| saved_bodies.get(&key)) else {
+                continue;
+            };
+            transform.translation = Vec3::from_array(saved.translation);
+            transform.rotation = Quat::from_array(saved.rotation);
+            if let (Some(linear), Some(angular)) = (saved.linear_velocity, saved.angular_velocity) {
+                commands.entity(entity).insert(Velocity {
+                    linvel: Vec3::from_array(linear),
+                    angvel: Vec3::from_array(angular),
+                });
+            }
+            restored += 1;
+        }
+        for (entity, command) in joints.iter_mut() {
+            let Some(saved) = keys.key(entity).and_then(|key| saved_joints.get(&key)) else {
+                continue;
+            };
+            if let (Some(mut command), Some(target)) = (command, saved.target) {
+                command.position = target;
+            }
+            let handle = context.entity2impulse_joint().get(&entity).copied();
+            if let Some(joint) = handle.and_then(|handle| context.impulse_joints.get_mut(handle)) {
+                joint.impulses = saved.impulses.into();
+            }
+        }
+        info!(
+            "Restored {restored} of {} bodies from {}",
+            saved_bodies.len(),
+            path.display()
+        );
+    }
+}
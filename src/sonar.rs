@@ -0,0 +1,150 @@
+use std::{collections::HashSet, f32::consts::TAU};
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    imu::global_pose, joint_encoders::update_measured_joint_positions,
+    simulation_rng::SimulationRng, simulation_time::PhysicsSchedule, RobotLink, RobotRoot,
+};
+
+/// Sonar links of the left and right sensor, each looking along its x axis
+const SONAR_LINKS: [&str; 2] = ["Sonar/Left", "Sonar/Right"];
+/// Closest distance the sonars resolve in meters
+const SONAR_MINIMUM_DISTANCE: f32 = 0.25;
+/// Distance reported when nothing is in range in meters
+pub const SONAR_MAXIMUM_DISTANCE: f32 = 2.55;
+/// Half opening angle of the sonar cone in radians
+const CONE_HALF_ANGLE: f32 = 0.5;
+/// The cone is sampled by a center ray and rings of rays at evenly spaced angles
+const CONE_RINGS: usize = 2;
+const RAYS_PER_RING: usize = 8;
+
+/// Simulates the two chest sonars of each robot by casting a cone of rays against the field, the
+/// robots and the ball.
+pub struct SonarPlugin;
+
+impl Plugin for SonarPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SonarNoise>()
+            .add_system(add_sonar_readings)
+            // sampled every physics step like the other sensors, so the noise drawn from the
+            // simulation RNG does not depend on the frame rate
+            .add_system(
+                update_sonar_readings
+                    .after(PhysicsSet::Writeback)
+                    .after(update_measured_joint_positions)
+                    .in_schedule(PhysicsSchedule),
+            );
+    }
+}
+
+/// Nearest obstacle in the cone of each sonar in meters.
+#[derive(Clone, Component, Debug)]
+pub struct SonarReadings {
+    pub left: f32,
+    pub right: f32,
+}
+
+impl Default for SonarReadings {
+    fn default() -> Self {
+        Self {
+            left: SONAR_MAXIMUM_DISTANCE,
+            right: SONAR_MAXIMUM_DISTANCE,
+        }
+    }
+}
+
+/// Standard deviation of the measured distances in meters.
+#[derive(Clone, Copy, Debug, Resource)]
+pub struct SonarNoise(pub f32);
+
+impl Default for SonarNoise {
+    fn default() -> Self {
+        Self(0.01)
+    }
+}
+
+fn add_sonar_readings(
+    mut commands: Commands,
+    robots: Query<Entity, (Added<RobotRoot>, Without<SonarReadings>)>,
+) {
+    for robot in robots.iter() {
+        commands.entity(robot).insert(SonarReadings::default());
+    }
+}
+
+fn update_sonar_readings(
+    context: Res<RapierContext>,
+    noise: Res<SonarNoise>,
+    mut rng: ResMut<SimulationRng>,
+    mut robots: Query<(Entity, &mut SonarReadings)>,
+    children: Query<&Children>,
+    links: Query<&RobotLink>,
+    parents: Query<&Parent>,
+    transforms: Query<&Transform>,
+) {
+    let directions = cone_directions();
+    for (robot, mut readings) in robots.iter_mut() {
+        let own_links: HashSet<_> = children.iter_descendants(robot).collect();
+        let not_own_link = |entity: Entity| !own_links.contains(&entity);
+        let filter = QueryFilter::new()
+            .groups(CollisionGroups::new(
+                Group::ALL,
+                Group::GROUP_1 | Group::GROUP_2 | Group::GROUP_3,
+            ))
+            .predicate(&not_own_link);
+
+        let mut distances = [SONAR_MAXIMUM_DISTANCE; 2];
+        for link in children.iter_descendants(robot) {
+            let Ok(robot_link) = links.get(link) else {
+                continue;
+            };
+            let Some(sonar) = SONAR_LINKS.iter().position(|name| *name == robot_link.name) else {
+                continue;
+            };
+            let Some(pose) = global_pose(link, &parents, &transforms) else {
+                continue;
+            };
+            let (_, rotation, origin) = pose.to_scale_rotation_translation();
+            let nearest = directions
+                .iter()
+                .filter_map(|direction| {
+                    context
+                        .cast_ray(
+                            origin,
+                            rotation * *direction,
+                            SONAR_MAXIMUM_DISTANCE,
+                            true,
+                            filter,
+                        )
+                        .map(|(_, distance)| distance)
+                })
+                .reduce(f32::min);
+            if let Some(distance) = nearest {
+                distances[sonar] = (distance + rng.gaussian(noise.0))
+                    .clamp(SONAR_MINIMUM_DISTANCE, SONAR_MAXIMUM_DISTANCE);
+            }
+        }
+        let [left, right] = distances;
+        readings.left = left;
+        readings.right = right;
+    }
+}
+
+/// Unit ray directions covering the sonar cone around the x axis.
+fn cone_directions() -> Vec<Vec3> {
+    let mut directions = vec![Vec3::X];
+    for ring in 1..=CONE_RINGS {
+        let elevation = CONE_HALF_ANGLE * ring as f32 / CONE_RINGS as f32;
+        for ray in 0..RAYS_PER_RING {
+            let azimuth = TAU * ray as f32 / RAYS_PER_RING as f32;
+            directions.push(Vec3::new(
+                elevation.cos(),
+                elevation.sin() * azimuth.cos(),
+                elevation.sin() * azimuth.sin(),
+            ));
+        }
+    }
+    directions
+}
@@ -0,0 +1,344 @@
+use std::{
+    collections::HashMap,
+    net::{Ipv4Addr, UdpSocket},
+    time::Duration,
+};
+
+use bevy::{prelude::*, time::common_conditions::on_timer};
+use color_eyre::{eyre::WrapErr, Result};
+
+use crate::{
+    arenas::{in_main_arena, Arena},
+    coordinate_frame::CoordinateFrame,
+    game_controller::GameControllerState,
+    player::{Player, RobotStatus},
+    Ball, RobotRoot,
+};
+
+const SPL_STANDARD_MESSAGE_HEADER: &[u8; 4] = b"SPL ";
+const SPL_STANDARD_MESSAGE_VERSION: u8 = 7;
+const SPL_STANDARD_MESSAGE_DATA_SIZE: usize = 474;
+/// Size of all fields before the user data
+const SPL_STANDARD_MESSAGE_HEADER_SIZE: usize = 34;
+/// Team communication of team `n` uses port `TEAM_PORT_BASE + n` by default
+const TEAM_PORT_BASE: u16 = 10000;
+const SEND_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Lets simulated robots exchange SPL standard messages on the team port of their team.
+///
+/// Every robot broadcasts its ground truth pose and the ball relative to it, received messages
+/// of teammates and external tools end up in [`TeamCommunication::received`].
+pub struct TeamCommunicationPlugin;
+
+impl Plugin for TeamCommunicationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TeamSockets>()
+            .add_system(add_team_communication)
+            .add_system(receive_team_messages)
+            .add_system(send_team_messages.run_if(on_timer(SEND_INTERVAL)));
+    }
+}
+
+/// Team communication state of a robot.
+#[derive(Clone, Component, Debug)]
+pub struct TeamCommunication {
+    pub enabled: bool,
+    pub team_number: u8,
+    /// Team specific payload appended to every message
+    pub data: Vec<u8>,
+    /// Messages received since the last frame, excluding the robot's own
+    pub received: Vec<SplStandardMessage>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SplStandardMessage {
+    pub player_number: u8,
+    pub team_number: u8,
+    pub fallen: bool,
+    /// Position in millimeters and orientation in radians on the field
+    pub pose: [f32; 3],
+    /// Seconds since the ball was seen, -1 if it was never seen
+    pub ball_age: f32,
+    /// Ball position relative to the robot in millimeters
+    pub ball: [f32; 2],
+    pub data: Vec<u8>,
+}
+
+impl SplStandardMessage {
+    fn serialize(&self) -> Vec<u8> {
+        let data = &self.data[..self.data.len().min(SPL_STANDARD_MESSAGE_DATA_SIZE)];
+        let mut message = Vec::with_capacity(SPL_STANDARD_MESSAGE_HEADER_SIZE + data.len());
+        message.extend_from_slice(SPL_STANDARD_MESSAGE_HEADER);
+        message.extend_from_slice(&[
+            SPL_STANDARD_MESSAGE_VERSION,
+            self.player_number,
+            self.team_number,
+            u8::from(self.fallen),
+        ]);
+        for value in self
+            .pose
+            .iter()
+            .chain([&self.ball_age])
+            .chain(self.ball.iter())
+        {
+            message.extend_from_slice(&value.to_le_bytes());
+        }
+        message.extend_from_slice(&(data.len() as u16).to_le_bytes());
+        message.extend_from_slice(data);
+        message
+    }
+
+    fn parse(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < SPL_STANDARD_MESSAGE_HEADER_SIZE
+            || &bytes[0..4] != SPL_STANDARD_MESSAGE_HEADER
+            || bytes[4] != SPL_STANDARD_MESSAGE_VERSION
+        {
+            return None;
+        }
+        let float = |offset: usize| {
+            f32::from_le_bytes([
+                bytes[offset],
+                bytes[offset + 1],
+                bytes[offset + 2],
+                bytes[offset + 3],
+            ])
+        };
+        let data_size = usize::from(u16::from_le_bytes([bytes[32], bytes[33]]));
+        let data = bytes
+            .get(SPL_STANDARD_MESSAGE_HEADER_SIZE..)?
+            .get(..data_size)?;
+        Some(Self {
+            player_number: bytes[5],
+            team_number: bytes[6],
+            fallen: bytes[7] != 0,
+            pose: [float(8), float(12), float(16)],
+            ball_age: float(20),
+            ball: [float(24), float(28)],
+            data: data.to_vec(),
+        })
+    }
+}
+
+/// One socket per team port, shared by all robots of the team.
+#[derive(Resource)]
+pub struct TeamSockets {
+    /// Team communication of team `n` uses port `port_base + n`
+    pub port_base: u16,
+    sockets: HashMap<u8, UdpSocket>,
+}
+
+impl Default for TeamSockets {
+    fn default() -> Self {
+        Self {
+            port_base: TEAM_PORT_BASE,
+            sockets: HashMap::new(),
+        }
+    }
+}
+
+impl TeamSockets {
+    fn port(&self, team_number: u8) -> Option<u16> {
+        let port = self.port_base.checked_add(u16::from(team_number));
+        if port.is_none() {
+            error!(
+                "Team port of team {team_number} exceeds {} with port base {}",
+                u16::MAX,
+                self.port_base
+            );
+        }
+        port
+    }
+
+    fn get_or_open(&mut self, team_number: u8) -> Option<&UdpSocket> {
+        if !self.sockets.contains_key(&team_number) {
+            match open_socket(self.port(team_number)?) {
+                Ok(socket) => {
+                    self.sockets.insert(team_number, socket);
+                }
+                Err(error) => {
+                    error!("{error:?}");
+                    return None;
+                }
+            }
+        }
+        self.sockets.get(&team_number)
+    }
+}
+
+fn open_socket(port: u16) -> Result<UdpSocket> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, port))
+        .wrap_err_with(|| format!("failed to bind team communication port {port}"))?;
+    socket
+        .set_nonblocking(true)
+        .wrap_err("failed to make team communication socket non-blocking")?;
+    socket
+        .set_broadcast(true)
+        .wrap_err("failed to enable broadcast on team communication socket")?;
+    Ok(socket)
+}
+
+/// Robots spawned without team communication join the team whose color they wear according to
+/// the GameController.
+fn add_team_communication(
+    mut commands: Commands,
+    game_controller_state: Res<GameControllerState>,
+    robots: Query<(Entity, &Player), (Added<Player>, Without<TeamCommunication>)>,
+) {
+    for (robot, player) in robots.iter() {
+        let team_number = game_controller_state
+            .teams
+            .iter()
+            .find(|team| team.field_player_color == player.team_color)
+            .map_or(1, |team| team.team_number);
+        commands.entity(robot).insert(TeamCommunication {
+            enabled: true,
+            team_number,
+            data: Vec::new(),
+            received: Vec::new(),
+        });
+    }
+}
+
+fn receive_team_messages(
+    sockets: Res<TeamSockets>,
+    mut robots: Query<(&Player, &mut TeamCommunication, Option<&Arena>)>,
+) {
+    let mut messages: HashMap<u8, Vec<SplStandardMessage>> = HashMap::new();
+    for (team_number, socket) in sockets.sockets.iter() {
+        let mut buffer = [0; SPL_STANDARD_MESSAGE_HEADER_SIZE + SPL_STANDARD_MESSAGE_DATA_SIZE];
+        while let Ok(size) = socket.recv(&mut buffer) {
+            if let Some(message) = SplStandardMessage::parse(&buffer[..size]) {
+                messages.entry(*team_number).or_default().push(message);
+            }
+        }
+    }
+    for (player, mut communication, arena) in robots.iter_mut() {
+        communication.received.clear();
+        // the ports are shared, only the main arena communicates
+        if !communication.enabled || !in_main_arena(arena) {
+            continue;
+        }
+        let Some(team_messages) = messages.get(&communication.team_number) else {
+            continue;
+        };
+        let team_number = communication.team_number;
+        communication.received.extend(
+            team_messages
+                .iter()
+                .filter(|message| {
+                    message.player_number != player.jersey_number
+                        || message.team_number != team_number
+                })
+                .cloned(),
+        );
+    }
+}
+
+fn send_team_messages(
+    mut sockets: ResMut<TeamSockets>,
+    frame: Res<CoordinateFrame>,
+    robots: Query<
+        (
+            &Player,
+            &RobotStatus,
+            &GlobalTransform,
+            &TeamCommunication,
+            Option<&Arena>,
+        ),
+        With<RobotRoot>,
+    >,
+    balls: Query<(&GlobalTransform, Option<&Arena>), With<Ball>>,
+) {
+    // the ports are shared, only the main arena communicates
+    let ball = balls
+        .iter()
+        .find(|(_, arena)| in_main_arena(*arena))
+        .map(|(ball, _)| frame.to_field(ball.translation()));
+    for (player, status, transform, communication, _) in
+        robots.iter().filter(|(.., arena)| in_main_arena(*arena))
+    {
+        if !communication.enabled {
+            continue;
+        }
+        let Transform {
+            translation,
+            rotation,
+            ..
+        } = frame.transform_to_field(transform.compute_transform());
+        let (yaw, _, _) = rotation.to_euler(EulerRot::ZYX);
+        let relative_ball = ball
+            .map(|ball| (Quat::from_rotation_z(-yaw) * (ball - translation)).truncate() * 1000.0);
+        let message = SplStandardMessage {
+            player_number: player.jersey_number,
+            team_number: communication.team_number,
+            fallen: status.fallen,
+            pose: [translation.x * 1000.0, translation.y * 1000.0, yaw],
+            ball_age: if relative_ball.is_some() { 0.0 } else { -1.0 },
+            ball: relative_ball.unwrap_or_default().to_array(),
+            data: communication.data.clone(),
+        };
+        let Some(port) = sockets.port(communication.team_number) else {
+            continue;
+        };
+        let Some(socket) = sockets.get_or_open(communication.team_number) else {
+            continue;
+        };
+        if let Err(error) = socket.send_to(&message.serialize(), (Ipv4Addr::BROADCAST, port)) {
+            warn!("Failed to send team message: {error}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(data: Vec<u8>) -> SplStandardMessage {
+        SplStandardMessage {
+            player_number: 4,
+            team_number: 24,
+            fallen: true,
+            pose: [-1500.0, 250.5, 1.25],
+            ball_age: 0.0,
+            ball: [830.0, -120.0],
+            data,
+        }
+    }
+
+    #[test]
+    fn spl_standard_message_round_trip() {
+        let original = message(vec![1, 2, 3, 255]);
+        let bytes = original.serialize();
+        assert_eq!(bytes.len(), SPL_STANDARD_MESSAGE_HEADER_SIZE + 4);
+        assert_eq!(&bytes[0..8], b"SPL \x07\x04\x18\x01");
+        assert_eq!(u16::from_le_bytes([bytes[32], bytes[33]]), 4);
+        assert_eq!(SplStandardMessage::parse(&bytes), Some(original));
+    }
+
+    #[test]
+    fn serialize_truncates_oversized_data() {
+        let bytes = message(vec![7; SPL_STANDARD_MESSAGE_DATA_SIZE + 10]).serialize();
+        assert_eq!(
+            bytes.len(),
+            SPL_STANDARD_MESSAGE_HEADER_SIZE + SPL_STANDARD_MESSAGE_DATA_SIZE
+        );
+        let parsed = SplStandardMessage::parse(&bytes).unwrap();
+        assert_eq!(parsed.data.len(), SPL_STANDARD_MESSAGE_DATA_SIZE);
+    }
+
+    #[test]
+    fn rejects_truncated_messages() {
+        let bytes = message(vec![1, 2, 3]).serialize();
+        assert!(
+            SplStandardMessage::parse(&bytes[..SPL_STANDARD_MESSAGE_HEADER_SIZE - 1]).is_none()
+        );
+        // the data announced in the header is missing
+        assert!(SplStandardMessage::parse(&bytes[..bytes.len() - 1]).is_none());
+        let mut oversized = bytes.clone();
+        oversized[32..34].copy_from_slice(&100u16.to_le_bytes());
+        assert!(SplStandardMessage::parse(&oversized).is_none());
+        let mut wrong_version = bytes;
+        wrong_version[4] = SPL_STANDARD_MESSAGE_VERSION + 1;
+        assert!(SplStandardMessage::parse(&wrong_version).is_none());
+    }
+}
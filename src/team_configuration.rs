@@ -0,0 +1,90 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use color_eyre::{eyre::WrapErr, Result};
+use serde::Deserialize;
+
+use crate::player::TeamColor;
+
+/// Robots to spawn at startup grouped by team, loaded from a JSON file.
+#[derive(Clone, Debug, Deserialize, Resource)]
+pub struct TeamConfiguration {
+    pub teams: Vec<TeamSetup>,
+    /// URDF of robots that do not name one
+    #[serde(skip, default = "default_urdf")]
+    pub default_urdf: PathBuf,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TeamSetup {
+    pub team_number: u8,
+    pub team_color: TeamColor,
+    pub robots: Vec<RobotSetup>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RobotSetup {
+    pub jersey_number: u8,
+    /// URDF describing the robot, relative to the working directory
+    pub urdf: Option<PathBuf>,
+    /// Position on the field in meters
+    pub position: [f32; 2],
+    /// Rotation around the vertical axis in radians
+    #[serde(default)]
+    pub orientation: f32,
+    /// Name of the registered [`RobotController`](crate::robot_controller::RobotController)
+    /// driving the joints
+    #[serde(default)]
+    pub controller: Option<String>,
+}
+
+fn default_urdf() -> PathBuf {
+    PathBuf::from("assets/NAO.urdf")
+}
+
+impl TeamConfiguration {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .wrap_err_with(|| format!("failed to read team configuration {}", path.display()))?;
+        serde_json::from_str(&content)
+            .wrap_err_with(|| format!("failed to parse team configuration {}", path.display()))
+    }
+
+    /// Drops all but the first `count` robots of each team.
+    pub fn limit_robots_per_team(&mut self, count: usize) {
+        for team in &mut self.teams {
+            if team.robots.len() < count {
+                warn!(
+                    "Team {} only has {} robots configured",
+                    team.team_number,
+                    team.robots.len()
+                );
+            }
+            team.robots.truncate(count);
+        }
+    }
+
+    pub fn urdf<'a>(&'a self, robot: &'a RobotSetup) -> &'a Path {
+        robot.urdf.as_deref().unwrap_or(&self.default_urdf)
+    }
+}
+
+impl TeamSetup {
+    /// Side of the goal the team defends, -1 or 1 along the field x axis, decided by the side the
+    /// team starts on.
+    pub fn own_goal_side(&self) -> Option<f32> {
+        let x: f32 = self.robots.iter().map(|robot| robot.position[0]).sum();
+        (x != 0.0).then(|| x.signum())
+    }
+}
+
+impl RobotSetup {
+    pub fn transform(&self) -> Transform {
+        Transform::from_xyz(self.position[0], self.position[1], 0.0)
+            .with_rotation(Quat::from_rotation_z(self.orientation))
+    }
+}
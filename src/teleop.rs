@@ -0,0 +1,162 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+use bevy_egui::EguiContexts;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    arenas::Arena,
+    coordinate_frame::CoordinateFrame,
+    field_dimensions::FieldDimensions,
+    kick_tool::KickBallToward,
+    selection::Selection,
+    shortcuts::{dispatch_shortcuts, ShortcutAction},
+    Ball, RobotRoot,
+};
+
+/// Distance in front of a robot within which its canned kick reaches the ball in meters
+const KICK_REACH: f32 = 0.3;
+/// Height of the root link of a robot lying on the ground in meters
+const FALLEN_ROOT_HEIGHT: f32 = 0.1;
+
+/// Drives the selected robot around the field with the keyboard, without a walking controller.
+///
+/// W/S or the up and down arrows move it forward and backward, A/D move it sideways, the left and
+/// right arrows turn it. Shortcuts trigger a canned kick of the ball in front of it and make it
+/// fall over.
+pub struct TeleopPlugin;
+
+impl Plugin for TeleopPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Teleop>()
+            .add_system(drive_selected_robot)
+            .add_system(teleop_actions.after(dispatch_shortcuts));
+    }
+}
+
+#[derive(Resource)]
+pub struct Teleop {
+    /// Speed in m/s
+    pub speed: f32,
+    /// Turn rate in rad/s
+    pub turn_rate: f32,
+}
+
+impl Default for Teleop {
+    fn default() -> Self {
+        Self {
+            speed: 0.5,
+            turn_rate: 1.0,
+        }
+    }
+}
+
+/// Moves dynamic robots by their velocity and all others by their transform.
+fn drive_selected_robot(
+    mut contexts: EguiContexts,
+    time: Res<Time>,
+    keys: Res<Input<KeyCode>>,
+    teleop: Res<Teleop>,
+    frame: Res<CoordinateFrame>,
+    selection: Res<Selection>,
+    mut robots: Query<(&mut Transform, Option<&mut Velocity>), With<RobotRoot>>,
+) {
+    if contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+    let Some((mut transform, velocity)) = selection
+        .entity
+        .and_then(|entity| robots.get_mut(entity).ok())
+    else {
+        return;
+    };
+    let pressed = |codes: &[KeyCode]| {
+        if keys.any_pressed(codes.iter().copied()) {
+            1.0
+        } else {
+            0.0
+        }
+    };
+    let axis = |positive: &[KeyCode], negative: &[KeyCode]| pressed(positive) - pressed(negative);
+    let forward = axis(&[KeyCode::W, KeyCode::Up], &[KeyCode::S, KeyCode::Down]);
+    let left = axis(&[KeyCode::A], &[KeyCode::D]);
+    let turn = axis(&[KeyCode::Left], &[KeyCode::Right]);
+    if forward == 0.0 && left == 0.0 && turn == 0.0 {
+        return;
+    }
+
+    let (yaw, _, _) = frame
+        .transform_to_field(*transform)
+        .rotation
+        .to_euler(EulerRot::ZYX);
+    let linear = frame
+        .vector_to_world(Quat::from_rotation_z(yaw) * Vec3::new(forward, left, 0.0) * teleop.speed);
+    let angular = turn * teleop.turn_rate;
+    let up = frame.up();
+    match velocity {
+        Some(mut velocity) => {
+            // keep falling and tilting, only the motion on the ground is driven
+            velocity.linvel = linear + up * velocity.linvel.dot(up);
+            let spin = velocity.angvel.dot(up);
+            velocity.angvel += up * (angular - spin);
+        }
+        None => {
+            let delta = time.delta_seconds();
+            transform.translation += linear * delta;
+            transform.rotate_axis(up, angular * delta);
+        }
+    }
+}
+
+fn teleop_actions(
+    mut actions: EventReader<ShortcutAction>,
+    frame: Res<CoordinateFrame>,
+    field_dimensions: Res<FieldDimensions>,
+    selection: Res<Selection>,
+    mut robots: Query<(&mut Transform, Option<&Arena>), With<RobotRoot>>,
+    balls: Query<(&Transform, Option<&Arena>), (With<Ball>, Without<RobotRoot>)>,
+    mut kicks: EventWriter<KickBallToward>,
+) {
+    // consume the actions even without a selected robot, they must not fire once one is selected
+    let actions: Vec<_> = actions.iter().copied().collect();
+    let Some((mut transform, arena)) = selection
+        .entity
+        .and_then(|entity| robots.get_mut(entity).ok())
+    else {
+        return;
+    };
+    let arena = arena.copied().unwrap_or_default();
+    let field_transform = frame.transform_to_field(*transform);
+    let (yaw, _, _) = field_transform.rotation.to_euler(EulerRot::ZYX);
+    let heading = Vec2::from_angle(yaw);
+    for action in actions {
+        match action {
+            ShortcutAction::TeleopKick => {
+                let offset = arena.offset(&field_dimensions);
+                let robot = (field_transform.translation - offset).truncate();
+                let reachable_ball = balls
+                    .iter()
+                    .filter(|(_, ball_arena)| ball_arena.copied().unwrap_or_default() == arena)
+                    .map(|(ball, _)| (frame.to_field(ball.translation) - offset).truncate())
+                    .find(|ball| {
+                        let offset = *ball - robot;
+                        offset.length() < KICK_REACH && offset.dot(heading) > 0.0
+                    });
+                if let Some(ball) = reachable_ball {
+                    kicks.send(KickBallToward {
+                        arena,
+                        target: ball + heading,
+                    });
+                }
+            }
+            ShortcutAction::TeleopFall => {
+                // tip over forward around the robot's own left axis
+                let mut fallen = field_transform;
+                fallen.rotation = Quat::from_rotation_z(yaw) * Quat::from_rotation_y(FRAC_PI_2);
+                fallen.translation.z = FALLEN_ROOT_HEIGHT;
+                *transform = frame.transform_to_world(fallen);
+            }
+            _ => {}
+        }
+    }
+}
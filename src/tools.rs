@@ -0,0 +1,58 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+/// Lets the user choose what a left click in the viewport does.
+pub struct ToolsPlugin;
+
+impl Plugin for ToolsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActiveTool>().add_system(tools_ui);
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Resource)]
+pub enum ActiveTool {
+    /// Left click pans the camera
+    #[default]
+    Camera,
+    /// Left click grabs a body and drags it with the cursor
+    Drag,
+    /// Left click kicks the ball
+    Kick,
+    /// Left click selects a robot or the ball and moves it with a transform gizmo
+    Gizmo,
+    /// Left click pushes a robot at the clicked point
+    Push,
+    /// Left click grabs a link of a robot and moves it by changing the joint angles
+    Pose,
+}
+
+impl ActiveTool {
+    const ALL: [ActiveTool; 6] = [
+        ActiveTool::Camera,
+        ActiveTool::Drag,
+        ActiveTool::Kick,
+        ActiveTool::Gizmo,
+        ActiveTool::Push,
+        ActiveTool::Pose,
+    ];
+}
+
+fn tools_ui(mut contexts: EguiContexts, mut active_tool: ResMut<ActiveTool>) {
+    egui::Window::new("Tools")
+        .anchor(egui::Align2::RIGHT_TOP, [-10.0, 10.0])
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                for tool in ActiveTool::ALL {
+                    // avoid triggering change detection when nothing was clicked
+                    if ui
+                        .selectable_label(*active_tool == tool, format!("{tool:?}"))
+                        .clicked()
+                    {
+                        *active_tool = tool;
+                    }
+                }
+            });
+        });
+}
@@ -0,0 +1,276 @@
+use std::f32::consts::TAU;
+
+use bevy::{prelude::*, render::mesh::PrimitiveTopology};
+use bevy_egui::EguiContexts;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    coordinate_frame::CoordinateFrame,
+    picking::Picking,
+    selection::{selectable_entity, Selection},
+    tools::ActiveTool,
+    Ball, RobotRoot,
+};
+
+const AXIS_LENGTH: f32 = 0.5;
+const RING_RADIUS: f32 = 0.4;
+/// Maximum distance between cursor ray and a handle to grab it in meters
+const GRAB_TOLERANCE: f32 = 0.04;
+
+/// Gizmo tool: click a robot or the ball to select it, then drag the axes to move it or the ring
+/// to rotate it around the vertical axis. The gizmo is aligned with the field axes, moved bodies
+/// keep zero velocity.
+pub struct TransformGizmoPlugin;
+
+impl Plugin for TransformGizmoPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GizmoDrag>()
+            .add_startup_system(spawn_gizmo)
+            .add_system(start_gizmo_drag)
+            .add_system(update_gizmo_drag.after(start_gizmo_drag))
+            .add_system(update_gizmo_visual.after(update_gizmo_drag));
+    }
+}
+
+#[derive(Clone, Copy)]
+enum GizmoHandle {
+    Axis(Vec3),
+    Yaw,
+}
+
+#[derive(Default, Resource)]
+struct GizmoDrag {
+    active: Option<ActiveGizmoDrag>,
+}
+
+struct ActiveGizmoDrag {
+    handle: GizmoHandle,
+    start_transform: Transform,
+    /// Position along the axis or yaw angle where the handle was grabbed
+    start_value: f32,
+}
+
+#[derive(Component)]
+struct TransformGizmo;
+
+fn spawn_gizmo(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let mut positions = Vec::new();
+    let mut colors = Vec::new();
+    for (axis, color) in [
+        (Vec3::X, Color::RED),
+        (Vec3::Y, Color::GREEN),
+        (Vec3::Z, Color::BLUE),
+    ] {
+        positions.extend([[0.0; 3], (axis * AXIS_LENGTH).to_array()]);
+        colors.extend([color.as_linear_rgba_f32(); 2]);
+    }
+    const SEGMENTS: usize = 64;
+    for index in 0..SEGMENTS {
+        for point in [index, index + 1] {
+            let angle = point as f32 / SEGMENTS as f32 * TAU;
+            positions.push([RING_RADIUS * angle.cos(), RING_RADIUS * angle.sin(), 0.0]);
+            colors.push(Color::YELLOW.as_linear_rgba_f32());
+        }
+    }
+    let mut mesh = Mesh::new(PrimitiveTopology::LineList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(mesh),
+            material: materials.add(StandardMaterial {
+                base_color: Color::WHITE,
+                unlit: true,
+                ..Default::default()
+            }),
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        },
+        TransformGizmo,
+        Name::new("transform gizmo"),
+    ));
+}
+
+#[allow(clippy::too_many_arguments)]
+fn start_gizmo_drag(
+    mut contexts: EguiContexts,
+    active_tool: Res<ActiveTool>,
+    mouse: Res<Input<MouseButton>>,
+    frame: Res<CoordinateFrame>,
+    picking: Picking,
+    mut selection: ResMut<Selection>,
+    mut drag: ResMut<GizmoDrag>,
+    transforms: Query<&Transform>,
+    parents: Query<&Parent>,
+    robots: Query<(), With<RobotRoot>>,
+    balls: Query<(), With<Ball>>,
+) {
+    if *active_tool != ActiveTool::Gizmo
+        || !mouse.just_pressed(MouseButton::Left)
+        || contexts.ctx_mut().is_pointer_over_area()
+    {
+        return;
+    }
+    let Some(ray) = picking.cursor_ray() else {
+        return;
+    };
+
+    if let Some(transform) = selection
+        .entity
+        .and_then(|entity| transforms.get(entity).ok())
+    {
+        let center = frame.to_field(transform.translation);
+        if let Some((handle, start_value)) = grab_handle(field_ray(&frame, ray), center) {
+            drag.active = Some(ActiveGizmoDrag {
+                handle,
+                start_transform: *transform,
+                start_value,
+            });
+            return;
+        }
+    }
+
+    selection.entity = picking
+        .pick(QueryFilter::default())
+        .map(|(picked, _)| selectable_entity(picked, &parents, &robots))
+        .filter(|&entity| robots.contains(entity) || balls.contains(entity));
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_gizmo_drag(
+    mut commands: Commands,
+    mouse: Res<Input<MouseButton>>,
+    frame: Res<CoordinateFrame>,
+    picking: Picking,
+    selection: Res<Selection>,
+    mut drag: ResMut<GizmoDrag>,
+    mut transforms: Query<&mut Transform>,
+    children: Query<&Children>,
+    bodies: Query<(), With<RigidBody>>,
+) {
+    let (Some(active), Some(entity)) = (&drag.active, selection.entity) else {
+        return;
+    };
+    if !mouse.pressed(MouseButton::Left) {
+        drag.active = None;
+        return;
+    }
+    let Some(ray) = picking.cursor_ray() else {
+        return;
+    };
+    let Ok(mut transform) = transforms.get_mut(entity) else {
+        return;
+    };
+
+    let ray = field_ray(&frame, ray);
+    let start = active.start_transform;
+    let center = frame.to_field(start.translation);
+    match active.handle {
+        GizmoHandle::Axis(axis) => {
+            if let Some((position, _)) = closest_point_on_axis(ray, center, axis) {
+                transform.translation = start.translation
+                    + frame.vector_to_world(axis * (position - active.start_value));
+            }
+        }
+        GizmoHandle::Yaw => {
+            if let Some(angle) = yaw_angle(ray, center) {
+                transform.rotation =
+                    Quat::from_axis_angle(frame.up(), angle - active.start_value) * start.rotation;
+            }
+        }
+    }
+
+    // the body is moved kinematically, do not let it keep any momentum
+    for body in std::iter::once(entity).chain(children.iter_descendants(entity)) {
+        if bodies.contains(body) {
+            commands.entity(body).insert(Velocity::zero());
+        }
+    }
+}
+
+fn update_gizmo_visual(
+    active_tool: Res<ActiveTool>,
+    selection: Res<Selection>,
+    frame: Res<CoordinateFrame>,
+    targets: Query<&GlobalTransform, Without<TransformGizmo>>,
+    mut gizmos: Query<(&mut Transform, &mut Visibility), With<TransformGizmo>>,
+) {
+    let target = selection
+        .entity
+        .filter(|_| *active_tool == ActiveTool::Gizmo)
+        .and_then(|entity| targets.get(entity).ok());
+    for (mut transform, mut visibility) in gizmos.iter_mut() {
+        match target {
+            Some(target) => {
+                transform.translation = target.translation();
+                transform.rotation = frame.rotation();
+                *visibility = Visibility::Inherited;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
+/// Cursor `ray` in field coordinates, the gizmo handles are computed in the field frame.
+fn field_ray(frame: &CoordinateFrame, ray: Ray) -> Ray {
+    Ray {
+        origin: frame.to_field(ray.origin),
+        direction: frame.vector_to_field(ray.direction),
+    }
+}
+
+/// Handle of a gizmo at `center` under the cursor ray and the value where it was grabbed.
+fn grab_handle(ray: Ray, center: Vec3) -> Option<(GizmoHandle, f32)> {
+    let axis_handle = [Vec3::X, Vec3::Y, Vec3::Z]
+        .into_iter()
+        .filter_map(|axis| {
+            let (position, distance) = closest_point_on_axis(ray, center, axis)?;
+            let on_handle = (0.0..=AXIS_LENGTH).contains(&position) && distance < GRAB_TOLERANCE;
+            on_handle.then_some((GizmoHandle::Axis(axis), position, distance))
+        })
+        .min_by(|(_, _, a), (_, _, b)| a.total_cmp(b))
+        .map(|(handle, position, _)| (handle, position));
+    if axis_handle.is_some() {
+        return axis_handle;
+    }
+
+    let distance = ray.intersect_plane(center, Vec3::Z)?;
+    let radius = ray.get_point(distance).distance(center);
+    if (radius - RING_RADIUS).abs() < GRAB_TOLERANCE {
+        return Some((GizmoHandle::Yaw, yaw_angle(ray, center)?));
+    }
+    None
+}
+
+/// Position along the axis closest to the ray and the distance between ray and axis there.
+fn closest_point_on_axis(ray: Ray, center: Vec3, axis: Vec3) -> Option<(f32, f32)> {
+    let offset = ray.origin - center;
+    let alignment = ray.direction.dot(axis);
+    let denominator = 1.0 - alignment * alignment;
+    if denominator < 1e-6 {
+        return None;
+    }
+    let ray_offset = ray.direction.dot(offset);
+    let axis_offset = axis.dot(offset);
+    let ray_distance = (alignment * axis_offset - ray_offset) / denominator;
+    if ray_distance < 0.0 {
+        return None;
+    }
+    let position = (axis_offset - alignment * ray_offset) / denominator;
+    let distance = ray
+        .get_point(ray_distance)
+        .distance(center + axis * position);
+    Some((position, distance))
+}
+
+/// Angle around the vertical axis through `center` where the ray hits the horizontal plane.
+fn yaw_angle(ray: Ray, center: Vec3) -> Option<f32> {
+    let distance = ray.intersect_plane(center, Vec3::Z)?;
+    let offset = ray.get_point(distance) - center;
+    Some(offset.y.atan2(offset.x))
+}
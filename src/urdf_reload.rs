@@ -0,0 +1,85 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use bevy::{prelude::*, time::common_conditions::on_timer};
+
+use crate::{
+    mesh_uris::{read_urdf, PackagePaths},
+    player::Player,
+    robot_assets::RobotAssets,
+    spawn_player,
+    team_configuration::TeamConfiguration,
+    RobotRoot,
+};
+
+/// Interval between checks whether a URDF changed
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Respawns the robots of a URDF when the file changes, keeping their pose on the field.
+///
+/// Links, joints and visuals are rebuilt from scratch, the joints start in the preset of
+/// [`RobotSpawn`](crate::robot_spawn::RobotSpawn).
+pub struct UrdfReloadPlugin;
+
+impl Plugin for UrdfReloadPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<UrdfModificationTimes>()
+            .add_system(reload_changed_urdfs.run_if(on_timer(POLL_INTERVAL)));
+    }
+}
+
+#[derive(Default, Resource)]
+struct UrdfModificationTimes(HashMap<PathBuf, SystemTime>);
+
+fn reload_changed_urdfs(
+    mut commands: Commands,
+    mut assets: RobotAssets,
+    team_configuration: Res<TeamConfiguration>,
+    packages: Res<PackagePaths>,
+    mut modification_times: ResMut<UrdfModificationTimes>,
+    robots: Query<(Entity, &Player, &Transform), With<RobotRoot>>,
+) {
+    let paths: HashSet<_> = team_configuration
+        .teams
+        .iter()
+        .flat_map(|team| team.robots.iter())
+        .map(|robot| team_configuration.urdf(robot).to_path_buf())
+        .collect();
+    for path in paths {
+        let Ok(modified) = fs::metadata(&path).and_then(|metadata| metadata.modified()) else {
+            continue;
+        };
+        let previous = modification_times.0.insert(path.clone(), modified);
+        // the first check only remembers the time of the URDF loaded at startup
+        if previous.map_or(true, |previous| previous == modified) {
+            continue;
+        }
+        let urdf = match read_urdf(&path, &packages) {
+            Ok(urdf) => urdf,
+            Err(error) => {
+                error!("{error:?}");
+                continue;
+            }
+        };
+        info!("Reloading {}", path.display());
+        for team in &team_configuration.teams {
+            for robot in &team.robots {
+                if team_configuration.urdf(robot) != path {
+                    continue;
+                }
+                let Some((entity, _, transform)) = robots.iter().find(|(_, player, _)| {
+                    player.team_color == team.team_color
+                        && player.jersey_number == robot.jersey_number
+                }) else {
+                    continue;
+                };
+                commands.entity(entity).despawn_recursive();
+                spawn_player(&mut commands, &mut assets, &urdf, team, robot, *transform);
+            }
+        }
+    }
+}
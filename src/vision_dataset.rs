@@ -0,0 +1,517 @@
+use std::{
+    fs::{self, File},
+    io::BufWriter,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver, SyncSender, TrySendError},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use bevy::{
+    prelude::*,
+    render::{
+        extract_resource::{ExtractResource, ExtractResourcePlugin},
+        primitives::Aabb,
+    },
+    transform::TransformSystem,
+};
+use bevy_rapier3d::prelude::*;
+use color_eyre::{eyre::WrapErr, Result};
+use serde::Serialize;
+
+use crate::{
+    camera_streams::StreamedImages,
+    capture::save_png,
+    coordinate_frame::CoordinateFrame,
+    field_dimensions::FieldDimensions,
+    field_markings::field_line_segments,
+    head_cameras::{HeadCamera, HEAD_CAMERAS},
+    mass_gizmos::convex_hull,
+    player::{Player, TeamColor},
+    simulation_time::SimulationTime,
+    Ball, RobotLink, RobotRoot,
+};
+
+/// Closest distance in front of a camera that is projected in meters
+const NEAR_DISTANCE: f32 = 0.01;
+/// Points on the silhouette of the ball projected for its bounding box
+const BALL_SILHOUETTE_POINTS: usize = 16;
+/// Frames buffered for the writer thread, further frames are dropped
+const WRITER_QUEUE_LENGTH: usize = 8;
+/// Frames between rewrites of the annotation file
+const ANNOTATION_FLUSH_INTERVAL: usize = 50;
+
+const BALL_CATEGORY: u32 = 1;
+const ROBOT_CATEGORY: u32 = 2;
+
+/// Exports the images of the streamed head cameras with ground truth annotations for training
+/// detectors.
+///
+/// The dataset directory holds the frames as PNGs in `images/` and a COCO style
+/// `annotations.json` with bounding boxes of the ball and of robots, polygon masks of robots,
+/// the visible field line segments and the camera matrix of every image. Masks are convex hulls of
+/// the bounding boxes of the robot meshes, occlusion is only checked for the ball.
+pub struct VisionDatasetPlugin;
+
+impl Plugin for VisionDatasetPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(ExtractResourcePlugin::<VisionDataset>::default())
+            .add_system(
+                annotate_streamed_images
+                    .in_base_set(CoreSet::PostUpdate)
+                    .after(TransformSystem::TransformPropagate)
+                    .run_if(resource_exists::<VisionDataset>()),
+            );
+    }
+}
+
+/// Destination of the exported frames, frames are only exported while this resource exists.
+#[derive(Clone, Resource)]
+pub struct VisionDataset {
+    /// Rendered frames between two exported frames
+    pub interval: u32,
+    /// The mutex only makes the sender `Sync`
+    frames: Arc<Mutex<SyncSender<DatasetFrame>>>,
+}
+
+impl ExtractResource for VisionDataset {
+    type Source = Self;
+
+    fn extract_resource(source: &Self::Source) -> Self {
+        source.clone()
+    }
+}
+
+impl VisionDataset {
+    /// Starts writing a dataset into `directory`, existing frames are overwritten.
+    pub fn create(directory: impl Into<PathBuf>, interval: u32) -> Result<Self> {
+        let directory = directory.into();
+        let images = directory.join("images");
+        fs::create_dir_all(&images)
+            .wrap_err_with(|| format!("failed to create {}", images.display()))?;
+        let (sender, receiver) = mpsc::sync_channel(WRITER_QUEUE_LENGTH);
+        thread::spawn(move || write_dataset(&directory, receiver));
+        Ok(Self {
+            interval: interval.max(1),
+            frames: Arc::new(Mutex::new(sender)),
+        })
+    }
+
+    pub fn send(&self, frame: DatasetFrame) {
+        match self.frames.lock().unwrap().try_send(frame) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => warn!("Dropping dataset frame, the writer is behind"),
+            Err(TrySendError::Disconnected(_)) => error!("Dataset writer stopped"),
+        }
+    }
+}
+
+/// Rendered image of a head camera and its ground truth.
+pub struct DatasetFrame {
+    pub rgba: Vec<u8>,
+    pub annotation: Arc<FrameAnnotation>,
+}
+
+/// Ground truth of one camera image, positions are pixels from the top left corner.
+#[derive(Debug, Serialize)]
+pub struct FrameAnnotation {
+    image: ImageInfo,
+    objects: Vec<ObjectInfo>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct ImageInfo {
+    width: u32,
+    height: u32,
+    /// Simulation time in seconds
+    time: f64,
+    camera: &'static str,
+    /// Pinhole intrinsics, row major
+    intrinsics: [[f32; 3]; 3],
+    /// Pose of the camera in field coordinates, the camera looks along its x axis with z up,
+    /// row major
+    camera_to_field: [[f32; 4]; 4],
+    /// Visible parts of the field line center lines as `[x1, y1, x2, y2]`
+    line_segments: Vec<[f32; 4]>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct ObjectInfo {
+    category_id: u32,
+    /// `[x, y, width, height]`
+    bbox: [f32; 4],
+    /// Area of the bounding box in pixels
+    area: f32,
+    /// Polygons as `[x1, y1, x2, y2, ...]`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    segmentation: Vec<Vec<f32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    occluded: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    robot: Option<RobotInfo>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct RobotInfo {
+    team_color: TeamColor,
+    jersey_number: u8,
+}
+
+/// Pinhole model of a head camera at its current pose.
+struct PinholeCamera {
+    world_to_camera: Transform,
+    focal_length: f32,
+    size: Vec2,
+}
+
+impl PinholeCamera {
+    fn new(camera: &HeadCamera, transform: Transform) -> Self {
+        Self {
+            world_to_camera: Transform::from_matrix(transform.compute_matrix().inverse()),
+            focal_length: camera.image_width as f32 / 2.0 / (camera.horizontal_fov / 2.0).tan(),
+            size: Vec2::new(camera.image_width as f32, camera.image_height as f32),
+        }
+    }
+
+    fn intrinsics(&self) -> [[f32; 3]; 3] {
+        let center = self.size / 2.0;
+        [
+            [self.focal_length, 0.0, center.x],
+            [0.0, self.focal_length, center.y],
+            [0.0, 0.0, 1.0],
+        ]
+    }
+
+    /// Pixel of a point in camera coordinates, the point has to be in front of the camera.
+    fn pixel(&self, point: Vec3) -> Vec2 {
+        self.size / 2.0 - Vec2::new(point.y, point.z) * self.focal_length / point.x
+    }
+
+    /// Pixel of the world `point`, `None` behind the camera.
+    fn project(&self, point: Vec3) -> Option<Vec2> {
+        let point = self.world_to_camera.transform_point(point);
+        (point.x >= NEAR_DISTANCE).then(|| self.pixel(point))
+    }
+
+    /// Part of the world segment from `start` to `end` visible in the image.
+    fn project_segment(&self, start: Vec3, end: Vec3) -> Option<[Vec2; 2]> {
+        let mut start = self.world_to_camera.transform_point(start);
+        let mut end = self.world_to_camera.transform_point(end);
+        if start.x < NEAR_DISTANCE && end.x < NEAR_DISTANCE {
+            return None;
+        }
+        // cut off the part behind the camera
+        let at_near =
+            |from: Vec3, to: Vec3| from.lerp(to, (NEAR_DISTANCE - from.x) / (to.x - from.x));
+        if start.x < NEAR_DISTANCE {
+            start = at_near(start, end);
+        } else if end.x < NEAR_DISTANCE {
+            end = at_near(end, start);
+        }
+        clip_segment(self.pixel(start), self.pixel(end), self.size)
+    }
+}
+
+/// Liang-Barsky clipping of a segment to the image rectangle.
+fn clip_segment(start: Vec2, end: Vec2, size: Vec2) -> Option<[Vec2; 2]> {
+    let delta = end - start;
+    let mut entering: f32 = 0.0;
+    let mut leaving: f32 = 1.0;
+    for (direction, distance) in [
+        (-delta.x, start.x),
+        (delta.x, size.x - start.x),
+        (-delta.y, start.y),
+        (delta.y, size.y - start.y),
+    ] {
+        if direction == 0.0 {
+            if distance < 0.0 {
+                return None;
+            }
+            continue;
+        }
+        let parameter = distance / direction;
+        if direction < 0.0 {
+            entering = entering.max(parameter);
+        } else {
+            leaving = leaving.min(parameter);
+        }
+    }
+    (entering < leaving).then(|| [start + delta * entering, start + delta * leaving])
+}
+
+/// Sutherland-Hodgman clipping of a convex polygon to the image rectangle.
+fn clip_polygon(mut polygon: Vec<Vec2>, size: Vec2) -> Vec<Vec2> {
+    let edges: [(Vec2, f32); 4] = [
+        (Vec2::X, 0.0),
+        (Vec2::NEG_X, -size.x),
+        (Vec2::Y, 0.0),
+        (Vec2::NEG_Y, -size.y),
+    ];
+    for (normal, offset) in edges {
+        let inside = |point: Vec2| point.dot(normal) >= offset;
+        let input = std::mem::take(&mut polygon);
+        for (index, &current) in input.iter().enumerate() {
+            let previous = input[(index + input.len() - 1) % input.len()];
+            if inside(current) != inside(previous) {
+                let progress = (offset - previous.dot(normal)) / (current - previous).dot(normal);
+                polygon.push(previous.lerp(current, progress));
+            }
+            if inside(current) {
+                polygon.push(current);
+            }
+        }
+    }
+    polygon
+}
+
+/// `[x, y, width, height]` around `points`, `None` if it is empty.
+fn bounding_box(points: impl IntoIterator<Item = Vec2>) -> Option<[f32; 4]> {
+    let (min, max) = points.into_iter().fold(
+        (Vec2::splat(f32::INFINITY), Vec2::splat(f32::NEG_INFINITY)),
+        |(min, max), point| (min.min(point), max.max(point)),
+    );
+    let size = max - min;
+    (size.x > 0.0 && size.y > 0.0).then(|| [min.x, min.y, size.x, size.y])
+}
+
+#[allow(clippy::too_many_arguments)]
+fn annotate_streamed_images(
+    dataset: Res<VisionDataset>,
+    frame: Res<CoordinateFrame>,
+    field_dimensions: Res<FieldDimensions>,
+    simulation_time: Res<SimulationTime>,
+    context: Res<RapierContext>,
+    mut frames_since_export: Local<u32>,
+    mut last_export: Local<Option<u64>>,
+    mut streamed_images: ResMut<StreamedImages>,
+    transforms: Query<&GlobalTransform>,
+    links: Query<&RobotLink>,
+    balls: Query<(Entity, &GlobalTransform), With<Ball>>,
+    robots: Query<(Entity, &Player), With<RobotRoot>>,
+    children: Query<&Children>,
+    meshes: Query<(&Aabb, &GlobalTransform)>,
+) {
+    *frames_since_export += 1;
+    // a paused simulation would export the same scene over and over
+    let export = *frames_since_export >= dataset.interval
+        && *last_export != Some(simulation_time.elapsed_steps());
+    if export {
+        *frames_since_export = 0;
+        *last_export = Some(simulation_time.elapsed_steps());
+    }
+    if !export
+        && streamed_images
+            .0
+            .iter()
+            .all(|image| image.annotation.is_none())
+    {
+        return;
+    }
+
+    let line_segments = field_line_segments(&field_dimensions);
+    for streamed_image in streamed_images.0.iter_mut() {
+        streamed_image.annotation = None;
+        if !export {
+            continue;
+        }
+        let (Ok(transform), Ok(link)) = (
+            transforms.get(streamed_image.link),
+            links.get(streamed_image.link),
+        ) else {
+            continue;
+        };
+        let camera_transform = transform.compute_transform();
+        let camera = PinholeCamera::new(&HEAD_CAMERAS[streamed_image.camera], camera_transform);
+        let own_robot = link.robot;
+
+        let line_segments = line_segments
+            .iter()
+            .filter_map(|[start, end]| {
+                camera.project_segment(
+                    frame.to_world(start.extend(0.0)),
+                    frame.to_world(end.extend(0.0)),
+                )
+            })
+            .map(|[start, end]| [start.x, start.y, end.x, end.y])
+            .collect();
+
+        let mut objects = Vec::new();
+        for (ball, ball_transform) in balls.iter() {
+            let center = ball_transform.translation();
+            let view = (center - camera_transform.translation).normalize_or_zero();
+            let side = view.any_orthonormal_vector();
+            let up = view.cross(side);
+            let silhouette: Vec<_> = (0..BALL_SILHOUETTE_POINTS)
+                .filter_map(|index| {
+                    let angle =
+                        index as f32 / BALL_SILHOUETTE_POINTS as f32 * std::f32::consts::TAU;
+                    let offset =
+                        (side * angle.cos() + up * angle.sin()) * field_dimensions.ball_radius;
+                    camera.project(center + offset)
+                })
+                .collect();
+            if silhouette.len() < BALL_SILHOUETTE_POINTS {
+                continue;
+            }
+            let Some(bbox) = bounding_box(clip_polygon(convex_hull(silhouette), camera.size))
+            else {
+                continue;
+            };
+            // the own body is ignored, the camera sits inside the head collider
+            let distance = camera_transform.translation.distance(center);
+            let not_own_link = |entity: Entity| {
+                links
+                    .get(entity)
+                    .map_or(true, |link| link.robot != own_robot)
+            };
+            let filter = QueryFilter::default().predicate(&not_own_link);
+            let occluded = context
+                .cast_ray(camera_transform.translation, view, distance, true, filter)
+                .map_or(false, |(entity, _)| entity != ball);
+            objects.push(ObjectInfo {
+                category_id: BALL_CATEGORY,
+                bbox,
+                area: bbox[2] * bbox[3],
+                segmentation: Vec::new(),
+                occluded: Some(occluded),
+                robot: None,
+            });
+        }
+
+        for (robot, player) in robots.iter().filter(|(robot, _)| *robot != own_robot) {
+            let segmentation: Vec<_> = children
+                .iter_descendants(robot)
+                .filter_map(|entity| meshes.get(entity).ok())
+                .filter_map(|(aabb, transform)| {
+                    let corners: Vec<_> = (0..8)
+                        .filter_map(|index| {
+                            let sign =
+                                |axis: usize| if index & (1 << axis) == 0 { -1.0 } else { 1.0 };
+                            let local = Vec3::from(aabb.center)
+                                + Vec3::new(sign(0), sign(1), sign(2))
+                                    * Vec3::from(aabb.half_extents);
+                            camera.project(transform.transform_point(local))
+                        })
+                        .collect();
+                    let polygon = clip_polygon(convex_hull(corners), camera.size);
+                    (polygon.len() >= 3).then_some(polygon)
+                })
+                .collect();
+            let Some(bbox) = bounding_box(segmentation.iter().flatten().copied()) else {
+                continue;
+            };
+            objects.push(ObjectInfo {
+                category_id: ROBOT_CATEGORY,
+                bbox,
+                area: bbox[2] * bbox[3],
+                segmentation: segmentation
+                    .iter()
+                    .map(|polygon| polygon.iter().flat_map(|point| point.to_array()).collect())
+                    .collect(),
+                occluded: None,
+                robot: Some(RobotInfo {
+                    team_color: player.team_color,
+                    jersey_number: player.jersey_number,
+                }),
+            });
+        }
+
+        let camera_to_field = frame.transform_to_field(camera_transform).compute_matrix();
+        streamed_image.annotation = Some(Arc::new(FrameAnnotation {
+            image: ImageInfo {
+                width: camera.size.x as u32,
+                height: camera.size.y as u32,
+                time: simulation_time.elapsed_seconds(),
+                camera: HEAD_CAMERAS[streamed_image.camera].link,
+                intrinsics: camera.intrinsics(),
+                camera_to_field: camera_to_field.transpose().to_cols_array_2d(),
+                line_segments,
+            },
+            objects,
+        }));
+    }
+}
+
+#[derive(Default, Serialize)]
+struct CocoDataset {
+    images: Vec<CocoImage>,
+    annotations: Vec<CocoAnnotation>,
+    categories: Vec<CocoCategory>,
+}
+
+#[derive(Serialize)]
+struct CocoImage {
+    id: usize,
+    file_name: String,
+    #[serde(flatten)]
+    info: ImageInfo,
+}
+
+#[derive(Serialize)]
+struct CocoAnnotation {
+    id: usize,
+    image_id: usize,
+    #[serde(flatten)]
+    object: ObjectInfo,
+}
+
+#[derive(Serialize)]
+struct CocoCategory {
+    id: u32,
+    name: &'static str,
+}
+
+fn write_dataset(directory: &Path, frames: Receiver<DatasetFrame>) {
+    let mut dataset = CocoDataset {
+        categories: vec![
+            CocoCategory {
+                id: BALL_CATEGORY,
+                name: "ball",
+            },
+            CocoCategory {
+                id: ROBOT_CATEGORY,
+                name: "robot",
+            },
+        ],
+        ..Default::default()
+    };
+    for frame in frames {
+        let id = dataset.images.len();
+        let file_name = format!("images/{id:06}.png");
+        let size = UVec2::new(frame.annotation.image.width, frame.annotation.image.height);
+        if let Err(error) = save_png(&directory.join(&file_name), size, &frame.rgba) {
+            error!("{error:?}");
+            continue;
+        }
+        for object in &frame.annotation.objects {
+            dataset.annotations.push(CocoAnnotation {
+                id: dataset.annotations.len(),
+                image_id: id,
+                object: object.clone(),
+            });
+        }
+        dataset.images.push(CocoImage {
+            id,
+            file_name,
+            info: frame.annotation.image.clone(),
+        });
+        if dataset.images.len() % ANNOTATION_FLUSH_INTERVAL == 0 {
+            if let Err(error) = save_annotations(directory, &dataset) {
+                error!("{error:?}");
+            }
+        }
+    }
+    if let Err(error) = save_annotations(directory, &dataset) {
+        error!("{error:?}");
+    }
+}
+
+fn save_annotations(directory: &Path, dataset: &CocoDataset) -> Result<()> {
+    let path = directory.join("annotations.json");
+    let file =
+        File::create(&path).wrap_err_with(|| format!("failed to create {}", path.display()))?;
+    serde_json::to_writer(BufWriter::new(file), dataset)
+        .wrap_err_with(|| format!("failed to write {}", path.display()))
+}
@@ -0,0 +1,334 @@
+use std::{
+    collections::HashMap,
+    io::ErrorKind,
+    net::{Ipv4Addr, TcpListener, TcpStream},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use bevy::{prelude::*, time::common_conditions::on_timer};
+use bevy_rapier3d::prelude::*;
+use color_eyre::{eyre::WrapErr, Result};
+use serde::{Deserialize, Serialize};
+use tungstenite::{Message, WebSocket};
+
+use crate::{
+    arenas::{in_main_arena, Arena},
+    coordinate_frame::CoordinateFrame,
+    field_dimensions::FieldDimensions,
+    game_controller::{GameControllerState, GameState},
+    game_phase::GamePhase,
+    joint_control::JointCommand,
+    player::{Player, RobotStatus, TeamColor},
+    referee::GameScore,
+    simulation_time::SimulationTime,
+    Ball, NaoJoint, RobotRoot,
+};
+
+/// Dashboards are updated 20 times per second
+const SEND_INTERVAL: Duration = Duration::from_millis(50);
+/// How long a client thread waits for commands before sending the next state
+const READ_TIMEOUT: Duration = Duration::from_millis(10);
+
+/// Streams the simulation state as JSON to WebSocket clients and executes their commands.
+///
+/// Every [`SEND_INTERVAL`] each client receives a [`TelemetryState`] text message. Clients send
+/// [`RemoteCommand`]s as JSON text messages, tagged by a `command` field, e.g.
+/// `{"command": "move_ball", "position": [1.0, 0.5]}`.
+pub struct WebSocketServerPlugin;
+
+impl Plugin for WebSocketServerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WebSocketServer>()
+            .add_startup_system(start_websocket_server)
+            .add_system(execute_remote_commands)
+            .add_system(send_telemetry.run_if(on_timer(SEND_INTERVAL)));
+    }
+}
+
+#[derive(Resource)]
+pub struct WebSocketServer {
+    pub port: u16,
+    clients: Arc<Mutex<Vec<Sender<String>>>>,
+    commands: Option<Mutex<Receiver<RemoteCommand>>>,
+}
+
+impl Default for WebSocketServer {
+    fn default() -> Self {
+        Self {
+            port: 9090,
+            clients: Default::default(),
+            commands: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum RemoteCommand {
+    /// Places the resting ball on the field at x, y in meters
+    MoveBall { position: [f32; 2] },
+    /// Sets the game state by name, e.g. "Playing"
+    SetGameState { state: String },
+    /// Sets the target position of a joint in radians
+    SetJointTarget {
+        team_color: TeamColor,
+        jersey_number: u8,
+        joint: String,
+        position: f32,
+    },
+}
+
+#[derive(Serialize)]
+pub struct TelemetryState {
+    /// Simulated time in seconds
+    pub time: f64,
+    pub ball: Option<BallState>,
+    pub robots: Vec<RobotState>,
+    pub game: GameTelemetry,
+}
+
+#[derive(Serialize)]
+pub struct BallState {
+    /// Position on the field in meters, the ground at zero height
+    pub position: [f32; 3],
+    pub velocity: [f32; 3],
+}
+
+#[derive(Serialize)]
+pub struct RobotState {
+    pub team_color: TeamColor,
+    pub jersey_number: u8,
+    /// Position and heading on the field in meters and radians
+    pub pose: [f32; 3],
+    pub penalized: bool,
+    pub fallen: bool,
+    /// Joint angles by joint name in radians
+    pub joints: HashMap<String, f32>,
+}
+
+#[derive(Serialize)]
+pub struct GameTelemetry {
+    pub state: String,
+    pub set_play: String,
+    pub kicking_team: Option<TeamColor>,
+    pub seconds_remaining: i16,
+    pub score: HashMap<TeamColor, u32>,
+}
+
+fn start_websocket_server(mut server: ResMut<WebSocketServer>) {
+    let listener = match TcpListener::bind((Ipv4Addr::UNSPECIFIED, server.port))
+        .wrap_err_with(|| format!("failed to bind WebSocket port {}", server.port))
+    {
+        Ok(listener) => listener,
+        Err(error) => {
+            error!("{error:?}");
+            return;
+        }
+    };
+    info!("Serving WebSocket telemetry on port {}", server.port);
+    let (command_sender, command_receiver) = mpsc::channel();
+    server.commands = Some(Mutex::new(command_receiver));
+    let clients = server.clients.clone();
+    thread::spawn(move || accept_clients(listener, clients, command_sender));
+}
+
+fn accept_clients(
+    listener: TcpListener,
+    clients: Arc<Mutex<Vec<Sender<String>>>>,
+    commands: Sender<RemoteCommand>,
+) {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(error) => {
+                error!("Failed to accept WebSocket client: {error}");
+                continue;
+            }
+        };
+        let (state_sender, state_receiver) = mpsc::channel();
+        clients.lock().unwrap().push(state_sender);
+        let commands = commands.clone();
+        thread::spawn(move || {
+            if let Err(error) = serve_client(stream, state_receiver, commands) {
+                warn!("WebSocket client disconnected: {error:?}");
+            }
+        });
+    }
+}
+
+/// Sends states to a client and forwards its commands until it disconnects.
+fn serve_client(
+    stream: TcpStream,
+    states: Receiver<String>,
+    commands: Sender<RemoteCommand>,
+) -> Result<()> {
+    let mut socket: WebSocket<TcpStream> =
+        tungstenite::accept(stream).wrap_err("WebSocket handshake failed")?;
+    // the handshake blocks, only waiting for commands is limited
+    socket
+        .get_mut()
+        .set_read_timeout(Some(READ_TIMEOUT))
+        .wrap_err("failed to set WebSocket read timeout")?;
+    loop {
+        match socket.read_message() {
+            Ok(Message::Text(text)) => match serde_json::from_str(&text) {
+                Ok(command) => {
+                    if commands.send(command).is_err() {
+                        return Ok(());
+                    }
+                }
+                Err(error) => warn!("Invalid WebSocket command {text:?}: {error}"),
+            },
+            Ok(Message::Close(_)) => return Ok(()),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(error))
+                if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(error) => return Err(error).wrap_err("failed to read WebSocket message"),
+        }
+        // only the latest state is worth sending to a slow client
+        if let Some(state) = states.try_iter().last() {
+            socket
+                .write_message(Message::Text(state))
+                .wrap_err("failed to send WebSocket message")?;
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn execute_remote_commands(
+    server: Res<WebSocketServer>,
+    frame: Res<CoordinateFrame>,
+    field_dimensions: Res<FieldDimensions>,
+    mut game_controller_state: ResMut<GameControllerState>,
+    mut balls: Query<(&mut Transform, &mut Velocity, Option<&Arena>), With<Ball>>,
+    robots: Query<(Entity, &Player, Option<&Arena>), With<RobotRoot>>,
+    children: Query<&Children>,
+    mut joints: Query<(&NaoJoint, &mut JointCommand)>,
+) {
+    let Some(commands) = &server.commands else {
+        return;
+    };
+    let commands: Vec<_> = commands.lock().unwrap().try_iter().collect();
+    for command in commands {
+        match command {
+            RemoteCommand::MoveBall { position } => {
+                for (mut transform, mut velocity, _) in
+                    balls.iter_mut().filter(|(.., arena)| in_main_arena(*arena))
+                {
+                    transform.translation = frame
+                        .to_world(Vec2::from_array(position).extend(field_dimensions.ball_radius));
+                    *velocity = Velocity::zero();
+                }
+            }
+            RemoteCommand::SetGameState { state } => {
+                match GameState::ALL
+                    .into_iter()
+                    .find(|candidate| format!("{candidate:?}").eq_ignore_ascii_case(&state))
+                {
+                    Some(state) => game_controller_state.state = state,
+                    None => warn!("Unknown game state {state:?}"),
+                }
+            }
+            RemoteCommand::SetJointTarget {
+                team_color,
+                jersey_number,
+                joint,
+                position,
+            } => {
+                let Some((robot, ..)) = robots.iter().find(|(_, player, arena)| {
+                    in_main_arena(*arena)
+                        && player.team_color == team_color
+                        && player.jersey_number == jersey_number
+                }) else {
+                    warn!("No robot {team_color:?} {jersey_number}");
+                    continue;
+                };
+                for link in children.iter_descendants(robot) {
+                    if let Ok((nao_joint, mut command)) = joints.get_mut(link) {
+                        if nao_joint.name == joint {
+                            command.position = position;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn send_telemetry(
+    server: Res<WebSocketServer>,
+    simulation_time: Res<SimulationTime>,
+    frame: Res<CoordinateFrame>,
+    game_controller_state: Res<GameControllerState>,
+    game_phase: Res<GamePhase>,
+    score: Res<GameScore>,
+    balls: Query<(&GlobalTransform, &Velocity, Option<&Arena>), With<Ball>>,
+    robots: Query<
+        (
+            Entity,
+            &Player,
+            &RobotStatus,
+            &GlobalTransform,
+            Option<&Arena>,
+        ),
+        With<RobotRoot>,
+    >,
+    children: Query<&Children>,
+    joints: Query<(&NaoJoint, &Transform)>,
+) {
+    let mut clients = server.clients.lock().unwrap();
+    if clients.is_empty() {
+        return;
+    }
+    let state = TelemetryState {
+        time: simulation_time.elapsed_seconds(),
+        ball: balls.iter().find(|(.., arena)| in_main_arena(*arena)).map(
+            |(transform, velocity, _)| BallState {
+                position: frame.to_field(transform.translation()).to_array(),
+                velocity: frame.vector_to_field(velocity.linvel).to_array(),
+            },
+        ),
+        robots: robots
+            .iter()
+            .filter(|(.., arena)| in_main_arena(*arena))
+            .map(|(robot, player, status, transform, _)| {
+                let transform = frame.transform_to_field(transform.compute_transform());
+                let (yaw, _, _) = transform.rotation.to_euler(EulerRot::ZYX);
+                RobotState {
+                    team_color: player.team_color,
+                    jersey_number: player.jersey_number,
+                    pose: [transform.translation.x, transform.translation.y, yaw],
+                    penalized: status.penalized,
+                    fallen: status.fallen,
+                    joints: children
+                        .iter_descendants(robot)
+                        .filter_map(|link| joints.get(link).ok())
+                        .map(|(joint, transform)| (joint.name.clone(), joint.angle(transform)))
+                        .collect(),
+                }
+            })
+            .collect(),
+        game: GameTelemetry {
+            state: format!("{:?}", game_controller_state.state),
+            set_play: format!("{:?}", game_phase.set_play),
+            kicking_team: game_phase.kicking_team,
+            seconds_remaining: game_controller_state.seconds_remaining,
+            score: score.goals.clone(),
+        },
+    };
+    let json = match serde_json::to_string(&state) {
+        Ok(json) => json,
+        Err(error) => {
+            error!("failed to serialize telemetry: {error:?}");
+            return;
+        }
+    };
+    // disconnected clients dropped their receiver
+    clients.retain(|client| client.send(json.clone()).is_ok());
+}
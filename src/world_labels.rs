@@ -0,0 +1,74 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::pan_orbit_camera::PanOrbitCamera;
+
+/// Draws [`WorldLabel`]s on top of the viewport.
+pub struct WorldLabelsPlugin;
+
+impl Plugin for WorldLabelsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(draw_world_labels);
+    }
+}
+
+/// Text kept at the screen position of the entity it is attached to.
+///
+/// Labels are only drawn while the entity is visible in the hierarchy, so hiding a parent hides
+/// all labels below it.
+#[derive(Component)]
+pub struct WorldLabel {
+    pub text: String,
+    pub color: Color,
+    /// Offset from the entity's global translation in world coordinates
+    pub offset: Vec3,
+}
+
+impl WorldLabel {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            color: Color::WHITE,
+            offset: Vec3::ZERO,
+        }
+    }
+}
+
+fn draw_world_labels(
+    mut contexts: EguiContexts,
+    cameras: Query<(&Camera, &GlobalTransform), With<PanOrbitCamera>>,
+    labels: Query<(&WorldLabel, &GlobalTransform, &ComputedVisibility)>,
+) {
+    let Ok((camera, camera_transform)) = cameras.get_single() else {
+        return;
+    };
+    let Some((viewport_min, viewport_max)) = camera.logical_viewport_rect() else {
+        return;
+    };
+    let painter = contexts
+        .ctx_mut()
+        .layer_painter(egui::LayerId::background());
+
+    for (label, transform, visibility) in labels.iter() {
+        if !visibility.is_visible_in_hierarchy() {
+            continue;
+        }
+        let world_position = transform.translation() + label.offset;
+        let Some(position) = camera.world_to_viewport(camera_transform, world_position) else {
+            continue;
+        };
+        // viewport coordinates start at the bottom left, egui coordinates at the top left
+        let position = egui::pos2(viewport_min.x + position.x, viewport_max.y - position.y);
+        let [r, g, b, a] = label
+            .color
+            .as_rgba_f32()
+            .map(|channel| (channel * 255.0) as u8);
+        painter.text(
+            position,
+            egui::Align2::CENTER_BOTTOM,
+            &label.text,
+            egui::FontId::proportional(14.0),
+            egui::Color32::from_rgba_unmultiplied(r, g, b, a),
+        );
+    }
+}
@@ -0,0 +1,464 @@
+use std::{
+    collections::HashMap,
+    f64::consts::PI,
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::{
+    eyre::{bail, eyre, WrapErr},
+    Result,
+};
+use xmltree::{Element, XMLNode};
+
+use crate::mesh_uris::PackagePaths;
+
+/// Expands the xacro file at `path` into URDF.
+///
+/// Supports the common subset of xacro: properties, arguments with their defaults, `${}` math
+/// expressions, includes, macros with plain parameters and conditional blocks. Block properties and
+/// block parameters of macros are not supported.
+pub fn expand_xacro(path: &Path, packages: &PackagePaths) -> Result<String> {
+    let mut expander = Expander {
+        packages,
+        scopes: vec![HashMap::new()],
+        arguments: HashMap::new(),
+        macros: HashMap::new(),
+    };
+    let mut root = parse(path)?;
+    let children = std::mem::take(&mut root.children);
+    root.children = expander.expand(children, parent_directory(path))?;
+    root.attributes = root
+        .attributes
+        .iter()
+        .map(|(name, value)| Ok((name.clone(), expander.substitute(value)?)))
+        .collect::<Result<_>>()?;
+    let mut urdf = Vec::new();
+    root.write(&mut urdf)
+        .wrap_err_with(|| format!("failed to write expanded {}", path.display()))?;
+    Ok(String::from_utf8(urdf)?)
+}
+
+fn parse(path: &Path) -> Result<Element> {
+    let file = File::open(path).wrap_err_with(|| format!("failed to open {}", path.display()))?;
+    Element::parse(BufReader::new(file))
+        .wrap_err_with(|| format!("failed to parse xacro {}", path.display()))
+}
+
+fn parent_directory(path: &Path) -> &Path {
+    path.parent().unwrap_or_else(|| Path::new(""))
+}
+
+struct Macro {
+    /// Parameter names with their default value
+    parameters: Vec<(String, Option<String>)>,
+    body: Vec<XMLNode>,
+}
+
+struct Expander<'a> {
+    packages: &'a PackagePaths,
+    /// Properties of the file and of the macros currently expanded, innermost last
+    scopes: Vec<HashMap<String, String>>,
+    arguments: HashMap<String, String>,
+    macros: HashMap<String, Macro>,
+}
+
+impl Expander<'_> {
+    fn expand(&mut self, nodes: Vec<XMLNode>, directory: &Path) -> Result<Vec<XMLNode>> {
+        let mut expanded = Vec::new();
+        for node in nodes {
+            match node {
+                XMLNode::Element(element) if element.prefix.as_deref() == Some("xacro") => {
+                    expanded.extend(self.expand_xacro_element(element, directory)?);
+                }
+                XMLNode::Element(mut element) => {
+                    for value in element.attributes.values_mut() {
+                        *value = self.substitute(value)?;
+                    }
+                    let children = std::mem::take(&mut element.children);
+                    element.children = self.expand(children, directory)?;
+                    expanded.push(XMLNode::Element(element));
+                }
+                XMLNode::Text(text) => expanded.push(XMLNode::Text(self.substitute(&text)?)),
+                node => expanded.push(node),
+            }
+        }
+        Ok(expanded)
+    }
+
+    fn expand_xacro_element(&mut self, element: Element, directory: &Path) -> Result<Vec<XMLNode>> {
+        let attribute = |name: &str| {
+            element
+                .attributes
+                .get(name)
+                .ok_or_else(|| eyre!("xacro:{} without {name} attribute", element.name))
+        };
+        match element.name.as_str() {
+            "property" => {
+                let name = attribute("name")?.clone();
+                let Some(value) = element.attributes.get("value") else {
+                    bail!("block property {name} is not supported");
+                };
+                let value = self.substitute(value)?;
+                self.scopes
+                    .last_mut()
+                    .expect("there is always the file scope")
+                    .insert(name, value);
+                Ok(Vec::new())
+            }
+            "arg" => {
+                let name = attribute("name")?.clone();
+                let default = element
+                    .attributes
+                    .get("default")
+                    .map(|default| self.substitute(default))
+                    .transpose()?
+                    .unwrap_or_default();
+                self.arguments.entry(name).or_insert(default);
+                Ok(Vec::new())
+            }
+            "include" => {
+                let path = directory.join(self.substitute(attribute("filename")?)?);
+                let included = parse(&path)?;
+                self.expand(included.children, parent_directory(&path))
+                    .wrap_err_with(|| format!("failed to expand {}", path.display()))
+            }
+            "macro" => {
+                let name = attribute("name")?.clone();
+                let parameters = element
+                    .attributes
+                    .get("params")
+                    .map_or("", String::as_str)
+                    .split_whitespace()
+                    .map(|parameter| {
+                        if parameter.starts_with('*') {
+                            bail!("block parameter {parameter} of macro {name} is not supported");
+                        }
+                        Ok(match parameter.split_once(":=") {
+                            Some((parameter, default)) => {
+                                (parameter.to_string(), Some(default.to_string()))
+                            }
+                            None => (parameter.to_string(), None),
+                        })
+                    })
+                    .collect::<Result<_>>()?;
+                self.macros.insert(
+                    name,
+                    Macro {
+                        parameters,
+                        body: element.children,
+                    },
+                );
+                Ok(Vec::new())
+            }
+            "if" | "unless" => {
+                let value = self.substitute(attribute("value")?)?;
+                let condition = match value.trim() {
+                    "true" | "True" => true,
+                    "false" | "False" => false,
+                    value => value
+                        .parse::<f64>()
+                        .map(|value| value != 0.0)
+                        .map_err(|_| eyre!("condition {value:?} is not a boolean"))?,
+                };
+                if condition == (element.name == "if") {
+                    self.expand(element.children, directory)
+                } else {
+                    Ok(Vec::new())
+                }
+            }
+            name => {
+                let Some(macro_) = self.macros.get(name) else {
+                    bail!("unknown xacro element or macro {name}");
+                };
+                let mut scope = HashMap::new();
+                for (parameter, default) in &macro_.parameters {
+                    let value = match (element.attributes.get(parameter), default) {
+                        (Some(value), _) | (None, Some(value)) => self.substitute(value)?,
+                        (None, None) => bail!("macro {name} is missing parameter {parameter}"),
+                    };
+                    scope.insert(parameter.clone(), value);
+                }
+                let body = macro_.body.clone();
+                self.scopes.push(scope);
+                let expanded = self
+                    .expand(body, directory)
+                    .wrap_err_with(|| format!("failed to expand macro {name}"));
+                self.scopes.pop();
+                expanded
+            }
+        }
+    }
+
+    fn property(&self, name: &str) -> Option<&String> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    /// Replaces `${expression}` and `$(command)` in `text`.
+    fn substitute(&self, text: &str) -> Result<String> {
+        let mut result = String::new();
+        let mut rest = text;
+        while let Some(start) = rest.find('$') {
+            result.push_str(&rest[..start]);
+            let after = &rest[start + 1..];
+            let (closing, substitution) = match after.chars().next() {
+                Some('{') => {
+                    let end = after
+                        .find('}')
+                        .ok_or_else(|| eyre!("unclosed expression in {text:?}"))?;
+                    (end, self.evaluate(&after[1..end])?)
+                }
+                Some('(') => {
+                    let end = after
+                        .find(')')
+                        .ok_or_else(|| eyre!("unclosed substitution in {text:?}"))?;
+                    (end, self.command(&after[1..end])?)
+                }
+                _ => {
+                    result.push('$');
+                    rest = after;
+                    continue;
+                }
+            };
+            result.push_str(&substitution);
+            rest = &after[closing + 1..];
+        }
+        result.push_str(rest);
+        Ok(result)
+    }
+
+    fn command(&self, command: &str) -> Result<String> {
+        let mut words = command.split_whitespace();
+        match (words.next(), words.next()) {
+            (Some("arg"), Some(name)) => self
+                .arguments
+                .get(name)
+                .cloned()
+                .ok_or_else(|| eyre!("unknown argument {name}")),
+            (Some("find"), Some(package)) => Ok(self
+                .packages
+                .0
+                .get(package)
+                .cloned()
+                .unwrap_or_else(|| PathBuf::from(package))
+                .to_string_lossy()
+                .into_owned()),
+            _ => bail!("unsupported substitution $({command})"),
+        }
+    }
+
+    fn evaluate(&self, expression: &str) -> Result<String> {
+        // properties may hold strings, those are only substituted as a whole
+        if let Some(value) = self.property(expression.trim()) {
+            return Ok(value.clone());
+        }
+        let mut parser = ExpressionParser {
+            expander: self,
+            input: expression.as_bytes(),
+            position: 0,
+        };
+        let value = parser
+            .expression()
+            .wrap_err_with(|| format!("failed to evaluate ${{{expression}}}"))?;
+        parser.skip_whitespace();
+        if parser.position != parser.input.len() {
+            bail!("unexpected characters in ${{{expression}}}");
+        }
+        Ok(value.to_string())
+    }
+}
+
+/// Recursive descent parser of arithmetic expressions over numbers, properties and a few
+/// functions.
+struct ExpressionParser<'a> {
+    expander: &'a Expander<'a>,
+    input: &'a [u8],
+    position: usize,
+}
+
+impl ExpressionParser<'_> {
+    fn expression(&mut self) -> Result<f64> {
+        let mut value = self.term()?;
+        loop {
+            if self.eat("+") {
+                value += self.term()?;
+            } else if self.eat("-") {
+                value -= self.term()?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<f64> {
+        let mut value = self.unary()?;
+        loop {
+            if self.eat("*") {
+                value *= self.unary()?;
+            } else if self.eat("/") {
+                value /= self.unary()?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Signs bind looser than powers like in Python, `-2**2` is -4.
+    fn unary(&mut self) -> Result<f64> {
+        if self.eat("-") {
+            return Ok(-self.unary()?);
+        }
+        if self.eat("+") {
+            return self.unary();
+        }
+        self.power()
+    }
+
+    /// Powers are right associative and their exponent may be signed, `2**-1` is 0.5.
+    fn power(&mut self) -> Result<f64> {
+        let base = self.primary()?;
+        if self.eat("**") {
+            return Ok(base.powf(self.unary()?));
+        }
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Result<f64> {
+        self.skip_whitespace();
+        if self.eat("(") {
+            let value = self.expression()?;
+            if !self.eat(")") {
+                bail!("missing closing parenthesis");
+            }
+            return Ok(value);
+        }
+        let start = self.position;
+        let Some(&first) = self.input.get(start) else {
+            bail!("unexpected end of expression");
+        };
+        if first.is_ascii_digit() || first == b'.' {
+            while self.input.get(self.position).map_or(false, |character| {
+                character.is_ascii_digit() || matches!(character, b'.' | b'e' | b'E')
+            }) {
+                // exponent signs belong to the number
+                if matches!(self.input[self.position], b'e' | b'E')
+                    && matches!(self.input.get(self.position + 1), Some(b'-' | b'+'))
+                {
+                    self.position += 1;
+                }
+                self.position += 1;
+            }
+            let number = std::str::from_utf8(&self.input[start..self.position])?;
+            return number
+                .parse()
+                .wrap_err_with(|| format!("invalid number {number}"));
+        }
+        if first.is_ascii_alphabetic() || first == b'_' {
+            while self.input.get(self.position).map_or(false, |character| {
+                character.is_ascii_alphanumeric() || *character == b'_'
+            }) {
+                self.position += 1;
+            }
+            let name = std::str::from_utf8(&self.input[start..self.position])?;
+            if self.eat("(") {
+                let argument = self.expression()?;
+                if !self.eat(")") {
+                    bail!("missing closing parenthesis of {name}");
+                }
+                return function(name, argument);
+            }
+            if name == "pi" {
+                return Ok(PI);
+            }
+            let value = self
+                .expander
+                .property(name)
+                .ok_or_else(|| eyre!("unknown property {name}"))?;
+            return value
+                .trim()
+                .parse()
+                .wrap_err_with(|| format!("property {name} = {value:?} is not a number"));
+        }
+        bail!("unexpected character {:?}", first as char)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self
+            .input
+            .get(self.position)
+            .map_or(false, u8::is_ascii_whitespace)
+        {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        self.input[self.position..].starts_with(token.as_bytes())
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        let found = self.peek(token);
+        if found {
+            self.position += token.len();
+        }
+        found
+    }
+}
+
+fn function(name: &str, argument: f64) -> Result<f64> {
+    Ok(match name {
+        "sin" => argument.sin(),
+        "cos" => argument.cos(),
+        "tan" => argument.tan(),
+        "asin" => argument.asin(),
+        "acos" => argument.acos(),
+        "atan" => argument.atan(),
+        "sqrt" => argument.sqrt(),
+        "abs" => argument.abs(),
+        "radians" => argument.to_radians(),
+        "degrees" => argument.to_degrees(),
+        _ => bail!("unknown function {name}"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn substitute(text: &str) -> String {
+        let packages = PackagePaths(HashMap::from([(
+            "nao".to_string(),
+            PathBuf::from("/packages/nao"),
+        )]));
+        let expander = Expander {
+            packages: &packages,
+            scopes: vec![HashMap::from([
+                ("width".to_string(), "0.5".to_string()),
+                ("link".to_string(), "torso".to_string()),
+            ])],
+            arguments: HashMap::from([("side".to_string(), "left".to_string())]),
+            macros: HashMap::new(),
+        };
+        expander.substitute(text).unwrap()
+    }
+
+    #[test]
+    fn exponentiation_binds_like_python() {
+        assert_eq!(substitute("${-2**2}"), "-4");
+        assert_eq!(substitute("${2**3**2}"), "512");
+        assert_eq!(substitute("${2*3**2}"), "18");
+        assert_eq!(substitute("${2**-1}"), "0.5");
+        assert_eq!(substitute("${(1+2)*3}"), "9");
+    }
+
+    #[test]
+    fn substitutes_properties_and_arguments() {
+        assert_eq!(substitute("${link}_joint"), "torso_joint");
+        assert_eq!(substitute("${width * 2} ${-width}"), "1 -0.5");
+        assert_eq!(substitute("$(arg side)_arm"), "left_arm");
+        assert_eq!(substitute("$(find nao)/meshes"), "/packages/nao/meshes");
+        assert_eq!(substitute("cost: $5"), "cost: $5");
+    }
+}